@@ -0,0 +1,131 @@
+//! Note amount representation.
+//!
+//! Claims carry `u128` amounts. The `wide-amount` feature adds
+//! [`WideClaimInput`](crate::WideClaimInput) and its journal, whose amounts
+//! are 32-byte big-endian words so token deposits above `u128::MAX` can be
+//! claimed. The wide journal packs the amount in 32 bytes instead of 16, so a
+//! guest built on it has a different circuit ID and journal layout. The
+//! feature only adds items; the `u128` API is the same with or without it.
+
+/// A note amount.
+pub type Amount = u128;
+/// A note amount, as a 32-byte big-endian word.
+#[cfg(feature = "wide-amount")]
+pub type WideAmount = [u8; 32];
+
+/// An amount type a claim can carry.
+pub trait NoteAmount: Copy + PartialEq {
+    /// Width of the amount field in the packed journal.
+    const LEN: usize;
+
+    /// The amount as a big-endian 32-byte word, as hashed into the notes hash.
+    fn to_word(&self) -> [u8; 32];
+
+    /// Sum of `amounts` as a big-endian word, or `None` if it overflows `Self`.
+    fn checked_sum(amounts: &[Self]) -> Option<[u8; 32]>;
+
+    /// Write the amount little-endian into `out`, which is `LEN` bytes.
+    fn write_le(&self, out: &mut [u8]);
+
+    /// Read a little-endian amount from `bytes`, which is `LEN` bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+impl NoteAmount for u128 {
+    const LEN: usize = 16;
+
+    fn to_word(&self) -> [u8; 32] {
+        u128_to_word(*self)
+    }
+
+    fn checked_sum(amounts: &[Self]) -> Option<[u8; 32]> {
+        amounts
+            .iter()
+            .try_fold(0u128, |total, &amt| total.checked_add(amt))
+            .map(u128_to_word)
+    }
+
+    fn write_le(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        let mut le = [0u8; 16];
+        le.copy_from_slice(bytes);
+        u128::from_le_bytes(le)
+    }
+}
+
+#[cfg(feature = "wide-amount")]
+impl NoteAmount for [u8; 32] {
+    const LEN: usize = 32;
+
+    fn to_word(&self) -> [u8; 32] {
+        *self
+    }
+
+    fn checked_sum(amounts: &[Self]) -> Option<[u8; 32]> {
+        amounts
+            .iter()
+            .try_fold([0u8; 32], |total, amt| checked_add_words(&total, amt))
+    }
+
+    fn write_le(&self, out: &mut [u8]) {
+        out.copy_from_slice(self);
+        out.reverse();
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        let mut out = [0u8; 32];
+        out.copy_from_slice(bytes);
+        out.reverse();
+        out
+    }
+}
+
+pub fn amount_from_u128(value: u128) -> Amount {
+    value
+}
+
+/// The amount as a big-endian 32-byte word, as hashed into the notes hash.
+pub fn amount_to_word<A: NoteAmount>(amount: &A) -> [u8; 32] {
+    amount.to_word()
+}
+
+pub(crate) fn u128_to_word(value: u128) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+#[cfg(feature = "wide-amount")]
+fn checked_add_words(a: &[u8; 32], b: &[u8; 32]) -> Option<[u8; 32]> {
+    let mut out = [0u8; 32];
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let sum = a[i] as u16 + b[i] as u16 + carry;
+        out[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    (carry == 0).then_some(out)
+}
+
+#[cfg(all(test, feature = "wide-amount"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_sum_carries_across_the_u128_boundary_and_detects_overflow() {
+        let max_low = u128_to_word(u128::MAX);
+        let mut expected = [0xffu8; 32];
+        expected[..15].fill(0);
+        expected[15] = 1;
+        expected[31] = 0xfe;
+        assert_eq!(WideAmount::checked_sum(&[max_low, max_low]), Some(expected));
+        assert_eq!(
+            WideAmount::checked_sum(&[[0xff; 32], u128_to_word(1)]),
+            None
+        );
+        assert_eq!(u128::checked_sum(&[u128::MAX, 1]), None);
+    }
+}
@@ -0,0 +1,89 @@
+//! Hash functions for the domain-separated (magic-label) derivations.
+//!
+//! Recipient hashes, target addresses and nullifiers are computed with the
+//! suite chosen at build time: SHA-256 by default, Poseidon with the
+//! `poseidon` feature. Ethereum hashes (block headers, trie nodes, storage
+//! keys) are always keccak and the notes hash is always SHA-256.
+//!
+//! The Poseidon suite exists for benchmarking only. A guest built with it has
+//! a different circuit ID, and its target addresses and nullifiers differ
+//! from the SHA-256 ones, so deposits made for one suite cannot be claimed
+//! with the other.
+
+/// A 32-byte hash over arbitrary input.
+pub trait HashSuite {
+    fn hash(data: &[u8]) -> [u8; 32];
+}
+
+/// SHA-256, the suite the deployed circuit uses.
+pub struct Sha256Suite;
+
+impl HashSuite for Sha256Suite {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        crate::sha256(data)
+    }
+}
+
+/// The suite used by [`crate::compute_recipient_hash`],
+/// [`crate::derive_target_address`] and [`crate::derive_nullifier`].
+#[cfg(not(feature = "poseidon"))]
+pub type ClaimHashSuite = Sha256Suite;
+#[cfg(feature = "poseidon")]
+pub type ClaimHashSuite = PoseidonSuite;
+
+#[cfg(feature = "poseidon")]
+pub use poseidon::PoseidonSuite;
+
+#[cfg(feature = "poseidon")]
+mod poseidon {
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{
+        poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge},
+        CryptographicSponge,
+    };
+    use ark_ff::{BigInteger, PrimeField};
+
+    use super::HashSuite;
+
+    const RATE: usize = 2;
+    const FULL_ROUNDS: usize = 8;
+    const PARTIAL_ROUNDS: usize = 57;
+    const ALPHA: u64 = 5;
+    /// Input bytes per field element, so every chunk is below the modulus.
+    const CHUNK_BYTES: usize = 31;
+
+    /// Poseidon over the BN254 scalar field (width 3, x^5, 8 full and 57
+    /// partial rounds, Grain LFSR constants). Input is absorbed as 31-byte
+    /// big-endian chunks after a length element; the digest is one squeezed
+    /// element, big-endian.
+    ///
+    /// Round constants are regenerated on every call, which dominates the
+    /// cost; keep that in mind when comparing cycle counts.
+    pub struct PoseidonSuite;
+
+    impl HashSuite for PoseidonSuite {
+        fn hash(data: &[u8]) -> [u8; 32] {
+            let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(
+                Fr::MODULUS_BIT_SIZE as u64,
+                RATE,
+                FULL_ROUNDS as u64,
+                PARTIAL_ROUNDS as u64,
+                0,
+            );
+            let config = PoseidonConfig::new(FULL_ROUNDS, PARTIAL_ROUNDS, ALPHA, mds, ark, RATE, 1);
+            let mut sponge = PoseidonSponge::new(&config);
+
+            sponge.absorb(&Fr::from(data.len() as u64));
+            for chunk in data.chunks(CHUNK_BYTES) {
+                sponge.absorb(&Fr::from_be_bytes_mod_order(chunk));
+            }
+            let digest = sponge.squeeze_field_elements::<Fr>(1)[0]
+                .into_bigint()
+                .to_bytes_be();
+
+            let mut out = [0u8; 32];
+            out[32 - digest.len()..].copy_from_slice(&digest);
+            out
+        }
+    }
+}
@@ -3,15 +3,33 @@
 extern crate alloc;
 
 use alloc::vec::Vec;
+use amount::u128_to_word;
+#[cfg(feature = "wide-amount")]
+pub use amount::WideAmount;
+pub use amount::{amount_from_u128, amount_to_word, Amount, NoteAmount};
+use hash_suite::{ClaimHashSuite, HashSuite};
+use rlp::{decode_rlp_item, decode_rlp_list_payload_items, rlp_list_items, RlpError};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tiny_keccak::{Hasher, Keccak};
+use zeroize::Zeroize;
 
+/// Default note capacity of a deposit.
+///
+/// The notes hash is padded to the capacity, so a crate that builds with a
+/// different one (via the `*_with_capacity` functions) derives different
+/// target addresses, and a guest built with it has a different circuit ID.
 pub const MAX_NOTES: usize = 5;
 pub const MAX_NOTE_WEI: u128 = 8_000_000_000_000_000_000;
 pub const MAX_PROOF_DEPTH: usize = 64;
 pub const MAX_NODE_BYTES: usize = 4096;
 
+pub mod amount;
+pub mod hash_suite;
+pub mod rlp;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
 const MAGIC_RECIPIENT: &[u8] = b"shadow.recipient.v1";
 const MAGIC_ADDRESS: &[u8] = b"shadow.address.v1";
 const MAGIC_NULLIFIER: &[u8] = b"shadow.nullifier.v1";
@@ -26,16 +44,16 @@ pub struct TokenClaimInput {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ClaimInput {
+pub struct ClaimInput<A = Amount> {
     pub block_number: u64,
     pub block_hash: [u8; 32],
     pub chain_id: u64,
     pub note_index: u32,
-    pub amount: u128,
+    pub amount: A,
     pub recipient: [u8; 20],
     pub secret: [u8; 32],
     pub note_count: u32,
-    pub amounts: Vec<u128>,
+    pub amounts: Vec<A>,
     pub recipient_hashes: Vec<[u8; 32]>,
     pub block_header_rlp: Vec<u8>,
     pub proof_depth: u32,
@@ -44,20 +62,27 @@ pub struct ClaimInput {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ClaimJournal {
+pub struct ClaimJournal<A = Amount> {
     pub block_number: u64,
     /// The block hash that was verified against the RLP-encoded block header.
     /// The stateRoot is derived in-circuit from this block header; we commit
     /// to blockHash because that's what the on-chain Anchor contract provides.
     pub block_hash: [u8; 32],
     pub chain_id: u64,
-    pub amount: u128,
+    pub amount: A,
     pub recipient: [u8; 20],
     pub nullifier: [u8; 32],
     /// Token contract address. [0u8; 20] = ETH (native).
     pub token: [u8; 20],
 }
 
+/// A claim whose note amounts are 32-byte big-endian words.
+#[cfg(feature = "wide-amount")]
+pub type WideClaimInput = ClaimInput<WideAmount>;
+/// The journal of a [`WideClaimInput`].
+#[cfg(feature = "wide-amount")]
+pub type WideClaimJournal = ClaimJournal<WideAmount>;
+
 // Packed journal layout (little-endian fields, fixed widths):
 // - block_number: u64 (8)      offset 0
 // - block_hash: bytes32 (32)   offset 8
@@ -67,8 +92,43 @@ pub struct ClaimJournal {
 // - nullifier: bytes32 (32)    offset 84
 // - token: address (20)        offset 116  [0u8; 20] = ETH
 //
+// The wide journal packs the amount in 32 bytes, shifting the later fields by
+// 16 and making it 152 bytes.
+//
 // NOTE: `note_index` is intentionally NOT part of the public journal.
-pub const PACKED_JOURNAL_LEN: usize = 136;
+const AMOUNT_OFFSET: usize = 48;
+const TOKEN_OFFSET: usize = token_offset(<Amount as NoteAmount>::LEN);
+pub const PACKED_JOURNAL_LEN: usize = TOKEN_OFFSET + 20;
+
+/// `(name, offset, length)` of each packed journal field, in order, as laid
+/// out above.
+pub const PACKED_JOURNAL_FIELDS: [(&str, usize, usize); 7] =
+    journal_fields(<Amount as NoteAmount>::LEN);
+
+#[cfg(feature = "wide-amount")]
+pub const WIDE_PACKED_JOURNAL_LEN: usize = token_offset(<WideAmount as NoteAmount>::LEN) + 20;
+
+/// [`PACKED_JOURNAL_FIELDS`] for the wide journal.
+#[cfg(feature = "wide-amount")]
+pub const WIDE_PACKED_JOURNAL_FIELDS: [(&str, usize, usize); 7] =
+    journal_fields(<WideAmount as NoteAmount>::LEN);
+
+const fn token_offset(amount_len: usize) -> usize {
+    AMOUNT_OFFSET + amount_len + 20 + 32
+}
+
+const fn journal_fields(amount_len: usize) -> [(&'static str, usize, usize); 7] {
+    let recipient = AMOUNT_OFFSET + amount_len;
+    [
+        ("block_number", 0, 8),
+        ("block_hash", 8, 32),
+        ("chain_id", 40, 8),
+        ("amount", AMOUNT_OFFSET, amount_len),
+        ("recipient", recipient, 20),
+        ("nullifier", recipient + 20, 32),
+        ("token", recipient + 52, 20),
+    ]
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PackedJournalError {
@@ -97,39 +157,69 @@ impl core::fmt::Display for PackedJournalError {
 
 pub fn pack_journal(journal: &ClaimJournal) -> [u8; PACKED_JOURNAL_LEN] {
     let mut out = [0u8; PACKED_JOURNAL_LEN];
-
-    out[0..8].copy_from_slice(&journal.block_number.to_le_bytes());
-    out[8..40].copy_from_slice(&journal.block_hash);
-    out[40..48].copy_from_slice(&journal.chain_id.to_le_bytes());
-    out[48..64].copy_from_slice(&journal.amount.to_le_bytes());
-    out[64..84].copy_from_slice(&journal.recipient);
-    out[84..116].copy_from_slice(&journal.nullifier);
-    out[116..136].copy_from_slice(&journal.token);
-
+    pack_journal_into(journal, &mut out);
     out
 }
 
 pub fn unpack_journal(bytes: &[u8]) -> Result<ClaimJournal, PackedJournalError> {
-    if bytes.len() != PACKED_JOURNAL_LEN {
-        return Err(PackedJournalError::invalid_length(bytes.len()));
+    unpack_journal_from(bytes)
+}
+
+/// Decode the leading `PACKED_JOURNAL_LEN` bytes and ignore any trailing
+/// extension. For read-only consumers; verification must use `unpack_journal`.
+pub fn unpack_journal_prefix(bytes: &[u8]) -> Result<ClaimJournal, PackedJournalError> {
+    match bytes.get(..PACKED_JOURNAL_LEN) {
+        Some(prefix) => unpack_journal(prefix),
+        None => Err(PackedJournalError::invalid_length(bytes.len())),
     }
+}
+
+#[cfg(feature = "wide-amount")]
+pub fn pack_wide_journal(journal: &WideClaimJournal) -> [u8; WIDE_PACKED_JOURNAL_LEN] {
+    let mut out = [0u8; WIDE_PACKED_JOURNAL_LEN];
+    pack_journal_into(journal, &mut out);
+    out
+}
+
+#[cfg(feature = "wide-amount")]
+pub fn unpack_wide_journal(bytes: &[u8]) -> Result<WideClaimJournal, PackedJournalError> {
+    unpack_journal_from(bytes)
+}
+
+/// Pack `journal` into `out`, which is exactly the journal's packed length.
+fn pack_journal_into<A: NoteAmount>(journal: &ClaimJournal<A>, out: &mut [u8]) {
+    let recipient = AMOUNT_OFFSET + A::LEN;
+    let nullifier = recipient + 20;
+    let token = nullifier + 32;
+
+    out[0..8].copy_from_slice(&journal.block_number.to_le_bytes());
+    out[8..40].copy_from_slice(&journal.block_hash);
+    out[40..AMOUNT_OFFSET].copy_from_slice(&journal.chain_id.to_le_bytes());
+    journal.amount.write_le(&mut out[AMOUNT_OFFSET..recipient]);
+    out[recipient..nullifier].copy_from_slice(&journal.recipient);
+    out[nullifier..token].copy_from_slice(&journal.nullifier);
+    out[token..].copy_from_slice(&journal.token);
+}
 
-    let block_number = u64::from_le_bytes(copy_array::<8>(&bytes[0..8]));
-    let block_hash = copy_array::<32>(&bytes[8..40]);
-    let chain_id = u64::from_le_bytes(copy_array::<8>(&bytes[40..48]));
-    let amount = u128::from_le_bytes(copy_array::<16>(&bytes[48..64]));
-    let recipient = copy_array::<20>(&bytes[64..84]);
-    let nullifier = copy_array::<32>(&bytes[84..116]);
-    let token = copy_array::<20>(&bytes[116..136]);
+fn unpack_journal_from<A: NoteAmount>(bytes: &[u8]) -> Result<ClaimJournal<A>, PackedJournalError> {
+    let recipient = AMOUNT_OFFSET + A::LEN;
+    let nullifier = recipient + 20;
+    let token = nullifier + 32;
+    if bytes.len() != token + 20 {
+        return Err(PackedJournalError {
+            expected: token + 20,
+            actual: bytes.len(),
+        });
+    }
 
     Ok(ClaimJournal {
-        block_number,
-        block_hash,
-        chain_id,
-        amount,
-        recipient,
-        nullifier,
-        token,
+        block_number: u64::from_le_bytes(copy_array::<8>(&bytes[0..8])),
+        block_hash: copy_array::<32>(&bytes[8..40]),
+        chain_id: u64::from_le_bytes(copy_array::<8>(&bytes[40..AMOUNT_OFFSET])),
+        amount: A::read_le(&bytes[AMOUNT_OFFSET..recipient]),
+        recipient: copy_array::<20>(&bytes[recipient..nullifier]),
+        nullifier: copy_array::<32>(&bytes[nullifier..token]),
+        token: copy_array::<20>(&bytes[token..]),
     })
 }
 
@@ -141,12 +231,14 @@ fn copy_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
 
 #[derive(Clone, Copy, Debug)]
 pub enum ClaimValidationError {
+    InvalidChainId,
     InvalidNoteCount,
     InvalidNoteIndex,
     InvalidInputLengths,
     InactiveNoteHasZeroAmount,
     SelectedAmountMismatch,
     RecipientHashMismatch,
+    NoteRecipientHashMismatch,
     NoteAmountExceeded,
     InvalidProofDepth,
     ProofShapeMismatch,
@@ -155,14 +247,20 @@ pub enum ClaimValidationError {
     InvalidRlpNode,
     InvalidTrieNode,
     InvalidTriePath,
+    PathDiverged,
+    PathOverrun,
+    LeafKeyIncomplete,
     MissingAccountValue,
+    AccountPresent,
     InvalidAccountValue,
+    InvalidAccountFieldLength,
     InsufficientAccountBalance,
     InvalidBlockHeaderHash,
     InvalidBlockHeaderShape,
     BlockNumberMismatch,
     StorageProofFailed,
     StorageRootMissing,
+    MissingStorageValue,
     InvalidStorageValue,
     InvalidTokenProofDepth,
     TokenProofNodeTooLarge,
@@ -172,12 +270,14 @@ pub enum ClaimValidationError {
 impl ClaimValidationError {
     pub const fn as_str(&self) -> &'static str {
         match self {
+            Self::InvalidChainId => "chain id must be non-zero",
             Self::InvalidNoteCount => "invalid note count",
             Self::InvalidNoteIndex => "invalid note index",
             Self::InvalidInputLengths => "invalid input array lengths",
             Self::InactiveNoteHasZeroAmount => "active note amount must be non-zero",
             Self::SelectedAmountMismatch => "selected note amount does not match public amount",
             Self::RecipientHashMismatch => "selected note recipient hash mismatch",
+            Self::NoteRecipientHashMismatch => "note recipient hash does not match its recipient",
             Self::NoteAmountExceeded => "note amount exceeds protocol limit",
             Self::InvalidProofDepth => "invalid account proof depth",
             Self::ProofShapeMismatch => "account proof depth/array shape mismatch",
@@ -186,14 +286,20 @@ impl ClaimValidationError {
             Self::InvalidRlpNode => "invalid RLP node encoding",
             Self::InvalidTrieNode => "invalid trie node shape",
             Self::InvalidTriePath => "invalid trie path for target address",
+            Self::PathDiverged => "trie node path diverges from target address key",
+            Self::PathOverrun => "trie node path runs past the end of the target address key",
+            Self::LeafKeyIncomplete => "trie leaf reached before consuming the full key",
             Self::MissingAccountValue => "account value missing from trie proof",
+            Self::AccountPresent => "account exists in the state trie",
             Self::InvalidAccountValue => "invalid account value encoding",
+            Self::InvalidAccountFieldLength => "account field has the wrong length for its type",
             Self::InsufficientAccountBalance => "account balance is insufficient for note total",
             Self::InvalidBlockHeaderHash => "block header hash mismatch",
             Self::InvalidBlockHeaderShape => "invalid block header shape",
             Self::BlockNumberMismatch => "block header number mismatch",
             Self::StorageProofFailed => "storage proof verification failed",
             Self::StorageRootMissing => "storage root missing from account",
+            Self::MissingStorageValue => "storage value missing from trie proof",
             Self::InvalidStorageValue => "invalid storage value encoding",
             Self::InvalidTokenProofDepth => "invalid token proof depth",
             Self::TokenProofNodeTooLarge => "token proof node exceeds max byte length",
@@ -202,11 +308,110 @@ impl ClaimValidationError {
     }
 }
 
+impl From<RlpError> for ClaimValidationError {
+    fn from(_: RlpError) -> Self {
+        Self::InvalidRlpNode
+    }
+}
+
+/// The trie proofs a claim walks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofKind {
+    Account,
+    TokenAccount,
+    TokenStorage,
+}
+
+impl ProofKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Account => "account proof",
+            Self::TokenAccount => "token account proof",
+            Self::TokenStorage => "token storage proof",
+        }
+    }
+}
+
+/// The proof node a trie walk stopped at: `node` (1-based) of `node_count`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofNodePosition {
+    pub proof: ProofKind,
+    pub node: usize,
+    pub node_count: usize,
+}
+
+/// A [`ClaimValidationError`] with the proof node it was raised at, when it
+/// came from walking a trie proof.
+#[derive(Clone, Copy, Debug)]
+pub struct ClaimFailure {
+    pub error: ClaimValidationError,
+    pub at: Option<ProofNodePosition>,
+}
+
+impl core::fmt::Display for ClaimFailure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.error.as_str())?;
+        if let Some(at) = self.at {
+            write!(
+                f,
+                " at {} node {}/{}",
+                at.proof.as_str(),
+                at.node,
+                at.node_count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate a claim and compute its journal.
+///
+/// When an input breaks several rules, the first failing group below decides
+/// the error, so callers see a stable one:
+/// 1. input shape: chain id, note count, note index, array lengths;
+/// 2. the selected note: amount, then recipient hash;
+/// 3. note amounts: any zero note, then any note over the limit, then the total;
+/// 4. account proof shape, block header, then the balance proof.
 pub fn evaluate_claim(input: &ClaimInput) -> Result<ClaimJournal, ClaimValidationError> {
+    evaluate_claim_with_capacity::<MAX_NOTES>(input)
+}
+
+/// [`evaluate_claim`], reporting which proof node a trie proof failed at.
+/// Meant for host-side diagnostics; the guest only needs the error.
+pub fn evaluate_claim_detailed(input: &ClaimInput) -> Result<ClaimJournal, ClaimFailure> {
+    let mut at = None;
+    evaluate_claim_at::<Amount, MAX_NOTES>(input, &mut at)
+        .map_err(|error| ClaimFailure { error, at })
+}
+
+/// [`evaluate_claim`] for deposits of up to `CAP` notes.
+pub fn evaluate_claim_with_capacity<const CAP: usize>(
+    input: &ClaimInput,
+) -> Result<ClaimJournal, ClaimValidationError> {
+    evaluate_claim_at::<Amount, CAP>(input, &mut None)
+}
+
+/// [`evaluate_claim`] for a claim with 32-byte amounts.
+#[cfg(feature = "wide-amount")]
+pub fn evaluate_wide_claim(
+    input: &WideClaimInput,
+) -> Result<WideClaimJournal, ClaimValidationError> {
+    evaluate_claim_at::<WideAmount, MAX_NOTES>(input, &mut None)
+}
+
+/// Evaluate a claim, recording in `at` the proof node a failing trie walk
+/// stopped at.
+fn evaluate_claim_at<A: NoteAmount, const CAP: usize>(
+    input: &ClaimInput<A>,
+    at: &mut Option<ProofNodePosition>,
+) -> Result<ClaimJournal<A>, ClaimValidationError> {
     let note_count = input.note_count as usize;
     let note_index = input.note_index as usize;
 
-    if note_count == 0 || note_count > MAX_NOTES {
+    if input.chain_id == 0 {
+        return Err(ClaimValidationError::InvalidChainId);
+    }
+    if note_count == 0 || note_count > CAP {
         return Err(ClaimValidationError::InvalidNoteCount);
     }
     if note_index >= note_count {
@@ -242,9 +447,9 @@ pub fn evaluate_claim(input: &ClaimInput) -> Result<ClaimJournal, ClaimValidatio
         }
     }
 
-    let notes_hash = compute_notes_hash(note_count, &input.amounts, &input.recipient_hashes)?;
+    let notes_hash = notes_hash::<A, CAP>(note_count, &input.amounts, &input.recipient_hashes)?;
     let target_address = derive_target_address(&input.secret, input.chain_id, &notes_hash);
-    let state_root = parse_state_root_from_block_header(
+    let state_root = parse_state_root_from_block_header::<EthereumHeaderLayout>(
         &input.block_hash,
         input.block_number,
         &input.block_header_rlp,
@@ -256,9 +461,10 @@ pub fn evaluate_claim(input: &ClaimInput) -> Result<ClaimJournal, ClaimValidatio
             &target_address,
             &input.proof_nodes,
             total_amount,
+            at,
         )?,
         Some(token_input) => {
-            verify_erc20_balance(&state_root, &target_address, token_input, total_amount)?
+            verify_erc20_balance(&state_root, &target_address, token_input, total_amount, at)?
         }
     };
 
@@ -275,36 +481,60 @@ pub fn evaluate_claim(input: &ClaimInput) -> Result<ClaimJournal, ClaimValidatio
     })
 }
 
-fn validate_note_amounts(
+/// Evaluate a claim after re-deriving every note's recipient hash.
+///
+/// `evaluate_claim` only checks the selected note's hash; the others feed the
+/// notes hash as supplied. Callers that know all recipients can use this to
+/// reject inputs whose non-selected hashes were not derived from them.
+pub fn evaluate_claim_strict(
+    input: &ClaimInput,
+    recipients: &[[u8; 20]],
+) -> Result<ClaimJournal, ClaimValidationError> {
+    let note_count = input.note_count as usize;
+    if note_count > MAX_NOTES
+        || recipients.len() != note_count
+        || input.recipient_hashes.len() < note_count
+    {
+        return Err(ClaimValidationError::InvalidInputLengths);
+    }
+    for (recipient, hash) in recipients.iter().zip(&input.recipient_hashes) {
+        if compute_recipient_hash(recipient) != *hash {
+            return Err(ClaimValidationError::NoteRecipientHashMismatch);
+        }
+    }
+    evaluate_claim(input)
+}
+
+/// Check every active note amount and return their total. Each rule is
+/// checked across all notes before the next, so the error does not depend on
+/// which note breaks it.
+fn validate_note_amounts<A: NoteAmount>(
     note_count: usize,
-    amounts: &[u128],
+    amounts: &[A],
     is_erc20: bool,
-) -> Result<u128, ClaimValidationError> {
-    let mut total_amount: u128 = 0;
-    for i in 0..note_count {
-        let amt = amounts[i];
-        if amt == 0 {
-            return Err(ClaimValidationError::InactiveNoteHasZeroAmount);
-        }
-        if !is_erc20 && amt > MAX_NOTE_WEI {
-            return Err(ClaimValidationError::NoteAmountExceeded);
-        }
-        total_amount = total_amount
-            .checked_add(amt)
-            .ok_or(ClaimValidationError::NoteAmountExceeded)?;
+) -> Result<[u8; 32], ClaimValidationError> {
+    let amounts = &amounts[..note_count];
+    if amounts.iter().any(|amt| amount_to_word(amt) == [0u8; 32]) {
+        return Err(ClaimValidationError::InactiveNoteHasZeroAmount);
+    }
+    let max_note = u128_to_word(MAX_NOTE_WEI);
+    if !is_erc20 && amounts.iter().any(|amt| amount_to_word(amt) > max_note) {
+        return Err(ClaimValidationError::NoteAmountExceeded);
     }
-    Ok(total_amount)
+    A::checked_sum(amounts).ok_or(ClaimValidationError::NoteAmountExceeded)
 }
 
 fn verify_eth_balance(
     state_root: &[u8; 32],
     target_address: &[u8; 20],
     proof_nodes: &[Vec<u8>],
-    total_amount: u128,
+    total_amount: [u8; 32],
+    at: &mut Option<ProofNodePosition>,
 ) -> Result<[u8; 20], ClaimValidationError> {
-    let account_balance =
-        verify_account_proof_and_get_field(state_root, target_address, proof_nodes, 1)?;
-    if !balance_gte_total(&account_balance, total_amount) {
+    let account_balance = walk_proof(ProofKind::Account, proof_nodes, at, |node| {
+        walk_account_proof(state_root, target_address, proof_nodes, 1, node)
+    })?;
+    if account_balance < total_amount {
         return Err(ClaimValidationError::InsufficientAccountBalance);
     }
     Ok([0u8; 20])
@@ -325,7 +555,8 @@ fn verify_erc20_balance(
     state_root: &[u8; 32],
     target_address: &[u8; 20],
     token_input: &TokenClaimInput,
-    total_amount: u128,
+    total_amount: [u8; 32],
+    at: &mut Option<ProofNodePosition>,
 ) -> Result<[u8; 20], ClaimValidationError> {
     for node in &token_input.token_account_proof_nodes {
         if node.len() > MAX_NODE_BYTES {
@@ -343,28 +574,40 @@ fn verify_erc20_balance(
         return Err(ClaimValidationError::StorageKeyMismatch);
     }
 
-    let storage_root = verify_account_proof_and_get_field(
-        state_root,
-        &token_input.token_address,
-        &token_input.token_account_proof_nodes,
-        2,
-    )?;
+    let account_nodes = &token_input.token_account_proof_nodes;
+    let storage_root = walk_proof(ProofKind::TokenAccount, account_nodes, at, |node| {
+        walk_account_proof(
+            state_root,
+            &token_input.token_address,
+            account_nodes,
+            2,
+            node,
+        )
+    })?;
     if storage_root == [0u8; 32] {
         return Err(ClaimValidationError::StorageRootMissing);
     }
 
-    let token_balance = verify_storage_proof_and_get_value(
-        &storage_root,
-        &token_input.balance_storage_key,
-        &token_input.balance_storage_proof_nodes,
-    )?;
-    if !balance_gte_total(&token_balance, total_amount) {
+    let storage_nodes = &token_input.balance_storage_proof_nodes;
+    let token_balance = walk_proof(ProofKind::TokenStorage, storage_nodes, at, |node| {
+        walk_storage_proof(
+            &storage_root,
+            &token_input.balance_storage_key,
+            storage_nodes,
+            node,
+        )
+    })?;
+    if token_balance < total_amount {
         return Err(ClaimValidationError::InsufficientAccountBalance);
     }
     Ok(token_input.token_address)
 }
 
 pub fn compute_recipient_hash(recipient: &[u8; 20]) -> [u8; 32] {
+    compute_recipient_hash_with_suite::<ClaimHashSuite>(recipient)
+}
+
+pub fn compute_recipient_hash_with_suite<H: HashSuite>(recipient: &[u8; 20]) -> [u8; 32] {
     let mut input = [0u8; 64];
     input[..32].copy_from_slice(&pad_magic_label(MAGIC_RECIPIENT));
 
@@ -372,22 +615,50 @@ pub fn compute_recipient_hash(recipient: &[u8; 20]) -> [u8; 32] {
     padded[12..].copy_from_slice(recipient);
     input[32..].copy_from_slice(&padded);
 
-    sha256(&input)
+    H::hash(&input)
 }
 
 pub fn compute_notes_hash(
     note_count: usize,
-    amounts: &[u128],
+    amounts: &[Amount],
     recipient_hashes: &[[u8; 32]],
 ) -> Result<[u8; 32], ClaimValidationError> {
-    if amounts.len() < note_count || recipient_hashes.len() < note_count || note_count > MAX_NOTES {
+    compute_notes_hash_with_capacity::<MAX_NOTES>(note_count, amounts, recipient_hashes)
+}
+
+/// Hash of the first `note_count` notes, zero-padded to `CAP` notes.
+pub fn compute_notes_hash_with_capacity<const CAP: usize>(
+    note_count: usize,
+    amounts: &[Amount],
+    recipient_hashes: &[[u8; 32]],
+) -> Result<[u8; 32], ClaimValidationError> {
+    notes_hash::<Amount, CAP>(note_count, amounts, recipient_hashes)
+}
+
+/// [`compute_notes_hash`] for 32-byte amounts. A `u128` amount hashes to the
+/// same word either way, so both agree on amounts that fit in a `u128`.
+#[cfg(feature = "wide-amount")]
+pub fn compute_wide_notes_hash(
+    note_count: usize,
+    amounts: &[WideAmount],
+    recipient_hashes: &[[u8; 32]],
+) -> Result<[u8; 32], ClaimValidationError> {
+    notes_hash::<WideAmount, MAX_NOTES>(note_count, amounts, recipient_hashes)
+}
+
+fn notes_hash<A: NoteAmount, const CAP: usize>(
+    note_count: usize,
+    amounts: &[A],
+    recipient_hashes: &[[u8; 32]],
+) -> Result<[u8; 32], ClaimValidationError> {
+    if amounts.len() < note_count || recipient_hashes.len() < note_count || note_count > CAP {
         return Err(ClaimValidationError::InvalidInputLengths);
     }
 
-    let mut buf = [0u8; MAX_NOTES * 64];
+    let mut buf = alloc::vec![0u8; CAP * 64];
     for i in 0..note_count {
         let start = i * 64;
-        buf[start..start + 32].copy_from_slice(&u128_to_bytes32(amounts[i]));
+        buf[start..start + 32].copy_from_slice(&amounts[i].to_word());
         buf[start + 32..start + 64].copy_from_slice(&recipient_hashes[i]);
     }
 
@@ -395,13 +666,23 @@ pub fn compute_notes_hash(
 }
 
 pub fn derive_target_address(secret: &[u8; 32], chain_id: u64, notes_hash: &[u8; 32]) -> [u8; 20] {
+    derive_target_address_with_suite::<ClaimHashSuite>(secret, chain_id, notes_hash)
+}
+
+pub fn derive_target_address_with_suite<H: HashSuite>(
+    secret: &[u8; 32],
+    chain_id: u64,
+    notes_hash: &[u8; 32],
+) -> [u8; 20] {
     let mut input = [0u8; 128];
     input[..32].copy_from_slice(&pad_magic_label(MAGIC_ADDRESS));
     input[32..64].copy_from_slice(&u64_to_bytes32(chain_id));
     input[64..96].copy_from_slice(secret);
     input[96..128].copy_from_slice(notes_hash);
 
-    let hash = sha256(&input);
+    let hash = H::hash(&input);
+    // The input holds the secret; scrub it before the frame is reused.
+    input.zeroize();
     let mut out = [0u8; 20];
     out.copy_from_slice(&hash[12..32]);
     out
@@ -413,14 +694,73 @@ pub fn derive_nullifier(
     note_index: u32,
     notes_hash: &[u8; 32],
 ) -> [u8; 32] {
+    derive_nullifier_with_suite::<ClaimHashSuite>(secret, chain_id, note_index, notes_hash)
+}
+
+pub fn derive_nullifier_with_suite<H: HashSuite>(
+    secret: &[u8; 32],
+    chain_id: u64,
+    note_index: u32,
+    notes_hash: &[u8; 32],
+) -> [u8; 32] {
+    let mut input = nullifier_preimage(secret, chain_id, notes_hash);
+    input[96..128].copy_from_slice(&u64_to_bytes32(note_index as u64));
+
+    let nullifier = H::hash(&input);
+    input.zeroize();
+    nullifier
+}
+
+/// Nullifiers of notes `0..note_count`, in order.
+///
+/// Byte-for-byte equal to calling [`derive_nullifier`] for each index; the
+/// preimage is laid out once and only its note-index word changes.
+pub fn derive_nullifiers(
+    secret: &[u8; 32],
+    chain_id: u64,
+    notes_hash: &[u8; 32],
+    note_count: u32,
+) -> Vec<[u8; 32]> {
+    derive_nullifiers_with_suite::<ClaimHashSuite>(secret, chain_id, notes_hash, note_count)
+}
+
+pub fn derive_nullifiers_with_suite<H: HashSuite>(
+    secret: &[u8; 32],
+    chain_id: u64,
+    notes_hash: &[u8; 32],
+    note_count: u32,
+) -> Vec<[u8; 32]> {
+    let mut input = nullifier_preimage(secret, chain_id, notes_hash);
+    let nullifiers = (0..note_count)
+        .map(|i| {
+            input[96..128].copy_from_slice(&u64_to_bytes32(i as u64));
+            H::hash(&input)
+        })
+        .collect();
+    input.zeroize();
+    nullifiers
+}
+
+/// Nullifier preimage with the note-index word (bytes 96..128) left zero.
+fn nullifier_preimage(secret: &[u8; 32], chain_id: u64, notes_hash: &[u8; 32]) -> [u8; 160] {
     let mut input = [0u8; 160];
     input[..32].copy_from_slice(&pad_magic_label(MAGIC_NULLIFIER));
     input[32..64].copy_from_slice(&u64_to_bytes32(chain_id));
     input[64..96].copy_from_slice(secret);
-    input[96..128].copy_from_slice(&u64_to_bytes32(note_index as u64));
     input[128..160].copy_from_slice(notes_hash);
+    input
+}
 
-    sha256(&input)
+/// Check that a journal nullifier was derived from the given deposit secret,
+/// chain ID, note index and notes hash.
+pub fn verify_nullifier(
+    journal_nullifier: &[u8; 32],
+    secret: &[u8; 32],
+    chain_id: u64,
+    note_index: u32,
+    notes_hash: &[u8; 32],
+) -> bool {
+    derive_nullifier(secret, chain_id, note_index, notes_hash) == *journal_nullifier
 }
 
 pub fn compute_proof_commitment(nodes: &[Vec<u8>]) -> [u8; 32] {
@@ -435,1240 +775,1967 @@ pub fn compute_proof_commitment(nodes: &[Vec<u8>]) -> [u8; 32] {
     digest
 }
 
-#[cfg(test)]
-mod tests {
-    extern crate std;
+fn u64_to_bytes32(value: u64) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[24..].copy_from_slice(&value.to_be_bytes());
+    out
+}
 
-    use super::*;
-    use alloc::vec;
+fn pad_magic_label(label: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let n = core::cmp::min(32, label.len());
+    out[..n].copy_from_slice(&label[..n]);
+    out
+}
 
-    fn rlp_encode_bytes(raw: &[u8]) -> Vec<u8> {
-        if raw.len() == 1 && raw[0] <= 0x7f {
-            return vec![raw[0]];
-        }
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    h.update(data);
+    let out = h.finalize();
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&out);
+    digest
+}
 
-        if raw.len() <= 55 {
-            let mut out = Vec::with_capacity(1 + raw.len());
-            out.push(0x80u8 + raw.len() as u8);
-            out.extend_from_slice(raw);
-            return out;
-        }
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(data);
+    let mut out = [0u8; 32];
+    keccak.finalize(&mut out);
+    out
+}
 
-        let len_bytes = usize_to_be_bytes(raw.len());
-        let mut out = Vec::with_capacity(1 + len_bytes.len() + raw.len());
-        out.push(0xb7u8 + len_bytes.len() as u8);
-        out.extend_from_slice(&len_bytes);
-        out.extend_from_slice(raw);
-        out
-    }
+/// Positions of the fields a claim reads from an RLP block header list.
+pub trait HeaderLayout {
+    /// Field counts of the header shapes this layout accepts.
+    const FIELD_COUNTS: &'static [usize];
+    const STATE_ROOT_INDEX: usize;
+    const NUMBER_INDEX: usize;
+    const TIMESTAMP_INDEX: usize;
+}
 
-    fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
-        let payload_len: usize = items.iter().map(|it| it.len()).sum();
-        let mut payload = Vec::with_capacity(payload_len);
-        for it in items {
-            payload.extend_from_slice(it);
-        }
+/// The standard Ethereum header layout, also used by Taiko.
+pub struct EthereumHeaderLayout;
 
-        if payload.len() <= 55 {
-            let mut out = Vec::with_capacity(1 + payload.len());
-            out.push(0xc0u8 + payload.len() as u8);
-            out.extend_from_slice(&payload);
-            return out;
-        }
+impl HeaderLayout for EthereumHeaderLayout {
+    /// London (16), Shanghai (+withdrawalsRoot, 17), Cancun (+blobGasUsed,
+    /// excessBlobGas, parentBeaconBlockRoot, 20) and Prague (+requestsHash, 21).
+    const FIELD_COUNTS: &'static [usize] = &[16, 17, 20, 21];
+    const STATE_ROOT_INDEX: usize = 3;
+    const NUMBER_INDEX: usize = 8;
+    const TIMESTAMP_INDEX: usize = 11;
+}
 
-        let len_bytes = usize_to_be_bytes(payload.len());
-        let mut out = Vec::with_capacity(1 + len_bytes.len() + payload.len());
-        out.push(0xf7u8 + len_bytes.len() as u8);
-        out.extend_from_slice(&len_bytes);
-        out.extend_from_slice(&payload);
-        out
-    }
+/// Extract the timestamp from an RLP-encoded block header.
+pub fn block_header_timestamp<L: HeaderLayout>(
+    block_header_rlp: &[u8],
+) -> Result<u64, ClaimValidationError> {
+    block_header_u64_field::<L>(block_header_rlp, L::TIMESTAMP_INDEX)
+}
 
-    fn usize_to_be_bytes(mut value: usize) -> Vec<u8> {
-        if value == 0 {
-            return vec![0u8];
-        }
-        let mut out = Vec::new();
-        while value > 0 {
-            out.push((value & 0xff) as u8);
-            value >>= 8;
-        }
-        out.reverse();
-        out
-    }
+/// Extract the block number from an RLP-encoded block header.
+pub fn block_header_number<L: HeaderLayout>(
+    block_header_rlp: &[u8],
+) -> Result<u64, ClaimValidationError> {
+    block_header_u64_field::<L>(block_header_rlp, L::NUMBER_INDEX)
+}
 
-    fn u64_to_min_be_bytes(value: u64) -> Vec<u8> {
-        if value == 0 {
-            return Vec::new();
-        }
-        let buf = value.to_be_bytes();
-        let first = buf.iter().position(|b| *b != 0).unwrap_or(buf.len());
-        buf[first..].to_vec()
-    }
-
-    fn make_block_header_rlp(block_number: u64, state_root: [u8; 32]) -> Vec<u8> {
-        let fields = vec![
-            rlp_encode_bytes(&[0x11u8; 32]),                      // parentHash
-            rlp_encode_bytes(&[0x22u8; 32]),                      // sha3Uncles
-            rlp_encode_bytes(&[0x33u8; 20]),                      // miner
-            rlp_encode_bytes(&state_root),                        // stateRoot
-            rlp_encode_bytes(&[0x44u8; 32]),                      // transactionsRoot
-            rlp_encode_bytes(&[0x55u8; 32]),                      // receiptsRoot
-            rlp_encode_bytes(&[0u8; 256]),                        // logsBloom
-            rlp_encode_bytes(&[]),                                // difficulty
-            rlp_encode_bytes(&u64_to_min_be_bytes(block_number)), // number
-            rlp_encode_bytes(&[0x01]),                            // gasLimit
-            rlp_encode_bytes(&[]),                                // gasUsed
-            rlp_encode_bytes(&[0x02]),                            // timestamp
-            rlp_encode_bytes(&[]),                                // extraData
-            rlp_encode_bytes(&[0x66u8; 32]),                      // mixHash
-            rlp_encode_bytes(&[0x77u8; 8]),                       // nonce
-            rlp_encode_bytes(&[0x01]),                            // baseFeePerGas
-            rlp_encode_bytes(&[0x88u8; 32]),                      // withdrawalsRoot
-        ];
-        rlp_encode_list(&fields)
+/// Extract the state root from an RLP-encoded block header.
+pub fn block_header_state_root<L: HeaderLayout>(
+    block_header_rlp: &[u8],
+) -> Result<[u8; 32], ClaimValidationError> {
+    let fields = decode_block_header_fields::<L>(block_header_rlp)?;
+    match fields.get(L::STATE_ROOT_INDEX) {
+        Some(state_root) if state_root.len() == 32 => Ok(to_32(state_root)),
+        _ => Err(ClaimValidationError::InvalidBlockHeaderShape),
     }
+}
 
-    fn nibbles_to_compact_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
-        let is_odd = (nibbles.len() % 2) == 1;
-        let flags = (if is_leaf { 0x2 } else { 0x0 }) | (if is_odd { 0x1 } else { 0x0 });
+fn block_header_u64_field<L: HeaderLayout>(
+    block_header_rlp: &[u8],
+    index: usize,
+) -> Result<u64, ClaimValidationError> {
+    let fields = decode_block_header_fields::<L>(block_header_rlp)?;
+    let field = fields
+        .get(index)
+        .ok_or(ClaimValidationError::InvalidBlockHeaderShape)?;
+    parse_u64_from_rlp_quantity(field).ok_or(ClaimValidationError::InvalidBlockHeaderShape)
+}
 
-        let mut out = Vec::new();
-        if is_odd {
-            out.push((flags << 4) | (nibbles[0] & 0x0f));
-            for pair in nibbles[1..].chunks(2) {
-                out.push((pair[0] << 4) | (pair[1] & 0x0f));
-            }
-        } else {
-            out.push(flags << 4);
-            for pair in nibbles.chunks(2) {
-                out.push((pair[0] << 4) | (pair[1] & 0x0f));
-            }
-        }
-        out
+/// Decode the header's fields, rejecting any shape the layout does not know.
+fn decode_block_header_fields<L: HeaderLayout>(
+    block_header_rlp: &[u8],
+) -> Result<Vec<&[u8]>, ClaimValidationError> {
+    let fields = decode_rlp_list_payload_items(block_header_rlp)?;
+    if !L::FIELD_COUNTS.contains(&fields.len()) {
+        return Err(ClaimValidationError::InvalidBlockHeaderShape);
     }
+    Ok(fields)
+}
 
-    #[test]
-    fn nullifier_includes_note_index() {
-        let secret = [7u8; 32];
-        let chain_id = 167013u64;
-        let notes_hash = [0xabu8; 32];
-
-        let n0 = derive_nullifier(&secret, chain_id, 0, &notes_hash);
-        let n1 = derive_nullifier(&secret, chain_id, 1, &notes_hash);
-
-        assert_ne!(n0, n1);
+fn parse_state_root_from_block_header<L: HeaderLayout>(
+    expected_block_hash: &[u8; 32],
+    expected_block_number: u64,
+    block_header_rlp: &[u8],
+) -> Result<[u8; 32], ClaimValidationError> {
+    if keccak256(block_header_rlp) != *expected_block_hash {
+        return Err(ClaimValidationError::InvalidBlockHeaderHash);
     }
 
-    #[test]
-    fn nullifier_differs_for_different_notes_hash() {
-        let secret = [7u8; 32];
-        let chain_id = 167013u64;
-        let notes_hash_a = [0xabu8; 32];
-        let notes_hash_b = [0xcdu8; 32];
-
-        let n_a = derive_nullifier(&secret, chain_id, 0, &notes_hash_a);
-        let n_b = derive_nullifier(&secret, chain_id, 0, &notes_hash_b);
-
-        assert_ne!(n_a, n_b);
+    let fields = decode_block_header_fields::<L>(block_header_rlp)?;
+    let (Some(state_root), Some(number)) =
+        (fields.get(L::STATE_ROOT_INDEX), fields.get(L::NUMBER_INDEX))
+    else {
+        return Err(ClaimValidationError::InvalidBlockHeaderShape);
+    };
+    if state_root.len() != 32 {
+        return Err(ClaimValidationError::InvalidBlockHeaderShape);
+    }
+    let block_number =
+        parse_u64_from_rlp_quantity(number).ok_or(ClaimValidationError::InvalidBlockHeaderShape)?;
+    if block_number != expected_block_number {
+        return Err(ClaimValidationError::BlockNumberMismatch);
     }
 
-    #[test]
-    fn decode_rlp_item_handles_single_byte_and_empty_string() {
-        let item = decode_rlp_item(&[0x7f], 0).unwrap();
-        assert!(!item.is_list);
-        assert_eq!(item.payload_offset, 0);
-        assert_eq!(item.payload_len, 1);
-        assert_eq!(item.total_len, 1);
+    Ok(to_32(state_root))
+}
 
-        // 0x80 encodes an empty string.
-        let item = decode_rlp_item(&[0x80], 0).unwrap();
-        assert!(!item.is_list);
-        assert_eq!(item.payload_offset, 1);
-        assert_eq!(item.payload_len, 0);
-        assert_eq!(item.total_len, 1);
+fn parse_u64_from_rlp_quantity(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() > 8 {
+        return None;
     }
 
-    #[test]
-    fn decode_rlp_list_payload_items_returns_full_rlp_bytes_for_nested_list_items() {
-        // [[0x01]] is a list whose only element is another list (inline trie node).
-        // The full RLP encoding of the inner list is returned, not an error.
-        let inner = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
-        let outer = rlp_encode_list(&[inner.clone()]);
-        let items = decode_rlp_list_payload_items(&outer).unwrap();
-        assert_eq!(items.len(), 1);
-        assert_eq!(items[0], inner.as_slice());
+    if bytes.len() > 1 && bytes[0] == 0 {
+        return None;
     }
 
-    #[test]
-    fn decode_compact_nibbles_roundtrip_even_leaf_and_odd_extension() {
-        let even = vec![0x0, 0x1, 0x2, 0x3];
-        let encoded = nibbles_to_compact_path(&even, true);
-        let (is_leaf, decoded) = decode_compact_nibbles(&encoded).unwrap();
-        assert!(is_leaf);
-        assert_eq!(decoded, even);
+    let mut out = 0u64;
+    for b in bytes {
+        out = out.checked_mul(256)?;
+        out = out.checked_add(*b as u64)?;
+    }
+    Some(out)
+}
 
-        let odd = vec![0xa, 0xb, 0xc];
-        let encoded = nibbles_to_compact_path(&odd, false);
-        let (is_leaf, decoded) = decode_compact_nibbles(&encoded).unwrap();
-        assert!(!is_leaf);
-        assert_eq!(decoded, odd);
+/// Run a trie walk that counts the proof nodes it consumes, and record where
+/// it stopped in `at` if it fails.
+fn walk_proof<T>(
+    proof: ProofKind,
+    proof_nodes: &[Vec<u8>],
+    at: &mut Option<ProofNodePosition>,
+    walk: impl FnOnce(&mut usize) -> Result<T, ClaimValidationError>,
+) -> Result<T, ClaimValidationError> {
+    let mut node = 0usize;
+    let result = walk(&mut node);
+    if result.is_err() && node > 0 {
+        *at = Some(ProofNodePosition {
+            proof,
+            node,
+            node_count: proof_nodes.len(),
+        });
     }
+    result
+}
 
-    #[test]
-    fn parse_state_root_from_block_header_accepts_matching_block_number() {
-        let state_root = [0xaau8; 32];
-        let block_number = 4_739_555u64;
-        let header = make_block_header_rlp(block_number, state_root);
-        let block_hash = keccak256(&header);
+fn verify_account_proof_and_get_field(
+    state_root: &[u8; 32],
+    target_address: &[u8; 20],
+    proof_nodes: &[Vec<u8>],
+    field_index: usize,
+) -> Result<[u8; 32], ClaimValidationError> {
+    walk_account_proof(state_root, target_address, proof_nodes, field_index, &mut 0)
+}
 
-        let parsed =
-            parse_state_root_from_block_header(&block_hash, block_number, &header).unwrap();
-        assert_eq!(parsed, state_root);
-    }
+/// Walk an account proof and return one field of the account. `node` tracks
+/// the 1-based index of the proof node being checked.
+fn walk_account_proof(
+    state_root: &[u8; 32],
+    target_address: &[u8; 20],
+    proof_nodes: &[Vec<u8>],
+    field_index: usize,
+    node: &mut usize,
+) -> Result<[u8; 32], ClaimValidationError> {
+    let key_hash = keccak256(target_address);
+    let key_nibbles = hash_to_nibbles(&key_hash);
 
-    #[test]
-    fn parse_state_root_from_block_header_rejects_block_number_mismatch() {
-        let state_root = [0xbbu8; 32];
-        let block_number = 4_739_555u64;
-        let header = make_block_header_rlp(block_number, state_root);
-        let block_hash = keccak256(&header);
+    // Node bytes, references and values all borrow from `proof_nodes` (inline
+    // nodes are slices of their parent), so the walk copies no node data.
+    let mut key_index = 0usize;
+    let mut expected_ref: Option<&[u8]> = None;
+    let mut account_rlp: Option<&[u8]> = None;
+    let mut pending_inline: Option<&[u8]> = None;
+    let mut path_nibbles = Vec::with_capacity(key_nibbles.len());
+    let mut proof_idx = 0usize;
 
-        let err =
-            parse_state_root_from_block_header(&block_hash, block_number + 1, &header).unwrap_err();
-        assert!(matches!(err, ClaimValidationError::BlockNumberMismatch));
-    }
+    loop {
+        // Source the current node from a pending inline ref or the next proof_nodes entry.
+        // Inline nodes are authenticated by being embedded in an already-verified parent,
+        // so they skip the reference check.
+        let (node_bytes, needs_ref_check) = match pending_inline.take() {
+            Some(inline) => (inline, false),
+            None => {
+                if proof_idx >= proof_nodes.len() {
+                    break;
+                }
+                let b = proof_nodes[proof_idx].as_slice();
+                proof_idx += 1;
+                *node = proof_idx;
+                (b, true)
+            }
+        };
 
-    #[test]
-    fn node_matches_reference_supports_hashed_and_inlined_children() {
-        let node = b"some rlp node bytes".to_vec();
+        if needs_ref_check {
+            if proof_idx == 1 {
+                if keccak256(node_bytes) != *state_root {
+                    return Err(ClaimValidationError::InvalidNodeReference);
+                }
+            } else {
+                let r = expected_ref.ok_or(ClaimValidationError::InvalidTriePath)?;
+                if !node_matches_reference(node_bytes, r) {
+                    return Err(ClaimValidationError::InvalidNodeReference);
+                }
+            }
+            expected_ref = None;
+        }
 
-        let digest = keccak256(&node);
-        assert!(node_matches_reference(&node, &digest));
+        let (elements, len) = trie_node_items(node_bytes)?;
+        match len {
+            17 => {
+                if key_index == key_nibbles.len() {
+                    let value = elements[16];
+                    if value.is_empty() {
+                        return Err(ClaimValidationError::MissingAccountValue);
+                    }
+                    account_rlp = Some(value);
+                    if proof_idx != proof_nodes.len() || pending_inline.is_some() {
+                        return Err(ClaimValidationError::InvalidTriePath);
+                    }
+                    break;
+                }
 
-        let mut wrong = digest;
-        wrong[0] ^= 1;
-        assert!(!node_matches_reference(&node, &wrong));
+                let next_ref = elements[key_nibbles[key_index] as usize];
+                if next_ref.is_empty() {
+                    return Err(ClaimValidationError::MissingAccountValue);
+                }
+                if is_inline_node(next_ref) {
+                    pending_inline = Some(next_ref);
+                } else {
+                    expected_ref = Some(next_ref);
+                }
+                key_index += 1;
+            }
+            2 => {
+                let is_leaf = decode_compact_nibbles_into(elements[0], &mut path_nibbles)?;
+                if key_index + path_nibbles.len() > key_nibbles.len() {
+                    return Err(ClaimValidationError::PathOverrun);
+                }
+                if key_nibbles[key_index..key_index + path_nibbles.len()] != path_nibbles[..] {
+                    return Err(ClaimValidationError::PathDiverged);
+                }
+                key_index += path_nibbles.len();
 
-        // Inline reference is a literal byte-equality check.
-        assert!(node_matches_reference(&node, &node));
-        assert!(!node_matches_reference(&node, b"other"));
+                if is_leaf {
+                    if key_index != key_nibbles.len() {
+                        return Err(ClaimValidationError::LeafKeyIncomplete);
+                    }
+                    let value = elements[1];
+                    if value.is_empty() {
+                        return Err(ClaimValidationError::MissingAccountValue);
+                    }
+                    account_rlp = Some(value);
+                    if proof_idx != proof_nodes.len() || pending_inline.is_some() {
+                        return Err(ClaimValidationError::InvalidTriePath);
+                    }
+                    break;
+                }
+
+                let next_ref = elements[1];
+                if next_ref.is_empty() {
+                    return Err(ClaimValidationError::InvalidTriePath);
+                }
+                if is_inline_node(next_ref) {
+                    pending_inline = Some(next_ref);
+                } else {
+                    expected_ref = Some(next_ref);
+                }
+            }
+            _ => return Err(ClaimValidationError::InvalidTrieNode),
+        }
     }
 
-    #[test]
-    fn verify_account_proof_accepts_single_leaf_root_and_extracts_balance() {
-        let target_address = [0x11u8; 20];
-        let key_hash = keccak256(&target_address);
-        let key_nibbles = hash_to_nibbles(&key_hash);
+    let account = account_rlp.ok_or(ClaimValidationError::MissingAccountValue)?;
+    decode_account_field(account, field_index)
+}
 
-        // Leaf path commits the entire key (this models a trie with a single key at the root).
-        let path = nibbles_to_compact_path(&key_nibbles, true);
+fn node_matches_reference(node: &[u8], reference: &[u8]) -> bool {
+    match reference.len() {
+        0 => false,
+        32 => keccak256(node) == to_32(reference),
+        _ => node == reference,
+    }
+}
 
-        // Account RLP: [nonce, balance, storageRoot, codeHash]
-        let nonce = rlp_encode_bytes(&[]); // 0
-        let balance_raw = [0x01u8, 0x02, 0x03, 0x04, 0x05];
-        let balance = rlp_encode_bytes(&balance_raw);
-        let storage_root = rlp_encode_bytes(&[0x22u8; 32]);
-        let code_hash = rlp_encode_bytes(&[0x33u8; 32]);
-        let account_rlp = rlp_encode_list(&[nonce, balance, storage_root, code_hash]);
+/// Items of a trie node, and how many there are: 17 for a branch, 2 for an
+/// extension or leaf. Any other count is returned as-is for the caller to
+/// reject, after the whole node has been checked as RLP.
+fn trie_node_items(node: &[u8]) -> Result<([&[u8]; 17], usize), RlpError> {
+    let mut items = [&[][..]; 17];
+    let mut len = 0;
+    for item in rlp_list_items(node) {
+        let item = item?;
+        if let Some(slot) = items.get_mut(len) {
+            *slot = item;
+        }
+        len += 1;
+    }
+    Ok((items, len))
+}
 
-        // MPT leaf node RLP: [path, accountRlpBytes]
-        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
-        let state_root = keccak256(&leaf_node);
+fn is_inline_node(bytes: &[u8]) -> bool {
+    // In Ethereum's MPT, a child reference is either a 32-byte keccak256 hash or an
+    // inline RLP node (the node is small enough to embed directly, so it's always < 32
+    // bytes). A 32-byte hash must never be treated as an inline node, even when its
+    // first byte happens to be >= 0xc0.
+    !bytes.is_empty() && bytes.len() < 32 && bytes[0] >= 0xc0
+}
 
-        let balance_32 =
-            verify_account_proof_and_get_field(&state_root, &target_address, &[leaf_node], 1)
-                .unwrap();
+fn decode_account_field(
+    account_rlp: &[u8],
+    field_index: usize,
+) -> Result<[u8; 32], ClaimValidationError> {
+    let fields = decode_rlp_list_payload_items(account_rlp)?;
+    if fields.len() != 4 || field_index >= 4 {
+        return Err(ClaimValidationError::InvalidAccountValue);
+    }
 
-        let mut expected = [0u8; 32];
-        expected[32 - balance_raw.len()..].copy_from_slice(&balance_raw);
-        assert_eq!(balance_32, expected);
+    // [nonce (uint64), balance (uint256), storageRoot, codeHash]
+    let [nonce, balance, storage_root, code_hash] = [fields[0], fields[1], fields[2], fields[3]];
+    if nonce.len() > 8 || balance.len() > 32 || storage_root.len() != 32 || code_hash.len() != 32 {
+        return Err(ClaimValidationError::InvalidAccountFieldLength);
     }
 
-    #[test]
-    fn verify_account_proof_rejects_state_root_mismatch() {
-        let target_address = [0x11u8; 20];
-        let key_hash = keccak256(&target_address);
-        let key_nibbles = hash_to_nibbles(&key_hash);
-        let path = nibbles_to_compact_path(&key_nibbles, true);
+    let field_raw = fields[field_index];
 
-        let account_rlp = rlp_encode_list(&[
-            rlp_encode_bytes(&[]),
-            rlp_encode_bytes(&[0x01]),
-            rlp_encode_bytes(&[0x22u8; 32]),
-            rlp_encode_bytes(&[0x33u8; 32]),
-        ]);
-        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
+    let mut out = [0u8; 32];
+    out[32 - field_raw.len()..].copy_from_slice(field_raw);
+    Ok(out)
+}
 
-        let wrong_root = [0x99u8; 32];
-        let err = verify_account_proof_and_get_field(&wrong_root, &target_address, &[leaf_node], 1)
-            .unwrap_err();
-        assert!(matches!(err, ClaimValidationError::InvalidNodeReference));
-    }
+/// Check that `proof_nodes` prove `target_address` has no account under
+/// `state_root`: the walk must end at an empty branch slot, or at an
+/// extension or leaf whose path diverges from the key, with no nodes left.
+pub fn verify_account_absent(
+    state_root: &[u8; 32],
+    target_address: &[u8; 20],
+    proof_nodes: &[Vec<u8>],
+) -> Result<(), ClaimValidationError> {
+    let key_nibbles = hash_to_nibbles(&keccak256(target_address));
+    let mut key_index = 0usize;
+    let mut reference: &[u8] = state_root;
+    let mut pending_inline: Option<&[u8]> = None;
+    let mut path = Vec::with_capacity(key_nibbles.len());
+    let mut proof_idx = 0usize;
 
-    #[test]
-    fn verify_account_proof_rejects_trie_path_mismatch() {
-        let target_address = [0x11u8; 20];
-        let key_hash = keccak256(&target_address);
-        let mut key_nibbles = hash_to_nibbles(&key_hash);
-        key_nibbles[0] ^= 1; // corrupt the path
-        let path = nibbles_to_compact_path(&key_nibbles, true);
+    loop {
+        let node_bytes = match pending_inline.take() {
+            Some(inline) => inline,
+            None => {
+                let node = proof_nodes
+                    .get(proof_idx)
+                    .ok_or(ClaimValidationError::InvalidTriePath)?;
+                if !node_matches_reference(node, reference) {
+                    return Err(ClaimValidationError::InvalidNodeReference);
+                }
+                proof_idx += 1;
+                node.as_slice()
+            }
+        };
 
-        let account_rlp = rlp_encode_list(&[
-            rlp_encode_bytes(&[]),
-            rlp_encode_bytes(&[0x01]),
-            rlp_encode_bytes(&[0x22u8; 32]),
-            rlp_encode_bytes(&[0x33u8; 32]),
-        ]);
-        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
-        let state_root = keccak256(&leaf_node);
+        let (elements, len) = trie_node_items(node_bytes)?;
+        let next_ref = match len {
+            17 => {
+                // Account keys are 64 nibbles, so a branch never ends one.
+                let Some(&nibble) = key_nibbles.get(key_index) else {
+                    return Err(ClaimValidationError::InvalidTriePath);
+                };
+                key_index += 1;
+                let child = elements[nibble as usize];
+                if child.is_empty() {
+                    break;
+                }
+                child
+            }
+            2 => {
+                let is_leaf = decode_compact_nibbles_into(elements[0], &mut path)?;
+                let rest = &key_nibbles[key_index..];
+                if path.len() > rest.len() || (is_leaf && path.len() != rest.len()) {
+                    return Err(ClaimValidationError::InvalidTriePath);
+                }
+                if rest[..path.len()] != path[..] {
+                    break;
+                }
+                if is_leaf {
+                    return Err(ClaimValidationError::AccountPresent);
+                }
+                key_index += path.len();
+                if elements[1].is_empty() {
+                    return Err(ClaimValidationError::InvalidTriePath);
+                }
+                elements[1]
+            }
+            _ => return Err(ClaimValidationError::InvalidTrieNode),
+        };
+        if is_inline_node(next_ref) {
+            pending_inline = Some(next_ref);
+        } else {
+            reference = next_ref;
+        }
+    }
 
-        let err = verify_account_proof_and_get_field(&state_root, &target_address, &[leaf_node], 1)
-            .unwrap_err();
-        assert!(matches!(err, ClaimValidationError::InvalidTriePath));
+    if proof_idx != proof_nodes.len() {
+        return Err(ClaimValidationError::InvalidTriePath);
     }
+    Ok(())
+}
 
-    #[test]
-    fn verify_account_proof_traverses_branch_then_leaf_with_hashed_child_reference() {
-        let target_address = [0x11u8; 20];
-        let key_hash = keccak256(&target_address);
-        let key_nibbles = hash_to_nibbles(&key_hash);
+/// Walk an account proof from `state_root` and return the account's balance
+/// as a 32-byte big-endian word, exactly as the circuit reads it.
+pub fn verify_account_proof_and_get_balance(
+    state_root: &[u8; 32],
+    address: &[u8; 20],
+    proof_nodes: &[Vec<u8>],
+) -> Result<[u8; 32], ClaimValidationError> {
+    verify_account_proof_and_get_field(state_root, address, proof_nodes, 1)
+}
 
-        let nonce = rlp_encode_bytes(&[]);
-        let balance_raw = [0x05u8, 0x04, 0x03, 0x02, 0x01];
-        let balance = rlp_encode_bytes(&balance_raw);
-        let storage_root = rlp_encode_bytes(&[0x22u8; 32]);
-        let code_hash = rlp_encode_bytes(&[0x33u8; 32]);
-        let account_rlp = rlp_encode_list(&[nonce, balance, storage_root, code_hash]);
+/// Walk an account proof from `state_root` and return the account's
+/// `storageRoot`, the root a storage proof for that account chains off.
+pub fn verify_account_proof_and_get_storage_root(
+    state_root: &[u8; 32],
+    address: &[u8; 20],
+    proof_nodes: &[Vec<u8>],
+) -> Result<[u8; 32], ClaimValidationError> {
+    verify_account_proof_and_get_field(state_root, address, proof_nodes, 2)
+}
 
-        // Build leaf for the remaining nibbles after the branch consumed the first nibble.
-        let leaf_path = nibbles_to_compact_path(&key_nibbles[1..], true);
-        let leaf_node =
-            rlp_encode_list(&[rlp_encode_bytes(&leaf_path), rlp_encode_bytes(&account_rlp)]);
-        let leaf_hash = keccak256(&leaf_node);
+/// Walk an EIP-1186 storage proof from `storage_root` along `keccak256(slot)`
+/// and return the slot's value as a 32-byte big-endian word.
+pub fn verify_storage_proof_and_get_value(
+    storage_root: &[u8; 32],
+    slot: &[u8; 32],
+    proof_nodes: &[Vec<u8>],
+) -> Result<[u8; 32], ClaimValidationError> {
+    walk_storage_proof(storage_root, slot, proof_nodes, &mut 0)
+}
 
-        // Root branch chooses next child based on the first nibble.
-        let mut branch_items = Vec::with_capacity(17);
-        for idx in 0..16usize {
-            if idx == key_nibbles[0] as usize {
-                branch_items.push(rlp_encode_bytes(&leaf_hash));
+fn walk_storage_proof(
+    storage_root: &[u8; 32],
+    slot: &[u8; 32],
+    proof_nodes: &[Vec<u8>],
+    node: &mut usize,
+) -> Result<[u8; 32], ClaimValidationError> {
+    let key_hash = keccak256(slot);
+    let key_nibbles = hash_to_nibbles(&key_hash);
+
+    let mut key_index = 0usize;
+    let mut expected_ref: Option<&[u8]> = None;
+    let mut storage_value: Option<&[u8]> = None;
+    let mut pending_inline: Option<&[u8]> = None;
+    let mut path_nibbles = Vec::with_capacity(key_nibbles.len());
+    let mut proof_idx = 0usize;
+
+    loop {
+        let (node_bytes, needs_ref_check) = match pending_inline.take() {
+            Some(inline) => (inline, false),
+            None => {
+                if proof_idx >= proof_nodes.len() {
+                    break;
+                }
+                let b = proof_nodes[proof_idx].as_slice();
+                proof_idx += 1;
+                *node = proof_idx;
+                (b, true)
+            }
+        };
+
+        if needs_ref_check {
+            if proof_idx == 1 {
+                if keccak256(node_bytes) != *storage_root {
+                    return Err(ClaimValidationError::StorageProofFailed);
+                }
             } else {
-                branch_items.push(rlp_encode_bytes(&[]));
+                let r = expected_ref.ok_or(ClaimValidationError::StorageProofFailed)?;
+                if !node_matches_reference(node_bytes, r) {
+                    return Err(ClaimValidationError::StorageProofFailed);
+                }
             }
+            expected_ref = None;
         }
-        branch_items.push(rlp_encode_bytes(&[])); // branch value slot (unused)
-
-        let branch_node = rlp_encode_list(&branch_items);
-        let state_root = keccak256(&branch_node);
 
-        let balance_32 = verify_account_proof_and_get_field(
-            &state_root,
-            &target_address,
-            &[branch_node, leaf_node],
-            1,
-        )
-        .unwrap();
+        let (elements, len) =
+            trie_node_items(node_bytes).map_err(|_| ClaimValidationError::StorageProofFailed)?;
+        match len {
+            17 => {
+                if key_index == key_nibbles.len() {
+                    let value = elements[16];
+                    if value.is_empty() {
+                        return Err(ClaimValidationError::MissingStorageValue);
+                    }
+                    storage_value = Some(value);
+                    break;
+                }
 
-        let mut expected = [0u8; 32];
-        expected[32 - balance_raw.len()..].copy_from_slice(&balance_raw);
-        assert_eq!(balance_32, expected);
-    }
+                let next_ref = elements[key_nibbles[key_index] as usize];
+                if next_ref.is_empty() {
+                    return Err(ClaimValidationError::MissingStorageValue);
+                }
+                if is_inline_node(next_ref) {
+                    pending_inline = Some(next_ref);
+                } else {
+                    expected_ref = Some(next_ref);
+                }
+                key_index += 1;
+            }
+            2 => {
+                let is_leaf = decode_compact_nibbles_into(elements[0], &mut path_nibbles)
+                    .map_err(|_| ClaimValidationError::StorageProofFailed)?;
+                if key_index + path_nibbles.len() > key_nibbles.len() {
+                    return Err(ClaimValidationError::StorageProofFailed);
+                }
+                if key_nibbles[key_index..key_index + path_nibbles.len()] != path_nibbles[..] {
+                    return Err(ClaimValidationError::StorageProofFailed);
+                }
+                key_index += path_nibbles.len();
 
-    #[test]
-    fn decode_rlp_list_payload_items_accepts_inline_list_items_and_returns_full_rlp_bytes() {
-        // A branch node where slot 0 holds an inline trie node (an RLP list) rather than
-        // a 32-byte hash or empty string. The function must return the full list bytes.
-        let inline = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
+                if is_leaf {
+                    if key_index != key_nibbles.len() {
+                        return Err(ClaimValidationError::StorageProofFailed);
+                    }
+                    let value = elements[1];
+                    if value.is_empty() {
+                        return Err(ClaimValidationError::MissingStorageValue);
+                    }
+                    storage_value = Some(value);
+                    break;
+                }
 
-        let mut items: Vec<Vec<u8>> = (0..16)
-            .map(|i| {
-                if i == 0 {
-                    inline.clone()
+                let next_ref = elements[1];
+                if next_ref.is_empty() {
+                    return Err(ClaimValidationError::StorageProofFailed);
+                }
+                if is_inline_node(next_ref) {
+                    pending_inline = Some(next_ref);
                 } else {
-                    rlp_encode_bytes(&[])
+                    expected_ref = Some(next_ref);
                 }
-            })
-            .collect();
-        items.push(rlp_encode_bytes(&[]));
-        let branch = rlp_encode_list(&items);
-
-        let slots = decode_rlp_list_payload_items(&branch).unwrap();
-        assert_eq!(slots.len(), 17);
-        assert_eq!(slots[0], inline.as_slice());
-        assert!(slots[1].is_empty());
+            }
+            _ => return Err(ClaimValidationError::StorageProofFailed),
+        }
     }
 
-    #[test]
-    fn verify_account_proof_traverses_through_inline_branch_node() {
-        // Trie layout:
-        //   extension (covers nibbles 0..62, hashed) →
-        //   parent branch (nibble 63 → inline terminal branch) →
-        //   [inline] terminal branch (key exhausted, account value at slot 16)
-        //
-        // The terminal branch is 23 bytes (< 32) so it embeds inline in the parent.
-        // proof_nodes contains only the extension and the parent branch; the terminal
-        // branch is embedded inside the parent and processed in-place by the circuit.
-        let target_address = [0x55u8; 20];
-        let key_hash = keccak256(&target_address);
-        let key_nibbles = hash_to_nibbles(&key_hash);
+    let rlp_encoded = storage_value.ok_or(ClaimValidationError::MissingStorageValue)?;
+    // Storage trie values are RLP-encoded scalars. The trie leaf stores
+    // RLP(raw_value), and decode_rlp_list_payload_items strips the outer
+    // list encoding but leaves the inner RLP string encoding intact.
+    // Decode the RLP string to get the raw big-endian uint256 bytes.
+    let raw = decode_rlp_scalar(rlp_encoded)?;
+    if raw.len() > 32 {
+        return Err(ClaimValidationError::InvalidStorageValue);
+    }
+    let mut out = [0u8; 32];
+    out[32 - raw.len()..].copy_from_slice(raw);
+    Ok(out)
+}
 
-        // Minimal account: [nonce=0, balance=1, storageRoot=empty, codeHash=empty]
-        let account_rlp = rlp_encode_list(&[
-            rlp_encode_bytes(&[]),
-            rlp_encode_bytes(&[0x01]),
-            rlp_encode_bytes(&[]),
-            rlp_encode_bytes(&[]),
-        ]);
+fn hash_to_nibbles(hash: &[u8; 32]) -> [u8; 64] {
+    let mut out = [0u8; 64];
+    let mut idx = 0usize;
+    for b in hash {
+        out[idx] = b >> 4;
+        out[idx + 1] = b & 0x0f;
+        idx += 2;
+    }
+    out
+}
 
-        // Terminal branch: 16 empty nibble slots + account value at slot 16.
-        // Payload = 16 × 1 + 6 = 22 bytes → total = 23 bytes (inline-capable).
-        let mut terminal_items: Vec<Vec<u8>> = (0..16).map(|_| rlp_encode_bytes(&[])).collect();
-        terminal_items.push(rlp_encode_bytes(&account_rlp));
-        let terminal_branch = rlp_encode_list(&terminal_items);
-        assert!(
-            terminal_branch.len() < 32,
-            "terminal branch must be short enough to embed inline"
-        );
+#[cfg(test)]
+fn decode_compact_nibbles(encoded: &[u8]) -> Result<(bool, Vec<u8>), ClaimValidationError> {
+    let mut nibbles = Vec::new();
+    let is_leaf = decode_compact_nibbles_into(encoded, &mut nibbles)?;
+    Ok((is_leaf, nibbles))
+}
 
-        // Parent branch: slot key_nibbles[63] holds the inline terminal branch directly.
-        let mut parent_items: Vec<Vec<u8>> = (0..16)
-            .map(|i| {
-                if i == key_nibbles[63] as usize {
-                    terminal_branch.clone()
-                } else {
-                    rlp_encode_bytes(&[])
-                }
-            })
-            .collect();
-        parent_items.push(rlp_encode_bytes(&[]));
-        let parent_branch = rlp_encode_list(&parent_items);
-        let parent_hash = keccak256(&parent_branch);
+/// Decode a compact-encoded (hex-prefix) path into `nibbles`, replacing its
+/// contents so one buffer serves a whole proof walk. Returns whether the
+/// path ends a leaf.
+fn decode_compact_nibbles_into(
+    encoded: &[u8],
+    nibbles: &mut Vec<u8>,
+) -> Result<bool, ClaimValidationError> {
+    nibbles.clear();
+    if encoded.is_empty() {
+        return Err(ClaimValidationError::InvalidTriePath);
+    }
 
-        // Extension: covers nibbles 0..62 (63 nibbles), points to parent branch by hash.
-        let compact_path = nibbles_to_compact_path(&key_nibbles[..63], false);
-        let extension_node = rlp_encode_list(&[
-            rlp_encode_bytes(&compact_path),
-            rlp_encode_bytes(&parent_hash),
-        ]);
-        let state_root = keccak256(&extension_node);
+    let flag = encoded[0] >> 4;
+    if flag > 3 {
+        return Err(ClaimValidationError::InvalidTriePath);
+    }
+    let is_leaf = (flag & 0x2) != 0;
+    let is_odd = (flag & 0x1) != 0;
 
-        let balance_32 = verify_account_proof_and_get_field(
-            &state_root,
-            &target_address,
-            &[extension_node, parent_branch],
-            1,
-        )
-        .unwrap();
+    if !is_odd && (encoded[0] & 0x0f) != 0 {
+        return Err(ClaimValidationError::InvalidTriePath);
+    }
 
-        let mut expected = [0u8; 32];
-        expected[31] = 0x01;
-        assert_eq!(balance_32, expected);
+    if is_odd {
+        nibbles.push(encoded[0] & 0x0f);
     }
 
-    #[test]
-    fn is_inline_node_does_not_misidentify_32_byte_hash_reference() {
-        // A 32-byte hash whose first byte is >= 0xc0 must NOT be treated as an inline
-        // node. Before this fix, is_inline_node only checked bytes[0] >= 0xc0, causing
-        // 32-byte hash references to be misidentified as inline nodes → InvalidRlpNode.
-        let hash_c0 = [0xc0u8; 32];
-        assert!(
-            !is_inline_node(&hash_c0),
-            "32-byte hash with 0xc0 prefix is not inline"
-        );
-        let hash_ff = [0xffu8; 32];
-        assert!(
-            !is_inline_node(&hash_ff),
-            "32-byte hash with 0xff prefix is not inline"
-        );
+    let start = 1; // always skip byte 0 (flag byte); odd path already pushed its low nibble above
+    for byte in encoded.iter().skip(start) {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
 
-        // A genuine inline node (< 32 bytes, first byte >= 0xc0) IS inline.
-        let tiny_list = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
-        assert!(tiny_list.len() < 32);
-        assert!(is_inline_node(&tiny_list), "short RLP list is inline");
+    Ok(is_leaf)
+}
 
-        // Empty slice is never inline.
-        assert!(!is_inline_node(&[]), "empty is not inline");
+fn decode_rlp_scalar(input: &[u8]) -> Result<&[u8], ClaimValidationError> {
+    if input.is_empty() {
+        return Ok(&[]);
+    }
+    let item = decode_rlp_item(input, 0).map_err(|_| ClaimValidationError::InvalidStorageValue)?;
+    if item.is_list || item.total_len != input.len() {
+        return Err(ClaimValidationError::InvalidStorageValue);
     }
+    Ok(&input[item.payload_offset..item.payload_offset + item.payload_len])
+}
 
-    #[test]
-    fn verify_account_proof_handles_branch_child_hash_with_high_first_byte() {
-        // Regression: a branch node child that is a 32-byte hash whose first byte is
-        // 0xc0 or above must be followed as a hash reference (not treated as inline).
-        // We build a minimal trie: branch → leaf.  We iterate target addresses until
-        // we find one where the leaf node hashes to something with first byte >= 0xc0.
-        let account_rlp = rlp_encode_list(&[
-            rlp_encode_bytes(&[]),
-            rlp_encode_bytes(&[0x05]),
-            rlp_encode_bytes(&[0xaau8; 32]),
-            rlp_encode_bytes(&[0xbbu8; 32]),
-        ]);
-
-        // Find an address whose derived key produces a leaf hash with first byte >= 0xc0.
-        let mut found = None;
-        for seed in 0u8..=255 {
-            let addr = [seed; 20];
-            let key_hash = keccak256(&addr);
-            let key_nibbles = hash_to_nibbles(&key_hash);
-            let compact_path = nibbles_to_compact_path(&key_nibbles[1..], true);
-            let leaf_node = rlp_encode_list(&[
-                rlp_encode_bytes(&compact_path),
-                rlp_encode_bytes(&account_rlp),
-            ]);
-            let leaf_hash = keccak256(&leaf_node);
-            if leaf_hash[0] >= 0xc0 {
-                found = Some((addr, key_nibbles, leaf_node, leaf_hash));
-                break;
-            }
-        }
-
-        let (addr, key_nibbles, leaf_node, leaf_hash) =
-            found.expect("should find an address with leaf_hash[0] >= 0xc0 within 256 seeds");
+fn to_32(input: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(input);
+    out
+}
 
-        let mut branch_items: Vec<Vec<u8>> = (0..16)
-            .map(|i| {
-                if i == key_nibbles[0] as usize {
-                    rlp_encode_bytes(&leaf_hash)
-                } else {
-                    rlp_encode_bytes(&[])
-                }
-            })
-            .collect();
-        branch_items.push(rlp_encode_bytes(&[]));
-        let branch_node = rlp_encode_list(&branch_items);
-        let state_root = keccak256(&branch_node);
+#[cfg(test)]
+mod tests {
+    extern crate std;
 
-        let balance =
-            verify_account_proof_and_get_field(&state_root, &addr, &[branch_node, leaf_node], 1)
-                .expect("proof should succeed when hash-reference first byte >= 0xc0");
+    use super::*;
+    use crate::test_support::*;
+    use alloc::{string::ToString, vec};
 
-        let mut expected = [0u8; 32];
-        expected[31] = 0x05;
-        assert_eq!(balance, expected);
+    #[test]
+    fn evaluate_claim_rejects_zero_chain_id() {
+        let recipient = [0x11u8; 20];
+        let input = ClaimInput {
+            block_number: 1,
+            block_hash: [0u8; 32],
+            chain_id: 0,
+            note_index: 0,
+            amount: amount_from_u128(1),
+            recipient,
+            secret: [7u8; 32],
+            note_count: 1,
+            amounts: vec![amount_from_u128(1)],
+            recipient_hashes: vec![compute_recipient_hash(&recipient)],
+            block_header_rlp: Vec::new(),
+            proof_depth: 1,
+            proof_nodes: vec![Vec::new()],
+            token: None,
+        };
+        assert!(matches!(
+            evaluate_claim(&input),
+            Err(ClaimValidationError::InvalidChainId)
+        ));
     }
 
     #[test]
-    fn verify_account_proof_rejects_when_parent_child_reference_hash_mismatches() {
-        let target_address = [0x11u8; 20];
-        let key_hash = keccak256(&target_address);
-        let key_nibbles = hash_to_nibbles(&key_hash);
-
-        let account_rlp = rlp_encode_list(&[
-            rlp_encode_bytes(&[]),
-            rlp_encode_bytes(&[0x01]),
-            rlp_encode_bytes(&[0x22u8; 32]),
-            rlp_encode_bytes(&[0x33u8; 32]),
-        ]);
-
-        let leaf_path = nibbles_to_compact_path(&key_nibbles[1..], true);
-        let leaf_node =
-            rlp_encode_list(&[rlp_encode_bytes(&leaf_path), rlp_encode_bytes(&account_rlp)]);
-        let mut leaf_hash = keccak256(&leaf_node);
-        leaf_hash[0] ^= 1;
-
-        let mut branch_items = Vec::with_capacity(17);
-        for idx in 0..16usize {
-            if idx == key_nibbles[0] as usize {
-                branch_items.push(rlp_encode_bytes(&leaf_hash));
-            } else {
-                branch_items.push(rlp_encode_bytes(&[]));
-            }
+    fn zero_note_takes_precedence_over_oversized_note() {
+        let recipient = [0x11u8; 20];
+        let hashes = vec![compute_recipient_hash(&recipient); 2];
+        for (note_index, amounts) in [(0, [MAX_NOTE_WEI + 1, 0]), (1, [0, MAX_NOTE_WEI + 1])] {
+            let input = ClaimInput {
+                block_number: 1,
+                block_hash: [0u8; 32],
+                chain_id: 167013,
+                note_index,
+                amount: amount_from_u128(MAX_NOTE_WEI + 1),
+                recipient,
+                secret: [7u8; 32],
+                note_count: 2,
+                amounts: amounts.map(amount_from_u128).to_vec(),
+                recipient_hashes: hashes.clone(),
+                block_header_rlp: Vec::new(),
+                proof_depth: 1,
+                proof_nodes: vec![Vec::new()],
+                token: None,
+            };
+            assert!(matches!(
+                evaluate_claim(&input),
+                Err(ClaimValidationError::InactiveNoteHasZeroAmount)
+            ));
         }
-        branch_items.push(rlp_encode_bytes(&[]));
-        let branch_node = rlp_encode_list(&branch_items);
-        let state_root = keccak256(&branch_node);
-
-        let err = verify_account_proof_and_get_field(
-            &state_root,
-            &target_address,
-            &[branch_node, leaf_node],
-            1,
-        )
-        .unwrap_err();
-        assert!(matches!(err, ClaimValidationError::InvalidNodeReference));
     }
 
     #[test]
-    fn pack_unpack_journal_roundtrip_with_zero_token() {
-        let journal = ClaimJournal {
-            block_number: 12345,
-            block_hash: [0xaau8; 32],
+    fn oversized_note_takes_precedence_over_proof_shape() {
+        let recipient = [0x11u8; 20];
+        let input = ClaimInput {
+            block_number: 1,
+            block_hash: [0u8; 32],
             chain_id: 167013,
-            amount: 1_000_000_000_000,
-            recipient: [0xbbu8; 20],
-            nullifier: [0xccu8; 32],
-            token: [0u8; 20],
+            note_index: 0,
+            amount: amount_from_u128(MAX_NOTE_WEI + 1),
+            recipient,
+            secret: [7u8; 32],
+            note_count: 1,
+            amounts: vec![amount_from_u128(MAX_NOTE_WEI + 1)],
+            recipient_hashes: vec![compute_recipient_hash(&recipient)],
+            block_header_rlp: Vec::new(),
+            proof_depth: 0,
+            proof_nodes: Vec::new(),
+            token: None,
         };
-        let packed = pack_journal(&journal);
-        assert_eq!(packed.len(), PACKED_JOURNAL_LEN);
-        let unpacked = unpack_journal(&packed).unwrap();
-        assert_eq!(unpacked.block_number, journal.block_number);
-        assert_eq!(unpacked.block_hash, journal.block_hash);
-        assert_eq!(unpacked.chain_id, journal.chain_id);
-        assert_eq!(unpacked.amount, journal.amount);
-        assert_eq!(unpacked.recipient, journal.recipient);
-        assert_eq!(unpacked.nullifier, journal.nullifier);
-        assert_eq!(unpacked.token, [0u8; 20]);
+        assert!(matches!(
+            evaluate_claim(&input),
+            Err(ClaimValidationError::NoteAmountExceeded)
+        ));
     }
 
     #[test]
-    fn pack_unpack_journal_roundtrip_with_nonzero_token() {
-        let token_addr = [
-            0xDE, 0xAD, 0xBE, 0xEF, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A,
-            0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x10,
-        ];
-        let journal = ClaimJournal {
-            block_number: 99999,
-            block_hash: [0x11u8; 32],
-            chain_id: 1,
-            amount: 5_000_000_000_000_000_000,
-            recipient: [0x22u8; 20],
-            nullifier: [0x33u8; 32],
-            token: token_addr,
+    fn evaluate_claim_strict_rejects_tampered_unselected_hash() {
+        let recipients = [[0x11u8; 20], [0x22u8; 20]];
+        let mut input = ClaimInput {
+            block_number: 1,
+            block_hash: [0u8; 32],
+            chain_id: 167013,
+            note_index: 0,
+            amount: amount_from_u128(1),
+            recipient: recipients[0],
+            secret: [7u8; 32],
+            note_count: 2,
+            amounts: vec![amount_from_u128(1), amount_from_u128(2)],
+            recipient_hashes: recipients.iter().map(compute_recipient_hash).collect(),
+            block_header_rlp: Vec::new(),
+            proof_depth: 1,
+            proof_nodes: vec![Vec::new()],
+            token: None,
         };
-        let packed = pack_journal(&journal);
-        assert_eq!(packed.len(), PACKED_JOURNAL_LEN);
-        // Verify token is at offset 116
-        assert_eq!(&packed[116..136], &token_addr);
-        let unpacked = unpack_journal(&packed).unwrap();
-        assert_eq!(unpacked.token, token_addr);
-        assert_eq!(unpacked.amount, journal.amount);
+
+        // Honest hashes pass the strict check and fail later on the empty header.
+        assert!(matches!(
+            evaluate_claim_strict(&input, &recipients),
+            Err(ClaimValidationError::InvalidBlockHeaderHash)
+        ));
+
+        input.recipient_hashes[1] = [0xeeu8; 32];
+        assert!(matches!(
+            evaluate_claim_strict(&input, &recipients),
+            Err(ClaimValidationError::NoteRecipientHashMismatch)
+        ));
+        assert!(matches!(
+            evaluate_claim_strict(&input, &recipients[..1]),
+            Err(ClaimValidationError::InvalidInputLengths)
+        ));
     }
 
     #[test]
-    fn unpack_journal_rejects_wrong_length() {
-        let short = [0u8; 100];
-        assert!(unpack_journal(&short).is_err());
-        let long = [0u8; 200];
-        assert!(unpack_journal(&long).is_err());
+    fn nullifier_includes_note_index() {
+        let secret = [7u8; 32];
+        let chain_id = 167013u64;
+        let notes_hash = [0xabu8; 32];
+
+        let n0 = derive_nullifier(&secret, chain_id, 0, &notes_hash);
+        let n1 = derive_nullifier(&secret, chain_id, 1, &notes_hash);
+
+        assert_ne!(n0, n1);
     }
 
     #[test]
-    fn verify_account_proof_extracts_storage_root_field2() {
-        // Test that field_index=2 correctly extracts storageRoot from account RLP
-        let target_address = [0x44u8; 20];
-        let key_hash = keccak256(&target_address);
-        let key_nibbles = hash_to_nibbles(&key_hash);
-
-        let path = nibbles_to_compact_path(&key_nibbles, true);
+    fn verify_nullifier_checks_derivation() {
+        let secret = [7u8; 32];
+        let chain_id = 167013u64;
+        let notes_hash = [0xabu8; 32];
+        let nullifier = derive_nullifier(&secret, chain_id, 1, &notes_hash);
 
-        let storage_root_raw = [0xAAu8; 32];
-        let account_rlp = rlp_encode_list(&[
-            rlp_encode_bytes(&[]),               // nonce
-            rlp_encode_bytes(&[0x01]),           // balance
-            rlp_encode_bytes(&storage_root_raw), // storageRoot (field[2])
-            rlp_encode_bytes(&[0xBBu8; 32]),     // codeHash
-        ]);
+        assert!(verify_nullifier(
+            &nullifier,
+            &secret,
+            chain_id,
+            1,
+            &notes_hash
+        ));
+        assert!(!verify_nullifier(
+            &nullifier,
+            &secret,
+            chain_id,
+            0,
+            &notes_hash
+        ));
+    }
 
-        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
-        let state_root = keccak256(&leaf_node);
+    #[test]
+    fn nullifier_differs_for_different_notes_hash() {
+        let secret = [7u8; 32];
+        let chain_id = 167013u64;
+        let notes_hash_a = [0xabu8; 32];
+        let notes_hash_b = [0xcdu8; 32];
 
-        let result =
-            verify_account_proof_and_get_field(&state_root, &target_address, &[leaf_node], 2)
-                .unwrap();
+        let n_a = derive_nullifier(&secret, chain_id, 0, &notes_hash_a);
+        let n_b = derive_nullifier(&secret, chain_id, 0, &notes_hash_b);
 
-        let mut expected = [0u8; 32];
-        expected.copy_from_slice(&storage_root_raw);
-        assert_eq!(result, expected);
+        assert_ne!(n_a, n_b);
     }
 
     #[test]
-    fn verify_storage_proof_decodes_rlp_encoded_value() {
-        // Storage trie values are RLP-encoded scalars. Build a minimal storage
-        // trie with a single leaf whose value is RLP(0x1bc16d674ec80000) = 2 ETH.
-        let storage_key = [0x55u8; 32];
-        let key_hash = keccak256(&storage_key);
-        let key_nibbles = hash_to_nibbles(&key_hash);
-
-        let path = nibbles_to_compact_path(&key_nibbles, true);
+    fn detailed_evaluation_reports_the_failing_proof_node() {
+        let mut input = build_single_leaf_claim_input([0x11; 32], 167_000, [0x44; 20], 1_000, 9);
+        let leaf = input.proof_nodes[0].clone();
+        input.proof_nodes.push(leaf);
+        input.proof_depth = 2;
+
+        let failure = evaluate_claim_detailed(&input).unwrap_err();
+        assert!(matches!(
+            failure.error,
+            ClaimValidationError::InvalidTriePath
+        ));
+        assert_eq!(
+            failure.at,
+            Some(ProofNodePosition {
+                proof: ProofKind::Account,
+                node: 1,
+                node_count: 2,
+            })
+        );
+        assert_eq!(
+            failure.to_string(),
+            "invalid trie path for target address at account proof node 1/2"
+        );
+        assert!(matches!(
+            evaluate_claim(&input),
+            Err(ClaimValidationError::InvalidTriePath)
+        ));
 
-        // The raw storage value (big-endian, trimmed)
-        let raw_value: Vec<u8> = vec![0x1b, 0xc1, 0x6d, 0x67, 0x4e, 0xc8, 0x00, 0x00];
+        // Errors raised before any trie walk carry no position.
+        input.chain_id = 0;
+        let failure = evaluate_claim_detailed(&input).unwrap_err();
+        assert!(failure.at.is_none());
+        assert_eq!(failure.to_string(), "chain id must be non-zero");
+    }
 
-        // In the storage trie, values are stored as RLP-encoded scalars.
-        // The leaf contains: RLP_LIST([compact_path, RLP_STRING(raw_value)])
-        let leaf_node = rlp_encode_list(&[
-            rlp_encode_bytes(&path),
-            rlp_encode_bytes(&rlp_encode_bytes(&raw_value)),
+    #[test]
+    fn absence_is_proven_by_each_kind_of_diverging_node() {
+        let target = [0x5au8; 20];
+        let key = hash_to_nibbles(&keccak256(&target));
+        let other_leaf = rlp_encode_list(&[
+            rlp_encode_bytes(&nibbles_to_compact_path(&[0x1; 63], true)),
+            rlp_encode_bytes(b"some account"),
+        ]);
+        let other_ref = rlp_encode_bytes(&keccak256(&other_leaf));
+
+        // Branch whose slot for the key's first nibble is empty.
+        let mut slots: Vec<Vec<u8>> = vec![rlp_encode_bytes(&[]); 17];
+        slots[((key[0] + 1) % 16) as usize] = other_ref.clone();
+        let branch = rlp_encode_list(&slots);
+        // Extension whose shared path diverges at the key's second nibble.
+        let extension = rlp_encode_list(&[
+            rlp_encode_bytes(&nibbles_to_compact_path(&[key[0], key[1] ^ 1], false)),
+            other_ref,
+        ]);
+        // Leaf for a key that differs from the target's in the last nibble.
+        let mut other_key = key;
+        other_key[63] ^= 1;
+        let leaf = rlp_encode_list(&[
+            rlp_encode_bytes(&nibbles_to_compact_path(&other_key, true)),
+            rlp_encode_bytes(b"some account"),
         ]);
-        let storage_root = keccak256(&leaf_node);
 
-        let result =
-            verify_storage_proof_and_get_value(&storage_root, &storage_key, &[leaf_node]).unwrap();
+        for node in [&branch, &extension, &leaf] {
+            let root = keccak256(node);
+            let proof = vec![node.clone()];
+            assert!(verify_account_absent(&root, &target, &proof).is_ok());
+
+            let trailing = vec![node.clone(), other_leaf.clone()];
+            assert!(matches!(
+                verify_account_absent(&root, &target, &trailing),
+                Err(ClaimValidationError::InvalidTriePath)
+            ));
+            assert!(matches!(
+                verify_account_absent(&[0u8; 32], &target, &proof),
+                Err(ClaimValidationError::InvalidNodeReference)
+            ));
+        }
+    }
 
-        let mut expected = [0u8; 32];
-        expected[24..].copy_from_slice(&raw_value);
-        assert_eq!(result, expected);
+    #[test]
+    fn absence_proof_rejects_paths_that_reach_or_continue_to_the_key() {
+        let target = [0x5au8; 20];
+        let key = hash_to_nibbles(&keccak256(&target));
+
+        let present = rlp_encode_list(&[
+            rlp_encode_bytes(&nibbles_to_compact_path(&key, true)),
+            rlp_encode_bytes(b"some account"),
+        ]);
+        assert!(matches!(
+            verify_account_absent(
+                &keccak256(&present),
+                &target,
+                core::slice::from_ref(&present)
+            ),
+            Err(ClaimValidationError::AccountPresent)
+        ));
+
+        // The key's slot is occupied, but the proof stops at the branch.
+        let mut slots: Vec<Vec<u8>> = vec![rlp_encode_bytes(&[]); 17];
+        slots[key[0] as usize] = rlp_encode_bytes(&keccak256(&present));
+        let branch = rlp_encode_list(&slots);
+        assert!(matches!(
+            verify_account_absent(&keccak256(&branch), &target, &[branch]),
+            Err(ClaimValidationError::InvalidTriePath)
+        ));
     }
 
     #[test]
-    fn verify_storage_proof_decodes_single_byte_value() {
-        // For values <= 0x7f, RLP encoding is just the byte itself.
-        let storage_key = [0x77u8; 32];
-        let key_hash = keccak256(&storage_key);
-        let key_nibbles = hash_to_nibbles(&key_hash);
+    fn sha256_suite_keeps_the_deployed_nullifier_derivation() {
+        let expected = [
+            0x16, 0x15, 0xb3, 0xb5, 0x61, 0x07, 0xdb, 0xb5, 0xdb, 0x70, 0xe8, 0x7f, 0x4a, 0xfb,
+            0xf4, 0x37, 0x5c, 0x88, 0x2f, 0xb5, 0x9c, 0x90, 0x7a, 0xaa, 0x4a, 0x91, 0xe8, 0x96,
+            0x50, 0xcd, 0x67, 0xa1,
+        ];
+        let nullifier = derive_nullifier_with_suite::<hash_suite::Sha256Suite>(
+            &[0x11; 32],
+            167_000,
+            1,
+            &[0x22; 32],
+        );
+        assert_eq!(nullifier, expected);
+        #[cfg(not(feature = "poseidon"))]
+        assert_eq!(
+            derive_nullifier(&[0x11; 32], 167_000, 1, &[0x22; 32]),
+            expected
+        );
+    }
 
-        let path = nibbles_to_compact_path(&key_nibbles, true);
-        let raw_value: Vec<u8> = vec![0x42];
+    /// Counts heap allocations made on the current thread.
+    mod alloc_count {
+        extern crate std;
 
-        let leaf_node = rlp_encode_list(&[
-            rlp_encode_bytes(&path),
-            rlp_encode_bytes(&rlp_encode_bytes(&raw_value)),
-        ]);
-        let storage_root = keccak256(&leaf_node);
+        use core::{
+            alloc::{GlobalAlloc, Layout},
+            cell::Cell,
+        };
+        use std::alloc::System;
 
-        let result =
-            verify_storage_proof_and_get_value(&storage_root, &storage_key, &[leaf_node]).unwrap();
+        std::thread_local! {
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
 
-        let mut expected = [0u8; 32];
-        expected[31] = 0x42;
-        assert_eq!(result, expected);
-    }
-}
+        struct Counting;
 
-fn u128_to_bytes32(value: u128) -> [u8; 32] {
-    let mut out = [0u8; 32];
-    out[16..].copy_from_slice(&value.to_be_bytes());
-    out
-}
+        // SAFETY: every call is forwarded to the system allocator unchanged.
+        unsafe impl GlobalAlloc for Counting {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+                System.alloc(layout)
+            }
 
-fn u64_to_bytes32(value: u64) -> [u8; 32] {
-    let mut out = [0u8; 32];
-    out[24..].copy_from_slice(&value.to_be_bytes());
-    out
-}
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
 
-fn pad_magic_label(label: &[u8]) -> [u8; 32] {
-    let mut out = [0u8; 32];
-    let n = core::cmp::min(32, label.len());
-    out[..n].copy_from_slice(&label[..n]);
-    out
-}
+        #[global_allocator]
+        static COUNTING: Counting = Counting;
 
-fn sha256(data: &[u8]) -> [u8; 32] {
-    let mut h = Sha256::new();
-    h.update(data);
-    let out = h.finalize();
-    let mut digest = [0u8; 32];
-    digest.copy_from_slice(&out);
-    digest
-}
-
-fn keccak256(data: &[u8]) -> [u8; 32] {
-    let mut keccak = Keccak::v256();
-    keccak.update(data);
-    let mut out = [0u8; 32];
-    keccak.finalize(&mut out);
-    out
-}
-
-fn parse_state_root_from_block_header(
-    expected_block_hash: &[u8; 32],
-    expected_block_number: u64,
-    block_header_rlp: &[u8],
-) -> Result<[u8; 32], ClaimValidationError> {
-    if keccak256(block_header_rlp) != *expected_block_hash {
-        return Err(ClaimValidationError::InvalidBlockHeaderHash);
+        pub fn allocations_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+            let before = ALLOCATIONS.with(Cell::get);
+            let out = f();
+            (out, ALLOCATIONS.with(Cell::get) - before)
+        }
     }
 
-    let fields = decode_rlp_list_payload_items(block_header_rlp)?;
-    if fields.len() < 9 || fields[3].len() != 32 {
-        return Err(ClaimValidationError::InvalidBlockHeaderShape);
+    /// An account proof through `depth` branches, each referencing the next
+    /// by hash, ending at a leaf for `address` with a balance of 1.
+    fn deep_account_proof(address: &[u8; 20], depth: usize) -> ([u8; 32], Vec<Vec<u8>>) {
+        let key = hash_to_nibbles(&keccak256(address));
+        let account = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[0x01]),
+            rlp_encode_bytes(&[0xaa; 32]),
+            rlp_encode_bytes(&[0xbb; 32]),
+        ]);
+        let path = nibbles_to_compact_path(&key[depth..], true);
+        let mut nodes = vec![rlp_encode_list(&[
+            rlp_encode_bytes(&path),
+            rlp_encode_bytes(&account),
+        ])];
+        for i in (0..depth).rev() {
+            let mut items: Vec<Vec<u8>> = (0..17).map(|_| rlp_encode_bytes(&[])).collect();
+            items[key[i] as usize] = rlp_encode_bytes(&keccak256(&nodes[0]));
+            nodes.insert(0, rlp_encode_list(&items));
+        }
+        (keccak256(&nodes[0]), nodes)
     }
-    let block_number = parse_u64_from_rlp_quantity(fields[8])
-        .ok_or(ClaimValidationError::InvalidBlockHeaderShape)?;
-    if block_number != expected_block_number {
-        return Err(ClaimValidationError::BlockNumberMismatch);
+
+    #[test]
+    fn account_proof_walk_allocations_do_not_grow_with_depth() {
+        let address = [0x66u8; 20];
+        let allocations = [1, 12].map(|depth| {
+            let (root, nodes) = deep_account_proof(&address, depth);
+            let (balance, count) = alloc_count::allocations_during(|| {
+                verify_account_proof_and_get_field(&root, &address, &nodes, 1)
+            });
+            assert_eq!(balance.unwrap()[31], 1);
+            count
+        });
+        assert_eq!(allocations[0], allocations[1], "{allocations:?}");
     }
 
-    Ok(to_32(fields[3]))
-}
+    #[test]
+    fn batch_nullifiers_match_single_derivations() {
+        let (secret, notes_hash) = ([0x11; 32], [0x22; 32]);
+        for count in 1..=MAX_NOTES as u32 {
+            let single: Vec<[u8; 32]> = (0..count)
+                .map(|i| derive_nullifier(&secret, 167_000, i, &notes_hash))
+                .collect();
+            assert_eq!(
+                derive_nullifiers(&secret, 167_000, &notes_hash, count),
+                single
+            );
+        }
+        assert!(derive_nullifiers(&secret, 167_000, &notes_hash, 0).is_empty());
+    }
 
-fn parse_u64_from_rlp_quantity(bytes: &[u8]) -> Option<u64> {
-    if bytes.len() > 8 {
-        return None;
+    #[test]
+    fn scrubbing_the_secret_leaves_the_target_address_unchanged() {
+        let (secret, notes_hash) = ([0x11; 32], [0x22; 32]);
+        let mut preimage = [0u8; 128];
+        preimage[..32].copy_from_slice(&pad_magic_label(MAGIC_ADDRESS));
+        preimage[32..64].copy_from_slice(&u64_to_bytes32(167_000));
+        preimage[64..96].copy_from_slice(&secret);
+        preimage[96..].copy_from_slice(&notes_hash);
+
+        let address = derive_target_address_with_suite::<hash_suite::Sha256Suite>(
+            &secret,
+            167_000,
+            &notes_hash,
+        );
+        assert_eq!(address[..], sha256(&preimage)[12..]);
     }
 
-    if bytes.len() > 1 && bytes[0] == 0 {
-        return None;
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn poseidon_suite_derives_different_values() {
+        use hash_suite::{PoseidonSuite, Sha256Suite};
+
+        let (secret, notes_hash) = ([0x11u8; 32], [0x22u8; 32]);
+        let poseidon = derive_nullifier_with_suite::<PoseidonSuite>(&secret, 1, 0, &notes_hash);
+        assert_ne!(
+            poseidon,
+            derive_nullifier_with_suite::<Sha256Suite>(&secret, 1, 0, &notes_hash)
+        );
+        assert_eq!(poseidon, derive_nullifier(&secret, 1, 0, &notes_hash));
+        assert_ne!(
+            poseidon,
+            derive_nullifier_with_suite::<PoseidonSuite>(&secret, 1, 1, &notes_hash)
+        );
+        assert_ne!(
+            derive_target_address_with_suite::<PoseidonSuite>(&secret, 1, &notes_hash),
+            derive_target_address_with_suite::<Sha256Suite>(&secret, 1, &notes_hash)
+        );
     }
 
-    let mut out = 0u64;
-    for b in bytes {
-        out = out.checked_mul(256)?;
-        out = out.checked_add(*b as u64)?;
+    #[test]
+    fn decode_compact_nibbles_roundtrip_even_leaf_and_odd_extension() {
+        let even = vec![0x0, 0x1, 0x2, 0x3];
+        let encoded = nibbles_to_compact_path(&even, true);
+        let (is_leaf, decoded) = decode_compact_nibbles(&encoded).unwrap();
+        assert!(is_leaf);
+        assert_eq!(decoded, even);
+
+        let odd = vec![0xa, 0xb, 0xc];
+        let encoded = nibbles_to_compact_path(&odd, false);
+        let (is_leaf, decoded) = decode_compact_nibbles(&encoded).unwrap();
+        assert!(!is_leaf);
+        assert_eq!(decoded, odd);
     }
-    Some(out)
-}
 
-#[derive(Clone, Copy)]
-struct RlpItem {
-    is_list: bool,
-    payload_offset: usize,
-    payload_len: usize,
-    total_len: usize,
-}
+    #[test]
+    fn parse_state_root_from_block_header_accepts_matching_block_number() {
+        let state_root = [0xaau8; 32];
+        let block_number = 4_739_555u64;
+        let header = make_block_header_rlp(block_number, state_root);
+        let block_hash = keccak256(&header);
 
-fn verify_account_proof_and_get_field(
-    state_root: &[u8; 32],
-    target_address: &[u8; 20],
-    proof_nodes: &[Vec<u8>],
-    field_index: usize,
-) -> Result<[u8; 32], ClaimValidationError> {
-    let key_hash = keccak256(target_address);
-    let key_nibbles = hash_to_nibbles(&key_hash);
+        let parsed = parse_state_root_from_block_header::<EthereumHeaderLayout>(
+            &block_hash,
+            block_number,
+            &header,
+        )
+        .unwrap();
+        assert_eq!(parsed, state_root);
+    }
 
-    let mut key_index = 0usize;
-    let mut expected_ref: Option<Vec<u8>> = None;
-    let mut account_rlp: Option<Vec<u8>> = None;
-    let mut pending_inline: Option<Vec<u8>> = None;
-    let mut proof_idx = 0usize;
+    #[test]
+    fn parse_state_root_from_block_header_rejects_block_number_mismatch() {
+        let state_root = [0xbbu8; 32];
+        let block_number = 4_739_555u64;
+        let header = make_block_header_rlp(block_number, state_root);
+        let block_hash = keccak256(&header);
 
-    loop {
-        // Source the current node from a pending inline ref or the next proof_nodes entry.
-        // Inline nodes are authenticated by being embedded in an already-verified parent,
-        // so they skip the reference check.
-        let (node_bytes, needs_ref_check) = match pending_inline.take() {
-            Some(inline) => (inline, false),
-            None => {
-                if proof_idx >= proof_nodes.len() {
-                    break;
-                }
-                let b = proof_nodes[proof_idx].clone();
-                proof_idx += 1;
-                (b, true)
-            }
-        };
+        let err = parse_state_root_from_block_header::<EthereumHeaderLayout>(
+            &block_hash,
+            block_number + 1,
+            &header,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ClaimValidationError::BlockNumberMismatch));
+    }
 
-        if needs_ref_check {
-            if proof_idx == 1 {
-                if keccak256(&node_bytes) != *state_root {
-                    return Err(ClaimValidationError::InvalidNodeReference);
-                }
+    #[test]
+    fn ten_note_deposit_hashes_consistently_at_larger_capacity() {
+        let secret = [0x42u8; 32];
+        let notes: Vec<([u8; 20], u128)> = (1..=10u8)
+            .map(|i| ([i; 20], 1_000_000_000_000 * i as u128))
+            .collect();
+        let input = build_leaf_claim_input_with_capacity::<16>(secret, 167013, &notes, 7, 1);
+
+        // Host derivation of the notes hash, as deposit creation does it.
+        let amounts: Vec<Amount> = notes.iter().map(|n| amount_from_u128(n.1)).collect();
+        let hashes: Vec<[u8; 32]> = notes.iter().map(|n| compute_recipient_hash(&n.0)).collect();
+        let notes_hash = compute_notes_hash_with_capacity::<16>(10, &amounts, &hashes).unwrap();
+
+        let journal = evaluate_claim_with_capacity::<16>(&input).unwrap();
+        assert_eq!(journal.amount, amount_from_u128(notes[7].1));
+        assert_eq!(
+            journal.nullifier,
+            derive_nullifier(&secret, 167013, 7, &notes_hash)
+        );
+        assert!(matches!(
+            evaluate_claim(&input),
+            Err(ClaimValidationError::InvalidNoteCount)
+        ));
+
+        // The default capacity keeps its padding, so existing hashes are unchanged.
+        assert_eq!(
+            compute_notes_hash(2, &amounts, &hashes).unwrap(),
+            compute_notes_hash_with_capacity::<MAX_NOTES>(2, &amounts, &hashes).unwrap()
+        );
+        assert_ne!(
+            compute_notes_hash(2, &amounts, &hashes).unwrap(),
+            compute_notes_hash_with_capacity::<16>(2, &amounts, &hashes).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_state_root_accepts_only_known_header_shapes() {
+        let state_root = [0xabu8; 32];
+        let shanghai = make_block_header_rlp(4_739_555, state_root);
+        let shanghai_fields = decode_rlp_list_payload_items(&shanghai).unwrap();
+
+        for (count, known) in [
+            (15, false),
+            (16, true),
+            (17, true),
+            (18, false),
+            (20, true),
+            (21, true),
+            (22, false),
+        ] {
+            let mut fields: Vec<Vec<u8>> = shanghai_fields
+                .iter()
+                .map(|f| rlp_encode_bytes(f))
+                .collect();
+            fields.resize(count, rlp_encode_bytes(&[0x99u8; 32]));
+            let header = rlp_encode_list(&fields);
+
+            let parsed = parse_state_root_from_block_header::<EthereumHeaderLayout>(
+                &keccak256(&header),
+                4_739_555,
+                &header,
+            );
+            if known {
+                assert_eq!(parsed.unwrap(), state_root, "{count} fields");
             } else {
-                let r = expected_ref
-                    .as_ref()
-                    .ok_or(ClaimValidationError::InvalidTriePath)?;
-                if !node_matches_reference(&node_bytes, r) {
-                    return Err(ClaimValidationError::InvalidNodeReference);
-                }
+                assert!(
+                    matches!(parsed, Err(ClaimValidationError::InvalidBlockHeaderShape)),
+                    "{count} fields"
+                );
             }
-            expected_ref = None;
         }
+    }
 
-        let elements = decode_rlp_list_payload_items(&node_bytes)?;
-        match elements.len() {
-            17 => {
-                if key_index == key_nibbles.len() {
-                    let value = elements[16];
-                    if value.is_empty() {
-                        return Err(ClaimValidationError::MissingAccountValue);
-                    }
-                    account_rlp = Some(value.to_vec());
-                    if proof_idx != proof_nodes.len() || pending_inline.is_some() {
-                        return Err(ClaimValidationError::InvalidTriePath);
-                    }
-                    break;
-                }
-
-                let next_ref = elements[key_nibbles[key_index] as usize];
-                if next_ref.is_empty() {
-                    return Err(ClaimValidationError::MissingAccountValue);
-                }
-                if is_inline_node(next_ref) {
-                    pending_inline = Some(next_ref.to_vec());
-                } else {
-                    expected_ref = Some(next_ref.to_vec());
-                }
-                key_index += 1;
-            }
-            2 => {
-                let (is_leaf, path_nibbles) = decode_compact_nibbles(elements[0])?;
-                if key_index + path_nibbles.len() > key_nibbles.len() {
-                    return Err(ClaimValidationError::InvalidTriePath);
-                }
-                if key_nibbles[key_index..key_index + path_nibbles.len()] != path_nibbles[..] {
-                    return Err(ClaimValidationError::InvalidTriePath);
-                }
-                key_index += path_nibbles.len();
+    #[test]
+    fn block_header_number_and_timestamp_read_their_fields() {
+        let header = make_block_header_rlp(4_739_555, [0xaau8; 32]);
+        assert_eq!(
+            block_header_timestamp::<EthereumHeaderLayout>(&header).unwrap(),
+            2
+        );
+        assert_eq!(
+            block_header_number::<EthereumHeaderLayout>(&header).unwrap(),
+            4_739_555
+        );
 
-                if is_leaf {
-                    if key_index != key_nibbles.len() {
-                        return Err(ClaimValidationError::InvalidTriePath);
-                    }
-                    let value = elements[1];
-                    if value.is_empty() {
-                        return Err(ClaimValidationError::MissingAccountValue);
-                    }
-                    account_rlp = Some(value.to_vec());
-                    if proof_idx != proof_nodes.len() || pending_inline.is_some() {
-                        return Err(ClaimValidationError::InvalidTriePath);
-                    }
-                    break;
-                }
+        let truncated = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
+        assert!(matches!(
+            block_header_timestamp::<EthereumHeaderLayout>(&truncated),
+            Err(ClaimValidationError::InvalidBlockHeaderShape)
+        ));
+    }
 
-                let next_ref = elements[1];
-                if next_ref.is_empty() {
-                    return Err(ClaimValidationError::InvalidTriePath);
-                }
-                if is_inline_node(next_ref) {
-                    pending_inline = Some(next_ref.to_vec());
-                } else {
-                    expected_ref = Some(next_ref.to_vec());
-                }
-            }
-            _ => return Err(ClaimValidationError::InvalidTrieNode),
+    #[test]
+    fn alternate_header_layout_reads_its_own_offsets() {
+        // A hypothetical chain whose header is [number, timestamp, stateRoot, ...].
+        struct CompactLayout;
+        impl HeaderLayout for CompactLayout {
+            const FIELD_COUNTS: &'static [usize] = &[3];
+            const STATE_ROOT_INDEX: usize = 2;
+            const NUMBER_INDEX: usize = 0;
+            const TIMESTAMP_INDEX: usize = 1;
         }
-    }
 
-    let account = account_rlp.ok_or(ClaimValidationError::MissingAccountValue)?;
-    decode_account_field(&account, field_index)
-}
+        let state_root = [0xccu8; 32];
+        let header = rlp_encode_list(&[
+            rlp_encode_bytes(&u64_to_min_be_bytes(77)),
+            rlp_encode_bytes(&u64_to_min_be_bytes(1_700_000_000)),
+            rlp_encode_bytes(&state_root),
+        ]);
+        let block_hash = keccak256(&header);
 
-fn node_matches_reference(node: &[u8], reference: &[u8]) -> bool {
-    match reference.len() {
-        0 => false,
-        32 => keccak256(node) == to_32(reference),
-        _ => node == reference,
+        let parsed =
+            parse_state_root_from_block_header::<CompactLayout>(&block_hash, 77, &header).unwrap();
+        assert_eq!(parsed, state_root);
+        assert_eq!(
+            block_header_timestamp::<CompactLayout>(&header).unwrap(),
+            1_700_000_000
+        );
+
+        // The same bytes do not fit the Ethereum layout.
+        assert!(matches!(
+            parse_state_root_from_block_header::<EthereumHeaderLayout>(&block_hash, 77, &header),
+            Err(ClaimValidationError::InvalidBlockHeaderShape)
+        ));
     }
-}
 
-fn is_inline_node(bytes: &[u8]) -> bool {
-    // In Ethereum's MPT, a child reference is either a 32-byte keccak256 hash or an
-    // inline RLP node (the node is small enough to embed directly, so it's always < 32
-    // bytes). A 32-byte hash must never be treated as an inline node, even when its
-    // first byte happens to be >= 0xc0.
-    !bytes.is_empty() && bytes.len() < 32 && bytes[0] >= 0xc0
-}
+    #[test]
+    fn node_matches_reference_supports_hashed_and_inlined_children() {
+        let node = b"some rlp node bytes".to_vec();
 
-fn decode_account_field(
-    account_rlp: &[u8],
-    field_index: usize,
-) -> Result<[u8; 32], ClaimValidationError> {
-    let fields = decode_rlp_list_payload_items(account_rlp)?;
-    if fields.len() != 4 || field_index >= 4 {
-        return Err(ClaimValidationError::InvalidAccountValue);
-    }
+        let digest = keccak256(&node);
+        assert!(node_matches_reference(&node, &digest));
 
-    let field_raw = fields[field_index];
-    if field_raw.len() > 32 {
-        return Err(ClaimValidationError::InvalidAccountValue);
+        let mut wrong = digest;
+        wrong[0] ^= 1;
+        assert!(!node_matches_reference(&node, &wrong));
+
+        // Inline reference is a literal byte-equality check.
+        assert!(node_matches_reference(&node, &node));
+        assert!(!node_matches_reference(&node, b"other"));
     }
 
-    let mut out = [0u8; 32];
-    out[32 - field_raw.len()..].copy_from_slice(field_raw);
-    Ok(out)
-}
+    #[test]
+    fn verify_account_proof_accepts_single_leaf_root_and_extracts_balance() {
+        let target_address = [0x11u8; 20];
+        let key_hash = keccak256(&target_address);
+        let key_nibbles = hash_to_nibbles(&key_hash);
 
-fn verify_storage_proof_and_get_value(
-    storage_root: &[u8; 32],
-    storage_key: &[u8; 32],
-    proof_nodes: &[Vec<u8>],
-) -> Result<[u8; 32], ClaimValidationError> {
-    let key_hash = keccak256(storage_key);
-    let key_nibbles = hash_to_nibbles(&key_hash);
+        // Leaf path commits the entire key (this models a trie with a single key at the root).
+        let path = nibbles_to_compact_path(&key_nibbles, true);
 
-    let mut key_index = 0usize;
-    let mut expected_ref: Option<Vec<u8>> = None;
-    let mut storage_value: Option<Vec<u8>> = None;
-    let mut pending_inline: Option<Vec<u8>> = None;
-    let mut proof_idx = 0usize;
+        // Account RLP: [nonce, balance, storageRoot, codeHash]
+        let nonce = rlp_encode_bytes(&[]); // 0
+        let balance_raw = [0x01u8, 0x02, 0x03, 0x04, 0x05];
+        let balance = rlp_encode_bytes(&balance_raw);
+        let storage_root = rlp_encode_bytes(&[0x22u8; 32]);
+        let code_hash = rlp_encode_bytes(&[0x33u8; 32]);
+        let account_rlp = rlp_encode_list(&[nonce, balance, storage_root, code_hash]);
 
-    loop {
-        let (node_bytes, needs_ref_check) = match pending_inline.take() {
-            Some(inline) => (inline, false),
-            None => {
-                if proof_idx >= proof_nodes.len() {
-                    break;
-                }
-                let b = proof_nodes[proof_idx].clone();
-                proof_idx += 1;
-                (b, true)
-            }
-        };
+        // MPT leaf node RLP: [path, accountRlpBytes]
+        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
+        let state_root = keccak256(&leaf_node);
 
-        if needs_ref_check {
-            if proof_idx == 1 {
-                if keccak256(&node_bytes) != *storage_root {
-                    return Err(ClaimValidationError::StorageProofFailed);
-                }
+        let balance_32 =
+            verify_account_proof_and_get_field(&state_root, &target_address, &[leaf_node], 1)
+                .unwrap();
+
+        let mut expected = [0u8; 32];
+        expected[32 - balance_raw.len()..].copy_from_slice(&balance_raw);
+        assert_eq!(balance_32, expected);
+    }
+
+    #[test]
+    fn verify_account_proof_rejects_state_root_mismatch() {
+        let target_address = [0x11u8; 20];
+        let key_hash = keccak256(&target_address);
+        let key_nibbles = hash_to_nibbles(&key_hash);
+        let path = nibbles_to_compact_path(&key_nibbles, true);
+
+        let account_rlp = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[0x01]),
+            rlp_encode_bytes(&[0x22u8; 32]),
+            rlp_encode_bytes(&[0x33u8; 32]),
+        ]);
+        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
+
+        let wrong_root = [0x99u8; 32];
+        let err = verify_account_proof_and_get_field(&wrong_root, &target_address, &[leaf_node], 1)
+            .unwrap_err();
+        assert!(matches!(err, ClaimValidationError::InvalidNodeReference));
+    }
+
+    /// Verify a proof for `[0x11; 20]` against a single-leaf trie whose leaf
+    /// path is built from `edit`ed key nibbles.
+    fn single_leaf_path_error(edit: impl FnOnce(&mut Vec<u8>)) -> ClaimValidationError {
+        let target_address = [0x11u8; 20];
+        let mut path_nibbles = hash_to_nibbles(&keccak256(&target_address)).to_vec();
+        edit(&mut path_nibbles);
+        let path = nibbles_to_compact_path(&path_nibbles, true);
+
+        let account_rlp = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[0x01]),
+            rlp_encode_bytes(&[0x22u8; 32]),
+            rlp_encode_bytes(&[0x33u8; 32]),
+        ]);
+        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
+        let state_root = keccak256(&leaf_node);
+
+        verify_account_proof_and_get_field(&state_root, &target_address, &[leaf_node], 1)
+            .unwrap_err()
+    }
+
+    #[test]
+    fn verify_account_proof_rejects_trie_path_mismatch() {
+        let err = single_leaf_path_error(|nibbles| nibbles[0] ^= 1);
+        assert!(matches!(err, ClaimValidationError::PathDiverged));
+    }
+
+    #[test]
+    fn verify_account_proof_rejects_path_past_key_end() {
+        let err = single_leaf_path_error(|nibbles| nibbles.push(0));
+        assert!(matches!(err, ClaimValidationError::PathOverrun));
+    }
+
+    #[test]
+    fn verify_account_proof_rejects_leaf_short_of_key_end() {
+        let err = single_leaf_path_error(|nibbles| {
+            nibbles.pop();
+        });
+        assert!(matches!(err, ClaimValidationError::LeafKeyIncomplete));
+    }
+
+    #[test]
+    fn verify_account_proof_traverses_branch_then_leaf_with_hashed_child_reference() {
+        let target_address = [0x11u8; 20];
+        let key_hash = keccak256(&target_address);
+        let key_nibbles = hash_to_nibbles(&key_hash);
+
+        let nonce = rlp_encode_bytes(&[]);
+        let balance_raw = [0x05u8, 0x04, 0x03, 0x02, 0x01];
+        let balance = rlp_encode_bytes(&balance_raw);
+        let storage_root = rlp_encode_bytes(&[0x22u8; 32]);
+        let code_hash = rlp_encode_bytes(&[0x33u8; 32]);
+        let account_rlp = rlp_encode_list(&[nonce, balance, storage_root, code_hash]);
+
+        // Build leaf for the remaining nibbles after the branch consumed the first nibble.
+        let leaf_path = nibbles_to_compact_path(&key_nibbles[1..], true);
+        let leaf_node =
+            rlp_encode_list(&[rlp_encode_bytes(&leaf_path), rlp_encode_bytes(&account_rlp)]);
+        let leaf_hash = keccak256(&leaf_node);
+
+        // Root branch chooses next child based on the first nibble.
+        let mut branch_items = Vec::with_capacity(17);
+        for idx in 0..16usize {
+            if idx == key_nibbles[0] as usize {
+                branch_items.push(rlp_encode_bytes(&leaf_hash));
             } else {
-                let r = expected_ref
-                    .as_ref()
-                    .ok_or(ClaimValidationError::StorageProofFailed)?;
-                if !node_matches_reference(&node_bytes, r) {
-                    return Err(ClaimValidationError::StorageProofFailed);
-                }
+                branch_items.push(rlp_encode_bytes(&[]));
             }
-            expected_ref = None;
         }
+        branch_items.push(rlp_encode_bytes(&[])); // branch value slot (unused)
 
-        let elements = decode_rlp_list_payload_items(&node_bytes)
-            .map_err(|_| ClaimValidationError::StorageProofFailed)?;
-        match elements.len() {
-            17 => {
-                if key_index == key_nibbles.len() {
-                    let value = elements[16];
-                    if value.is_empty() {
-                        return Err(ClaimValidationError::InvalidStorageValue);
-                    }
-                    storage_value = Some(value.to_vec());
-                    break;
-                }
+        let branch_node = rlp_encode_list(&branch_items);
+        let state_root = keccak256(&branch_node);
 
-                let next_ref = elements[key_nibbles[key_index] as usize];
-                if next_ref.is_empty() {
-                    return Err(ClaimValidationError::InvalidStorageValue);
+        let balance_32 = verify_account_proof_and_get_field(
+            &state_root,
+            &target_address,
+            &[branch_node, leaf_node],
+            1,
+        )
+        .unwrap();
+
+        let mut expected = [0u8; 32];
+        expected[32 - balance_raw.len()..].copy_from_slice(&balance_raw);
+        assert_eq!(balance_32, expected);
+    }
+
+    #[test]
+    fn decode_rlp_list_payload_items_accepts_inline_list_items_and_returns_full_rlp_bytes() {
+        // A branch node where slot 0 holds an inline trie node (an RLP list) rather than
+        // a 32-byte hash or empty string. The function must return the full list bytes.
+        let inline = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
+
+        let mut items: Vec<Vec<u8>> = (0..16)
+            .map(|i| {
+                if i == 0 {
+                    inline.clone()
+                } else {
+                    rlp_encode_bytes(&[])
                 }
-                if is_inline_node(next_ref) {
-                    pending_inline = Some(next_ref.to_vec());
+            })
+            .collect();
+        items.push(rlp_encode_bytes(&[]));
+        let branch = rlp_encode_list(&items);
+
+        let slots = decode_rlp_list_payload_items(&branch).unwrap();
+        assert_eq!(slots.len(), 17);
+        assert_eq!(slots[0], inline.as_slice());
+        assert!(slots[1].is_empty());
+    }
+
+    #[test]
+    fn verify_account_proof_traverses_through_inline_branch_node() {
+        // Trie layout:
+        //   extension (covers nibbles 0..62, hashed) →
+        //   parent branch (nibble 63 → inline terminal branch) →
+        //   [inline] terminal branch (key exhausted, account value at slot 16)
+        //
+        // The terminal branch is 23 bytes (< 32) so it embeds inline in the parent.
+        // proof_nodes contains only the extension and the parent branch; the terminal
+        // branch is embedded inside the parent and processed in-place by the circuit.
+        let target_address = [0x55u8; 20];
+        let key_hash = keccak256(&target_address);
+        let key_nibbles = hash_to_nibbles(&key_hash);
+
+        // Minimal account: [nonce=0, balance=1, storageRoot=empty, codeHash=empty].
+        // Real accounts are too large to inline, and this one's empty roots are
+        // rejected once it is reached, which is how the test sees the walk succeed.
+        let account_rlp = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[0x01]),
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[]),
+        ]);
+
+        // Terminal branch: 16 empty nibble slots + account value at slot 16.
+        // Payload = 16 × 1 + 6 = 22 bytes → total = 23 bytes (inline-capable).
+        let mut terminal_items: Vec<Vec<u8>> = (0..16).map(|_| rlp_encode_bytes(&[])).collect();
+        terminal_items.push(rlp_encode_bytes(&account_rlp));
+        let terminal_branch = rlp_encode_list(&terminal_items);
+        assert!(
+            terminal_branch.len() < 32,
+            "terminal branch must be short enough to embed inline"
+        );
+
+        // Parent branch: slot key_nibbles[63] holds the inline terminal branch directly.
+        let mut parent_items: Vec<Vec<u8>> = (0..16)
+            .map(|i| {
+                if i == key_nibbles[63] as usize {
+                    terminal_branch.clone()
                 } else {
-                    expected_ref = Some(next_ref.to_vec());
+                    rlp_encode_bytes(&[])
                 }
-                key_index += 1;
+            })
+            .collect();
+        parent_items.push(rlp_encode_bytes(&[]));
+        let parent_branch = rlp_encode_list(&parent_items);
+        let parent_hash = keccak256(&parent_branch);
+
+        // Extension: covers nibbles 0..62 (63 nibbles), points to parent branch by hash.
+        let compact_path = nibbles_to_compact_path(&key_nibbles[..63], false);
+        let extension_node = rlp_encode_list(&[
+            rlp_encode_bytes(&compact_path),
+            rlp_encode_bytes(&parent_hash),
+        ]);
+        let state_root = keccak256(&extension_node);
+
+        let result = verify_account_proof_and_get_field(
+            &state_root,
+            &target_address,
+            &[extension_node, parent_branch],
+            1,
+        );
+        assert!(matches!(
+            result,
+            Err(ClaimValidationError::InvalidAccountFieldLength)
+        ));
+    }
+
+    #[test]
+    fn is_inline_node_does_not_misidentify_32_byte_hash_reference() {
+        // A 32-byte hash whose first byte is >= 0xc0 must NOT be treated as an inline
+        // node. Before this fix, is_inline_node only checked bytes[0] >= 0xc0, causing
+        // 32-byte hash references to be misidentified as inline nodes → InvalidRlpNode.
+        let hash_c0 = [0xc0u8; 32];
+        assert!(
+            !is_inline_node(&hash_c0),
+            "32-byte hash with 0xc0 prefix is not inline"
+        );
+        let hash_ff = [0xffu8; 32];
+        assert!(
+            !is_inline_node(&hash_ff),
+            "32-byte hash with 0xff prefix is not inline"
+        );
+
+        // A genuine inline node (< 32 bytes, first byte >= 0xc0) IS inline.
+        let tiny_list = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
+        assert!(tiny_list.len() < 32);
+        assert!(is_inline_node(&tiny_list), "short RLP list is inline");
+
+        // Empty slice is never inline.
+        assert!(!is_inline_node(&[]), "empty is not inline");
+    }
+
+    #[test]
+    fn verify_account_proof_handles_branch_child_hash_with_high_first_byte() {
+        // Regression: a branch node child that is a 32-byte hash whose first byte is
+        // 0xc0 or above must be followed as a hash reference (not treated as inline).
+        // We build a minimal trie: branch → leaf.  We iterate target addresses until
+        // we find one where the leaf node hashes to something with first byte >= 0xc0.
+        let account_rlp = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[0x05]),
+            rlp_encode_bytes(&[0xaau8; 32]),
+            rlp_encode_bytes(&[0xbbu8; 32]),
+        ]);
+
+        // Find an address whose derived key produces a leaf hash with first byte >= 0xc0.
+        let mut found = None;
+        for seed in 0u8..=255 {
+            let addr = [seed; 20];
+            let key_hash = keccak256(&addr);
+            let key_nibbles = hash_to_nibbles(&key_hash);
+            let compact_path = nibbles_to_compact_path(&key_nibbles[1..], true);
+            let leaf_node = rlp_encode_list(&[
+                rlp_encode_bytes(&compact_path),
+                rlp_encode_bytes(&account_rlp),
+            ]);
+            let leaf_hash = keccak256(&leaf_node);
+            if leaf_hash[0] >= 0xc0 {
+                found = Some((addr, key_nibbles, leaf_node, leaf_hash));
+                break;
             }
-            2 => {
-                let (is_leaf, path_nibbles) = decode_compact_nibbles(elements[0])
-                    .map_err(|_| ClaimValidationError::StorageProofFailed)?;
-                if key_index + path_nibbles.len() > key_nibbles.len() {
-                    return Err(ClaimValidationError::StorageProofFailed);
-                }
-                if key_nibbles[key_index..key_index + path_nibbles.len()] != path_nibbles[..] {
-                    return Err(ClaimValidationError::StorageProofFailed);
-                }
-                key_index += path_nibbles.len();
+        }
 
-                if is_leaf {
-                    if key_index != key_nibbles.len() {
-                        return Err(ClaimValidationError::StorageProofFailed);
-                    }
-                    let value = elements[1];
-                    if value.is_empty() {
-                        return Err(ClaimValidationError::InvalidStorageValue);
-                    }
-                    storage_value = Some(value.to_vec());
-                    break;
+        let (addr, key_nibbles, leaf_node, leaf_hash) =
+            found.expect("should find an address with leaf_hash[0] >= 0xc0 within 256 seeds");
+
+        let mut branch_items: Vec<Vec<u8>> = (0..16)
+            .map(|i| {
+                if i == key_nibbles[0] as usize {
+                    rlp_encode_bytes(&leaf_hash)
+                } else {
+                    rlp_encode_bytes(&[])
                 }
+            })
+            .collect();
+        branch_items.push(rlp_encode_bytes(&[]));
+        let branch_node = rlp_encode_list(&branch_items);
+        let state_root = keccak256(&branch_node);
+
+        let balance =
+            verify_account_proof_and_get_field(&state_root, &addr, &[branch_node, leaf_node], 1)
+                .expect("proof should succeed when hash-reference first byte >= 0xc0");
+
+        let mut expected = [0u8; 32];
+        expected[31] = 0x05;
+        assert_eq!(balance, expected);
+    }
+
+    #[test]
+    fn verify_account_proof_rejects_when_parent_child_reference_hash_mismatches() {
+        let target_address = [0x11u8; 20];
+        let key_hash = keccak256(&target_address);
+        let key_nibbles = hash_to_nibbles(&key_hash);
+
+        let account_rlp = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[0x01]),
+            rlp_encode_bytes(&[0x22u8; 32]),
+            rlp_encode_bytes(&[0x33u8; 32]),
+        ]);
+
+        let leaf_path = nibbles_to_compact_path(&key_nibbles[1..], true);
+        let leaf_node =
+            rlp_encode_list(&[rlp_encode_bytes(&leaf_path), rlp_encode_bytes(&account_rlp)]);
+        let mut leaf_hash = keccak256(&leaf_node);
+        leaf_hash[0] ^= 1;
 
-                let next_ref = elements[1];
-                if next_ref.is_empty() {
-                    return Err(ClaimValidationError::StorageProofFailed);
-                }
-                if is_inline_node(next_ref) {
-                    pending_inline = Some(next_ref.to_vec());
-                } else {
-                    expected_ref = Some(next_ref.to_vec());
-                }
+        let mut branch_items = Vec::with_capacity(17);
+        for idx in 0..16usize {
+            if idx == key_nibbles[0] as usize {
+                branch_items.push(rlp_encode_bytes(&leaf_hash));
+            } else {
+                branch_items.push(rlp_encode_bytes(&[]));
             }
-            _ => return Err(ClaimValidationError::StorageProofFailed),
         }
-    }
+        branch_items.push(rlp_encode_bytes(&[]));
+        let branch_node = rlp_encode_list(&branch_items);
+        let state_root = keccak256(&branch_node);
 
-    let rlp_encoded = storage_value.ok_or(ClaimValidationError::InvalidStorageValue)?;
-    // Storage trie values are RLP-encoded scalars. The trie leaf stores
-    // RLP(raw_value), and decode_rlp_list_payload_items strips the outer
-    // list encoding but leaves the inner RLP string encoding intact.
-    // Decode the RLP string to get the raw big-endian uint256 bytes.
-    let raw = decode_rlp_scalar(&rlp_encoded)?;
-    if raw.len() > 32 {
-        return Err(ClaimValidationError::InvalidStorageValue);
+        let err = verify_account_proof_and_get_field(
+            &state_root,
+            &target_address,
+            &[branch_node, leaf_node],
+            1,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ClaimValidationError::InvalidNodeReference));
     }
-    let mut out = [0u8; 32];
-    out[32 - raw.len()..].copy_from_slice(raw);
-    Ok(out)
-}
 
-fn balance_gte_total(balance: &[u8; 32], total: u128) -> bool {
-    if balance[..16].iter().any(|b| *b != 0) {
-        return true;
+    #[test]
+    fn pack_unpack_journal_roundtrip_with_zero_token() {
+        let journal = ClaimJournal {
+            block_number: 12345,
+            block_hash: [0xaau8; 32],
+            chain_id: 167013,
+            amount: amount_from_u128(1_000_000_000_000),
+            recipient: [0xbbu8; 20],
+            nullifier: [0xccu8; 32],
+            token: [0u8; 20],
+        };
+        let packed = pack_journal(&journal);
+        assert_eq!(packed.len(), PACKED_JOURNAL_LEN);
+        let unpacked = unpack_journal(&packed).unwrap();
+        assert_eq!(unpacked.block_number, journal.block_number);
+        assert_eq!(unpacked.block_hash, journal.block_hash);
+        assert_eq!(unpacked.chain_id, journal.chain_id);
+        assert_eq!(unpacked.amount, journal.amount);
+        assert_eq!(unpacked.recipient, journal.recipient);
+        assert_eq!(unpacked.nullifier, journal.nullifier);
+        assert_eq!(unpacked.token, [0u8; 20]);
     }
-    let mut low = [0u8; 16];
-    low.copy_from_slice(&balance[16..]);
-    u128::from_be_bytes(low) >= total
-}
 
-fn hash_to_nibbles(hash: &[u8; 32]) -> [u8; 64] {
-    let mut out = [0u8; 64];
-    let mut idx = 0usize;
-    for b in hash {
-        out[idx] = b >> 4;
-        out[idx + 1] = b & 0x0f;
-        idx += 2;
+    #[test]
+    fn pack_unpack_journal_roundtrip_with_nonzero_token() {
+        let token_addr = [
+            0xDE, 0xAD, 0xBE, 0xEF, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A,
+            0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x10,
+        ];
+        let journal = ClaimJournal {
+            block_number: 99999,
+            block_hash: [0x11u8; 32],
+            chain_id: 1,
+            amount: amount_from_u128(5_000_000_000_000_000_000),
+            recipient: [0x22u8; 20],
+            nullifier: [0x33u8; 32],
+            token: token_addr,
+        };
+        let packed = pack_journal(&journal);
+        assert_eq!(packed.len(), PACKED_JOURNAL_LEN);
+        // The token is the trailing field (offset 116).
+        assert_eq!(&packed[TOKEN_OFFSET..], &token_addr);
+        let unpacked = unpack_journal(&packed).unwrap();
+        assert_eq!(unpacked.token, token_addr);
+        assert_eq!(unpacked.amount, journal.amount);
     }
-    out
-}
 
-fn decode_compact_nibbles(encoded: &[u8]) -> Result<(bool, Vec<u8>), ClaimValidationError> {
-    if encoded.is_empty() {
-        return Err(ClaimValidationError::InvalidTriePath);
+    #[test]
+    fn unpack_journal_rejects_wrong_length() {
+        let short = [0u8; 100];
+        assert!(unpack_journal(&short).is_err());
+        let long = [0u8; 200];
+        assert!(unpack_journal(&long).is_err());
     }
 
-    let flag = encoded[0] >> 4;
-    if flag > 3 {
-        return Err(ClaimValidationError::InvalidTriePath);
-    }
-    let is_leaf = (flag & 0x2) != 0;
-    let is_odd = (flag & 0x1) != 0;
+    #[test]
+    fn unpack_journal_prefix_ignores_trailing_bytes() {
+        let journal = ClaimJournal {
+            block_number: 7,
+            block_hash: [0x44u8; 32],
+            chain_id: 167013,
+            amount: amount_from_u128(42),
+            recipient: [0x55u8; 20],
+            nullifier: [0x66u8; 32],
+            token: [0x77u8; 20],
+        };
+        let mut extended = pack_journal(&journal).to_vec();
+        extended.extend_from_slice(&[0xffu8; 24]);
 
-    if !is_odd && (encoded[0] & 0x0f) != 0 {
-        return Err(ClaimValidationError::InvalidTriePath);
-    }
+        assert!(unpack_journal(&extended).is_err());
+        let decoded = unpack_journal_prefix(&extended).unwrap();
+        assert_eq!(decoded.block_number, journal.block_number);
+        assert_eq!(decoded.nullifier, journal.nullifier);
+        assert_eq!(decoded.token, journal.token);
 
-    let mut nibbles = Vec::with_capacity(encoded.len() * 2);
-    if is_odd {
-        nibbles.push(encoded[0] & 0x0f);
+        let err = unpack_journal_prefix(&extended[..100]).unwrap_err();
+        assert_eq!(err.actual, 100);
     }
 
-    let start = 1; // always skip byte 0 (flag byte); odd path already pushed its low nibble above
-    for byte in encoded.iter().skip(start) {
-        nibbles.push(byte >> 4);
-        nibbles.push(byte & 0x0f);
+    #[cfg(feature = "wide-amount")]
+    #[test]
+    fn wide_amount_journal_roundtrips_amounts_above_u128() {
+        let mut amount = [0u8; 32];
+        amount[0] = 0x01;
+        amount[31] = 0x02;
+        let journal = WideClaimJournal {
+            block_number: 8,
+            block_hash: [0x12u8; 32],
+            chain_id: 167013,
+            amount,
+            recipient: [0x34u8; 20],
+            nullifier: [0x56u8; 32],
+            token: [0x78u8; 20],
+        };
+        let packed = pack_wide_journal(&journal);
+        assert_eq!(packed.len(), 152);
+        assert_eq!(WIDE_PACKED_JOURNAL_FIELDS[6], ("token", 132, 20));
+        // Little-endian like the other numeric fields.
+        assert_eq!(packed[48], 0x02);
+        assert_eq!(packed[79], 0x01);
+
+        let unpacked = unpack_wide_journal(&packed).unwrap();
+        assert_eq!(unpacked.amount, amount);
+        assert_eq!(unpacked.recipient, journal.recipient);
+        assert_eq!(unpacked.nullifier, journal.nullifier);
+        assert_eq!(unpacked.token, journal.token);
+        let err = unpack_wide_journal(&packed[..PACKED_JOURNAL_LEN]).unwrap_err();
+        assert_eq!((err.expected, err.actual), (152, PACKED_JOURNAL_LEN));
+        // The narrow journal is unchanged by the feature.
+        assert_eq!(PACKED_JOURNAL_LEN, 136);
     }
 
-    Ok((is_leaf, nibbles))
-}
+    #[cfg(feature = "wide-amount")]
+    #[test]
+    fn wide_claim_evaluates_to_the_same_journal_as_its_u128_claim() {
+        let input = build_single_leaf_claim_input([0x21; 32], 167013, [0x31; 20], 1_000, 9);
+        let wide = WideClaimInput {
+            amount: amount_to_word(&input.amount),
+            amounts: input.amounts.iter().map(amount_to_word).collect(),
+            block_number: input.block_number,
+            block_hash: input.block_hash,
+            chain_id: input.chain_id,
+            note_index: input.note_index,
+            recipient: input.recipient,
+            secret: input.secret,
+            note_count: input.note_count,
+            recipient_hashes: input.recipient_hashes.clone(),
+            block_header_rlp: input.block_header_rlp.clone(),
+            proof_depth: input.proof_depth,
+            proof_nodes: input.proof_nodes.clone(),
+            token: None,
+        };
 
-fn decode_rlp_scalar(input: &[u8]) -> Result<&[u8], ClaimValidationError> {
-    if input.is_empty() {
-        return Ok(&[]);
-    }
-    let item = decode_rlp_item(input, 0).map_err(|_| ClaimValidationError::InvalidStorageValue)?;
-    if item.is_list || item.total_len != input.len() {
-        return Err(ClaimValidationError::InvalidStorageValue);
+        let journal = evaluate_claim(&input).unwrap();
+        let wide_journal = evaluate_wide_claim(&wide).unwrap();
+        assert_eq!(wide_journal.amount, amount_to_word(&journal.amount));
+        assert_eq!(wide_journal.nullifier, journal.nullifier);
+        assert_eq!(
+            &pack_wide_journal(&wide_journal)[WIDE_PACKED_JOURNAL_FIELDS[4].1..],
+            &pack_journal(&journal)[PACKED_JOURNAL_FIELDS[4].1..]
+        );
     }
-    Ok(&input[item.payload_offset..item.payload_offset + item.payload_len])
-}
 
-fn decode_rlp_list_payload_items(input: &[u8]) -> Result<Vec<&[u8]>, ClaimValidationError> {
-    let top = decode_rlp_item(input, 0)?;
-    if !top.is_list || top.total_len != input.len() {
-        return Err(ClaimValidationError::InvalidRlpNode);
+    #[cfg(feature = "wide-amount")]
+    #[test]
+    fn wide_notes_hash_matches_the_u128_hash_for_small_amounts() {
+        let hashes = [[0x0au8; 32], [0x0bu8; 32]];
+        assert_eq!(
+            compute_wide_notes_hash(
+                2,
+                &[amount_to_word(&7u128), amount_to_word(&9u128)],
+                &hashes
+            )
+            .unwrap(),
+            compute_notes_hash(2, &[7, 9], &hashes).unwrap()
+        );
     }
 
-    let mut out = Vec::new();
-    let mut cursor = top.payload_offset;
-    let end = top.payload_offset + top.payload_len;
+    #[test]
+    fn verify_account_proof_extracts_storage_root_field2() {
+        // Test that field_index=2 correctly extracts storageRoot from account RLP
+        let target_address = [0x44u8; 20];
+        let key_hash = keccak256(&target_address);
+        let key_nibbles = hash_to_nibbles(&key_hash);
 
-    while cursor < end {
-        let item = decode_rlp_item(input, cursor)?;
-        if item.is_list {
-            // Inline trie node: return the complete RLP encoding (list prefix + payload)
-            // so the caller can byte-compare it via node_matches_reference.
-            out.push(&input[cursor..cursor + item.total_len]);
-        } else {
-            let payload_end = item.payload_offset + item.payload_len;
-            out.push(&input[item.payload_offset..payload_end]);
-        }
-        cursor += item.total_len;
-    }
+        let path = nibbles_to_compact_path(&key_nibbles, true);
 
-    if cursor != end {
-        return Err(ClaimValidationError::InvalidRlpNode);
-    }
-    Ok(out)
-}
+        let storage_root_raw = [0xAAu8; 32];
+        let account_rlp = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),               // nonce
+            rlp_encode_bytes(&[0x01]),           // balance
+            rlp_encode_bytes(&storage_root_raw), // storageRoot (field[2])
+            rlp_encode_bytes(&[0xBBu8; 32]),     // codeHash
+        ]);
 
-fn decode_rlp_item(input: &[u8], offset: usize) -> Result<RlpItem, ClaimValidationError> {
-    if offset >= input.len() {
-        return Err(ClaimValidationError::InvalidRlpNode);
-    }
+        let leaf_node = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
+        let state_root = keccak256(&leaf_node);
 
-    let prefix = input[offset];
-    if prefix <= 0x7f {
-        return Ok(RlpItem {
-            is_list: false,
-            payload_offset: offset,
-            payload_len: 1,
-            total_len: 1,
-        });
-    }
+        let result =
+            verify_account_proof_and_get_field(&state_root, &target_address, &[leaf_node], 2)
+                .unwrap();
 
-    if prefix <= 0xb7 {
-        let len = (prefix - 0x80) as usize;
-        let payload_offset = offset + 1;
-        let total_len = 1 + len;
-        if payload_offset + len > input.len() {
-            return Err(ClaimValidationError::InvalidRlpNode);
-        }
-        return Ok(RlpItem {
-            is_list: false,
-            payload_offset,
-            payload_len: len,
-            total_len,
-        });
+        let mut expected = [0u8; 32];
+        expected.copy_from_slice(&storage_root_raw);
+        assert_eq!(result, expected);
     }
 
-    if prefix <= 0xbf {
-        let len_of_len = (prefix - 0xb7) as usize;
-        let len_offset = offset + 1;
-        if len_offset + len_of_len > input.len() {
-            return Err(ClaimValidationError::InvalidRlpNode);
-        }
-        let len = read_be_usize(&input[len_offset..len_offset + len_of_len])?;
-        let payload_offset = len_offset + len_of_len;
-        let total_len = 1 + len_of_len + len;
-        if payload_offset + len > input.len() {
-            return Err(ClaimValidationError::InvalidRlpNode);
-        }
-        return Ok(RlpItem {
-            is_list: false,
-            payload_offset,
-            payload_len: len,
-            total_len,
-        });
-    }
+    #[test]
+    fn account_fields_with_the_wrong_length_are_rejected() {
+        let target_address = [0x44u8; 20];
+        let path = nibbles_to_compact_path(&hash_to_nibbles(&keccak256(&target_address)), true);
+        let prove = |nonce: &[u8], storage_root: &[u8], code_hash: &[u8]| {
+            let account_rlp = rlp_encode_list(&[
+                rlp_encode_bytes(nonce),
+                rlp_encode_bytes(&[0x01]),
+                rlp_encode_bytes(storage_root),
+                rlp_encode_bytes(code_hash),
+            ]);
+            let leaf = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account_rlp)]);
+            verify_account_proof_and_get_field(&keccak256(&leaf), &target_address, &[leaf], 1)
+        };
 
-    if prefix <= 0xf7 {
-        let len = (prefix - 0xc0) as usize;
-        let payload_offset = offset + 1;
-        let total_len = 1 + len;
-        if payload_offset + len > input.len() {
-            return Err(ClaimValidationError::InvalidRlpNode);
+        assert!(prove(&[0x01], &[0xaa; 32], &[0xbb; 32]).is_ok());
+        for (nonce, storage_root, code_hash) in [
+            (&[0x01; 9][..], &[0xaa; 32][..], &[0xbb; 32][..]),
+            (&[0x01], &[0xaa; 20], &[0xbb; 32]),
+            (&[0x01], &[0xaa; 32], &[]),
+        ] {
+            assert!(matches!(
+                prove(nonce, storage_root, code_hash),
+                Err(ClaimValidationError::InvalidAccountFieldLength)
+            ));
         }
-        return Ok(RlpItem {
-            is_list: true,
-            payload_offset,
-            payload_len: len,
-            total_len,
-        });
     }
 
-    let len_of_len = (prefix - 0xf7) as usize;
-    let len_offset = offset + 1;
-    if len_offset + len_of_len > input.len() {
-        return Err(ClaimValidationError::InvalidRlpNode);
+    #[test]
+    fn verify_storage_proof_decodes_rlp_encoded_value() {
+        // Storage trie values are RLP-encoded scalars. Build a minimal storage
+        // trie with a single leaf whose value is RLP(0x1bc16d674ec80000) = 2 ETH.
+        let storage_key = [0x55u8; 32];
+        let key_hash = keccak256(&storage_key);
+        let key_nibbles = hash_to_nibbles(&key_hash);
+
+        let path = nibbles_to_compact_path(&key_nibbles, true);
+
+        // The raw storage value (big-endian, trimmed)
+        let raw_value: Vec<u8> = vec![0x1b, 0xc1, 0x6d, 0x67, 0x4e, 0xc8, 0x00, 0x00];
+
+        // In the storage trie, values are stored as RLP-encoded scalars.
+        // The leaf contains: RLP_LIST([compact_path, RLP_STRING(raw_value)])
+        let leaf_node = rlp_encode_list(&[
+            rlp_encode_bytes(&path),
+            rlp_encode_bytes(&rlp_encode_bytes(&raw_value)),
+        ]);
+        let storage_root = keccak256(&leaf_node);
+
+        let result =
+            verify_storage_proof_and_get_value(&storage_root, &storage_key, &[leaf_node]).unwrap();
+
+        let mut expected = [0u8; 32];
+        expected[24..].copy_from_slice(&raw_value);
+        assert_eq!(result, expected);
     }
-    let len = read_be_usize(&input[len_offset..len_offset + len_of_len])?;
-    let payload_offset = len_offset + len_of_len;
-    let total_len = 1 + len_of_len + len;
-    if payload_offset + len > input.len() {
-        return Err(ClaimValidationError::InvalidRlpNode);
+
+    #[test]
+    fn verify_storage_proof_decodes_single_byte_value() {
+        // For values <= 0x7f, RLP encoding is just the byte itself.
+        let storage_key = [0x77u8; 32];
+        let key_hash = keccak256(&storage_key);
+        let key_nibbles = hash_to_nibbles(&key_hash);
+
+        let path = nibbles_to_compact_path(&key_nibbles, true);
+        let raw_value: Vec<u8> = vec![0x42];
+
+        let leaf_node = rlp_encode_list(&[
+            rlp_encode_bytes(&path),
+            rlp_encode_bytes(&rlp_encode_bytes(&raw_value)),
+        ]);
+        let storage_root = keccak256(&leaf_node);
+
+        let result =
+            verify_storage_proof_and_get_value(&storage_root, &storage_key, &[leaf_node]).unwrap();
+
+        let mut expected = [0u8; 32];
+        expected[31] = 0x42;
+        assert_eq!(result, expected);
     }
-    Ok(RlpItem {
-        is_list: true,
-        payload_offset,
-        payload_len: len,
-        total_len,
-    })
-}
 
-fn read_be_usize(input: &[u8]) -> Result<usize, ClaimValidationError> {
-    if input.is_empty() || input.len() > core::mem::size_of::<usize>() {
-        return Err(ClaimValidationError::InvalidRlpNode);
+    /// A storage trie of extension → branch → leaf along `keccak256(slot)`.
+    /// Returns the root and the proof nodes, root first.
+    fn storage_trie_through_branch(slot: &[u8; 32], raw_value: &[u8]) -> ([u8; 32], Vec<Vec<u8>>) {
+        let nibbles = hash_to_nibbles(&keccak256(slot));
+        let leaf = rlp_encode_list(&[
+            rlp_encode_bytes(&nibbles_to_compact_path(&nibbles[3..], true)),
+            rlp_encode_bytes(&rlp_encode_bytes(raw_value)),
+        ]);
+        let mut children = vec![rlp_encode_bytes(&[]); 17];
+        children[nibbles[2] as usize] = rlp_encode_bytes(&keccak256(&leaf));
+        let branch = rlp_encode_list(&children);
+        let extension = rlp_encode_list(&[
+            rlp_encode_bytes(&nibbles_to_compact_path(&nibbles[..2], false)),
+            rlp_encode_bytes(&keccak256(&branch)),
+        ]);
+        (keccak256(&extension), vec![extension, branch, leaf])
     }
-    let mut out = 0usize;
-    for b in input {
-        out = out
-            .checked_mul(256)
-            .ok_or(ClaimValidationError::InvalidRlpNode)?;
-        out = out
-            .checked_add(*b as usize)
-            .ok_or(ClaimValidationError::InvalidRlpNode)?;
+
+    #[test]
+    fn storage_proof_chains_off_account_storage_root() {
+        let slot = [0x09u8; 32];
+        let (storage_root, storage_proof) = storage_trie_through_branch(&slot, &[0x01]);
+
+        let address = [0x44u8; 20];
+        let account = rlp_encode_list(&[
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&[]),
+            rlp_encode_bytes(&storage_root),
+            rlp_encode_bytes(&[0xBBu8; 32]),
+        ]);
+        let path = nibbles_to_compact_path(&hash_to_nibbles(&keccak256(&address)), true);
+        let account_leaf = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account)]);
+
+        let root = verify_account_proof_and_get_storage_root(
+            &keccak256(&account_leaf),
+            &address,
+            &[account_leaf],
+        )
+        .unwrap();
+        assert_eq!(root, storage_root);
+
+        let value = verify_storage_proof_and_get_value(&root, &slot, &storage_proof).unwrap();
+        let mut expected = [0u8; 32];
+        expected[31] = 0x01;
+        assert_eq!(value, expected);
     }
-    Ok(out)
-}
 
-fn to_32(input: &[u8]) -> [u8; 32] {
-    let mut out = [0u8; 32];
-    out.copy_from_slice(input);
-    out
+    #[test]
+    fn storage_proof_rejects_tampered_nodes_and_absent_slots() {
+        let slot = [0x09u8; 32];
+        let (storage_root, proof) = storage_trie_through_branch(&slot, &[0x01]);
+
+        // A leaf that no longer hashes to the branch's reference.
+        let mut tampered = proof.clone();
+        let last = tampered[2].len() - 1;
+        tampered[2][last] ^= 0x01;
+        assert!(matches!(
+            verify_storage_proof_and_get_value(&storage_root, &slot, &tampered),
+            Err(ClaimValidationError::StorageProofFailed)
+        ));
+
+        // A slot sharing the extension but taking an empty branch child.
+        let ours = hash_to_nibbles(&keccak256(&slot));
+        let mut absent = slot;
+        loop {
+            absent[0] = absent[0].wrapping_add(1);
+            let nibbles = hash_to_nibbles(&keccak256(&absent));
+            if nibbles[..2] == ours[..2] && nibbles[2] != ours[2] {
+                break;
+            }
+        }
+        assert!(matches!(
+            verify_storage_proof_and_get_value(&storage_root, &absent, &proof[..2]),
+            Err(ClaimValidationError::MissingStorageValue)
+        ));
+
+        // A proof that stops at the branch without reaching a value.
+        assert!(matches!(
+            verify_storage_proof_and_get_value(&storage_root, &slot, &proof[..2]),
+            Err(ClaimValidationError::MissingStorageValue)
+        ));
+    }
 }
@@ -0,0 +1,358 @@
+//! Recursive Length Prefix (RLP) decoding.
+//!
+//! The decoders borrow from the input and never allocate for byte strings,
+//! so they can run inside the guest. Lengths are checked against the input
+//! before any slice is taken.
+
+use alloc::vec::Vec;
+
+/// Deepest list nesting [`decode_rlp_nested_list`] descends into.
+pub const MAX_RLP_NESTING: usize = 32;
+
+/// Why an RLP encoding was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RlpError {
+    /// An item runs past the end of the input or of its enclosing list.
+    Truncated,
+    /// A long-form length is empty or does not fit in a `usize`.
+    InvalidLength,
+    /// A list was expected but a byte string was found.
+    ExpectedList,
+    /// Bytes remain after the top-level item or inside a list payload.
+    TrailingBytes,
+    /// Lists nest deeper than [`MAX_RLP_NESTING`].
+    NestingTooDeep,
+}
+
+impl RlpError {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Truncated => "RLP item runs past the end of the input",
+            Self::InvalidLength => "invalid RLP length prefix",
+            Self::ExpectedList => "expected an RLP list",
+            Self::TrailingBytes => "trailing bytes after RLP item",
+            Self::NestingTooDeep => "RLP lists nest too deeply",
+        }
+    }
+}
+
+impl core::fmt::Display for RlpError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Position of one RLP item within the input it was decoded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RlpItem {
+    /// Whether the item is a list rather than a byte string.
+    pub is_list: bool,
+    /// Offset of the payload, i.e. just past the prefix.
+    pub payload_offset: usize,
+    /// Length of the payload in bytes.
+    pub payload_len: usize,
+    /// Length of the whole item, prefix included.
+    pub total_len: usize,
+}
+
+impl RlpItem {
+    /// The payload bytes of this item within `input`.
+    pub fn payload<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        &input[self.payload_offset..self.payload_offset + self.payload_len]
+    }
+}
+
+/// A fully decoded RLP value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RlpValue<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<RlpValue<'a>>),
+}
+
+/// Decode the prefix of the item starting at `offset`.
+pub fn decode_rlp_item(input: &[u8], offset: usize) -> Result<RlpItem, RlpError> {
+    if offset >= input.len() {
+        return Err(RlpError::Truncated);
+    }
+
+    let prefix = input[offset];
+    if prefix <= 0x7f {
+        return Ok(RlpItem {
+            is_list: false,
+            payload_offset: offset,
+            payload_len: 1,
+            total_len: 1,
+        });
+    }
+
+    let (is_list, short_base, long_base) = if prefix <= 0xbf {
+        (false, 0x80, 0xb7)
+    } else {
+        (true, 0xc0, 0xf7)
+    };
+
+    let (len_of_len, len) = if prefix <= long_base {
+        (0, (prefix - short_base) as usize)
+    } else {
+        let len_of_len = (prefix - long_base) as usize;
+        let len_offset = offset + 1;
+        if len_offset + len_of_len > input.len() {
+            return Err(RlpError::Truncated);
+        }
+        let len = read_be_usize(&input[len_offset..len_offset + len_of_len])?;
+        (len_of_len, len)
+    };
+
+    let payload_offset = offset + 1 + len_of_len;
+    let end = payload_offset
+        .checked_add(len)
+        .ok_or(RlpError::InvalidLength)?;
+    if end > input.len() {
+        return Err(RlpError::Truncated);
+    }
+    Ok(RlpItem {
+        is_list,
+        payload_offset,
+        payload_len: len,
+        total_len: 1 + len_of_len + len,
+    })
+}
+
+/// Split a top-level RLP list into its items.
+///
+/// Byte-string items are returned as their payload. Nested list items are
+/// returned as their complete encoding (prefix included) without being
+/// decoded, which is what trie verification compares inline nodes against;
+/// use [`decode_rlp_nested_list`] to decode them as well.
+pub fn decode_rlp_list_payload_items(input: &[u8]) -> Result<Vec<&[u8]>, RlpError> {
+    rlp_list_items(input).collect()
+}
+
+/// Iterate over the items of a top-level RLP list without allocating.
+///
+/// Yields the same slices as [`decode_rlp_list_payload_items`], in order. A
+/// malformed list yields one error and then ends; a caller that stops early
+/// does not see errors in the items it skipped.
+pub fn rlp_list_items(input: &[u8]) -> RlpListItems<'_> {
+    let state = match decode_rlp_item(input, 0) {
+        Ok(top) if !top.is_list => Err(RlpError::ExpectedList),
+        Ok(top) if top.total_len != input.len() => Err(RlpError::TrailingBytes),
+        Ok(top) => Ok((top.payload_offset, top.payload_offset + top.payload_len)),
+        Err(err) => Err(err),
+    };
+    RlpListItems {
+        input,
+        state: Some(state),
+    }
+}
+
+/// Iterator returned by [`rlp_list_items`].
+pub struct RlpListItems<'a> {
+    input: &'a [u8],
+    /// Cursor and end of the list payload, the pending header error, or
+    /// `None` once exhausted.
+    state: Option<Result<(usize, usize), RlpError>>,
+}
+
+impl<'a> Iterator for RlpListItems<'a> {
+    type Item = Result<&'a [u8], RlpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (cursor, end) = match self.state.take()? {
+            Ok(range) => range,
+            Err(err) => return Some(Err(err)),
+        };
+        if cursor >= end {
+            return None;
+        }
+        let item = match decode_rlp_item(self.input, cursor) {
+            Ok(item) if cursor + item.total_len <= end => item,
+            Ok(_) => return Some(Err(RlpError::TrailingBytes)),
+            Err(err) => return Some(Err(err)),
+        };
+        self.state = Some(Ok((cursor + item.total_len, end)));
+        if item.is_list {
+            Some(Ok(&self.input[cursor..cursor + item.total_len]))
+        } else {
+            Some(Ok(item.payload(self.input)))
+        }
+    }
+}
+
+/// Decode a top-level RLP list, descending into nested lists (for example
+/// the logs and topics of a transaction receipt).
+pub fn decode_rlp_nested_list(input: &[u8]) -> Result<Vec<RlpValue<'_>>, RlpError> {
+    let top = decode_rlp_item(input, 0)?;
+    if !top.is_list {
+        return Err(RlpError::ExpectedList);
+    }
+    if top.total_len != input.len() {
+        return Err(RlpError::TrailingBytes);
+    }
+    decode_list_payload(input, &top, 1)
+}
+
+fn decode_list_payload<'a>(
+    input: &'a [u8],
+    list: &RlpItem,
+    depth: usize,
+) -> Result<Vec<RlpValue<'a>>, RlpError> {
+    if depth > MAX_RLP_NESTING {
+        return Err(RlpError::NestingTooDeep);
+    }
+
+    let mut out = Vec::new();
+    let mut cursor = list.payload_offset;
+    let end = list.payload_offset + list.payload_len;
+
+    while cursor < end {
+        let item = decode_rlp_item(input, cursor)?;
+        if cursor + item.total_len > end {
+            return Err(RlpError::Truncated);
+        }
+        if item.is_list {
+            out.push(RlpValue::List(decode_list_payload(
+                input,
+                &item,
+                depth + 1,
+            )?));
+        } else {
+            out.push(RlpValue::Bytes(item.payload(input)));
+        }
+        cursor += item.total_len;
+    }
+    Ok(out)
+}
+
+fn read_be_usize(input: &[u8]) -> Result<usize, RlpError> {
+    if input.is_empty() || input.len() > core::mem::size_of::<usize>() {
+        return Err(RlpError::InvalidLength);
+    }
+    let mut out = 0usize;
+    for b in input {
+        out = out.checked_mul(256).ok_or(RlpError::InvalidLength)?;
+        out = out
+            .checked_add(*b as usize)
+            .ok_or(RlpError::InvalidLength)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::test_support::{rlp_encode_bytes, rlp_encode_list};
+
+    #[test]
+    fn decode_rlp_item_handles_single_byte_and_empty_string() {
+        let item = decode_rlp_item(&[0x7f], 0).unwrap();
+        assert!(!item.is_list);
+        assert_eq!(item.payload_offset, 0);
+        assert_eq!(item.payload_len, 1);
+        assert_eq!(item.total_len, 1);
+
+        // 0x80 encodes an empty string.
+        let item = decode_rlp_item(&[0x80], 0).unwrap();
+        assert!(!item.is_list);
+        assert_eq!(item.payload_offset, 1);
+        assert_eq!(item.payload_len, 0);
+        assert_eq!(item.total_len, 1);
+    }
+
+    #[test]
+    fn decode_rlp_list_payload_items_returns_full_rlp_bytes_for_nested_list_items() {
+        // [[0x01]] is a list whose only element is another list (inline trie node).
+        // The full RLP encoding of the inner list is returned, not an error.
+        let inner = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
+        let outer = rlp_encode_list(core::slice::from_ref(&inner));
+        let items = decode_rlp_list_payload_items(&outer).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0], inner.as_slice());
+    }
+
+    #[test]
+    fn list_iterator_matches_vec_decoder_and_stops_after_an_error() {
+        let inner = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
+        let list = rlp_encode_list(&[rlp_encode_bytes(b"abc"), inner, rlp_encode_bytes(&[])]);
+        let items: Result<Vec<_>, _> = rlp_list_items(&list).collect();
+        assert_eq!(items, decode_rlp_list_payload_items(&list));
+
+        let mut iter = rlp_list_items(&[0xc3, 0x01]);
+        assert_eq!(iter.next(), Some(Err(RlpError::Truncated)));
+        assert_eq!(iter.next(), None);
+
+        // The second item claims more bytes than the list holds.
+        let mut iter = rlp_list_items(&[0xc3, 0x01, 0x82, 0x02]);
+        assert_eq!(iter.next(), Some(Ok(&[0x01][..])));
+        assert_eq!(iter.next(), Some(Err(RlpError::Truncated)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn nested_list_decodes_receipt_shaped_input() {
+        // [status, cumulativeGas, bloom, [[address, [topic], data]]]
+        let topic = [0x11u8; 32];
+        let log = rlp_encode_list(&[
+            rlp_encode_bytes(&[0x22u8; 20]),
+            rlp_encode_list(&[rlp_encode_bytes(&topic)]),
+            rlp_encode_bytes(b"data"),
+        ]);
+        let bloom = [0u8; 256];
+        let receipt = rlp_encode_list(&[
+            rlp_encode_bytes(&[0x01]),
+            rlp_encode_bytes(&[0x52, 0x08]),
+            rlp_encode_bytes(&bloom),
+            rlp_encode_list(&[log]),
+        ]);
+
+        let decoded = decode_rlp_nested_list(&receipt).unwrap();
+        let expected_log = RlpValue::List(vec![
+            RlpValue::Bytes(&[0x22u8; 20]),
+            RlpValue::List(vec![RlpValue::Bytes(&topic)]),
+            RlpValue::Bytes(b"data"),
+        ]);
+        assert_eq!(
+            decoded,
+            vec![
+                RlpValue::Bytes(&[0x01]),
+                RlpValue::Bytes(&[0x52, 0x08]),
+                RlpValue::Bytes(&bloom),
+                RlpValue::List(vec![expected_log]),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_input_reports_a_specific_error() {
+        assert_eq!(decode_rlp_item(&[], 0), Err(RlpError::Truncated));
+        assert_eq!(decode_rlp_item(&[0x83, 0x01], 0), Err(RlpError::Truncated));
+        assert_eq!(decode_rlp_item(&[0xb9, 0x01], 0), Err(RlpError::Truncated));
+        assert_eq!(
+            decode_rlp_item(
+                &[0xbf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+                0
+            ),
+            Err(RlpError::InvalidLength)
+        );
+        assert_eq!(
+            decode_rlp_list_payload_items(&rlp_encode_bytes(&[0x01, 0x02])),
+            Err(RlpError::ExpectedList)
+        );
+        let mut list = rlp_encode_list(&[rlp_encode_bytes(&[0x01])]);
+        list.push(0x00);
+        assert_eq!(decode_rlp_nested_list(&list), Err(RlpError::TrailingBytes));
+        // An inner list whose length runs past its parent's payload.
+        assert_eq!(
+            decode_rlp_nested_list(&[0xc5, 0xc2, 0xc3, 0x01, 0x02, 0x03]),
+            Err(RlpError::Truncated)
+        );
+
+        let mut deep = rlp_encode_list(&[]);
+        for _ in 0..MAX_RLP_NESTING {
+            deep = rlp_encode_list(&[deep]);
+        }
+        assert_eq!(decode_rlp_nested_list(&deep), Err(RlpError::NestingTooDeep));
+    }
+}
@@ -0,0 +1,212 @@
+//! Builders for valid claim inputs, shared with downstream crates' tests.
+//!
+//! Enabled for this crate's tests and by the `test-support` feature.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    amount_from_u128, compute_notes_hash_with_capacity, compute_recipient_hash,
+    derive_target_address, hash_to_nibbles, keccak256, Amount, ClaimInput, MAX_NOTES,
+};
+
+/// RLP-encode a byte string.
+pub fn rlp_encode_bytes(raw: &[u8]) -> Vec<u8> {
+    if raw.len() == 1 && raw[0] <= 0x7f {
+        return vec![raw[0]];
+    }
+
+    if raw.len() <= 55 {
+        let mut out = Vec::with_capacity(1 + raw.len());
+        out.push(0x80u8 + raw.len() as u8);
+        out.extend_from_slice(raw);
+        return out;
+    }
+
+    let len_bytes = usize_to_be_bytes(raw.len());
+    let mut out = Vec::with_capacity(1 + len_bytes.len() + raw.len());
+    out.push(0xb7u8 + len_bytes.len() as u8);
+    out.extend_from_slice(&len_bytes);
+    out.extend_from_slice(raw);
+    out
+}
+
+/// RLP-encode a list of already-encoded items.
+pub fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_len: usize = items.iter().map(|it| it.len()).sum();
+    let mut payload = Vec::with_capacity(payload_len);
+    for it in items {
+        payload.extend_from_slice(it);
+    }
+
+    if payload.len() <= 55 {
+        let mut out = Vec::with_capacity(1 + payload.len());
+        out.push(0xc0u8 + payload.len() as u8);
+        out.extend_from_slice(&payload);
+        return out;
+    }
+
+    let len_bytes = usize_to_be_bytes(payload.len());
+    let mut out = Vec::with_capacity(1 + len_bytes.len() + payload.len());
+    out.push(0xf7u8 + len_bytes.len() as u8);
+    out.extend_from_slice(&len_bytes);
+    out.extend_from_slice(&payload);
+    out
+}
+
+fn usize_to_be_bytes(mut value: usize) -> Vec<u8> {
+    if value == 0 {
+        return vec![0u8];
+    }
+    let mut out = Vec::new();
+    while value > 0 {
+        out.push((value & 0xff) as u8);
+        value >>= 8;
+    }
+    out.reverse();
+    out
+}
+
+/// Minimal big-endian bytes of `value` (empty for zero), as RLP quantities use.
+pub fn u64_to_min_be_bytes(value: u64) -> Vec<u8> {
+    if value == 0 {
+        return Vec::new();
+    }
+    let buf = value.to_be_bytes();
+    let first = buf.iter().position(|b| *b != 0).unwrap_or(buf.len());
+    buf[first..].to_vec()
+}
+
+/// A 17-field post-Shanghai header with the given number and state root.
+pub fn make_block_header_rlp(block_number: u64, state_root: [u8; 32]) -> Vec<u8> {
+    let fields = vec![
+        rlp_encode_bytes(&[0x11u8; 32]),                      // parentHash
+        rlp_encode_bytes(&[0x22u8; 32]),                      // sha3Uncles
+        rlp_encode_bytes(&[0x33u8; 20]),                      // miner
+        rlp_encode_bytes(&state_root),                        // stateRoot
+        rlp_encode_bytes(&[0x44u8; 32]),                      // transactionsRoot
+        rlp_encode_bytes(&[0x55u8; 32]),                      // receiptsRoot
+        rlp_encode_bytes(&[0u8; 256]),                        // logsBloom
+        rlp_encode_bytes(&[]),                                // difficulty
+        rlp_encode_bytes(&u64_to_min_be_bytes(block_number)), // number
+        rlp_encode_bytes(&[0x01]),                            // gasLimit
+        rlp_encode_bytes(&[]),                                // gasUsed
+        rlp_encode_bytes(&[0x02]),                            // timestamp
+        rlp_encode_bytes(&[]),                                // extraData
+        rlp_encode_bytes(&[0x66u8; 32]),                      // mixHash
+        rlp_encode_bytes(&[0x77u8; 8]),                       // nonce
+        rlp_encode_bytes(&[0x01]),                            // baseFeePerGas
+        rlp_encode_bytes(&[0x88u8; 32]),                      // withdrawalsRoot
+    ];
+    rlp_encode_list(&fields)
+}
+
+/// Hex-prefix encode a nibble path for a leaf or extension node.
+pub fn nibbles_to_compact_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let is_odd = (nibbles.len() % 2) == 1;
+    let flags = (if is_leaf { 0x2 } else { 0x0 }) | (if is_odd { 0x1 } else { 0x0 });
+
+    let mut out = Vec::new();
+    if is_odd {
+        out.push((flags << 4) | (nibbles[0] & 0x0f));
+        for pair in nibbles[1..].chunks(2) {
+            out.push((pair[0] << 4) | (pair[1] & 0x0f));
+        }
+    } else {
+        out.push(flags << 4);
+        for pair in nibbles.chunks(2) {
+            out.push((pair[0] << 4) | (pair[1] & 0x0f));
+        }
+    }
+    out
+}
+
+/// Build a state trie holding only `address`, with `balance` wei and no code
+/// or storage.
+///
+/// The leaf path commits the whole key, so the leaf is the trie root and
+/// alone makes up the account proof. Returns the leaf and the state root.
+pub fn build_single_account_trie(address: &[u8; 20], balance: u128) -> (Vec<u8>, [u8; 32]) {
+    // Account RLP: [nonce, balance, storageRoot, codeHash]
+    let balance = balance.to_be_bytes();
+    let first = balance
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(balance.len());
+    let account = rlp_encode_list(&[
+        rlp_encode_bytes(&[]),
+        rlp_encode_bytes(&balance[first..]),
+        rlp_encode_bytes(&keccak256(&[0x80])), // empty storage trie
+        rlp_encode_bytes(&keccak256(&[])),     // no code
+    ]);
+
+    let path = nibbles_to_compact_path(&hash_to_nibbles(&keccak256(address)), true);
+    let leaf = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account)]);
+    let state_root = keccak256(&leaf);
+    (leaf, state_root)
+}
+
+/// Build a valid single-note ETH claim over a one-leaf state trie.
+///
+/// The trie holds only the deposit's target address with exactly `amount`
+/// wei, under a header at `block_number` built by [`make_block_header_rlp`].
+pub fn build_single_leaf_claim_input(
+    secret: [u8; 32],
+    chain_id: u64,
+    recipient: [u8; 20],
+    amount: u128,
+    block_number: u64,
+) -> ClaimInput {
+    build_leaf_claim_input_with_capacity::<MAX_NOTES>(
+        secret,
+        chain_id,
+        &[(recipient, amount)],
+        0,
+        block_number,
+    )
+}
+
+/// Build a valid claim of note `note_index` of a deposit of up to `CAP`
+/// `(recipient, amount)` notes, over a one-leaf state trie holding exactly
+/// the note total.
+pub fn build_leaf_claim_input_with_capacity<const CAP: usize>(
+    secret: [u8; 32],
+    chain_id: u64,
+    notes: &[([u8; 20], u128)],
+    note_index: u32,
+    block_number: u64,
+) -> ClaimInput {
+    let amounts: Vec<Amount> = notes
+        .iter()
+        .map(|(_, amount)| amount_from_u128(*amount))
+        .collect();
+    let recipient_hashes: Vec<[u8; 32]> = notes
+        .iter()
+        .map(|(recipient, _)| compute_recipient_hash(recipient))
+        .collect();
+    let notes_hash =
+        compute_notes_hash_with_capacity::<CAP>(notes.len(), &amounts, &recipient_hashes)
+            .expect("notes fit the capacity");
+    let target_address = derive_target_address(&secret, chain_id, &notes_hash);
+
+    let total = notes.iter().map(|(_, amount)| amount).sum();
+    let (leaf, state_root) = build_single_account_trie(&target_address, total);
+    let header = make_block_header_rlp(block_number, state_root);
+
+    let (recipient, amount) = notes[note_index as usize];
+    ClaimInput {
+        block_number,
+        block_hash: keccak256(&header),
+        chain_id,
+        note_index,
+        amount: amount_from_u128(amount),
+        recipient,
+        secret,
+        note_count: notes.len() as u32,
+        amounts,
+        recipient_hashes,
+        block_header_rlp: header,
+        proof_depth: 1,
+        proof_nodes: vec![leaf],
+        token: None,
+    }
+}
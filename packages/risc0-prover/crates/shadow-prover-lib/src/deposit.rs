@@ -1,236 +1,27 @@
-//! Deposit file loading, validation, and derivation utilities.
+//! Deposit file loading and filename utilities.
 //!
-//! Handles the v2 deposit file format:
-//! ```json
-//! {
-//!   "version": "v2",
-//!   "chainId": "167013",
-//!   "secret": "0x...",
-//!   "notes": [{ "recipient": "0x...", "amount": "123", "label": "..." }],
-//!   "targetAddress": "0x..."
-//! }
-//! ```
+//! The file format, its validation and derivation live in
+//! [`shadow_util::deposit`] so the server shares them; they are re-exported
+//! here.
 
-use std::{fs, path::Path};
+use std::path::Path;
 
-use anyhow::{bail, Context, Result};
-use serde::{Deserialize, Serialize};
-use shadow_proof_core::{
-    compute_notes_hash, compute_recipient_hash, derive_nullifier, derive_target_address, MAX_NOTES,
+use anyhow::{Context, Result};
+pub use shadow_util::deposit::{
+    deposit_nullifiers, derive_deposit_info, validate_deposit, DepositFile, DepositNote,
+    DerivedDepositInfo, DerivedNoteInfo, NoteOrder,
 };
 
-/// A parsed deposit file (v2 schema).
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DepositFile {
-    pub version: String,
-    pub chain_id: String,
-    pub secret: String,
-    pub notes: Vec<DepositNote>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target_address: Option<String>,
-    /// Token contract address (0x-prefixed hex). Absent/null = ETH.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub token: Option<String>,
-}
-
-/// A single note within a deposit.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DepositNote {
-    pub recipient: String,
-    pub amount: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub label: Option<String>,
-}
-
-/// Derived information from a deposit file (addresses, nullifiers, hashes).
-#[derive(Debug, Clone)]
-pub struct DerivedDepositInfo {
-    /// The chain ID as u64.
-    pub chain_id: u64,
-    /// The 32-byte secret.
-    pub secret: [u8; 32],
-    /// The derived target address (20 bytes).
-    pub target_address: [u8; 20],
-    /// The SHA-256 notes hash.
-    pub notes_hash: [u8; 32],
-    /// Per-note derived info.
-    pub notes: Vec<DerivedNoteInfo>,
-    /// Total amount across all notes (in wei).
-    pub total_amount: u128,
-    /// Token contract address (None = ETH).
-    pub token: Option<[u8; 20]>,
-}
-
-/// Per-note derived information.
-#[derive(Debug, Clone)]
-pub struct DerivedNoteInfo {
-    /// Note index (0-based).
-    pub index: u32,
-    /// Recipient address (20 bytes).
-    pub recipient: [u8; 20],
-    /// Amount in wei.
-    pub amount: u128,
-    /// Optional label.
-    pub label: Option<String>,
-    /// The nullifier for this note: SHA-256(magic || chainId || secret || noteIndex || notesHash).
-    pub nullifier: [u8; 32],
-    /// The recipient hash: SHA-256(magic || left-padded recipient).
-    pub recipient_hash: [u8; 32],
-}
+use crate::{read_json_file, JsonLimits};
 
 /// Load a deposit file from disk.
-pub fn load_deposit(path: &Path) -> Result<DepositFile> {
-    let raw =
-        fs::read(path).with_context(|| format!("failed reading deposit {}", path.display()))?;
+pub fn load_deposit(path: &Path, limits: JsonLimits) -> Result<DepositFile> {
+    let raw = read_json_file(path, limits).context("failed reading deposit")?;
     let deposit: DepositFile = serde_json::from_slice(&raw)
         .with_context(|| format!("failed parsing deposit JSON {}", path.display()))?;
     Ok(deposit)
 }
 
-/// Validate a deposit file against the v2 schema constraints.
-pub fn validate_deposit(deposit: &DepositFile) -> Result<()> {
-    if deposit.version != "v2" {
-        bail!(
-            "unsupported deposit version: {} (expected v2)",
-            deposit.version
-        );
-    }
-
-    // chainId must be a decimal number string
-    if deposit.chain_id.is_empty() || !deposit.chain_id.chars().all(|c| c.is_ascii_digit()) {
-        bail!("invalid chainId: must be a decimal number string");
-    }
-
-    // secret must be a 32-byte hex string (0x-prefixed, 64 hex chars)
-    parse_hex_bytes32(&deposit.secret).context("invalid secret")?;
-
-    // notes: 1..=5
-    if deposit.notes.is_empty() || deposit.notes.len() > MAX_NOTES {
-        bail!(
-            "invalid note count: {} (must be 1..{})",
-            deposit.notes.len(),
-            MAX_NOTES
-        );
-    }
-
-    for (i, note) in deposit.notes.iter().enumerate() {
-        // recipient must be a 20-byte hex address
-        parse_hex_address(&note.recipient)
-            .with_context(|| format!("invalid recipient in note {}", i))?;
-
-        // amount must be a non-zero decimal number
-        let amount = note
-            .amount
-            .parse::<u128>()
-            .with_context(|| format!("invalid amount in note {}: {}", i, note.amount))?;
-        if amount == 0 {
-            bail!("note {} amount must be non-zero", i);
-        }
-
-        // label (optional) max 64 chars
-        if let Some(ref label) = note.label {
-            if label.len() > 64 {
-                bail!("note {} label exceeds 64 characters", i);
-            }
-        }
-    }
-
-    // targetAddress (optional)
-    if let Some(ref addr) = deposit.target_address {
-        parse_hex_address(addr).context("invalid targetAddress")?;
-    }
-
-    // token (optional)
-    if let Some(ref token) = deposit.token {
-        parse_hex_address(token).context("invalid token address")?;
-    }
-
-    Ok(())
-}
-
-/// Derive all cryptographic information from a deposit file.
-///
-/// This computes:
-/// - Target address from (secret, chainId, notesHash)
-/// - Nullifiers for each note from (secret, chainId, noteIndex, notesHash)
-/// - Notes hash, total amount
-pub fn derive_deposit_info(deposit: &DepositFile) -> Result<DerivedDepositInfo> {
-    let chain_id: u64 = deposit
-        .chain_id
-        .parse()
-        .context("chainId is not a valid u64")?;
-    let secret = parse_hex_bytes32(&deposit.secret)?;
-
-    let note_count = deposit.notes.len();
-    let mut amounts = Vec::with_capacity(note_count);
-    let mut recipients = Vec::with_capacity(note_count);
-    let mut recipient_hashes = Vec::with_capacity(note_count);
-    let mut total_amount: u128 = 0;
-
-    for (i, note) in deposit.notes.iter().enumerate() {
-        let recipient = parse_hex_address(&note.recipient)?;
-        let amount: u128 = note
-            .amount
-            .parse()
-            .with_context(|| format!("invalid amount in note {}", i))?;
-        let recipient_hash = compute_recipient_hash(&recipient);
-
-        amounts.push(amount);
-        recipients.push(recipient);
-        recipient_hashes.push(recipient_hash);
-        total_amount = total_amount
-            .checked_add(amount)
-            .context("total amount overflow")?;
-    }
-
-    let notes_hash = compute_notes_hash(note_count, &amounts, &recipient_hashes)
-        .map_err(|e| anyhow::anyhow!("notes hash computation failed: {}", e.as_str()))?;
-    let target_address = derive_target_address(&secret, chain_id, &notes_hash);
-
-    let mut derived_notes = Vec::with_capacity(note_count);
-    for (i, note) in deposit.notes.iter().enumerate() {
-        let nullifier = derive_nullifier(&secret, chain_id, i as u32, &notes_hash);
-        derived_notes.push(DerivedNoteInfo {
-            index: i as u32,
-            recipient: recipients[i],
-            amount: amounts[i],
-            label: note.label.clone(),
-            nullifier,
-            recipient_hash: recipient_hashes[i],
-        });
-    }
-
-    // If targetAddress is present in the deposit file, verify it matches
-    if let Some(ref expected_addr) = deposit.target_address {
-        let expected = parse_hex_address(expected_addr)?;
-        if expected != target_address {
-            bail!(
-                "targetAddress mismatch: file says 0x{} but derived 0x{}",
-                hex::encode(expected),
-                hex::encode(target_address)
-            );
-        }
-    }
-
-    let token = deposit
-        .token
-        .as_ref()
-        .map(|t| parse_hex_address(t))
-        .transpose()
-        .context("invalid token address")?;
-
-    Ok(DerivedDepositInfo {
-        chain_id,
-        secret,
-        target_address,
-        notes_hash,
-        notes: derived_notes,
-        total_amount,
-        token,
-    })
-}
-
 // ---------------------------------------------------------------------------
 // Filename utilities
 // ---------------------------------------------------------------------------
@@ -315,38 +106,6 @@ pub fn format_timestamp_secs(unix_secs: u64) -> String {
     )
 }
 
-// ---------------------------------------------------------------------------
-// Hex parsing helpers
-// ---------------------------------------------------------------------------
-
-fn parse_hex_bytes32(hex_str: &str) -> Result<[u8; 32]> {
-    let stripped = hex_str
-        .strip_prefix("0x")
-        .or_else(|| hex_str.strip_prefix("0X"))
-        .context("secret must start with 0x")?;
-    if stripped.len() != 64 {
-        bail!("expected 32 bytes (64 hex chars), got {}", stripped.len());
-    }
-    let bytes = hex::decode(stripped).context("invalid hex")?;
-    let mut out = [0u8; 32];
-    out.copy_from_slice(&bytes);
-    Ok(out)
-}
-
-fn parse_hex_address(hex_str: &str) -> Result<[u8; 20]> {
-    let stripped = hex_str
-        .strip_prefix("0x")
-        .or_else(|| hex_str.strip_prefix("0X"))
-        .context("address must start with 0x")?;
-    if stripped.len() != 40 {
-        bail!("expected 20 bytes (40 hex chars), got {}", stripped.len());
-    }
-    let bytes = hex::decode(stripped).context("invalid hex")?;
-    let mut out = [0u8; 20];
-    out.copy_from_slice(&bytes);
-    Ok(out)
-}
-
 // ---------------------------------------------------------------------------
 // Civil date computation (Gregorian calendar from day count since epoch)
 // Algorithm from Howard Hinnant's chrono-Compatible Low-Level Date Algorithms
@@ -430,198 +189,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn validate_deposit_good() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "1230000000000".into(),
-                label: Some("example".into()),
-            }],
-            target_address: None,
-            token: None,
-        };
-        validate_deposit(&deposit).unwrap();
-    }
-
-    #[test]
-    fn validate_deposit_bad_version() {
-        let deposit = DepositFile {
-            version: "v1".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "100".into(),
-                label: None,
-            }],
-            target_address: None,
-            token: None,
-        };
-        assert!(validate_deposit(&deposit).is_err());
-    }
-
-    #[test]
-    fn validate_deposit_empty_notes() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![],
-            target_address: None,
-            token: None,
-        };
-        assert!(validate_deposit(&deposit).is_err());
-    }
-
-    #[test]
-    fn validate_deposit_zero_amount() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "0".into(),
-                label: None,
-            }],
-            target_address: None,
-            token: None,
-        };
-        assert!(validate_deposit(&deposit).is_err());
-    }
-
-    #[test]
-    fn derive_deposit_info_computes_correctly() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![
-                DepositNote {
-                    recipient: "0x1111111111111111111111111111111111111111".into(),
-                    amount: "1230000000000".into(),
-                    label: Some("note #0".into()),
-                },
-                DepositNote {
-                    recipient: "0x2222222222222222222222222222222222222222".into(),
-                    amount: "4560000000000".into(),
-                    label: None,
-                },
-            ],
-            target_address: None,
-            token: None,
-        };
-
-        let info = derive_deposit_info(&deposit).unwrap();
-        assert_eq!(info.chain_id, 167013);
-        assert_eq!(info.notes.len(), 2);
-        assert_eq!(info.total_amount, 1230000000000 + 4560000000000);
-        assert_eq!(info.notes[0].index, 0);
-        assert_eq!(info.notes[1].index, 1);
-        assert_eq!(info.notes[0].amount, 1230000000000);
-        assert_eq!(info.notes[1].amount, 4560000000000);
-        // Nullifiers should differ per note index
-        assert_ne!(info.notes[0].nullifier, info.notes[1].nullifier);
-        // Target address should be 20 bytes (non-zero)
-        assert_ne!(info.target_address, [0u8; 20]);
-    }
-
-    #[test]
-    fn derive_deposit_info_verifies_target_address() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "1230000000000".into(),
-                label: None,
-            }],
-            target_address: Some("0x0000000000000000000000000000000000000001".into()),
-            token: None,
-        };
-
-        // Should fail because the computed target address won't match
-        assert!(derive_deposit_info(&deposit).is_err());
-    }
-
-    #[test]
-    fn validate_deposit_good_with_token() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "1230000000000".into(),
-                label: None,
-            }],
-            target_address: None,
-            token: Some("0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF".into()),
-        };
-        validate_deposit(&deposit).unwrap();
-    }
-
-    #[test]
-    fn validate_deposit_rejects_bad_token_address() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "100".into(),
-                label: None,
-            }],
-            target_address: None,
-            token: Some("0xBAD".into()),
-        };
-        assert!(validate_deposit(&deposit).is_err());
-    }
-
-    #[test]
-    fn derive_deposit_info_with_token() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "1230000000000".into(),
-                label: None,
-            }],
-            target_address: None,
-            token: Some("0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF".into()),
-        };
-        let info = derive_deposit_info(&deposit).unwrap();
-        assert!(info.token.is_some());
-        let token_bytes = info.token.unwrap();
-        assert_eq!(token_bytes[0], 0xDE);
-        assert_eq!(token_bytes[19], 0xeF);
-    }
-
-    #[test]
-    fn derive_deposit_info_without_token() {
-        let deposit = DepositFile {
-            version: "v2".into(),
-            chain_id: "167013".into(),
-            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
-            notes: vec![DepositNote {
-                recipient: "0x1111111111111111111111111111111111111111".into(),
-                amount: "1230000000000".into(),
-                label: None,
-            }],
-            target_address: None,
-            token: None,
-        };
-        let info = derive_deposit_info(&deposit).unwrap();
-        assert!(info.token.is_none());
-    }
-
     #[test]
     fn format_timestamp_secs_epoch() {
         assert_eq!(format_timestamp_secs(0), "19700101T000000");
@@ -7,17 +7,25 @@ pub mod deposit;
 
 use std::{
     env, fs,
+    io::Read,
     path::{Path, PathBuf},
     time::Instant,
 };
 
 use anyhow::{anyhow, bail, Context, Result};
-use risc0_zkvm::{default_prover, ExecutorEnv, InnerReceipt, ProverOpts, Receipt};
+use risc0_zkvm::{
+    default_executor, default_prover, sha::Digestible, ExecutorEnv, InnerReceipt, ProverOpts,
+};
 use serde::{Deserialize, Serialize};
-use shadow_proof_core::{evaluate_claim, unpack_journal, ClaimInput, ClaimJournal, MAX_NOTES};
+use shadow_proof_core::{
+    compute_notes_hash, derive_target_address, evaluate_claim_detailed, unpack_journal,
+    ClaimFailure, ClaimInput, ClaimJournal, MAX_NOTES, MAX_NOTE_WEI, PACKED_JOURNAL_FIELDS,
+    PACKED_JOURNAL_LEN,
+};
 use shadow_risc0_methods::{SHADOW_CLAIM_GUEST_ELF, SHADOW_CLAIM_GUEST_ID};
 
 // Re-export types that callers need
+pub use risc0_zkvm::{Groth16ReceiptVerifierParameters, Receipt};
 pub use shadow_proof_core::{ClaimInput as ClaimInputCore, ClaimJournal as ClaimJournalCore};
 
 /// The RISC Zero guest program image ID (circuit ID).
@@ -32,11 +40,64 @@ pub fn circuit_id() -> [u32; 8] {
 /// byte order, words 0 → 7.  This is the representation the on-chain
 /// `RiscZeroGroth16Verifier.verify()` expects for `imageId`.
 pub fn circuit_id_hex() -> String {
-    let id = SHADOW_CLAIM_GUEST_ID;
+    image_id_hex(&SHADOW_CLAIM_GUEST_ID)
+}
+
+/// Hex-encode any image ID in the same byte order as `circuit_id_hex()`.
+pub fn image_id_hex(id: &[u32; 8]) -> String {
     let bytes: Vec<u8> = id.iter().flat_map(|w| w.to_le_bytes()).collect();
     format!("0x{}", hex::encode(bytes))
 }
 
+/// Circuit metadata an integrator needs to deploy and call the on-chain
+/// verifier, as written by the host's `export-circuit-info`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitInfo {
+    /// [`circuit_id_hex`].
+    pub image_id: String,
+    pub image_id_words: [u32; 8],
+    /// Seal prefix expected by a Groth16 verifier with the default parameters.
+    pub groth16_selector: String,
+    pub packed_journal_len: usize,
+    pub packed_journal_layout: Vec<JournalField>,
+    pub max_notes: usize,
+    /// Per-note cap on ETH amounts, in wei (decimal string).
+    pub max_note_wei: String,
+    /// `maxNotes * maxNoteWei`: the largest ETH deposit, in wei (decimal string).
+    pub max_total_wei: String,
+}
+
+/// One field of the packed journal; integers are little-endian.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalField {
+    pub name: &'static str,
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// Metadata of the compiled circuit.
+pub fn circuit_info() -> CircuitInfo {
+    let selector = groth16_selector(&Groth16ReceiptVerifierParameters::default());
+    CircuitInfo {
+        image_id: circuit_id_hex(),
+        image_id_words: circuit_id(),
+        groth16_selector: format!("0x{}", hex::encode(selector)),
+        packed_journal_len: PACKED_JOURNAL_LEN,
+        packed_journal_layout: PACKED_JOURNAL_FIELDS
+            .iter()
+            .map(|&(name, offset, length)| JournalField {
+                name,
+                offset,
+                length,
+            })
+            .collect(),
+        max_notes: MAX_NOTES,
+        max_note_wei: MAX_NOTE_WEI.to_string(),
+        max_total_wei: (MAX_NOTES as u128 * MAX_NOTE_WEI).to_string(),
+    }
+}
+
 /// Exported proof payload for on-chain submission.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportedProof {
@@ -45,6 +106,130 @@ pub struct ExportedProof {
     pub journal_hex: String,
 }
 
+/// What was being done with a receipt when it was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptOperation {
+    Export,
+    Compress,
+}
+
+impl ReceiptOperation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Export => "export",
+            Self::Compress => "compress",
+        }
+    }
+}
+
+/// A receipt kind [`export_proof`] or [`compress_receipt`] cannot handle,
+/// carried by [`ProverError::Export`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedReceipt {
+    pub operation: ReceiptOperation,
+    /// The receipt kind, as named by [`describe_receipt_kind`].
+    pub kind: &'static str,
+    /// Debug form of the variant's discriminant, for kinds this crate does not name.
+    pub discriminant: String,
+}
+
+impl UnsupportedReceipt {
+    fn new(operation: ReceiptOperation, inner: &InnerReceipt) -> Self {
+        Self {
+            operation,
+            kind: describe_receipt_kind(inner),
+            discriminant: format!("{:?}", std::mem::discriminant(inner)),
+        }
+    }
+}
+
+impl std::fmt::Display for UnsupportedReceipt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ReceiptOperation::*;
+        match (self.operation, self.kind) {
+            (Export, "composite") => write!(
+                f,
+                "cannot export on-chain proof from composite receipt; \
+                 re-run prove with --receipt-kind succinct"
+            ),
+            (Export, "fake") => write!(
+                f,
+                "cannot export on-chain proof from fake receipt outside dev mode"
+            ),
+            (Compress, "groth16") => write!(f, "receipt is already Groth16"),
+            (Compress, "composite") => write!(
+                f,
+                "cannot compress composite receipt directly to Groth16; \
+                 use --receipt-kind succinct first"
+            ),
+            (operation, "unknown") => write!(
+                f,
+                "unsupported receipt type for {} ({})",
+                operation.as_str(),
+                self.discriminant
+            ),
+            (operation, kind) => write!(f, "cannot {} a {} receipt", operation.as_str(), kind),
+        }
+    }
+}
+
+impl std::error::Error for UnsupportedReceipt {}
+
+/// Why proving, verifying, exporting or reading a receipt failed.
+///
+/// Converts into `anyhow::Error` with `?`, so callers that only report the
+/// error need not match on it.
+#[derive(Debug, thiserror::Error)]
+pub enum ProverError {
+    /// A claim input the guest would reject, caught on the host before
+    /// proving. The guest still runs the same check; this only saves a doomed
+    /// proving attempt.
+    #[error("invalid claim input: {0}")]
+    Validation(ClaimFailure),
+    /// Building the executor environment, running the prover or compressing
+    /// failed.
+    #[error(transparent)]
+    Proving(anyhow::Error),
+    /// A receipt does not verify against the circuit.
+    #[error(transparent)]
+    Verification(anyhow::Error),
+    /// The receipt kind cannot be exported or compressed.
+    #[error(transparent)]
+    Export(#[from] UnsupportedReceipt),
+    /// Reading or writing a receipt file failed.
+    #[error("I/O error on {}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A receipt or its journal could not be encoded or decoded.
+    #[error(transparent)]
+    Serde(anyhow::Error),
+}
+
+/// Whether [`prove_claim`] verifies the receipt it just produced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerifyPolicy {
+    /// Verify against the circuit before returning (default).
+    #[default]
+    Verify,
+    /// Skip verification, e.g. while iterating on guest logic.
+    Skip,
+}
+
+/// Executor limits for large claims; `None` keeps the RISC Zero default.
+///
+/// Each field maps to one `ExecutorEnvBuilder` setting applied before the
+/// environment is built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProveConfig {
+    /// `segment_limit_po2`: each segment runs at most `2^po2` cycles (default
+    /// 2^20). Larger segments mean fewer of them, but more prover memory each.
+    pub segment_limit_po2: Option<u32>,
+    /// `session_limit`: abort execution after this many total cycles.
+    pub session_limit: Option<u64>,
+}
+
 /// Result of a proof generation run.
 pub struct ProveResult {
     pub receipt: Receipt,
@@ -92,16 +277,29 @@ pub fn configure_risc0_env() {
 
 /// Generate a proof for a claim input.
 ///
-/// Returns the receipt and decoded journal.
-pub fn prove_claim(input: &ClaimInput, receipt_kind: &str) -> Result<ProveResult> {
-    let env = ExecutorEnv::builder()
-        .write(input)
-        .context("failed writing claim input to executor env")?
-        .build()
-        .context("failed to build executor env")?;
+/// Returns the receipt and decoded journal. The receipt is verified first
+/// unless `verify` is [`VerifyPolicy::Skip`]. An input the guest would reject
+/// fails with [`ProverError::Validation`] before the prover starts.
+pub fn prove_claim(
+    input: &ClaimInput,
+    receipt_kind: &str,
+    verify: VerifyPolicy,
+) -> Result<ProveResult, ProverError> {
+    prove_claim_with_config(input, receipt_kind, verify, &ProveConfig::default())
+}
+
+/// [`prove_claim`] with executor limits from `config`.
+pub fn prove_claim_with_config(
+    input: &ClaimInput,
+    receipt_kind: &str,
+    verify: VerifyPolicy,
+    config: &ProveConfig,
+) -> Result<ProveResult, ProverError> {
+    evaluate_claim_detailed(input).map_err(ProverError::Validation)?;
+    let env = claim_env(input, config).map_err(ProverError::Proving)?;
 
     let started = Instant::now();
-    let opts = parse_prover_opts(receipt_kind)?;
+    let opts = parse_prover_opts(receipt_kind).map_err(ProverError::Proving)?;
     let prove_info = default_prover()
         .prove_with_opts(env, SHADOW_CLAIM_GUEST_ELF, &opts)
         .map_err(|e| {
@@ -109,15 +307,12 @@ pub fn prove_claim(input: &ClaimInput, receipt_kind: &str) -> Result<ProveResult
             let chain: Vec<String> = std::iter::once(e.to_string())
                 .chain(e.chain().skip(1).map(|c| c.to_string()))
                 .collect();
-            anyhow::anyhow!("prover execution failed: {}", chain.join(" | "))
+            ProverError::Proving(anyhow!("prover execution failed: {}", chain.join(" | ")))
         })?;
     let receipt = prove_info.receipt;
     let elapsed = started.elapsed();
 
-    receipt
-        .verify(SHADOW_CLAIM_GUEST_ID)
-        .context("receipt verification failed immediately after proving")?;
-
+    check_fresh_receipt(&receipt, verify)?;
     let journal = decode_journal(&receipt)?;
 
     Ok(ProveResult {
@@ -127,46 +322,186 @@ pub fn prove_claim(input: &ClaimInput, receipt_kind: &str) -> Result<ProveResult
     })
 }
 
+/// Guest execution statistics from a run without proving.
+#[derive(Debug, Clone)]
+pub struct ExecutionStats {
+    /// User cycles across all segments, without continuation or padding overhead.
+    pub total_cycles: u64,
+    pub segment_count: usize,
+    /// Journal bytes committed by the guest.
+    pub journal: Vec<u8>,
+}
+
+/// Execute the guest on a claim input without proving, to size a proof run
+/// and check the input cheaply. `config` applies the same limits as proving.
+pub fn execute_claim(input: &ClaimInput, config: &ProveConfig) -> Result<ExecutionStats> {
+    let session = default_executor()
+        .execute(claim_env(input, config)?, SHADOW_CLAIM_GUEST_ELF)
+        .context("guest execution failed")?;
+    Ok(ExecutionStats {
+        total_cycles: session.cycles(),
+        segment_count: session.segments.len(),
+        journal: session.journal.bytes,
+    })
+}
+
+fn claim_env(input: &ClaimInput, config: &ProveConfig) -> Result<ExecutorEnv<'static>> {
+    let mut builder = ExecutorEnv::builder();
+    builder
+        .write(input)
+        .context("failed writing claim input to executor env")?
+        .session_limit(config.session_limit);
+    if let Some(po2) = config.segment_limit_po2 {
+        builder.segment_limit_po2(po2);
+    }
+    builder.build().context("failed to build executor env")
+}
+
+fn check_fresh_receipt(receipt: &Receipt, verify: VerifyPolicy) -> Result<(), ProverError> {
+    match verify {
+        VerifyPolicy::Verify => receipt
+            .verify(SHADOW_CLAIM_GUEST_ID)
+            .context("receipt verification failed immediately after proving")
+            .map_err(ProverError::Verification),
+        VerifyPolicy::Skip => Ok(()),
+    }
+}
+
 /// Verify an existing receipt and return the decoded journal.
-pub fn verify_receipt(receipt: &Receipt) -> Result<ClaimJournal> {
+pub fn verify_receipt(receipt: &Receipt) -> Result<ClaimJournal, ProverError> {
     receipt
         .verify(SHADOW_CLAIM_GUEST_ID)
-        .context("receipt verification failed")?;
+        .context("receipt verification failed")
+        .map_err(ProverError::Verification)?;
     decode_journal(receipt)
 }
 
+/// Journal fields a caller expects a receipt to commit to; `None` fields are
+/// not checked.
+#[derive(Debug, Clone, Default)]
+pub struct ClaimJournalExpectation {
+    pub chain_id: Option<u64>,
+    pub recipient: Option<[u8; 20]>,
+    pub amount: Option<u128>,
+    pub nullifier: Option<[u8; 32]>,
+}
+
+impl ClaimJournalExpectation {
+    /// Fail on the first field of `journal` that differs from the expectation.
+    pub fn check(&self, journal: &ClaimJournal) -> Result<()> {
+        if let Some(chain_id) = self.chain_id {
+            if journal.chain_id != chain_id {
+                bail!(
+                    "chain_id mismatch: journal has {} expected {chain_id}",
+                    journal.chain_id
+                );
+            }
+        }
+        if let Some(recipient) = self.recipient {
+            check_bytes_field("recipient", &journal.recipient, &recipient)?;
+        }
+        if let Some(amount) = self.amount {
+            if journal.amount != amount {
+                bail!(
+                    "amount mismatch: journal has {} expected {amount}",
+                    journal.amount
+                );
+            }
+        }
+        if let Some(nullifier) = self.nullifier {
+            check_bytes_field("nullifier", &journal.nullifier, &nullifier)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_bytes_field(name: &str, actual: &[u8], expected: &[u8]) -> Result<()> {
+    if actual != expected {
+        bail!(
+            "{name} mismatch: journal has 0x{} expected 0x{}",
+            hex::encode(actual),
+            hex::encode(expected)
+        );
+    }
+    Ok(())
+}
+
+/// Verify a receipt and check its journal against `expected`, as a relayer
+/// does before submitting a claim on-chain.
+pub fn verify_receipt_against(
+    receipt: &Receipt,
+    expected: &ClaimJournalExpectation,
+) -> Result<ClaimJournal> {
+    let journal = verify_receipt(receipt)?;
+    expected.check(&journal)?;
+    Ok(journal)
+}
+
+/// What a claim input would prove, plus the deposit address it spends from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimInspection {
+    pub block_number: u64,
+    pub chain_id: u64,
+    pub note_index: u32,
+    /// Note amount in wei or token base units (decimal string).
+    pub amount: String,
+    pub nullifier: String,
+    /// EIP-55 checksummed.
+    pub target_address: String,
+}
+
 /// Validate and evaluate a claim input without running the prover.
 ///
-/// Returns the expected journal (as if the proof succeeded).
-pub fn inspect_claim(input: &ClaimInput) -> Result<ClaimJournal> {
-    evaluate_claim(input).map_err(|e| anyhow!("claim evaluation failed: {}", e.as_str()))
+/// Reports the journal fields the proof would commit to (as if it
+/// succeeded) and the deposit's derived target address.
+pub fn inspect_claim(input: &ClaimInput) -> Result<ClaimInspection, ProverError> {
+    let journal = evaluate_claim_detailed(input).map_err(ProverError::Validation)?;
+    let notes_hash = compute_notes_hash(
+        input.note_count as usize,
+        &input.amounts,
+        &input.recipient_hashes,
+    )
+    .map_err(|error| ProverError::Validation(ClaimFailure { error, at: None }))?;
+    let target = derive_target_address(&input.secret, input.chain_id, &notes_hash);
+
+    Ok(ClaimInspection {
+        block_number: journal.block_number,
+        chain_id: journal.chain_id,
+        note_index: input.note_index,
+        amount: journal.amount.to_string(),
+        nullifier: format!("0x{}", hex::encode(journal.nullifier)),
+        target_address: shadow_util::to_checksum_address(&target),
+    })
 }
 
 /// Export seal+journal bytes from a receipt for on-chain verification.
-pub fn export_proof(receipt: &Receipt) -> Result<ExportedProof> {
+///
+/// Fake receipts only export in dev mode, with an empty seal, so the encoding
+/// layers above can be exercised without a real prover.
+pub fn export_proof(receipt: &Receipt) -> Result<ExportedProof, ProverError> {
+    export_proof_with_params(receipt, &Groth16ReceiptVerifierParameters::default())
+}
+
+/// Like [`export_proof`], but prefixes Groth16 seals with the selector of
+/// `params`, for a deployed verifier that does not use the default ones.
+pub fn export_proof_with_params(
+    receipt: &Receipt,
+    params: &Groth16ReceiptVerifierParameters,
+) -> Result<ExportedProof, ProverError> {
     let (receipt_kind, seal_bytes) = match &receipt.inner {
         InnerReceipt::Succinct(inner) => ("succinct".to_string(), inner.get_seal_bytes()),
         InnerReceipt::Groth16(inner) => {
-            use risc0_zkvm::sha::Digestible as _;
-            use risc0_zkvm::Groth16ReceiptVerifierParameters;
-
-            let selector = {
-                let digest = Groth16ReceiptVerifierParameters::default().digest();
-                let mut out = [0u8; 4];
-                out.copy_from_slice(&digest.as_bytes()[..4]);
-                out
-            };
-
+            let selector = groth16_selector(params);
             let mut out = Vec::with_capacity(4 + inner.seal.len());
             out.extend_from_slice(&selector);
             out.extend_from_slice(&inner.seal);
             ("groth16".to_string(), out)
         }
-        InnerReceipt::Composite(_) => bail!(
-            "cannot export on-chain proof from composite receipt; re-run prove with --receipt-kind succinct"
-        ),
-        InnerReceipt::Fake(_) => bail!("cannot export on-chain proof from fake receipt"),
-        _ => bail!("unsupported receipt type for export"),
+        InnerReceipt::Fake(_) if receipt.verify(SHADOW_CLAIM_GUEST_ID).is_ok() => {
+            ("fake".to_string(), Vec::new())
+        }
+        inner => return Err(UnsupportedReceipt::new(ReceiptOperation::Export, inner).into()),
     };
 
     Ok(ExportedProof {
@@ -176,29 +511,115 @@ pub fn export_proof(receipt: &Receipt) -> Result<ExportedProof> {
     })
 }
 
+/// ABI-encode an exported proof as `abi.encode(bytes seal, bytes journal)`,
+/// the `_proof` argument `Risc0CircuitVerifier.decodeProof()` expects. The
+/// contract hashes the journal itself, so the full journal bytes are passed.
+pub fn encode_claim_proof_calldata(exported: &ExportedProof) -> Result<Vec<u8>> {
+    let decode = |hex_str: &str| hex::decode(hex_str.strip_prefix("0x").unwrap_or(hex_str));
+    let seal = decode(&exported.seal_hex).context("invalid seal hex")?;
+    let journal = decode(&exported.journal_hex).context("invalid journal hex")?;
+
+    // Head: offset of seal | offset of journal, both past the two head slots.
+    // Tail: each as length word + data right-padded to a 32-byte boundary.
+    let seal_padded_len = seal.len().div_ceil(32) * 32;
+    let journal_offset = 64 + 32 + seal_padded_len;
+    let mut encoded = Vec::with_capacity(journal_offset + 32 + journal.len().div_ceil(32) * 32);
+    encoded.extend_from_slice(&abi_word(64));
+    encoded.extend_from_slice(&abi_word(journal_offset));
+    for bytes in [&seal, &journal] {
+        encoded.extend_from_slice(&abi_word(bytes.len()));
+        encoded.extend_from_slice(bytes);
+        encoded.resize(encoded.len().div_ceil(32) * 32, 0);
+    }
+    Ok(encoded)
+}
+
+/// `value` as a big-endian ABI `uint256` word.
+fn abi_word(value: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// The 4-byte seal selector a RISC Zero Groth16 verifier deployed with
+/// `params` expects: the leading bytes of the parameters' digest.
+pub fn groth16_selector(params: &Groth16ReceiptVerifierParameters) -> [u8; 4] {
+    use risc0_zkvm::sha::Digestible as _;
+
+    let mut out = [0u8; 4];
+    out.copy_from_slice(&params.digest().as_bytes()[..4]);
+    out
+}
+
 /// Compress a succinct receipt to Groth16 for on-chain verification.
-pub fn compress_receipt(receipt: &Receipt) -> Result<Receipt> {
-    match &receipt.inner {
-        InnerReceipt::Succinct(_) => {}
-        InnerReceipt::Groth16(_) => bail!("Receipt is already Groth16"),
-        InnerReceipt::Composite(_) => bail!(
-            "Cannot compress composite receipt directly to Groth16; use --receipt-kind succinct first"
-        ),
-        _ => bail!("Unsupported receipt type for compression"),
+pub fn compress_receipt(receipt: &Receipt) -> Result<Receipt, ProverError> {
+    if !matches!(receipt.inner, InnerReceipt::Succinct(_)) {
+        return Err(UnsupportedReceipt::new(ReceiptOperation::Compress, &receipt.inner).into());
     }
 
     let prover = default_prover();
     let compressed = prover
         .compress(&ProverOpts::groth16(), receipt)
-        .context("failed to compress receipt to Groth16")?;
+        .context("failed to compress receipt to Groth16")
+        .map_err(ProverError::Proving)?;
 
     compressed
         .verify(SHADOW_CLAIM_GUEST_ID)
-        .context("compressed receipt verification failed")?;
+        .context("compressed receipt verification failed")
+        .map_err(ProverError::Verification)?;
 
     Ok(compressed)
 }
 
+/// Check that `groth16` is a faithful compression of `succinct`.
+///
+/// Both receipts must verify against the circuit and commit byte-equal
+/// journals. Fake receipts are accepted in either role, but only verify in
+/// dev mode.
+pub fn verify_groth16_matches_succinct(succinct: &Receipt, groth16: &Receipt) -> Result<()> {
+    if !matches!(
+        succinct.inner,
+        InnerReceipt::Succinct(_) | InnerReceipt::Fake(_)
+    ) {
+        bail!(
+            "expected a succinct receipt, got {}",
+            describe_receipt_kind(&succinct.inner)
+        );
+    }
+    if !matches!(
+        groth16.inner,
+        InnerReceipt::Groth16(_) | InnerReceipt::Fake(_)
+    ) {
+        bail!(
+            "expected a Groth16 receipt, got {}",
+            describe_receipt_kind(&groth16.inner)
+        );
+    }
+
+    succinct
+        .verify(SHADOW_CLAIM_GUEST_ID)
+        .context("succinct receipt verification failed")?;
+    groth16
+        .verify(SHADOW_CLAIM_GUEST_ID)
+        .context("Groth16 receipt verification failed")?;
+
+    if succinct.journal.bytes != groth16.journal.bytes {
+        bail!("Groth16 journal does not match the succinct receipt's journal");
+    }
+    Ok(())
+}
+
+/// The image ID `receipt` claims to prove, without verifying its seal.
+///
+/// Returns `None` when the claim (or its pre-state) is pruned or cannot be
+/// assembled. Compare against `circuit_id()` to tell which circuit a receipt
+/// targets before attempting a full verify.
+pub fn receipt_claimed_image_id(receipt: &Receipt) -> Option<[u32; 8]> {
+    let claim = receipt.claim().ok()?;
+    let pre = &claim.as_value().ok()?.pre;
+    Some(pre.digest().into())
+}
+
 /// Describe the receipt kind as a human-readable string.
 pub fn describe_receipt_kind(inner: &InnerReceipt) -> &'static str {
     match inner {
@@ -210,30 +631,218 @@ pub fn describe_receipt_kind(inner: &InnerReceipt) -> &'static str {
     }
 }
 
+/// Magic bytes opening a bincode receipt written with a header. Headerless
+/// bincode receipts start with a small little-endian enum tag instead.
+const RECEIPT_MAGIC: [u8; 4] = *b"SHRC";
+
+/// Version of the bincode receipt encoding, bumped when `Receipt`'s layout
+/// changes incompatibly (e.g. a RISC Zero upgrade).
+pub const RECEIPT_FORMAT_VERSION: u16 = 1;
+
+/// Magic, little-endian format version, then CRC-32 of the bincode payload.
+const RECEIPT_HEADER_LEN: usize = 4 + 2 + 4;
+
+/// Encoding of a serialized receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptFormat {
+    /// The default, written by [`write_receipt`]: bincode behind a header
+    /// carrying [`RECEIPT_FORMAT_VERSION`] and a checksum. Headerless receipts
+    /// from earlier releases are still read.
+    Bincode,
+    /// serde_json, as produced by services outside this workspace.
+    Json,
+}
+
 /// Serialize a receipt to bytes (bincode format).
-pub fn serialize_receipt(receipt: &Receipt) -> Result<Vec<u8>> {
-    bincode::serialize(receipt).context("failed serializing receipt")
+pub fn serialize_receipt(receipt: &Receipt) -> Result<Vec<u8>, ProverError> {
+    serialize_receipt_as(receipt, ReceiptFormat::Bincode)
+}
+
+/// Serialize a receipt to bytes in `format`.
+pub fn serialize_receipt_as(
+    receipt: &Receipt,
+    format: ReceiptFormat,
+) -> Result<Vec<u8>, ProverError> {
+    match format {
+        ReceiptFormat::Bincode => {
+            let payload = bincode::serialize(receipt)
+                .context("failed serializing receipt")
+                .map_err(ProverError::Serde)?;
+            let mut bytes = Vec::with_capacity(RECEIPT_HEADER_LEN + payload.len());
+            bytes.extend_from_slice(&RECEIPT_MAGIC);
+            bytes.extend_from_slice(&RECEIPT_FORMAT_VERSION.to_le_bytes());
+            bytes.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+            bytes.extend_from_slice(&payload);
+            Ok(bytes)
+        }
+        ReceiptFormat::Json => serde_json::to_vec(receipt)
+            .context("failed serializing receipt as JSON")
+            .map_err(ProverError::Serde),
+    }
 }
 
 /// Deserialize a receipt from bytes (bincode format).
-pub fn deserialize_receipt(bytes: &[u8]) -> Result<Receipt> {
-    bincode::deserialize(bytes).context("failed deserializing receipt")
+pub fn deserialize_receipt(bytes: &[u8]) -> Result<Receipt, ProverError> {
+    deserialize_receipt_as(bytes, ReceiptFormat::Bincode)
+}
+
+/// Deserialize a receipt from bytes in `format`.
+pub fn deserialize_receipt_as(bytes: &[u8], format: ReceiptFormat) -> Result<Receipt, ProverError> {
+    match format {
+        ReceiptFormat::Bincode => receipt_payload(bytes).and_then(|payload| {
+            bincode::deserialize(payload).context("failed deserializing receipt")
+        }),
+        ReceiptFormat::Json => {
+            serde_json::from_slice(bytes).context("failed deserializing receipt as JSON")
+        }
+    }
+    .map_err(ProverError::Serde)
+}
+
+/// The bincode payload of a receipt, after checking its header if it has one.
+fn receipt_payload(bytes: &[u8]) -> Result<&[u8]> {
+    if !bytes.starts_with(&RECEIPT_MAGIC) {
+        return Ok(bytes);
+    }
+    if bytes.len() < RECEIPT_HEADER_LEN {
+        bail!("receipt header truncated");
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != RECEIPT_FORMAT_VERSION {
+        bail!("receipt format v{version}, expected v{RECEIPT_FORMAT_VERSION}");
+    }
+    let checksum = u32::from_le_bytes(bytes[6..10].try_into().expect("4-byte slice"));
+    let payload = &bytes[RECEIPT_HEADER_LEN..];
+    if crc32fast::hash(payload) != checksum {
+        bail!("receipt checksum mismatch; the file is corrupt");
+    }
+    Ok(payload)
+}
+
+/// Deserialize a receipt that may be bincode or JSON, trying bincode first.
+/// A receipt with a bincode header is never retried as JSON.
+pub fn deserialize_receipt_auto(bytes: &[u8]) -> Result<Receipt, ProverError> {
+    if bytes.starts_with(&RECEIPT_MAGIC) {
+        return deserialize_receipt_as(bytes, ReceiptFormat::Bincode);
+    }
+    let bincode_err = match deserialize_receipt_as(bytes, ReceiptFormat::Bincode) {
+        Ok(receipt) => return Ok(receipt),
+        Err(e) => e,
+    };
+    deserialize_receipt_as(bytes, ReceiptFormat::Json).map_err(|json_err| {
+        ProverError::Serde(anyhow!(
+            "receipt is neither bincode ({bincode_err:#}) nor JSON ({json_err:#})"
+        ))
+    })
 }
 
 /// Write a receipt to a file (bincode format).
-pub fn write_receipt(path: &Path, receipt: &Receipt) -> Result<()> {
+pub fn write_receipt(path: &Path, receipt: &Receipt) -> Result<(), ProverError> {
     ensure_parent(path)?;
     let bytes = serialize_receipt(receipt)?;
-    fs::write(path, bytes)
-        .with_context(|| format!("failed writing receipt to {}", path.display()))?;
-    Ok(())
+    fs::write(path, bytes).map_err(|source| ProverError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
 /// Read a receipt from a file (bincode format).
-pub fn read_receipt(path: &Path) -> Result<Receipt> {
-    let bytes =
-        fs::read(path).with_context(|| format!("failed reading receipt {}", path.display()))?;
-    deserialize_receipt(&bytes)
+pub fn read_receipt(path: &Path) -> Result<Receipt, ProverError> {
+    read_receipt_as(path, Some(ReceiptFormat::Bincode))
+}
+
+/// Read a receipt from a file in `format`, or detect it when `None`.
+pub fn read_receipt_as(path: &Path, format: Option<ReceiptFormat>) -> Result<Receipt, ProverError> {
+    let bytes = fs::read(path).map_err(|source| ProverError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    match format {
+        Some(format) => deserialize_receipt_as(&bytes, format),
+        None => deserialize_receipt_auto(&bytes),
+    }
+    .map_err(|e| match e {
+        ProverError::Serde(e) => {
+            ProverError::Serde(e.context(format!("invalid receipt {}", path.display())))
+        }
+        e => e,
+    })
+}
+
+/// Size and nesting limits for JSON files read from disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonLimits {
+    /// Largest file accepted, in bytes.
+    pub max_bytes: u64,
+    /// Deepest array/object nesting accepted (serde_json also stops at 128).
+    pub max_depth: usize,
+}
+
+/// Default size limit: far above the largest legacy claim input (64 proof
+/// nodes of 4096 bytes, one decimal string per byte).
+pub const DEFAULT_MAX_JSON_BYTES: u64 = 16 * 1024 * 1024;
+
+impl Default for JsonLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_JSON_BYTES,
+            max_depth: 32,
+        }
+    }
+}
+
+/// Read a JSON file, rejecting it before parsing when it breaks `limits`.
+pub fn read_json_file(path: &Path, limits: JsonLimits) -> Result<Vec<u8>> {
+    let file =
+        fs::File::open(path).with_context(|| format!("failed opening {}", path.display()))?;
+    let mut raw = Vec::new();
+    file.take(limits.max_bytes + 1)
+        .read_to_end(&mut raw)
+        .with_context(|| format!("failed reading {}", path.display()))?;
+    if raw.len() as u64 > limits.max_bytes {
+        bail!(
+            "{} exceeds the {}-byte input limit",
+            path.display(),
+            limits.max_bytes
+        );
+    }
+    if json_nesting_exceeds(&raw, limits.max_depth) {
+        bail!(
+            "{} nests deeper than {} levels",
+            path.display(),
+            limits.max_depth
+        );
+    }
+    Ok(raw)
+}
+
+/// Whether arrays/objects in `raw` nest deeper than `max_depth`. Only
+/// brackets outside strings count; malformed JSON is left to the parser.
+fn json_nesting_exceeds(raw: &[u8], max_depth: usize) -> bool {
+    let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+    for &b in raw {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
 }
 
 /// Write a value as pretty-printed JSON to a file.
@@ -276,8 +885,8 @@ pub struct LegacyClaimInput {
 }
 
 /// Load a claim input from a JSON file (supports both native and legacy formats).
-pub fn load_claim_input(path: &Path) -> Result<ClaimInput> {
-    let raw = fs::read(path).with_context(|| format!("failed reading input {}", path.display()))?;
+pub fn load_claim_input(path: &Path, limits: JsonLimits) -> Result<ClaimInput> {
+    let raw = read_json_file(path, limits).context("failed reading claim input")?;
 
     if let Ok(native) = serde_json::from_slice::<ClaimInput>(&raw) {
         return Ok(native);
@@ -349,12 +958,16 @@ pub fn legacy_to_input(legacy: LegacyClaimInput) -> Result<ClaimInput> {
 // Internal helpers
 // ---------------------------------------------------------------------------
 
-fn decode_journal(receipt: &Receipt) -> Result<ClaimJournal> {
+fn decode_journal(receipt: &Receipt) -> Result<ClaimJournal, ProverError> {
     match unpack_journal(&receipt.journal.bytes) {
         Ok(journal) => Ok(journal),
-        Err(packed_err) => receipt.journal.decode::<ClaimJournal>().with_context(|| {
-            format!("failed decoding claim journal; packed decode error: {packed_err}")
-        }),
+        Err(packed_err) => receipt
+            .journal
+            .decode::<ClaimJournal>()
+            .with_context(|| {
+                format!("failed decoding claim journal; packed decode error: {packed_err}")
+            })
+            .map_err(ProverError::Serde),
     }
 }
 
@@ -369,13 +982,18 @@ fn parse_prover_opts(receipt_kind: &str) -> Result<ProverOpts> {
     }
 }
 
-fn ensure_parent(path: &Path) -> Result<()> {
-    let parent = path
-        .parent()
-        .ok_or_else(|| anyhow!("path has no parent: {}", path.display()))?;
-    fs::create_dir_all(parent)
-        .with_context(|| format!("failed creating parent dir {}", parent.display()))?;
-    Ok(())
+fn ensure_parent(path: &Path) -> Result<(), ProverError> {
+    let io_error = |source| ProverError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let parent = path.parent().ok_or_else(|| {
+        io_error(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "path has no parent",
+        ))
+    })?;
+    fs::create_dir_all(parent).map_err(io_error)
 }
 
 fn parse_u8_vec(values: &[String]) -> Result<Vec<u8>> {
@@ -425,11 +1043,454 @@ fn parse_u64(value: &str) -> Result<u64> {
 }
 
 fn parse_u128(value: &str) -> Result<u128> {
-    if let Some(hex_str) = value.strip_prefix("0x") {
-        u128::from_str_radix(hex_str, 16).with_context(|| format!("invalid u128 hex: {value}"))
-    } else {
-        value
-            .parse::<u128>()
-            .with_context(|| format!("invalid u128 value: {value}"))
+    shadow_util::parse_u128(value).with_context(|| format!("invalid u128 value: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm::{FakeReceipt, ReceiptClaim};
+
+    use super::*;
+
+    fn fake_receipt(journal: &[u8]) -> Receipt {
+        let claim = ReceiptClaim::ok(SHADOW_CLAIM_GUEST_ID, journal.to_vec());
+        Receipt::new(
+            InnerReceipt::Fake(FakeReceipt::new(claim)),
+            journal.to_vec(),
+        )
+    }
+
+    #[test]
+    fn compression_check_compares_journals() {
+        env::set_var("RISC0_DEV_MODE", "1");
+        let succinct = fake_receipt(b"shadow journal");
+
+        verify_groth16_matches_succinct(&succinct, &fake_receipt(b"shadow journal")).unwrap();
+
+        let err = verify_groth16_matches_succinct(&succinct, &fake_receipt(b"other journal"))
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err:#}");
+    }
+
+    #[test]
+    fn groth16_selector_follows_verifier_params() {
+        let default = Groth16ReceiptVerifierParameters::default();
+        let custom = Groth16ReceiptVerifierParameters {
+            control_root: risc0_zkvm::sha::Digest::ZERO,
+            ..Groth16ReceiptVerifierParameters::default()
+        };
+        assert_eq!(
+            groth16_selector(&default),
+            groth16_selector(&default.clone())
+        );
+        assert_ne!(groth16_selector(&default), groth16_selector(&custom));
+    }
+
+    #[test]
+    fn receipts_round_trip_in_each_format() {
+        let receipt = fake_receipt(b"shadow journal");
+        let bincode_bytes = serialize_receipt(&receipt).unwrap();
+        for format in [ReceiptFormat::Bincode, ReceiptFormat::Json] {
+            let bytes = serialize_receipt_as(&receipt, format).unwrap();
+            for decoded in [
+                deserialize_receipt_as(&bytes, format).unwrap(),
+                deserialize_receipt_auto(&bytes).unwrap(),
+            ] {
+                assert_eq!(
+                    serialize_receipt(&decoded).unwrap(),
+                    bincode_bytes,
+                    "{format:?}"
+                );
+            }
+        }
+
+        let json = serialize_receipt_as(&receipt, ReceiptFormat::Json).unwrap();
+        assert!(deserialize_receipt(&json).is_err());
+        let err = deserialize_receipt_auto(b"not a receipt").unwrap_err();
+        assert!(err.to_string().contains("neither bincode"), "{err:#}");
+    }
+
+    #[test]
+    fn receipt_header_rejects_other_versions_and_corruption() {
+        let receipt = fake_receipt(b"shadow journal");
+        let bytes = serialize_receipt(&receipt).unwrap();
+        assert_eq!(&bytes[..4], b"SHRC");
+
+        let legacy = bincode::serialize(&receipt).unwrap();
+        let decoded = deserialize_receipt(&legacy).unwrap();
+        assert_eq!(serialize_receipt(&decoded).unwrap(), bytes);
+
+        let mut future = bytes.clone();
+        future[4..6].copy_from_slice(&2u16.to_le_bytes());
+        let err = deserialize_receipt_auto(&future).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("receipt format v2, expected v1"),
+            "{err:#}"
+        );
+
+        let mut corrupt = bytes;
+        *corrupt.last_mut().unwrap() ^= 1;
+        let err = deserialize_receipt(&corrupt).unwrap_err();
+        assert!(format!("{err:#}").contains("checksum mismatch"), "{err:#}");
+    }
+
+    #[test]
+    fn receipt_file_errors_are_classified() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.bin");
+        let Err(ProverError::Io { path, source }) = read_receipt(&missing) else {
+            panic!("expected an I/O error");
+        };
+        assert_eq!(path, missing);
+        assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+
+        let garbage = dir.path().join("garbage.bin");
+        fs::write(&garbage, b"not a receipt").unwrap();
+        let err = read_receipt_as(&garbage, None).unwrap_err();
+        assert!(matches!(err, ProverError::Serde(_)), "{err:#}");
+        assert!(format!("{err:#}").contains("invalid receipt"), "{err:#}");
+    }
+
+    fn sample_journal() -> ClaimJournal {
+        ClaimJournal {
+            block_number: 7,
+            block_hash: [0xbb; 32],
+            chain_id: 167_000,
+            amount: 1_000,
+            recipient: [0x11; 20],
+            nullifier: [0x22; 32],
+            token: [0; 20],
+        }
+    }
+
+    #[test]
+    fn receipt_is_checked_against_the_expected_journal() {
+        env::set_var("RISC0_DEV_MODE", "1");
+        let journal = sample_journal();
+        let receipt = fake_receipt(&shadow_proof_core::pack_journal(&journal));
+
+        let expected = ClaimJournalExpectation {
+            chain_id: Some(167_000),
+            recipient: Some([0x11; 20]),
+            amount: Some(1_000),
+            nullifier: Some([0x22; 32]),
+        };
+        assert_eq!(
+            verify_receipt_against(&receipt, &expected)
+                .unwrap()
+                .nullifier,
+            journal.nullifier
+        );
+        let any = ClaimJournalExpectation::default();
+        assert_eq!(
+            verify_receipt_against(&receipt, &any).unwrap().block_number,
+            7
+        );
+
+        let err = verify_receipt_against(
+            &receipt,
+            &ClaimJournalExpectation {
+                amount: Some(999),
+                ..expected.clone()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "amount mismatch: journal has 1000 expected 999"
+        );
+    }
+
+    #[test]
+    fn each_journal_mismatch_is_reported() {
+        let journal = sample_journal();
+        let mismatch =
+            |expected: ClaimJournalExpectation| expected.check(&journal).unwrap_err().to_string();
+
+        assert_eq!(
+            mismatch(ClaimJournalExpectation {
+                chain_id: Some(1),
+                ..Default::default()
+            }),
+            "chain_id mismatch: journal has 167000 expected 1"
+        );
+        assert_eq!(
+            mismatch(ClaimJournalExpectation {
+                recipient: Some([0x33; 20]),
+                ..Default::default()
+            }),
+            format!(
+                "recipient mismatch: journal has 0x{} expected 0x{}",
+                "11".repeat(20),
+                "33".repeat(20)
+            )
+        );
+        assert_eq!(
+            mismatch(ClaimJournalExpectation {
+                nullifier: Some([0x44; 32]),
+                ..Default::default()
+            }),
+            format!(
+                "nullifier mismatch: journal has 0x{} expected 0x{}",
+                "22".repeat(32),
+                "44".repeat(32)
+            )
+        );
+        // The first mismatching field is the one reported.
+        assert!(mismatch(ClaimJournalExpectation {
+            chain_id: Some(1),
+            nullifier: Some([0x44; 32]),
+            ..Default::default()
+        })
+        .starts_with("chain_id mismatch"));
+    }
+
+    fn composite_receipt() -> Receipt {
+        // `CompositeReceipt` is non-exhaustive, so build an empty one from JSON.
+        let inner: InnerReceipt = serde_json::from_value(serde_json::json!({
+            "Composite": {
+                "segments": [],
+                "assumption_receipts": [],
+                "verifier_parameters": [0, 0, 0, 0, 0, 0, 0, 0],
+            }
+        }))
+        .unwrap();
+        Receipt::new(inner, b"shadow journal".to_vec())
+    }
+
+    fn unsupported(err: ProverError) -> UnsupportedReceipt {
+        let ProverError::Export(unsupported) = err else {
+            panic!("expected an export error, got {err:#}");
+        };
+        unsupported
+    }
+
+    #[test]
+    fn rejected_receipts_report_their_kind() {
+        let composite = unsupported(export_proof(&composite_receipt()).unwrap_err());
+        assert_eq!(composite.operation, ReceiptOperation::Export);
+        assert_eq!(composite.kind, "composite");
+        assert!(composite.to_string().contains("--receipt-kind succinct"));
+
+        // A claim for another image never verifies, even in dev mode.
+        let claim = ReceiptClaim::ok([0x5eu32; 8], b"journal".to_vec());
+        let fake = Receipt::new(
+            InnerReceipt::Fake(FakeReceipt::new(claim)),
+            b"journal".to_vec(),
+        );
+        let fake = unsupported(export_proof(&fake).unwrap_err());
+        assert_eq!(fake.kind, "fake");
+        assert_ne!(fake.discriminant, composite.discriminant);
+
+        let compress = unsupported(compress_receipt(&composite_receipt()).unwrap_err());
+        assert_eq!(compress.operation, ReceiptOperation::Compress);
+        assert_eq!(compress.kind, "composite");
+    }
+
+    #[test]
+    fn oversized_claim_input_is_rejected_before_parsing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.json");
+        fs::write(&path, format!("{{\"pad\": \"{}\"}}", "x".repeat(200))).unwrap();
+
+        let limits = JsonLimits {
+            max_bytes: 128,
+            ..JsonLimits::default()
+        };
+        let err = load_claim_input(&path, limits).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("exceeds the 128-byte input limit"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn deeply_nested_claim_input_is_rejected_gracefully() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.json");
+        let depth = 100_000;
+        fs::write(&path, format!("{}{}", "[".repeat(depth), "]".repeat(depth))).unwrap();
+
+        let err = load_claim_input(&path, JsonLimits::default()).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("nests deeper than 32 levels"),
+            "{err:#}"
+        );
+
+        // Brackets inside strings do not count towards the depth.
+        assert!(!json_nesting_exceeds(br#"{"a": "[[[[\"{{{{"}"#, 1));
+        assert!(json_nesting_exceeds(br#"{"a": [[]]}"#, 2));
+    }
+
+    #[test]
+    fn fake_receipt_exports_empty_seal_in_dev_mode() {
+        let receipt = fake_receipt(b"shadow journal");
+
+        env::set_var("RISC0_DEV_MODE", "1");
+        let exported = export_proof(&receipt).unwrap();
+        assert_eq!(exported.receipt_kind, "fake");
+        assert_eq!(exported.seal_hex, "0x");
+        assert_eq!(
+            exported.journal_hex,
+            format!("0x{}", hex::encode(b"shadow journal"))
+        );
+    }
+
+    #[test]
+    fn skip_policy_bypasses_fresh_receipt_verification() {
+        // A claim for another image never verifies, even in dev mode.
+        let claim = ReceiptClaim::ok([0x5eu32; 8], b"journal".to_vec());
+        let receipt = Receipt::new(
+            InnerReceipt::Fake(FakeReceipt::new(claim)),
+            b"journal".to_vec(),
+        );
+
+        assert!(check_fresh_receipt(&receipt, VerifyPolicy::Verify).is_err());
+        check_fresh_receipt(&receipt, VerifyPolicy::Skip).unwrap();
+    }
+
+    #[test]
+    fn claimed_image_id_reports_compiled_circuit() {
+        // Dev-mode proving yields exactly this fake receipt for the guest.
+        let receipt = fake_receipt(b"shadow journal");
+        assert_eq!(receipt_claimed_image_id(&receipt), Some(circuit_id()));
+
+        let claim = ReceiptClaim::ok([0x5eu32; 8], b"journal".to_vec());
+        let other = Receipt::new(
+            InnerReceipt::Fake(FakeReceipt::new(claim)),
+            b"journal".to_vec(),
+        );
+        assert_eq!(receipt_claimed_image_id(&other), Some([0x5eu32; 8]));
+    }
+
+    #[test]
+    #[ignore = "executes the guest; needs a build without RISC0_SKIP_BUILD"]
+    fn larger_segment_limit_runs_in_fewer_segments() {
+        let input = shadow_proof_core::test_support::build_single_leaf_claim_input(
+            [0x11; 32], 167_013, [0x22; 20], 1_000, 1,
+        );
+        let small = ProveConfig {
+            segment_limit_po2: Some(15),
+            ..ProveConfig::default()
+        };
+        let large = ProveConfig {
+            segment_limit_po2: Some(22),
+            ..ProveConfig::default()
+        };
+        let small_run = execute_claim(&input, &small).unwrap();
+        let large_run = execute_claim(&input, &large).unwrap();
+        assert!(large_run.segment_count < small_run.segment_count);
+        assert_eq!(large_run.journal, small_run.journal);
+
+        let capped = ProveConfig {
+            session_limit: Some(1_000),
+            ..ProveConfig::default()
+        };
+        assert!(execute_claim(&input, &capped).is_err());
+    }
+
+    #[test]
+    fn circuit_info_matches_the_circuit_and_journal_packing() {
+        let info = circuit_info();
+        assert_eq!(info.image_id, circuit_id_hex());
+        assert_eq!(info.image_id_words, circuit_id());
+        assert_eq!(info.groth16_selector.len(), 2 + 8);
+
+        let journal = sample_journal();
+        let packed = shadow_proof_core::pack_journal(&journal);
+        let names: Vec<&str> = info.packed_journal_layout.iter().map(|f| f.name).collect();
+        assert_eq!(
+            names,
+            [
+                "block_number",
+                "block_hash",
+                "chain_id",
+                "amount",
+                "recipient",
+                "nullifier",
+                "token"
+            ]
+        );
+        let field = |i: usize| {
+            let f = &info.packed_journal_layout[i];
+            &packed[f.offset..f.offset + f.length]
+        };
+        assert_eq!(field(0), journal.block_number.to_le_bytes());
+        assert_eq!(field(1), journal.block_hash);
+        assert_eq!(field(2), journal.chain_id.to_le_bytes());
+        assert_eq!(field(3), journal.amount.to_le_bytes());
+        assert_eq!(field(4), journal.recipient);
+        assert_eq!(field(5), journal.nullifier);
+        let last = info.packed_journal_layout.last().unwrap();
+        assert_eq!(last.offset + last.length, info.packed_journal_len);
+    }
+
+    #[test]
+    fn claim_inspection_json_shape() {
+        let input = shadow_proof_core::test_support::build_single_leaf_claim_input(
+            [0x11; 32], 167_013, [0x22; 20], 1_000, 7,
+        );
+        let inspection = inspect_claim(&input).unwrap();
+        let notes_hash = compute_notes_hash(1, &input.amounts, &input.recipient_hashes).unwrap();
+        let target = derive_target_address(&[0x11; 32], 167_013, &notes_hash);
+        let nullifier = shadow_proof_core::derive_nullifier(&[0x11; 32], 167_013, 0, &notes_hash);
+
+        assert_eq!(
+            serde_json::to_value(&inspection).unwrap(),
+            serde_json::json!({
+                "blockNumber": 7,
+                "chainId": 167_013,
+                "noteIndex": 0,
+                "amount": "1000",
+                "nullifier": format!("0x{}", hex::encode(nullifier)),
+                "targetAddress": shadow_util::to_checksum_address(&target),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_input_fails_before_proving() {
+        let mut input = shadow_proof_core::test_support::build_single_leaf_claim_input(
+            [0x11; 32], 167_013, [0x22; 20], 1_000, 1,
+        );
+        input.amount = 999;
+
+        // Had the prover run, this would be a guest panic wrapped in
+        // "prover execution failed", not a validation error.
+        let Err(err) = prove_claim(&input, "succinct", VerifyPolicy::Verify) else {
+            panic!("an invalid input was proved");
+        };
+        assert!(matches!(err, ProverError::Validation(_)), "{err:#}");
+        assert_eq!(
+            err.to_string(),
+            "invalid claim input: selected note amount does not match public amount"
+        );
+    }
+
+    #[test]
+    fn claim_proof_calldata_matches_abi_layout() {
+        let exported = ExportedProof {
+            receipt_kind: "groth16".to_string(),
+            seal_hex: format!("0x{}", "aa".repeat(36)),
+            journal_hex: format!("0x{}", "bb".repeat(3)),
+        };
+        let encoded = encode_claim_proof_calldata(&exported).unwrap();
+
+        let word = |i: usize| &encoded[i * 32..(i + 1) * 32];
+        // Seal offset, journal offset: 64 + 32 + 64 (36 bytes padded).
+        assert_eq!(word(0), abi_word(64));
+        assert_eq!(word(1), abi_word(160));
+        assert_eq!(word(2), abi_word(36));
+        assert_eq!(&encoded[96..132], [0xaa; 36]);
+        assert_eq!(&encoded[132..160], [0; 28]);
+        assert_eq!(word(5), abi_word(3));
+        assert_eq!(&encoded[192..195], [0xbb; 3]);
+        assert_eq!(&encoded[195..], [0; 29]);
+
+        let bad = ExportedProof {
+            seal_hex: "0xzz".to_string(),
+            ..exported
+        };
+        assert!(encode_claim_proof_calldata(&bad).is_err());
     }
 }
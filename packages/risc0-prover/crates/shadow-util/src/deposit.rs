@@ -0,0 +1,761 @@
+//! The v2 deposit file format, its validation, and derivation of the
+//! deposit's target address and nullifiers.
+//!
+//! ```json
+//! {
+//!   "version": "v2",
+//!   "chainId": "167013",
+//!   "secret": "0x...",
+//!   "notes": [{ "recipient": "0x...", "amount": "123", "label": "..." }],
+//!   "targetAddress": "0x..."
+//! }
+//! ```
+//!
+//! Note order is significant; see [`NoteOrder`].
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use shadow_proof_core::{
+    compute_notes_hash, compute_recipient_hash, derive_nullifiers, derive_target_address, MAX_NOTES,
+};
+use zeroize::Zeroizing;
+
+use crate::{parse_u128, Hex20, Hex32, MAX_NOTE_DESCRIPTION_LEN, MAX_NOTE_LABEL_LEN};
+
+/// A parsed deposit file (v2 schema).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositFile {
+    pub version: String,
+    /// Creation time as `YYYYMMDDTHHMMSS` UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    pub chain_id: String,
+    pub secret: String,
+    pub notes: Vec<DepositNote>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Token contract address (0x-prefixed hex). Absent/null = ETH.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "NoteOrder::is_listed")]
+    pub note_order: NoteOrder,
+}
+
+/// The order a deposit's notes are derived in.
+///
+/// Note order is load-bearing: the notes hash covers notes by position, so
+/// the order fixes the target address, every nullifier and each note's
+/// index. `listed` (the default) uses the `notes` array as written.
+/// `canonical` sorts notes by recipient, then amount, before derivation, so
+/// tools that do not preserve array order still derive the same deposit;
+/// note indices then refer to the sorted order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteOrder {
+    #[default]
+    Listed,
+    Canonical,
+}
+
+impl NoteOrder {
+    fn is_listed(&self) -> bool {
+        *self == Self::Listed
+    }
+}
+
+/// A single note within a deposit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositNote {
+    pub recipient: String,
+    pub amount: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Optional long-form note text; `label` stays the short display name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Derived information from a deposit file (addresses, nullifiers, hashes).
+#[derive(Debug, Clone)]
+pub struct DerivedDepositInfo {
+    /// The chain ID as u64.
+    pub chain_id: u64,
+    /// The 32-byte secret, scrubbed from memory on drop.
+    pub secret: Zeroizing<[u8; 32]>,
+    /// The derived target address (20 bytes).
+    pub target_address: [u8; 20],
+    /// The SHA-256 notes hash.
+    pub notes_hash: [u8; 32],
+    /// Per-note derived info.
+    pub notes: Vec<DerivedNoteInfo>,
+    /// Total amount across all notes (in wei).
+    pub total_amount: u128,
+    /// Token contract address (None = ETH).
+    pub token: Option<[u8; 20]>,
+}
+
+/// Per-note derived information.
+#[derive(Debug, Clone)]
+pub struct DerivedNoteInfo {
+    /// Note index (0-based).
+    pub index: u32,
+    /// Recipient address (20 bytes).
+    pub recipient: [u8; 20],
+    /// Amount in wei.
+    pub amount: u128,
+    /// Optional label.
+    pub label: Option<String>,
+    /// Optional long-form note text.
+    pub description: Option<String>,
+    /// The nullifier for this note: SHA-256(magic || chainId || secret || noteIndex || notesHash).
+    pub nullifier: [u8; 32],
+    /// The recipient hash: SHA-256(magic || left-padded recipient).
+    pub recipient_hash: [u8; 32],
+}
+
+/// Validate a deposit file against the v2 schema constraints, including
+/// that a present `targetAddress` matches what the notes derive.
+pub fn validate_deposit(deposit: &DepositFile) -> Result<()> {
+    derive_deposit_info(deposit).map(drop)
+}
+
+/// Check each field's format and limits, without deriving anything.
+fn check_fields(deposit: &DepositFile) -> Result<()> {
+    if deposit.version != "v2" {
+        bail!(
+            "unsupported deposit version: {} (expected v2)",
+            deposit.version
+        );
+    }
+
+    // chainId must be a decimal number string
+    if deposit.chain_id.is_empty() || !deposit.chain_id.chars().all(|c| c.is_ascii_digit()) {
+        bail!("invalid chainId: must be a decimal number string");
+    }
+    if deposit.chain_id.bytes().all(|b| b == b'0') {
+        bail!("invalid chainId: must be non-zero");
+    }
+
+    // secret must be a 32-byte hex string (0x-prefixed, 64 hex chars)
+    deposit.secret.parse::<Hex32>().context("invalid secret")?;
+
+    // notes: 1..=5
+    if deposit.notes.is_empty() || deposit.notes.len() > MAX_NOTES {
+        bail!(
+            "invalid note count: {} (must be 1..{})",
+            deposit.notes.len(),
+            MAX_NOTES
+        );
+    }
+
+    for (i, note) in deposit.notes.iter().enumerate() {
+        // recipient must be a 20-byte hex address
+        note.recipient
+            .parse::<Hex20>()
+            .with_context(|| format!("invalid recipient in note {}", i))?;
+
+        // amount must be a non-zero decimal or 0x-prefixed hex number
+        let amount = parse_u128(&note.amount)
+            .with_context(|| format!("invalid amount in note {}: {}", i, note.amount))?;
+        if amount == 0 {
+            bail!("note {} amount must be non-zero", i);
+        }
+
+        // label (optional) max 64 chars
+        if let Some(ref label) = note.label {
+            if label.len() > MAX_NOTE_LABEL_LEN {
+                bail!("note {} label exceeds {} characters", i, MAX_NOTE_LABEL_LEN);
+            }
+        }
+
+        // description (optional) max 512 chars
+        if let Some(ref description) = note.description {
+            if description.len() > MAX_NOTE_DESCRIPTION_LEN {
+                bail!(
+                    "note {} description exceeds {} characters",
+                    i,
+                    MAX_NOTE_DESCRIPTION_LEN
+                );
+            }
+        }
+    }
+
+    // token (optional)
+    if let Some(ref token) = deposit.token {
+        token.parse::<Hex20>().context("invalid token address")?;
+    }
+
+    // targetAddress (optional); derivation checks that it matches
+    if let Some(ref addr) = deposit.target_address {
+        addr.parse::<Hex20>().context("invalid targetAddress")?;
+    }
+
+    Ok(())
+}
+
+/// Validate a deposit file and derive all cryptographic information from it.
+///
+/// This computes:
+/// - Target address from (secret, chainId, notesHash)
+/// - Nullifiers for each note from (secret, chainId, noteIndex, notesHash)
+/// - Notes hash, total amount
+pub fn derive_deposit_info(deposit: &DepositFile) -> Result<DerivedDepositInfo> {
+    derive_deposit_info_with(deposit, compute_recipient_hash)
+}
+
+/// [`derive_deposit_info`] with recipient hashes taken from `recipient_hash`,
+/// so callers that derive many deposits can cache them.
+pub fn derive_deposit_info_with(
+    deposit: &DepositFile,
+    recipient_hash: impl Fn(&[u8; 20]) -> [u8; 32],
+) -> Result<DerivedDepositInfo> {
+    check_fields(deposit)?;
+    let chain_id: u64 = deposit
+        .chain_id
+        .parse()
+        .context("chainId is not a valid u64")?;
+    let secret = Zeroizing::new(deposit.secret.parse::<Hex32>()?.into_bytes());
+
+    let notes = ordered_notes(deposit)?;
+    let note_count = notes.len();
+    let amounts: Vec<u128> = notes.iter().map(|n| n.amount).collect();
+    let recipient_hashes: Vec<[u8; 32]> =
+        notes.iter().map(|n| recipient_hash(&n.recipient)).collect();
+    let total_amount = amounts
+        .iter()
+        .try_fold(0u128, |total, &amount| total.checked_add(amount))
+        .context("total amount overflow")?;
+
+    let notes_hash = notes_hash_of(&amounts, &recipient_hashes)?;
+    let target_address = derive_target_address(&secret, chain_id, &notes_hash);
+
+    let nullifiers = deposit_nullifiers(&secret, chain_id, &notes_hash, note_count as u32);
+    let mut derived_notes = Vec::with_capacity(note_count);
+    for (i, (note, nullifier)) in notes.iter().zip(nullifiers).enumerate() {
+        derived_notes.push(DerivedNoteInfo {
+            index: i as u32,
+            recipient: note.recipient,
+            amount: note.amount,
+            label: note.note.label.clone(),
+            description: note.note.description.clone(),
+            nullifier,
+            recipient_hash: recipient_hashes[i],
+        });
+    }
+
+    // If targetAddress is present in the deposit file, verify it matches
+    if let Some(ref expected_addr) = deposit.target_address {
+        let expected = expected_addr.parse::<Hex20>()?.into_bytes();
+        if expected != target_address {
+            let hint = if another_order_matches(
+                &secret,
+                chain_id,
+                &amounts,
+                &recipient_hashes,
+                &expected,
+            )? {
+                " (the notes derive it in a different order; note order is significant)"
+            } else {
+                ""
+            };
+            bail!(
+                "targetAddress mismatch: file says 0x{} but derived 0x{}{}",
+                hex::encode(expected),
+                hex::encode(target_address),
+                hint
+            );
+        }
+    }
+
+    let token = deposit
+        .token
+        .as_ref()
+        .map(|t| t.parse::<Hex20>().map(Hex20::into_bytes))
+        .transpose()
+        .context("invalid token address")?;
+
+    Ok(DerivedDepositInfo {
+        chain_id,
+        secret,
+        target_address,
+        notes_hash,
+        notes: derived_notes,
+        total_amount,
+        token,
+    })
+}
+
+/// A note with its recipient and amount parsed.
+struct ParsedNote<'a> {
+    recipient: [u8; 20],
+    amount: u128,
+    note: &'a DepositNote,
+}
+
+/// The deposit's notes in the order they are derived in.
+fn ordered_notes(deposit: &DepositFile) -> Result<Vec<ParsedNote<'_>>> {
+    let mut notes = deposit
+        .notes
+        .iter()
+        .enumerate()
+        .map(|(i, note)| {
+            Ok(ParsedNote {
+                recipient: note.recipient.parse::<Hex20>()?.into_bytes(),
+                amount: parse_u128(&note.amount)
+                    .with_context(|| format!("invalid amount in note {}", i))?,
+                note,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if deposit.note_order == NoteOrder::Canonical {
+        notes.sort_by_key(|n| (n.recipient, n.amount));
+    }
+    Ok(notes)
+}
+
+fn notes_hash_of(amounts: &[u128], recipient_hashes: &[[u8; 32]]) -> Result<[u8; 32]> {
+    compute_notes_hash(amounts.len(), amounts, recipient_hashes)
+        .map_err(|e| anyhow::anyhow!("notes hash computation failed: {}", e.as_str()))
+}
+
+/// Whether some reordering of the notes derives `expected`.
+fn another_order_matches(
+    secret: &[u8; 32],
+    chain_id: u64,
+    amounts: &[u128],
+    recipient_hashes: &[[u8; 32]],
+    expected: &[u8; 20],
+) -> Result<bool> {
+    let mut order: Vec<usize> = (0..amounts.len()).collect();
+    while next_permutation(&mut order) {
+        let amounts: Vec<u128> = order.iter().map(|&i| amounts[i]).collect();
+        let hashes: Vec<[u8; 32]> = order.iter().map(|&i| recipient_hashes[i]).collect();
+        let notes_hash = notes_hash_of(&amounts, &hashes)?;
+        if derive_target_address(secret, chain_id, &notes_hash) == *expected {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Advance `order` to its next lexicographic permutation, or return false
+/// once it is the last one.
+fn next_permutation(order: &mut [usize]) -> bool {
+    let Some(i) = (1..order.len()).rev().find(|&i| order[i - 1] < order[i]) else {
+        return false;
+    };
+    let j = (i..order.len())
+        .rev()
+        .find(|&j| order[j] > order[i - 1])
+        .unwrap();
+    order.swap(i - 1, j);
+    order[i..].reverse();
+    true
+}
+
+/// Nullifiers of notes `0..note_count` of a deposit, in note order.
+///
+/// For batch status checks against the chain when the secret and notes hash
+/// are already known, without building a `DepositFile`.
+pub fn deposit_nullifiers(
+    secret: &[u8; 32],
+    chain_id: u64,
+    notes_hash: &[u8; 32],
+    note_count: u32,
+) -> Vec<[u8; 32]> {
+    derive_nullifiers(secret, chain_id, notes_hash, note_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_deposit_good() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "1230000000000".into(),
+                label: Some("example".into()),
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        validate_deposit(&deposit).unwrap();
+    }
+
+    #[test]
+    fn validate_deposit_note_description_limit() {
+        let deposit_with = |description: String| DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "1230000000000".into(),
+                label: Some("rent".into()),
+                description: Some(description),
+            }],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+
+        validate_deposit(&deposit_with("x".repeat(MAX_NOTE_DESCRIPTION_LEN))).unwrap();
+        let err =
+            validate_deposit(&deposit_with("x".repeat(MAX_NOTE_DESCRIPTION_LEN + 1))).unwrap_err();
+        assert!(err.to_string().contains("description"));
+    }
+
+    #[test]
+    fn validate_deposit_bad_version() {
+        let deposit = DepositFile {
+            version: "v1".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "100".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        assert!(validate_deposit(&deposit).is_err());
+    }
+
+    #[test]
+    fn validate_deposit_zero_chain_id() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "0".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "100".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        let err = validate_deposit(&deposit).unwrap_err();
+        assert!(err.to_string().contains("non-zero"));
+    }
+
+    #[test]
+    fn validate_deposit_empty_notes() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        assert!(validate_deposit(&deposit).is_err());
+    }
+
+    #[test]
+    fn validate_deposit_zero_amount() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "0".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        assert!(validate_deposit(&deposit).is_err());
+    }
+
+    #[test]
+    fn derive_deposit_info_computes_correctly() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![
+                DepositNote {
+                    recipient: "0x1111111111111111111111111111111111111111".into(),
+                    amount: "1230000000000".into(),
+                    label: Some("note #0".into()),
+                    description: None,
+                },
+                DepositNote {
+                    recipient: "0x2222222222222222222222222222222222222222".into(),
+                    amount: "4560000000000".into(),
+                    label: None,
+                    description: None,
+                },
+            ],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+
+        let info = derive_deposit_info(&deposit).unwrap();
+        assert_eq!(info.chain_id, 167013);
+        assert_eq!(info.notes.len(), 2);
+        assert_eq!(info.total_amount, 1230000000000 + 4560000000000);
+        assert_eq!(info.notes[0].index, 0);
+        assert_eq!(info.notes[1].index, 1);
+        assert_eq!(info.notes[0].amount, 1230000000000);
+        assert_eq!(info.notes[1].amount, 4560000000000);
+        // Nullifiers should differ per note index
+        assert_ne!(info.notes[0].nullifier, info.notes[1].nullifier);
+        // Target address should be 20 bytes (non-zero)
+        assert_ne!(info.target_address, [0u8; 20]);
+    }
+
+    #[test]
+    fn deposit_nullifiers_match_derived_note_nullifiers() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: (1..=3u8)
+                .map(|i| DepositNote {
+                    recipient: format!("0x{}", hex::encode([i; 20])),
+                    amount: "1000".into(),
+                    label: None,
+                    description: None,
+                })
+                .collect(),
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        let info = derive_deposit_info(&deposit).unwrap();
+
+        let nullifiers = deposit_nullifiers(&info.secret, info.chain_id, &info.notes_hash, 3);
+        let expected: Vec<[u8; 32]> = info.notes.iter().map(|n| n.nullifier).collect();
+        assert_eq!(nullifiers, expected);
+        assert!(deposit_nullifiers(&info.secret, info.chain_id, &info.notes_hash, 0).is_empty());
+    }
+
+    fn three_note_deposit(note_order: NoteOrder) -> DepositFile {
+        DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: [(3u8, "300"), (1, "100"), (2, "200")]
+                .into_iter()
+                .map(|(i, amount)| DepositNote {
+                    recipient: format!("0x{}", hex::encode([i; 20])),
+                    amount: amount.into(),
+                    label: Some(format!("note {i}")),
+                    description: None,
+                })
+                .collect(),
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order,
+        }
+    }
+
+    #[test]
+    fn reordering_notes_changes_the_target_unless_canonical() {
+        for order in [NoteOrder::Listed, NoteOrder::Canonical] {
+            let deposit = three_note_deposit(order);
+            let mut reordered = deposit.clone();
+            reordered.notes.reverse();
+
+            let target = derive_deposit_info(&deposit).unwrap().target_address;
+            let reordered_target = derive_deposit_info(&reordered).unwrap().target_address;
+            assert_eq!(target == reordered_target, order == NoteOrder::Canonical);
+        }
+
+        let info = derive_deposit_info(&three_note_deposit(NoteOrder::Canonical)).unwrap();
+        let labels: Vec<_> = info
+            .notes
+            .iter()
+            .map(|n| n.label.clone().unwrap())
+            .collect();
+        assert_eq!(labels, ["note 1", "note 2", "note 3"]);
+    }
+
+    #[test]
+    fn validate_deposit_flags_a_target_address_from_another_note_order() {
+        let mut deposit = three_note_deposit(NoteOrder::Listed);
+        let target = derive_deposit_info(&deposit).unwrap().target_address;
+        deposit.target_address = Some(format!("0x{}", hex::encode(target)));
+        validate_deposit(&deposit).unwrap();
+
+        deposit.notes.swap(0, 2);
+        let err = validate_deposit(&deposit).unwrap_err();
+        assert!(
+            err.to_string().contains("note order is significant"),
+            "{err:#}"
+        );
+
+        deposit.target_address = Some(format!("0x{}", "11".repeat(20)));
+        let err = validate_deposit(&deposit).unwrap_err();
+        assert!(!err.to_string().contains("note order"), "{err:#}");
+    }
+
+    #[test]
+    fn derive_deposit_info_verifies_target_address() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "1230000000000".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: Some("0x0000000000000000000000000000000000000001".into()),
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+
+        // Should fail because the computed target address won't match
+        assert!(derive_deposit_info(&deposit).is_err());
+    }
+
+    #[test]
+    fn validate_deposit_good_with_token() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "1230000000000".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: Some("0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF".into()),
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        validate_deposit(&deposit).unwrap();
+    }
+
+    #[test]
+    fn validate_deposit_rejects_bad_token_address() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "100".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: Some("0xBAD".into()),
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        assert!(validate_deposit(&deposit).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::mixed_case_hex_literals)] // mirrors the checksummed address
+    fn derive_deposit_info_with_token() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "1230000000000".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: Some("0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF".into()),
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        let info = derive_deposit_info(&deposit).unwrap();
+        assert!(info.token.is_some());
+        let token_bytes = info.token.unwrap();
+        assert_eq!(token_bytes[0], 0xDE);
+        assert_eq!(token_bytes[19], 0xeF);
+    }
+
+    #[test]
+    fn derive_deposit_info_without_token() {
+        let deposit = DepositFile {
+            version: "v2".into(),
+            created: None,
+            chain_id: "167013".into(),
+            secret: "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa".into(),
+            notes: vec![DepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".into(),
+                amount: "1230000000000".into(),
+                label: None,
+                description: None,
+            }],
+            target_address: None,
+            comment: None,
+            token: None,
+            token_symbol: None,
+            note_order: NoteOrder::Listed,
+        };
+        let info = derive_deposit_info(&deposit).unwrap();
+        assert!(info.token.is_none());
+    }
+}
@@ -0,0 +1,265 @@
+//! Small helpers shared by the prover library and the server.
+
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use tiny_keccak::{Hasher, Keccak};
+
+pub mod deposit;
+
+/// A 20-byte value such as an Ethereum address.
+pub type Hex20 = HexBytes<20>;
+/// A 32-byte value such as a secret, hash or nullifier.
+pub type Hex32 = HexBytes<32>;
+
+/// Fixed-length bytes written as a `0x`-prefixed hex string.
+///
+/// Parsing accepts a `0x` or `0X` prefix followed by exactly `2 * N` hex
+/// digits of either case. Formatting always emits lowercase with `0x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HexBytes<const N: usize>(pub [u8; N]);
+
+/// Why a string could not be parsed as [`HexBytes`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum HexError {
+    MissingPrefix,
+    WrongLength { expected: usize, actual: usize },
+    InvalidHex(hex::FromHexError),
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::MissingPrefix => write!(f, "hex value must start with 0x"),
+            HexError::WrongLength { expected, actual } => write!(
+                f,
+                "expected {} bytes ({} hex chars), got {} hex chars",
+                expected,
+                expected * 2,
+                actual
+            ),
+            HexError::InvalidHex(e) => write!(f, "invalid hex: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
+impl<const N: usize> HexBytes<N> {
+    /// The raw bytes.
+    pub fn into_bytes(self) -> [u8; N] {
+        self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for HexBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> FromStr for HexBytes<N> {
+    type Err = HexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .ok_or(HexError::MissingPrefix)?;
+        if digits.len() != N * 2 {
+            return Err(HexError::WrongLength {
+                expected: N,
+                actual: digits.len(),
+            });
+        }
+        let mut out = [0u8; N];
+        hex::decode_to_slice(digits, &mut out).map_err(HexError::InvalidHex)?;
+        Ok(Self(out))
+    }
+}
+
+impl<const N: usize> fmt::Display for HexBytes<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0))
+    }
+}
+
+impl<const N: usize> Serialize for HexBytes<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for HexBytes<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Maximum length of a deposit note `label`.
+pub const MAX_NOTE_LABEL_LEN: usize = 64;
+
+/// Maximum length of a deposit note `description`.
+pub const MAX_NOTE_DESCRIPTION_LEN: usize = 512;
+
+/// Format an address with the EIP-55 mixed-case checksum, for display.
+///
+/// Parsing stays case-insensitive; the checksum only lets users spot a
+/// mistyped address when they copy it into a wallet.
+pub fn to_checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(lower.as_bytes());
+    keccak.finalize(&mut hash);
+
+    let mut out = String::with_capacity(42);
+    out.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+        out.push(if nibble >= 8 {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        });
+    }
+    out
+}
+
+/// Why a string could not be parsed by [`parse_u128`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmountError {
+    /// A `+` or `-` sign, which the std integer parsers would accept.
+    Signed,
+    Invalid(ParseIntError),
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountError::Signed => write!(f, "amount must not have a sign"),
+            AmountError::Invalid(e) => write!(f, "invalid amount: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+/// Parse an amount written in decimal or as `0x`/`0X`-prefixed hex.
+///
+/// Only digits may follow the prefix; a sign is rejected.
+pub fn parse_u128(value: &str) -> Result<u128, AmountError> {
+    let (digits, radix) = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(digits) => (digits, 16),
+        None => (value, 10),
+    };
+    if digits.starts_with(['+', '-']) {
+        return Err(AmountError::Signed);
+    }
+    u128::from_str_radix(digits, radix).map_err(AmountError::Invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "0x1111111111111111111111111111111111111111";
+
+    #[test]
+    fn accepts_either_prefix_case() {
+        let lower: Hex20 = ADDRESS.parse().unwrap();
+        let upper: Hex20 = ADDRESS.replacen("0x", "0X", 1).parse().unwrap();
+        assert_eq!(lower, upper);
+        assert_eq!(lower.into_bytes(), [0x11; 20]);
+
+        let mixed: Hex32 = format!("0x{}", "aB".repeat(32)).parse().unwrap();
+        assert_eq!(mixed.0, [0xab; 32]);
+    }
+
+    #[test]
+    fn rejects_missing_prefix() {
+        let err = ADDRESS[2..].parse::<Hex20>().unwrap_err();
+        assert_eq!(err, HexError::MissingPrefix);
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        let err = "0x1234".parse::<Hex20>().unwrap_err();
+        assert_eq!(
+            err,
+            HexError::WrongLength {
+                expected: 20,
+                actual: 4
+            }
+        );
+        assert!(ADDRESS.parse::<Hex32>().is_err());
+        assert!(format!("{ADDRESS}0").parse::<Hex20>().is_err());
+    }
+
+    #[test]
+    fn rejects_non_hex_digits() {
+        let err = format!("0x{}", "zz".repeat(20))
+            .parse::<Hex20>()
+            .unwrap_err();
+        assert!(matches!(err, HexError::InvalidHex(_)));
+    }
+
+    #[test]
+    fn serde_round_trips_as_lowercase_string() {
+        let value: Hex32 = format!("0X{}", "AB".repeat(32)).parse().unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, format!("\"0x{}\"", "ab".repeat(32)));
+        assert_eq!(serde_json::from_str::<Hex32>(&json).unwrap(), value);
+
+        let err = serde_json::from_str::<Hex20>("\"0x12\"").unwrap_err();
+        assert!(err.to_string().contains("expected 20 bytes"));
+    }
+
+    #[test]
+    fn checksum_address_matches_eip55_vectors() {
+        for expected in [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+            "0xde709f2102306220921060314715629080e2fb77",
+            "0x27b1fdb04752bbc536007a920d24acb045561c26",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address: Hex20 = expected.to_lowercase().parse().unwrap();
+            assert_eq!(to_checksum_address(&address.0), expected);
+            assert_eq!(expected.parse::<Hex20>().unwrap(), address);
+        }
+    }
+
+    #[test]
+    fn parse_u128_accepts_decimal_and_hex() {
+        assert_eq!(parse_u128("100"), Ok(100));
+        assert_eq!(parse_u128("0x64"), Ok(100));
+        assert!(parse_u128("0x").is_err());
+        assert!(parse_u128("0xzz").is_err());
+        assert!(parse_u128("64h").is_err());
+    }
+
+    #[test]
+    fn parse_u128_accepts_either_prefix_case() {
+        assert_eq!(parse_u128("0X10"), Ok(16));
+        assert_eq!(parse_u128("0xFf"), Ok(255));
+        assert!(parse_u128("0").is_ok());
+        assert!(parse_u128("x10").is_err());
+    }
+
+    #[test]
+    fn parse_u128_rejects_signs() {
+        for value in ["+100", "-1", "0x+64", "0X-1", "+0x64"] {
+            assert!(parse_u128(value).is_err(), "{value}");
+        }
+        assert_eq!(parse_u128("+100"), Err(AmountError::Signed));
+        assert_eq!(parse_u128("0x+64"), Err(AmountError::Signed));
+    }
+}
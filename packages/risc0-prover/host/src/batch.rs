@@ -0,0 +1,220 @@
+//! `batch-prove`: prove every note of every deposit file in a directory.
+//!
+//! The host does not fetch chain state, so each note's claim input must sit
+//! next to its deposit as `<deposit-stem>/note-<i>.input.json`, e.g. as
+//! served by the server's `claim-input` route. The subdirectory keeps input
+//! files from matching the deposit filename pattern.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use shadow_proof_core::ClaimInput;
+use shadow_prover_lib::{
+    deposit::{
+        deposit_stem, derive_deposit_info, is_deposit_filename, load_deposit, proof_filename,
+        timestamp_now, validate_deposit, DerivedNoteInfo,
+    },
+    inspect_claim, load_claim_input, write_json, write_receipt, JsonLimits, ProveResult,
+    ProverError,
+};
+
+/// Result of proving one note, or of loading a deposit when `note_index` is
+/// `None`.
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub deposit: String,
+    pub note_index: Option<u32>,
+    pub elapsed: Duration,
+    /// The written receipt, or why this entry failed.
+    pub result: Result<PathBuf, String>,
+}
+
+/// Path, relative to the batch directory, of the claim input of note
+/// `note_index` of a deposit.
+pub fn claim_input_path(stem: &str, note_index: u32) -> PathBuf {
+    Path::new(stem).join(format!("note-{note_index}.input.json"))
+}
+
+/// Prove each note of each deposit file in `dir` with `prove`, writing
+/// receipts and journals under `out_dir/<deposit-stem>.proof-<ts>/`.
+///
+/// Failures are recorded per deposit or note and do not stop the batch.
+pub fn batch_prove<P>(
+    dir: &Path,
+    out_dir: &Path,
+    limits: JsonLimits,
+    mut prove: P,
+) -> Result<Vec<BatchOutcome>>
+where
+    P: FnMut(&ClaimInput) -> Result<ProveResult, ProverError>,
+{
+    let mut deposits = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed reading {}", dir.display()))? {
+        let name = entry?.file_name();
+        if let Some(name) = name.to_str().filter(|n| is_deposit_filename(n)) {
+            deposits.push(name.to_string());
+        }
+    }
+    deposits.sort();
+
+    let timestamp = timestamp_now();
+    let mut outcomes = Vec::new();
+    for deposit in deposits {
+        let started = Instant::now();
+        let notes = match deposit_notes(&dir.join(&deposit), limits) {
+            Ok(notes) => notes,
+            Err(e) => {
+                outcomes.push(BatchOutcome {
+                    deposit,
+                    note_index: None,
+                    elapsed: started.elapsed(),
+                    result: Err(format!("{e:#}")),
+                });
+                continue;
+            }
+        };
+
+        let stem = deposit_stem(&deposit);
+        let proof_dir = out_dir.join(deposit_stem(&proof_filename(stem, &timestamp)));
+        for note in &notes {
+            let started = Instant::now();
+            let input_path = dir.join(claim_input_path(stem, note.index));
+            let result = prove_note(&input_path, note, &proof_dir, limits, &mut prove);
+            outcomes.push(BatchOutcome {
+                deposit: deposit.clone(),
+                note_index: Some(note.index),
+                elapsed: started.elapsed(),
+                result: result.map_err(|e| format!("{e:#}")),
+            });
+        }
+    }
+    Ok(outcomes)
+}
+
+fn deposit_notes(path: &Path, limits: JsonLimits) -> Result<Vec<DerivedNoteInfo>> {
+    let deposit = load_deposit(path, limits)?;
+    validate_deposit(&deposit)?;
+    Ok(derive_deposit_info(&deposit)?.notes)
+}
+
+/// Prove one note from its claim input file, after checking the input
+/// claims that note, and return the receipt path.
+fn prove_note<P>(
+    input_path: &Path,
+    note: &DerivedNoteInfo,
+    proof_dir: &Path,
+    limits: JsonLimits,
+    prove: &mut P,
+) -> Result<PathBuf>
+where
+    P: FnMut(&ClaimInput) -> Result<ProveResult, ProverError>,
+{
+    let input = load_claim_input(input_path, limits)?;
+    let nullifier = inspect_claim(&input)?.nullifier;
+    if nullifier != format!("0x{}", hex::encode(note.nullifier)) {
+        bail!(
+            "{} does not claim note {} of this deposit",
+            input_path.display(),
+            note.index
+        );
+    }
+
+    let result = prove(&input)?;
+    let receipt = proof_dir.join(format!("note-{}.receipt.bin", note.index));
+    write_receipt(&receipt, &result.receipt)?;
+    write_json(
+        &proof_dir.join(format!("note-{}.journal.json", note.index)),
+        &result.journal,
+    )?;
+    Ok(receipt)
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, ReceiptClaim};
+    use shadow_proof_core::{evaluate_claim, test_support::build_single_leaf_claim_input};
+    use shadow_prover_lib::{circuit_id, Receipt};
+
+    use super::*;
+
+    const CHAIN_ID: u64 = 167_013;
+    const RECIPIENT: [u8; 20] = [0x22; 20];
+
+    /// Write a one-note deposit and, when it is valid, its claim input.
+    fn write_deposit(dir: &Path, name: &str, secret: [u8; 32], amount: u128) {
+        let deposit = serde_json::json!({
+            "version": "v2",
+            "chainId": CHAIN_ID.to_string(),
+            "secret": format!("0x{}", hex::encode(secret)),
+            "notes": [{
+                "recipient": format!("0x{}", hex::encode(RECIPIENT)),
+                "amount": amount.to_string(),
+            }],
+        });
+        fs::write(dir.join(name), deposit.to_string()).unwrap();
+        if amount > 0 {
+            let input = build_single_leaf_claim_input(secret, CHAIN_ID, RECIPIENT, amount, 1);
+            let path = dir.join(claim_input_path(deposit_stem(name), 0));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, serde_json::to_vec(&input).unwrap()).unwrap();
+        }
+    }
+
+    /// Stand-in for the prover: a fake receipt committing the claim's journal.
+    fn fake_prove(input: &ClaimInput) -> Result<ProveResult, ProverError> {
+        let journal = evaluate_claim(input).unwrap();
+        let bytes = serde_json::to_vec(&journal).unwrap();
+        let claim = ReceiptClaim::ok(circuit_id(), bytes.clone());
+        Ok(ProveResult {
+            receipt: Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), bytes),
+            journal,
+            elapsed: Duration::ZERO,
+        })
+    }
+
+    #[test]
+    fn batch_continues_past_an_invalid_deposit() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        write_deposit(
+            dir.path(),
+            "deposit-aaaa-aaaa-20260101T000000.json",
+            [1; 32],
+            1_000,
+        );
+        write_deposit(
+            dir.path(),
+            "deposit-bbbb-bbbb-20260101T000000.json",
+            [0; 32],
+            0,
+        );
+        write_deposit(
+            dir.path(),
+            "deposit-cccc-cccc-20260101T000000.json",
+            [3; 32],
+            2_000,
+        );
+
+        let outcomes =
+            batch_prove(dir.path(), out.path(), JsonLimits::default(), fake_prove).unwrap();
+
+        assert_eq!(outcomes.len(), 3);
+        let failed: Vec<_> = outcomes.iter().filter(|o| o.result.is_err()).collect();
+        assert_eq!(failed.len(), 1, "{outcomes:#?}");
+        assert!(failed[0].deposit.starts_with("deposit-bbbb"));
+        assert_eq!(failed[0].note_index, None);
+        assert!(failed[0].result.as_ref().unwrap_err().contains("non-zero"));
+
+        for outcome in outcomes.iter().filter(|o| o.result.is_ok()) {
+            assert_eq!(outcome.note_index, Some(0));
+            let receipt = outcome.result.as_ref().unwrap();
+            assert!(receipt.starts_with(out.path()), "{}", receipt.display());
+            assert!(receipt.is_file());
+            assert!(receipt.with_file_name("note-0.journal.json").is_file());
+        }
+    }
+}
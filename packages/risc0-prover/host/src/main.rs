@@ -1,16 +1,24 @@
+mod batch;
+
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
 use shadow_prover_lib::{
-    circuit_id_hex, compress_receipt, configure_risc0_env, describe_receipt_kind, export_proof,
-    inspect_claim, load_claim_input, prove_claim, read_receipt, verify_receipt, write_json,
-    write_receipt,
+    circuit_id, circuit_id_hex, circuit_info, compress_receipt, configure_risc0_env,
+    describe_receipt_kind, encode_claim_proof_calldata, execute_claim, export_proof, image_id_hex,
+    inspect_claim, load_claim_input, prove_claim, prove_claim_with_config, read_receipt,
+    read_receipt_as, receipt_claimed_image_id, verify_groth16_matches_succinct, verify_receipt,
+    write_json, write_receipt, JsonLimits, ProveConfig, ReceiptFormat, VerifyPolicy,
+    DEFAULT_MAX_JSON_BYTES,
 };
 #[derive(Debug, Parser)]
 #[command(name = "shadow-risc0-host")]
 #[command(about = "Local RISC Zero prover for Shadow claims")]
 struct Cli {
+    /// Reject input JSON files larger than this many bytes.
+    #[arg(long, global = true, default_value_t = DEFAULT_MAX_JSON_BYTES)]
+    max_input_bytes: u64,
     #[command(subcommand)]
     command: Command,
 }
@@ -27,16 +35,45 @@ enum Command {
         journal: PathBuf,
         #[arg(long, default_value = "composite")]
         receipt_kind: String,
+        /// Skip verifying the receipt after proving (verify it later with `verify`).
+        #[arg(long)]
+        no_verify: bool,
+        #[command(flatten)]
+        limits: ExecutorLimits,
+    },
+    /// Prove every note of every deposit file in a directory, continuing past
+    /// failures. Each note's claim input must sit next to its deposit as
+    /// `<deposit-stem>/note-<i>.input.json`.
+    BatchProve {
+        #[arg(long)]
+        dir: PathBuf,
+        #[arg(long, default_value = "build/risc0/batch")]
+        out_dir: PathBuf,
+        #[arg(long, default_value = "composite")]
+        receipt_kind: String,
     },
     /// Verify an existing receipt and print decoded journal.
     Verify {
         #[arg(long, default_value = "build/risc0/receipt.bin")]
         receipt: PathBuf,
+        /// Encoding of the receipt file; `auto` tries bincode, then JSON.
+        #[arg(long, value_enum, default_value_t = ReceiptFileFormat::Bincode)]
+        format: ReceiptFileFormat,
     },
     /// Validate and inspect an input without running the prover.
     Inspect {
         #[arg(long)]
         input: PathBuf,
+        /// Print the result as one JSON object instead of `key: value` lines.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Execute the guest on an input without proving and report its cycle count.
+    Stats {
+        #[arg(long)]
+        input: PathBuf,
+        #[command(flatten)]
+        limits: ExecutorLimits,
     },
     /// Export seal+journal bytes from a receipt for on-chain verification payloads.
     ExportProof {
@@ -44,6 +81,10 @@ enum Command {
         receipt: PathBuf,
         #[arg(long, default_value = "build/risc0/proof.json")]
         out: PathBuf,
+        /// Also write `proof_hex`: the ABI-encoded `(bytes seal, bytes journal)`
+        /// passed as the proof argument of `claim`.
+        #[arg(long)]
+        abi: bool,
     },
     /// Compress a succinct receipt to Groth16 for on-chain verification.
     /// This step requires Docker to be available.
@@ -53,14 +94,77 @@ enum Command {
         #[arg(long, default_value = "build/risc0/groth16-receipt.bin")]
         out: PathBuf,
     },
+    /// Check that a Groth16 receipt is a faithful compression of a succinct one.
+    VerifyCompression {
+        #[arg(long)]
+        succinct: PathBuf,
+        #[arg(long)]
+        groth16: PathBuf,
+    },
+    /// Print a receipt's kind and the circuit it claims, without verifying it.
+    ReceiptInfo {
+        #[arg(long, default_value = "build/risc0/receipt.bin")]
+        receipt: PathBuf,
+    },
     /// Print the circuit ID (RISC Zero guest program hash).
-    CircuitId,
+    CircuitId {
+        /// Also print the ID as the `uint32[8]` image ID words.
+        #[arg(long)]
+        words: bool,
+    },
+    /// Write the image ID, Groth16 selector, journal layout and note limits as
+    /// JSON for deploying the on-chain verifier.
+    ExportCircuitInfo {
+        #[arg(long, default_value = "build/risc0/circuit-info.json")]
+        out: PathBuf,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct ExecutorLimits {
+    /// Cycles per segment as a power of two (RISC Zero default 20); larger
+    /// segments mean fewer of them but more prover memory each.
+    #[arg(long)]
+    segment_limit_po2: Option<u32>,
+    /// Abort execution after this many total cycles.
+    #[arg(long)]
+    session_limit: Option<u64>,
+}
+
+impl ExecutorLimits {
+    fn config(&self) -> ProveConfig {
+        ProveConfig {
+            segment_limit_po2: self.segment_limit_po2,
+            session_limit: self.session_limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ReceiptFileFormat {
+    Bincode,
+    Json,
+    Auto,
+}
+
+impl ReceiptFileFormat {
+    fn receipt_format(self) -> Option<ReceiptFormat> {
+        match self {
+            Self::Bincode => Some(ReceiptFormat::Bincode),
+            Self::Json => Some(ReceiptFormat::Json),
+            Self::Auto => None,
+        }
+    }
 }
 
 fn main() -> Result<()> {
     configure_risc0_env();
 
     let cli = Cli::parse();
+    let limits = JsonLimits {
+        max_bytes: cli.max_input_bytes,
+        ..JsonLimits::default()
+    };
 
     match cli.command {
         Command::Prove {
@@ -68,9 +172,22 @@ fn main() -> Result<()> {
             receipt,
             journal,
             receipt_kind,
+            no_verify,
+            limits: executor_limits,
         } => {
-            let claim_input = load_claim_input(&input)?;
-            let result = prove_claim(&claim_input, &receipt_kind)?;
+            let claim_input = load_claim_input(&input, limits)?;
+            let verify = if no_verify {
+                eprintln!("Warning: --no-verify set; the receipt was not verified");
+                VerifyPolicy::Skip
+            } else {
+                VerifyPolicy::Verify
+            };
+            let result = prove_claim_with_config(
+                &claim_input,
+                &receipt_kind,
+                verify,
+                &executor_limits.config(),
+            )?;
 
             write_receipt(&receipt, &result.receipt)?;
             write_json(&journal, &result.journal)?;
@@ -85,29 +202,69 @@ fn main() -> Result<()> {
             );
             Ok(())
         }
-        Command::Verify { receipt } => {
-            let rcpt = read_receipt(&receipt)?;
+        Command::BatchProve {
+            dir,
+            out_dir,
+            receipt_kind,
+        } => {
+            let outcomes = batch::batch_prove(&dir, &out_dir, limits, |input| {
+                prove_claim(input, &receipt_kind, VerifyPolicy::Verify)
+            })?;
+            print_batch_summary(&outcomes);
+            let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+            if failed > 0 {
+                bail!("{failed} of {} batch entries failed", outcomes.len());
+            }
+            Ok(())
+        }
+        Command::Verify { receipt, format } => {
+            let rcpt = read_receipt_as(&receipt, format.receipt_format())?;
             let journal = verify_receipt(&rcpt)?;
             println!("Receipt verified: {}", receipt.display());
             println!("Nullifier: 0x{}", hex::encode(journal.nullifier));
             Ok(())
         }
-        Command::Inspect { input } => {
-            let claim_input = load_claim_input(&input)?;
-            let journal = inspect_claim(&claim_input)?;
+        Command::Inspect { input, json } => {
+            let claim_input = load_claim_input(&input, limits)?;
+            let inspection = inspect_claim(&claim_input)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&inspection)?);
+                return Ok(());
+            }
 
             println!("Input validated: {}", input.display());
-            println!("blockNumber: {}", journal.block_number);
-            println!("chainId: {}", journal.chain_id);
-            println!("noteIndex: {}", claim_input.note_index);
-            println!("amount: {}", journal.amount);
-            println!("nullifier: 0x{}", hex::encode(journal.nullifier));
+            println!("blockNumber: {}", inspection.block_number);
+            println!("chainId: {}", inspection.chain_id);
+            println!("noteIndex: {}", inspection.note_index);
+            println!("amount: {}", inspection.amount);
+            println!("nullifier: {}", inspection.nullifier);
+            println!("targetAddress: {}", inspection.target_address);
+            Ok(())
+        }
+        Command::Stats {
+            input,
+            limits: executor_limits,
+        } => {
+            let claim_input = load_claim_input(&input, limits)?;
+            let stats = execute_claim(&claim_input, &executor_limits.config())?;
+
+            println!("Input executed: {}", input.display());
+            println!("cycles: {}", stats.total_cycles);
+            println!("segments: {}", stats.segment_count);
+            println!("journal: 0x{}", hex::encode(&stats.journal));
             Ok(())
         }
-        Command::ExportProof { receipt, out } => {
+        Command::ExportProof { receipt, out, abi } => {
             let rcpt = read_receipt(&receipt)?;
             let exported = export_proof(&rcpt)?;
-            write_json(&out, &exported)?;
+            if abi {
+                let calldata = encode_claim_proof_calldata(&exported)?;
+                let mut payload = serde_json::to_value(&exported)?;
+                payload["proof_hex"] = format!("0x{}", hex::encode(calldata)).into();
+                write_json(&out, &payload)?;
+            } else {
+                write_json(&out, &exported)?;
+            }
             println!("Exported proof payload: {}", out.display());
             Ok(())
         }
@@ -128,9 +285,150 @@ fn main() -> Result<()> {
             println!("Receipt kind: {}", describe_receipt_kind(&compressed.inner));
             Ok(())
         }
-        Command::CircuitId => {
+        Command::VerifyCompression { succinct, groth16 } => {
+            let succinct_rcpt = read_receipt(&succinct)?;
+            let groth16_rcpt = read_receipt(&groth16)?;
+            verify_groth16_matches_succinct(&succinct_rcpt, &groth16_rcpt)?;
+            let journal = verify_receipt(&groth16_rcpt)?;
+            println!("Groth16 receipt matches succinct receipt");
+            println!("Nullifier: 0x{}", hex::encode(journal.nullifier));
+            Ok(())
+        }
+        Command::ReceiptInfo { receipt } => {
+            let rcpt = read_receipt(&receipt)?;
+            println!("Receipt: {}", receipt.display());
+            println!("Receipt kind: {}", describe_receipt_kind(&rcpt.inner));
+            match receipt_claimed_image_id(&rcpt) {
+                Some(image_id) => {
+                    println!("Claimed circuit ID: {}", image_id_hex(&image_id));
+                    println!("Matches this build: {}", image_id == circuit_id());
+                }
+                None => println!("Claimed circuit ID: unavailable (claim is pruned)"),
+            }
+            Ok(())
+        }
+        Command::CircuitId { words } => {
             println!("{}", circuit_id_hex());
+            if words {
+                println!("{:?}", circuit_id());
+            }
+            Ok(())
+        }
+        Command::ExportCircuitInfo { out } => {
+            let info = circuit_info();
+            write_json(&out, &info)?;
+            println!("Circuit info: {}", out.display());
+            println!("imageId: {}", info.image_id);
             Ok(())
         }
     }
 }
+
+/// Print one row per batch entry: deposit, note, status, elapsed and the
+/// receipt path or error.
+fn print_batch_summary(outcomes: &[batch::BatchOutcome]) {
+    let width = outcomes.iter().map(|o| o.deposit.len()).max().unwrap_or(0);
+    println!(
+        "{:<width$}  {:>4}  {:<6}  {:>10}  DETAIL",
+        "DEPOSIT", "NOTE", "STATUS", "ELAPSED"
+    );
+    for outcome in outcomes {
+        let note = outcome
+            .note_index
+            .map_or_else(|| "-".to_string(), |i| i.to_string());
+        let (status, detail) = match &outcome.result {
+            Ok(receipt) => ("ok", receipt.display().to_string()),
+            Err(e) => ("failed", e.clone()),
+        };
+        println!(
+            "{:<width$}  {:>4}  {:<6}  {:>10}  {}",
+            outcome.deposit,
+            note,
+            status,
+            format!("{:.2?}", outcome.elapsed),
+            detail
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_accepts_receipt_format() {
+        let cli = Cli::try_parse_from(["shadow-risc0-host", "verify"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Verify {
+                format: ReceiptFileFormat::Bincode,
+                ..
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["shadow-risc0-host", "verify", "--format", "auto"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Verify {
+                format: ReceiptFileFormat::Auto,
+                ..
+            }
+        ));
+        assert!(Cli::try_parse_from(["shadow-risc0-host", "verify", "--format", "cbor"]).is_err());
+    }
+
+    #[test]
+    fn inspect_prints_text_unless_json_is_requested() {
+        let inspect = |extra: &[&str]| {
+            let args = ["shadow-risc0-host", "inspect", "--input", "in.json"];
+            Cli::try_parse_from(args.iter().chain(extra))
+                .unwrap()
+                .command
+        };
+        assert!(matches!(inspect(&[]), Command::Inspect { json: false, .. }));
+        assert!(matches!(
+            inspect(&["--json"]),
+            Command::Inspect { json: true, .. }
+        ));
+    }
+
+    #[test]
+    fn circuit_id_is_a_prefixed_32_byte_hex() {
+        let id = circuit_id_hex();
+        assert!(id.starts_with("0x"));
+        assert_eq!(id.len(), 66);
+        assert!(hex::decode(&id[2..]).is_ok());
+
+        let cli = Cli::try_parse_from(["shadow-risc0-host", "circuit-id", "--words"]).unwrap();
+        assert!(matches!(cli.command, Command::CircuitId { words: true }));
+    }
+
+    #[test]
+    fn prove_accepts_no_verify_flag() {
+        let cli =
+            Cli::try_parse_from(["shadow-risc0-host", "prove", "--input", "in.json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Prove {
+                no_verify: false,
+                ..
+            }
+        ));
+
+        let cli = Cli::try_parse_from([
+            "shadow-risc0-host",
+            "prove",
+            "--input",
+            "in.json",
+            "--no-verify",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Prove {
+                no_verify: true,
+                ..
+            }
+        ));
+    }
+}
@@ -1,3 +1,15 @@
+use std::collections::HashMap;
+
+use risc0_build::GuestOptionsBuilder;
+
 fn main() {
-    risc0_build::embed_methods();
+    let mut features = Vec::new();
+    if std::env::var_os("CARGO_FEATURE_POSEIDON").is_some() {
+        features.push("poseidon".to_string());
+    }
+    let options = GuestOptionsBuilder::default()
+        .features(features)
+        .build()
+        .expect("valid guest options");
+    risc0_build::embed_methods_with_options(HashMap::from([("shadow_claim_guest", options)]));
 }
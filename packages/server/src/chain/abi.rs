@@ -0,0 +1,212 @@
+//! ABI encoding and decoding of Shadow `claim` calldata.
+
+use anyhow::{bail, Context, Result};
+
+/// Size of the static head: the proof offset word plus six `PublicInput` words.
+const CLAIM_HEAD_LEN: usize = 7 * 32;
+
+/// Function selector of `claim(bytes,(uint64,uint64,uint256,address,bytes32,address))`.
+pub fn claim_selector() -> [u8; 4] {
+    use tiny_keccak::{Hasher, Keccak};
+    let mut keccak = Keccak::v256();
+    keccak.update(b"claim(bytes,(uint64,uint64,uint256,address,bytes32,address))");
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&hash[..4]);
+    selector
+}
+
+/// ABI-encode `claim(bytes _proof, (uint64,uint64,uint256,address,bytes32,address) _input)`.
+pub fn encode_claim_calldata(
+    proof_bytes: &[u8],
+    block_number: u64,
+    chain_id: u64,
+    amount: u128,
+    recipient: &[u8],
+    nullifier: &[u8],
+    token: &[u8],
+) -> Vec<u8> {
+    let mut calldata = Vec::new();
+    // Function selector (4 bytes)
+    calldata.extend_from_slice(&claim_selector());
+
+    // For function(bytes, Tuple) where Tuple is static (6 fields = 6 words):
+    // Head: [offset_param1 (32)] [param2.field1..6 (6 x 32)]
+    // = 7 x 32 = 224 bytes head
+    // Tail: [length (32)] [data (padded)]
+    // offset_param1 = 224
+
+    let mut offset_bytes = [0u8; 32];
+    offset_bytes[28..32].copy_from_slice(&224u32.to_be_bytes());
+    calldata.extend_from_slice(&offset_bytes);
+
+    // _input.blockNumber (uint64, left-padded to 32 bytes)
+    let mut bn = [0u8; 32];
+    bn[24..32].copy_from_slice(&block_number.to_be_bytes());
+    calldata.extend_from_slice(&bn);
+
+    // _input.chainId (uint64)
+    let mut cid = [0u8; 32];
+    cid[24..32].copy_from_slice(&chain_id.to_be_bytes());
+    calldata.extend_from_slice(&cid);
+
+    // _input.amount (uint256)
+    let mut amt = [0u8; 32];
+    amt[16..32].copy_from_slice(&amount.to_be_bytes());
+    calldata.extend_from_slice(&amt);
+
+    // _input.recipient (address, left-padded to 32 bytes)
+    let mut rcpt = [0u8; 32];
+    if recipient.len() == 20 {
+        rcpt[12..32].copy_from_slice(recipient);
+    }
+    calldata.extend_from_slice(&rcpt);
+
+    // _input.nullifier (bytes32)
+    let mut nul = [0u8; 32];
+    if nullifier.len() == 32 {
+        nul.copy_from_slice(nullifier);
+    }
+    calldata.extend_from_slice(&nul);
+
+    // _input.token (address, left-padded to 32 bytes)
+    let mut tok = [0u8; 32];
+    if token.len() == 20 {
+        tok[12..32].copy_from_slice(token);
+    }
+    calldata.extend_from_slice(&tok);
+
+    // Proof bytes dynamic data
+    let mut proof_len = [0u8; 32];
+    proof_len[28..32].copy_from_slice(&(proof_bytes.len() as u32).to_be_bytes());
+    calldata.extend_from_slice(&proof_len);
+
+    calldata.extend_from_slice(proof_bytes);
+    let proof_padded_len = proof_bytes.len().div_ceil(32) * 32;
+    let padding = proof_padded_len - proof_bytes.len();
+    calldata.extend(std::iter::repeat_n(0u8, padding));
+
+    calldata
+}
+
+/// Arguments recovered from `claim` calldata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedClaim {
+    pub proof: Vec<u8>,
+    pub block_number: u64,
+    pub chain_id: u64,
+    pub amount: u128,
+    pub recipient: [u8; 20],
+    pub nullifier: [u8; 32],
+    pub token: [u8; 20],
+}
+
+/// Decode calldata produced by [`encode_claim_calldata`], rejecting anything
+/// that is not a well-formed `claim` call.
+pub fn decode_claim_calldata(calldata: &[u8]) -> Result<DecodedClaim> {
+    let (selector, args) = calldata
+        .split_at_checked(4)
+        .context("calldata shorter than a function selector")?;
+    if selector != claim_selector() {
+        bail!("selector 0x{} is not claim()", hex::encode(selector));
+    }
+    if args.len() < CLAIM_HEAD_LEN + 32 {
+        bail!(
+            "calldata too short: {} argument bytes, expected at least {}",
+            args.len(),
+            CLAIM_HEAD_LEN + 32
+        );
+    }
+    let word = |i: usize| -> &[u8] { &args[i * 32..(i + 1) * 32] };
+
+    let offset = word_to_u64(word(0), "proof offset")? as usize;
+    let block_number = word_to_u64(word(1), "blockNumber")?;
+    let chain_id = word_to_u64(word(2), "chainId")?;
+    if word(3)[..16].iter().any(|b| *b != 0) {
+        bail!("amount exceeds 128 bits");
+    }
+    let amount = u128::from_be_bytes(word(3)[16..].try_into().expect("16-byte slice"));
+    let recipient = word_to_address(word(4), "recipient")?;
+    let nullifier: [u8; 32] = word(5).try_into().expect("32-byte word");
+    let token = word_to_address(word(6), "token")?;
+
+    let len_word = args
+        .get(offset..offset.saturating_add(32))
+        .context("proof offset points past the end of calldata")?;
+    let proof_len = word_to_u64(len_word, "proof length")? as usize;
+    let proof = args
+        .get(offset + 32..(offset + 32).saturating_add(proof_len))
+        .context("proof length exceeds calldata")?
+        .to_vec();
+
+    Ok(DecodedClaim {
+        proof,
+        block_number,
+        chain_id,
+        amount,
+        recipient,
+        nullifier,
+        token,
+    })
+}
+
+fn word_to_u64(word: &[u8], field: &str) -> Result<u64> {
+    if word[..24].iter().any(|b| *b != 0) {
+        bail!("{} does not fit in 64 bits", field);
+    }
+    Ok(u64::from_be_bytes(
+        word[24..].try_into().expect("8-byte slice"),
+    ))
+}
+
+fn word_to_address(word: &[u8], field: &str) -> Result<[u8; 20]> {
+    if word[..12].iter().any(|b| *b != 0) {
+        bail!("{} is not a left-padded address", field);
+    }
+    Ok(word[12..].try_into().expect("20-byte slice"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_inverts_encode() {
+        let proof: Vec<u8> = (0..70).collect();
+        let calldata = encode_claim_calldata(
+            &proof,
+            4_739_555,
+            167013,
+            1_230_000_000_000,
+            &[0x11; 20],
+            &[0xab; 32],
+            &[0x22; 20],
+        );
+
+        let decoded = decode_claim_calldata(&calldata).unwrap();
+        assert_eq!(
+            decoded,
+            DecodedClaim {
+                proof,
+                block_number: 4_739_555,
+                chain_id: 167013,
+                amount: 1_230_000_000_000,
+                recipient: [0x11; 20],
+                nullifier: [0xab; 32],
+                token: [0x22; 20],
+            }
+        );
+    }
+
+    #[test]
+    fn decode_rejects_foreign_or_truncated_calldata() {
+        let calldata = encode_claim_calldata(&[1, 2, 3], 1, 1, 1, &[0x11; 20], &[0; 32], &[0; 20]);
+
+        let mut wrong_selector = calldata.clone();
+        wrong_selector[0] ^= 0xff;
+        assert!(decode_claim_calldata(&wrong_selector).is_err());
+
+        assert!(decode_claim_calldata(&calldata[..calldata.len() - 64]).is_err());
+    }
+}
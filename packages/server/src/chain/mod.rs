@@ -1,3 +1,5 @@
+pub mod abi;
+pub mod rpc_client;
 pub mod shadow_contract;
 
 pub use shadow_contract::ChainClient;
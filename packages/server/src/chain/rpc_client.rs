@@ -0,0 +1,98 @@
+//! The HTTP client used for every JSON-RPC request.
+//!
+//! Built once at startup and shared through `AppState`, so the configured auth
+//! headers go out with each RPC request and connections are pooled. Proxies
+//! come from the standard `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY`
+//! variables, which reqwest reads itself.
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+
+/// Credentials sent with every RPC request.
+#[derive(Debug, Clone, Default)]
+pub struct RpcAuth {
+    /// A `Name: value` header, e.g. `x-api-key: abc123`.
+    pub header: Option<String>,
+    /// Sent as `Authorization: Bearer <token>`.
+    pub bearer_token: Option<String>,
+}
+
+/// Build the shared RPC client, failing on a malformed header.
+pub fn build_rpc_client(auth: &RpcAuth) -> Result<reqwest::Client> {
+    let mut headers = HeaderMap::new();
+    if let Some(header) = &auth.header {
+        let (name, value) = header
+            .split_once(':')
+            .context("RPC auth header must look like `Name: value`")?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .context("invalid RPC auth header name")?;
+        headers.insert(name, sensitive(value.trim())?);
+    }
+    if let Some(token) = &auth.bearer_token {
+        if headers.contains_key(AUTHORIZATION) {
+            bail!("RPC auth header and bearer token both set Authorization");
+        }
+        headers.insert(AUTHORIZATION, sensitive(&format!("Bearer {token}"))?);
+    }
+
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .context("failed to build RPC HTTP client")
+}
+
+fn sensitive(value: &str) -> Result<HeaderValue> {
+    let mut value = HeaderValue::from_str(value).context("invalid RPC auth header value")?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{http::HeaderMap, routing::post, Json, Router};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn auth_headers_are_sent_with_rpc_requests() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |headers: HeaderMap| async move {
+                record.lock().unwrap().push(headers);
+                Json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": "0xa"}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = build_rpc_client(&RpcAuth {
+            header: Some("X-Api-Key: key-123".to_string()),
+            bearer_token: Some("token-456".to_string()),
+        })
+        .unwrap();
+        let chain_id = crate::prover::rpc::eth_chain_id(&client, &url)
+            .await
+            .unwrap();
+        assert_eq!(chain_id, 10);
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen[0]["x-api-key"], "key-123");
+        assert_eq!(seen[0]["authorization"], "Bearer token-456");
+    }
+
+    #[test]
+    fn malformed_auth_is_rejected() {
+        let auth = |header: &str, bearer: Option<&str>| RpcAuth {
+            header: Some(header.to_string()),
+            bearer_token: bearer.map(str::to_string),
+        };
+        assert!(build_rpc_client(&auth("x-api-key", None)).is_err());
+        assert!(build_rpc_client(&auth("bad name: v", None)).is_err());
+        assert!(build_rpc_client(&auth("Authorization: Basic abc", Some("t"))).is_err());
+    }
+}
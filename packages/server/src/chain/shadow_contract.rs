@@ -1,7 +1,9 @@
 //! On-chain queries for the Shadow contract.
 //!
-//! Reads `isConsumed(nullifier)` to check claim status, and reads the circuit ID
-//! from the verifier contract.
+//! Reads `isConsumed(nullifier)` to check claim status, reads the circuit ID
+//! from the verifier contract, and re-checks proving blocks against the
+//! canonical chain. Status lookups are cached, failures included, and a
+//! circuit breaker stops calling an RPC that keeps failing.
 
 use std::{
     collections::HashMap,
@@ -11,29 +13,119 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use serde_json::Value;
+use tokio::sync::Semaphore;
+
+/// Timeout for each on-chain query; the shared RPC client has none.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default cap on concurrent nullifier status `eth_call`s.
+pub const DEFAULT_STATUS_CONCURRENCY: usize = 4;
+
+/// How long nullifier status results are reused, and when to stop asking a
+/// failing RPC.
+#[derive(Debug, Clone, Copy)]
+pub struct StatusCachePolicy {
+    /// How long a successful `isConsumed` answer is reused.
+    pub ttl: Duration,
+    /// How long a failed lookup is reused before the RPC is asked again.
+    pub error_ttl: Duration,
+    /// Consecutive RPC failures that open the circuit breaker.
+    pub failure_threshold: u32,
+    /// How long an open breaker fails lookups without calling the RPC.
+    pub cooldown: Duration,
+}
+
+impl Default for StatusCachePolicy {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(300),
+            error_ttl: Duration::from_secs(15),
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
 
-/// Default TTL for cached on-chain query results.
-const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300); // 5 minutes
+/// Circuit breaker over `isConsumed` and block hash calls.
+///
+/// Opens after `failure_threshold` consecutive failures. Once the cooldown has
+/// elapsed one call is let through as a probe: success closes the breaker,
+/// failure reopens it for another cooldown. Other calls keep failing fast
+/// while the probe is out; if its outcome is never recorded, the next probe
+/// goes out a cooldown later.
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl Breaker {
+    /// Whether a call may go out at `now`. Admitting the probe of an open
+    /// breaker holds the others back for another cooldown.
+    fn admit(&mut self, now: Instant, policy: &StatusCachePolicy) -> bool {
+        match self.open_until {
+            None => true,
+            Some(until) if now >= until => {
+                self.open_until = Some(now + policy.cooldown);
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    fn record_failure(&mut self, now: Instant, policy: &StatusCachePolicy) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.consecutive_failures >= policy.failure_threshold {
+            self.open_until = Some(now + policy.cooldown);
+        }
+    }
+}
+
+/// Outcome of re-checking a block hash against the canonical chain.
+#[derive(Debug, Clone)]
+pub struct BlockCheck {
+    /// The canonical hash at that height, absent if the node does not know it.
+    pub canonical_hash: Option<String>,
+    /// The checked hash is still the canonical one.
+    pub still_canonical: bool,
+}
+
+/// A cached `isConsumed` outcome; errors keep only their message.
+type CachedStatus = (std::result::Result<bool, String>, Instant);
 
 /// Client for on-chain queries to the Shadow contract.
 pub struct ChainClient {
     http: reqwest::Client,
     rpc_url: String,
-    /// Nullifier consumption cache: nullifier_hex → (is_consumed, cached_at).
-    nullifier_cache: Mutex<HashMap<String, (bool, Instant)>>,
-    cache_ttl: Duration,
+    /// Nullifier consumption cache: nullifier_hex → (outcome, cached_at).
+    nullifier_cache: Mutex<HashMap<String, CachedStatus>>,
+    cache_policy: StatusCachePolicy,
+    breaker: Mutex<Breaker>,
+    /// Bounds in-flight `isConsumed` calls so batch checks stay under RPC rate limits.
+    status_permits: Semaphore,
 }
 
 impl ChainClient {
-    pub fn new(rpc_url: String) -> Self {
+    /// `http` is the shared RPC client; `status_concurrency` caps parallel
+    /// nullifier checks (at least 1).
+    pub fn new(
+        http: reqwest::Client,
+        rpc_url: String,
+        status_concurrency: usize,
+        cache_policy: StatusCachePolicy,
+    ) -> Self {
         Self {
-            http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build()
-                .unwrap_or_default(),
+            http,
             rpc_url,
             nullifier_cache: Mutex::new(HashMap::new()),
-            cache_ttl: DEFAULT_CACHE_TTL,
+            cache_policy,
+            breaker: Mutex::new(Breaker::default()),
+            status_permits: Semaphore::new(status_concurrency.max(1)),
         }
     }
 
@@ -41,44 +133,158 @@ impl ChainClient {
     ///
     /// `shadow_address` is the Shadow contract address (0x-prefixed hex).
     /// `nullifier` is the 32-byte nullifier (0x-prefixed hex).
+    ///
+    /// A failed lookup is remembered for `error_ttl`, and while the circuit
+    /// breaker is open lookups fail without calling the RPC.
     pub async fn is_consumed(&self, shadow_address: &str, nullifier: &str) -> Result<bool> {
-        // Check cache first
-        {
-            let cache = self.nullifier_cache.lock().unwrap();
-            if let Some(&(result, cached_at)) = cache.get(nullifier) {
-                if cached_at.elapsed() < self.cache_ttl {
-                    return Ok(result);
-                }
-            }
+        if let Some(cached) = self.cached_status(nullifier, Instant::now()) {
+            return cached.map_err(anyhow::Error::msg);
         }
 
-        // Call isConsumed(bytes32 nullifier) on the Shadow contract
-        // Function selector: keccak256("isConsumed(bytes32)") = first 4 bytes
-        let selector = "0x6346e832"; // keccak256("isConsumed(bytes32)")[..4]
-        let nullifier_padded = nullifier.strip_prefix("0x").unwrap_or(nullifier);
+        let calldata = is_consumed_calldata(nullifier)?;
+
+        let _permit = self.status_permits.acquire().await?;
+        self.ensure_breaker_closed()?;
+        let outcome = self
+            .eth_call(shadow_address, &calldata, "latest")
+            .await
+            .context("isConsumed call failed")
+            .map(|result| decode_bool(&result));
+        self.record_statuses(&[nullifier], std::slice::from_ref(&outcome));
 
-        if nullifier_padded.len() != 64 {
-            bail!("nullifier must be 32 bytes (64 hex chars)");
+        outcome
+    }
+
+    /// Check several nullifiers with one JSON-RPC batch request.
+    ///
+    /// Fresh cached results are reused and only the other nullifiers are sent.
+    /// Results are returned in the order of `nullifiers`.
+    pub async fn are_consumed(
+        &self,
+        shadow_address: &str,
+        nullifiers: &[String],
+    ) -> Vec<Result<bool>> {
+        let now = Instant::now();
+        let mut results: Vec<Option<Result<bool>>> = Vec::with_capacity(nullifiers.len());
+        let mut pending = Vec::new();
+        for (i, nullifier) in nullifiers.iter().enumerate() {
+            let result = match self.cached_status(nullifier, now) {
+                Some(cached) => Some(cached.map_err(anyhow::Error::msg)),
+                None => match is_consumed_calldata(nullifier) {
+                    Ok(calldata) => {
+                        pending.push((i, nullifier.as_str(), calldata));
+                        None
+                    }
+                    Err(e) => Some(Err(e)),
+                },
+            };
+            results.push(result);
         }
 
-        let calldata = format!("{}{}", selector, nullifier_padded);
+        if !pending.is_empty() {
+            let outcomes = self.call_consumed_batch(shadow_address, &pending).await;
+            for ((i, _, _), outcome) in pending.iter().zip(outcomes) {
+                results[*i] = Some(outcome);
+            }
+        }
 
-        let result = self
-            .eth_call(shadow_address, &calldata, "latest")
-            .await
-            .context("isConsumed call failed")?;
+        results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(anyhow::anyhow!("no isConsumed result"))))
+            .collect()
+    }
 
-        // Result is a bool encoded as uint256 (32 bytes, last byte is 0 or 1)
-        let result_hex = result.strip_prefix("0x").unwrap_or(&result);
-        let is_consumed = result_hex.ends_with('1');
+    /// Send one `isConsumed` batch for `pending` (index, nullifier, calldata)
+    /// entries and record the outcomes.
+    async fn call_consumed_batch(
+        &self,
+        shadow_address: &str,
+        pending: &[(usize, &str, String)],
+    ) -> Vec<Result<bool>> {
+        let all_failed = |e: anyhow::Error| {
+            let message = format!("{e:#}");
+            pending
+                .iter()
+                .map(|_| Err(anyhow::anyhow!("{message}")))
+                .collect()
+        };
 
-        // Update cache
+        let _permit = match self.status_permits.acquire().await {
+            Ok(permit) => permit,
+            Err(e) => return all_failed(e.into()),
+        };
+        if let Err(e) = self.ensure_breaker_closed() {
+            return all_failed(e);
+        }
+
+        let calldata: Vec<&str> = pending.iter().map(|(_, _, data)| data.as_str()).collect();
+        let outcomes: Vec<Result<bool>> = match self
+            .eth_call_batch(shadow_address, &calldata, "latest")
+            .await
+            .context("isConsumed batch call failed")
         {
-            let mut cache = self.nullifier_cache.lock().unwrap();
-            cache.insert(nullifier.to_string(), (is_consumed, Instant::now()));
+            Ok(results) => results
+                .into_iter()
+                .map(|r| {
+                    r.context("isConsumed call failed")
+                        .map(|result| decode_bool(&result))
+                })
+                .collect(),
+            Err(e) => all_failed(e),
+        };
+        let nullifiers: Vec<&str> = pending.iter().map(|(_, nullifier, _)| *nullifier).collect();
+        self.record_statuses(&nullifiers, &outcomes);
+        outcomes
+    }
+
+    fn ensure_breaker_closed(&self) -> Result<()> {
+        let admitted = self
+            .breaker
+            .lock()
+            .unwrap()
+            .admit(Instant::now(), &self.cache_policy);
+        if !admitted {
+            bail!("RPC call skipped: circuit breaker is open");
         }
+        Ok(())
+    }
 
-        Ok(is_consumed)
+    /// Feed the outcome of one RPC round-trip to the breaker.
+    fn record_call(&self, succeeded: bool) {
+        let mut breaker = self.breaker.lock().unwrap();
+        if succeeded {
+            breaker.record_success();
+        } else {
+            breaker.record_failure(Instant::now(), &self.cache_policy);
+        }
+    }
+
+    /// Feed the outcomes of one RPC round-trip to the breaker and the cache.
+    ///
+    /// The round-trip counts as a success if any lookup in it succeeded.
+    fn record_statuses(&self, nullifiers: &[&str], outcomes: &[Result<bool>]) {
+        self.record_call(outcomes.iter().any(|o| o.is_ok()));
+        let now = Instant::now();
+        let mut cache = self.nullifier_cache.lock().unwrap();
+        for (nullifier, outcome) in nullifiers.iter().zip(outcomes) {
+            let cached = outcome.as_ref().copied().map_err(|e| format!("{e:#}"));
+            cache.insert(nullifier.to_string(), (cached, now));
+        }
+    }
+
+    /// The cached outcome for `nullifier`, if still fresh at `now`.
+    fn cached_status(
+        &self,
+        nullifier: &str,
+        now: Instant,
+    ) -> Option<std::result::Result<bool, String>> {
+        let cache = self.nullifier_cache.lock().unwrap();
+        let (outcome, cached_at) = cache.get(nullifier)?;
+        let ttl = match outcome {
+            Ok(_) => self.cache_policy.ttl,
+            Err(_) => self.cache_policy.error_ttl,
+        };
+        (now.duration_since(*cached_at) < ttl).then(|| outcome.clone())
     }
 
     /// Force-refresh the claim status for a nullifier (bypass cache).
@@ -117,6 +323,7 @@ impl ChainClient {
     /// Read the circuit ID from the Risc0CircuitVerifier contract.
     ///
     /// `verifier_address` is the Risc0CircuitVerifier contract address (0x-prefixed hex).
+    /// Returns the 0x-prefixed 32-byte form comparable with `circuit_id_hex()`.
     pub async fn read_circuit_id(&self, verifier_address: &str) -> Result<String> {
         // imageId() selector: keccak256("imageId()")[..4]
         let selector = "0xef3f7dd5"; // keccak256("imageId()")[..4]
@@ -126,7 +333,80 @@ impl ChainClient {
             .await
             .context("imageId call failed")?;
 
-        Ok(result)
+        decode_image_id(&result)
+    }
+
+    /// Read the Groth16 seal selector of the RISC Zero verifier that a
+    /// Risc0CircuitVerifier delegates to, as 0x-prefixed hex.
+    ///
+    /// Fails if that verifier does not expose `SELECTOR()`, as a
+    /// `RiscZeroVerifierRouter` does not.
+    #[cfg(feature = "prove")]
+    pub async fn read_groth16_selector(&self, verifier_address: &str) -> Result<String> {
+        let risc0_verifier_raw = self
+            .eth_call(verifier_address, "0x5c9770c5", "latest") // risc0Verifier()
+            .await
+            .context("Risc0CircuitVerifier.risc0Verifier() call failed")?;
+        let risc0_verifier = decode_address(&risc0_verifier_raw)
+            .context("Risc0CircuitVerifier.risc0Verifier() returned invalid address")?;
+
+        let result = self
+            .eth_call(&risc0_verifier, "0x053c238d", "latest") // SELECTOR()
+            .await
+            .context("SELECTOR() call failed")?;
+        decode_selector(&result)
+    }
+
+    /// Fetch the canonical block hash at `number` (0x-prefixed hex).
+    ///
+    /// Returns `None` if the node does not know the block. Hashes are never
+    /// cached, since callers use them to spot reorgs, but the call goes
+    /// through the circuit breaker.
+    pub async fn get_block_hash(&self, number: u64) -> Result<Option<String>> {
+        self.ensure_breaker_closed()?;
+        let outcome = self.fetch_block_hash(number).await;
+        self.record_call(outcome.is_ok());
+        outcome
+    }
+
+    /// Re-check that block `number` still has `block_hash` (0x-prefixed hex)
+    /// on the canonical chain.
+    pub async fn block_still_canonical(&self, number: u64, block_hash: &str) -> Result<BlockCheck> {
+        let canonical_hash = self.get_block_hash(number).await?;
+        let still_canonical = canonical_hash
+            .as_ref()
+            .is_some_and(|h| h.eq_ignore_ascii_case(block_hash));
+        Ok(BlockCheck {
+            canonical_hash,
+            still_canonical,
+        })
+    }
+
+    async fn fetch_block_hash(&self, number: u64) -> Result<Option<String>> {
+        let req = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_getBlockByNumber",
+            "params": [format!("0x{:x}", number), false]
+        });
+
+        let resp: Value = self.post_rpc().json(&req).send().await?.json().await?;
+
+        if let Some(error) = resp.get("error") {
+            bail!(
+                "eth_getBlockByNumber error: {}",
+                error
+                    .get("message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown")
+            );
+        }
+
+        Ok(resp
+            .get("result")
+            .and_then(|block| block.get("hash"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()))
     }
 
     /// Query ETH balance of an address (returns wei as decimal string).
@@ -138,14 +418,7 @@ impl ChainClient {
             "params": [address, "latest"]
         });
 
-        let resp: serde_json::Value = self
-            .http
-            .post(&self.rpc_url)
-            .json(&req)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp: serde_json::Value = self.post_rpc().json(&req).send().await?.json().await?;
 
         if let Some(error) = resp.get("error") {
             bail!(
@@ -220,6 +493,11 @@ impl ChainClient {
         u128::from_str_radix(trimmed, 16).context("invalid maxShadowMintAmount hex")
     }
 
+    /// Start a JSON-RPC POST, bounded by [`QUERY_TIMEOUT`].
+    fn post_rpc(&self) -> reqwest::RequestBuilder {
+        self.http.post(&self.rpc_url).timeout(QUERY_TIMEOUT)
+    }
+
     /// Perform an `eth_call` (read-only contract call).
     async fn eth_call(&self, to: &str, data: &str, block: &str) -> Result<String> {
         let req = serde_json::json!({
@@ -229,32 +507,149 @@ impl ChainClient {
             "params": [{"to": to, "data": data}, block]
         });
 
-        let resp: Value = self
-            .http
-            .post(&self.rpc_url)
-            .json(&req)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp: Value = self.post_rpc().json(&req).send().await?.json().await?;
 
-        if let Some(error) = resp.get("error") {
+        eth_call_result(&resp)
+    }
+
+    /// Perform one `eth_call` per entry of `calldata` in a single JSON-RPC
+    /// batch request.
+    ///
+    /// Fails as a whole if the request does; otherwise each call gets its own
+    /// result, in the order of `calldata`.
+    async fn eth_call_batch(
+        &self,
+        to: &str,
+        calldata: &[&str],
+        block: &str,
+    ) -> Result<Vec<Result<String>>> {
+        let req: Vec<Value> = calldata
+            .iter()
+            .enumerate()
+            .map(|(id, data)| {
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": "eth_call",
+                    "params": [{"to": to, "data": data}, block]
+                })
+            })
+            .collect();
+
+        let resp: Value = self.post_rpc().json(&req).send().await?.json().await?;
+
+        // Nodes that reject batches answer with a single error object.
+        let Value::Array(responses) = resp else {
             bail!(
-                "eth_call error: {}",
-                error
-                    .get("message")
+                "eth_call batch error: {}",
+                resp.get("error")
+                    .and_then(|e| e.get("message"))
                     .and_then(|v| v.as_str())
-                    .unwrap_or("unknown")
+                    .unwrap_or("response is not a batch")
             );
+        };
+
+        // Batch responses may come back in any order; match them up by id.
+        let mut results: Vec<Option<Result<String>>> = calldata.iter().map(|_| None).collect();
+        for response in &responses {
+            let slot = response
+                .get("id")
+                .and_then(Value::as_u64)
+                .and_then(|id| results.get_mut(usize::try_from(id).ok()?));
+            if let Some(slot) = slot {
+                *slot = Some(eth_call_result(response));
+            }
         }
+        Ok(results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(anyhow::anyhow!("eth_call: missing from batch"))))
+            .collect())
+    }
+}
 
-        resp.get("result")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .ok_or_else(|| anyhow::anyhow!("eth_call: no result"))
+/// Extract the result of one `eth_call` JSON-RPC response.
+fn eth_call_result(resp: &Value) -> Result<String> {
+    if let Some(error) = resp.get("error") {
+        bail!(
+            "eth_call error: {}",
+            error
+                .get("message")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown")
+        );
+    }
+
+    resp.get("result")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| anyhow::anyhow!("eth_call: no result"))
+}
+
+/// Calldata for `isConsumed(bytes32 nullifier)` on the Shadow contract.
+fn is_consumed_calldata(nullifier: &str) -> Result<String> {
+    // Function selector: keccak256("isConsumed(bytes32)") = first 4 bytes
+    let selector = "0x6346e832"; // keccak256("isConsumed(bytes32)")[..4]
+    let nullifier_padded = nullifier.strip_prefix("0x").unwrap_or(nullifier);
+
+    if nullifier_padded.len() != 64 {
+        bail!("nullifier must be 32 bytes (64 hex chars)");
+    }
+
+    Ok(format!("{}{}", selector, nullifier_padded))
+}
+
+/// Decode an ABI-encoded `bool`.
+fn decode_bool(raw: &str) -> bool {
+    // A bool is encoded as uint256 (32 bytes, last byte is 0 or 1)
+    let hex = raw.strip_prefix("0x").unwrap_or(raw);
+    hex.ends_with('1')
+}
+
+/// Normalize an `imageId()` return value to 0x-prefixed 32-byte hex.
+///
+/// Accepts a `bytes32` word as is, or a `uint256[8]` of digest words, which
+/// are packed little-endian per word like `circuit_id_hex()`.
+fn decode_image_id(raw: &str) -> Result<String> {
+    let hex = raw.strip_prefix("0x").unwrap_or(raw);
+    match hex.len() {
+        0 => bail!("imageId() returned no data; is the circuit verifier deployed?"),
+        64 => {
+            let bytes = hex::decode(hex).context("invalid imageId hex")?;
+            Ok(format!("0x{}", hex::encode(bytes)))
+        }
+        512 => {
+            let words = hex::decode(hex).context("invalid imageId hex")?;
+            let mut bytes = Vec::with_capacity(32);
+            for word in words.chunks(32) {
+                if word[..28].iter().any(|b| *b != 0) {
+                    bail!("imageId() word does not fit in 32 bits");
+                }
+                let value = u32::from_be_bytes([word[28], word[29], word[30], word[31]]);
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Ok(format!("0x{}", hex::encode(bytes)))
+        }
+        len => bail!("unexpected imageId() return length: {} bytes", len / 2),
     }
 }
 
+/// Decode an ABI-encoded `bytes4` (left-aligned in its word) to 0x-prefixed hex.
+#[cfg(any(test, feature = "prove"))]
+fn decode_selector(raw: &str) -> Result<String> {
+    let hex = raw.strip_prefix("0x").unwrap_or(raw);
+    if hex.len() != 64 {
+        bail!(
+            "unexpected SELECTOR() return length: {} bytes",
+            hex.len() / 2
+        );
+    }
+    let bytes = hex::decode(hex).context("invalid SELECTOR() hex")?;
+    if bytes[4..].iter().any(|b| *b != 0) {
+        bail!("SELECTOR() did not return a bytes4");
+    }
+    Ok(format!("0x{}", hex::encode(&bytes[..4])))
+}
+
 /// Decode an ABI-encoded address (32-byte padded) into a checksummed 0x-prefixed string.
 fn decode_address(raw: &str) -> Result<String> {
     let hex = raw.strip_prefix("0x").unwrap_or(raw);
@@ -265,3 +660,228 @@ fn decode_address(raw: &str) -> Result<String> {
     let addr = &hex[hex.len() - 40..];
     Ok(format!("0x{}", addr))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use axum::{extract::State, routing::post, Json, Router};
+
+    use super::*;
+
+    /// JSON-RPC mock answering `eth_call` batches in reverse order, recording
+    /// the size of each batch.
+    ///
+    /// A nullifier is consumed if its last byte is odd; `0xee..` nullifiers
+    /// get a per-call error.
+    async fn batch_rpc(batches: Arc<Mutex<Vec<usize>>>) -> String {
+        async fn handle(
+            State(batches): State<Arc<Mutex<Vec<usize>>>>,
+            Json(req): Json<Vec<Value>>,
+        ) -> Json<Value> {
+            batches.lock().unwrap().push(req.len());
+            let responses: Vec<Value> = req
+                .iter()
+                .rev()
+                .map(|call| {
+                    let data = call["params"][0]["data"].as_str().unwrap();
+                    if data.ends_with("ee") {
+                        return serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": call["id"],
+                            "error": {"code": 3, "message": "execution reverted"},
+                        });
+                    }
+                    let last = u8::from_str_radix(&data[data.len() - 2..], 16).unwrap();
+                    serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": call["id"],
+                        "result": format!("0x{:064x}", last % 2),
+                    })
+                })
+                .collect();
+            Json(Value::Array(responses))
+        }
+
+        let app = Router::new().route("/", post(handle)).with_state(batches);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    /// JSON-RPC mock that fails every request and counts them.
+    async fn failing_rpc(calls: Arc<AtomicUsize>) -> String {
+        async fn handle(
+            State(calls): State<Arc<AtomicUsize>>,
+            Json(req): Json<Value>,
+        ) -> Json<Value> {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": req["id"],
+                "error": {"code": -32000, "message": "upstream unavailable"},
+            }))
+        }
+
+        let app = Router::new().route("/", post(handle)).with_state(calls);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn breaker_opens_after_threshold_and_closes_after_cooldown() {
+        let policy = StatusCachePolicy {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut breaker = Breaker::default();
+
+        for _ in 0..2 {
+            breaker.record_failure(at(0), &policy);
+        }
+        assert!(breaker.admit(at(0), &policy));
+        breaker.record_failure(at(0), &policy);
+        assert!(!breaker.admit(at(0), &policy));
+        assert!(!breaker.admit(at(29), &policy));
+
+        // After the cooldown one failure is enough to reopen it.
+        assert!(breaker.admit(at(30), &policy));
+        breaker.record_failure(at(30), &policy);
+        assert!(!breaker.admit(at(59), &policy));
+
+        assert!(breaker.admit(at(60), &policy));
+        breaker.record_success();
+        breaker.record_failure(at(60), &policy);
+        assert!(breaker.admit(at(60), &policy));
+    }
+
+    #[test]
+    fn half_open_breaker_admits_a_single_probe() {
+        let policy = StatusCachePolicy {
+            failure_threshold: 1,
+            cooldown: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut breaker = Breaker::default();
+        breaker.record_failure(at(0), &policy);
+
+        assert!(breaker.admit(at(30), &policy));
+        assert!(!breaker.admit(at(30), &policy));
+        assert!(!breaker.admit(at(45), &policy));
+
+        // The probe succeeds, so everyone is let through again.
+        breaker.record_success();
+        assert!(breaker.admit(at(45), &policy));
+        assert!(breaker.admit(at(45), &policy));
+
+        // A probe that never reports back is retried a cooldown later.
+        breaker.record_failure(at(50), &policy);
+        assert!(breaker.admit(at(80), &policy));
+        assert!(!breaker.admit(at(109), &policy));
+        assert!(breaker.admit(at(110), &policy));
+    }
+
+    #[tokio::test]
+    async fn failed_lookups_are_cached_and_trip_the_breaker() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let policy = StatusCachePolicy {
+            failure_threshold: 2,
+            ..Default::default()
+        };
+        let client = ChainClient::new(
+            reqwest::Client::new(),
+            failing_rpc(calls.clone()).await,
+            1,
+            policy,
+        );
+        let shadow = "0x77cdA0575e66A5FC95404fdA856615AD507d8A07";
+        let nullifier = |i: u8| format!("0x{}", hex::encode([i; 32]));
+
+        for _ in 0..3 {
+            let err = client.is_consumed(shadow, &nullifier(1)).await.unwrap_err();
+            assert!(
+                format!("{err:#}").contains("upstream unavailable"),
+                "{err:#}"
+            );
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert!(client.is_consumed(shadow, &nullifier(2)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let err = client.is_consumed(shadow, &nullifier(3)).await.unwrap_err();
+        assert!(err.to_string().contains("circuit breaker"), "{err:#}");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn image_id_accepts_bytes32_and_packed_words() {
+        let bytes32 = format!("0x{}", "A7dd3b3e".repeat(8));
+        assert_eq!(
+            decode_image_id(&bytes32).unwrap(),
+            bytes32.to_ascii_lowercase()
+        );
+
+        let words: String = (1u32..=8).map(|w| format!("{:064x}", w)).collect();
+        let expected: String = (1u32..=8).map(|w| hex::encode(w.to_le_bytes())).collect();
+        assert_eq!(
+            decode_image_id(&format!("0x{}", words)).unwrap(),
+            format!("0x{}", expected)
+        );
+    }
+
+    #[test]
+    fn image_id_reports_missing_verifier() {
+        let err = decode_image_id("0x").unwrap_err();
+        assert!(err.to_string().contains("deployed"), "{err:#}");
+        assert!(decode_image_id(&format!("0x{}", "00".repeat(20))).is_err());
+    }
+
+    #[test]
+    fn selector_is_read_from_the_left_of_the_word() {
+        let word = format!("0x73c457ba{}", "00".repeat(28));
+        assert_eq!(decode_selector(&word).unwrap(), "0x73c457ba");
+        assert!(decode_selector("0x").is_err());
+        assert!(decode_selector(&format!("0x{:064x}", 1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn are_consumed_sends_uncached_nullifiers_in_one_batch() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let client = ChainClient::new(
+            reqwest::Client::new(),
+            batch_rpc(batches.clone()).await,
+            DEFAULT_STATUS_CONCURRENCY,
+            StatusCachePolicy::default(),
+        );
+        let shadow = "0x77cdA0575e66A5FC95404fdA856615AD507d8A07";
+        let nullifier = |b: u8| format!("0x{}", hex::encode([b; 32]));
+
+        assert!(client.is_consumed(shadow, &nullifier(1)).await.is_err());
+        let nullifiers: Vec<String> = [1, 2, 3, 0xee].map(nullifier).to_vec();
+        let results = client.are_consumed(shadow, &nullifiers).await;
+
+        // The failed single call is cached, so only three nullifiers are sent.
+        assert_eq!(*batches.lock().unwrap(), vec![3]);
+        assert!(results[0].is_err());
+        assert!(!*results[1].as_ref().unwrap());
+        assert!(*results[2].as_ref().unwrap());
+        let err = results[3].as_ref().unwrap_err();
+        assert!(format!("{err:#}").contains("execution reverted"), "{err:#}");
+
+        let again = client.are_consumed(shadow, &nullifiers[1..3]).await;
+        assert_eq!(batches.lock().unwrap().len(), 1);
+        assert!(!again[0].as_ref().unwrap() && *again[1].as_ref().unwrap());
+    }
+}
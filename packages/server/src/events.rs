@@ -0,0 +1,154 @@
+//! Events pushed to WebSocket clients and streaming prove requests.
+//!
+//! The `type` strings and camelCase field names are what the UI matches on, so
+//! renaming either is a breaking change for it.
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::prover::queue::ProgressExtra;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum ServerEvent {
+    /// Deposit or proof files were added, removed or rewritten.
+    #[serde(rename = "workspace:changed")]
+    WorkspaceChanged,
+    /// A proof job joined the pending list at `position`.
+    #[serde(rename = "proof:queued", rename_all = "camelCase")]
+    ProofQueued {
+        deposit_id: String,
+        priority: u8,
+        position: usize,
+    },
+    #[serde(rename = "proof:started", rename_all = "camelCase")]
+    ProofStarted { deposit_id: String },
+    #[serde(rename = "proof:note_progress", rename_all = "camelCase")]
+    NoteProgress {
+        deposit_id: String,
+        note_index: u32,
+        total_notes: u32,
+        message: String,
+        #[serde(flatten)]
+        extra: ProgressExtra,
+    },
+    #[serde(rename = "proof:completed", rename_all = "camelCase")]
+    ProofCompleted {
+        deposit_id: String,
+        proof_file: String,
+        elapsed_secs: Option<f64>,
+    },
+    #[serde(rename = "proof:failed", rename_all = "camelCase")]
+    ProofFailed {
+        deposit_id: String,
+        note_index: u32,
+        error: String,
+    },
+    #[serde(rename = "proof:cancelled", rename_all = "camelCase")]
+    ProofCancelled { deposit_id: String },
+}
+
+impl ServerEvent {
+    /// Serialize and broadcast to every subscriber; dropped if there are none.
+    pub fn send(&self, event_tx: &broadcast::Sender<String>) {
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                let _ = event_tx.send(json);
+            }
+            Err(e) => tracing::warn!(error = %e, "failed to serialize server event"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(event: ServerEvent) -> serde_json::Value {
+        serde_json::to_value(event).unwrap()
+    }
+
+    #[test]
+    fn events_serialize_with_the_type_the_ui_expects() {
+        let id = || "deposit-a".to_string();
+        let cases = [
+            (ServerEvent::WorkspaceChanged, "workspace:changed"),
+            (
+                ServerEvent::ProofQueued {
+                    deposit_id: id(),
+                    priority: 0,
+                    position: 1,
+                },
+                "proof:queued",
+            ),
+            (
+                ServerEvent::ProofStarted { deposit_id: id() },
+                "proof:started",
+            ),
+            (
+                ServerEvent::NoteProgress {
+                    deposit_id: id(),
+                    note_index: 0,
+                    total_notes: 2,
+                    message: "proving".to_string(),
+                    extra: ProgressExtra::default(),
+                },
+                "proof:note_progress",
+            ),
+            (
+                ServerEvent::ProofCompleted {
+                    deposit_id: id(),
+                    proof_file: "proof.json".to_string(),
+                    elapsed_secs: None,
+                },
+                "proof:completed",
+            ),
+            (
+                ServerEvent::ProofFailed {
+                    deposit_id: id(),
+                    note_index: 1,
+                    error: "boom".to_string(),
+                },
+                "proof:failed",
+            ),
+            (
+                ServerEvent::ProofCancelled { deposit_id: id() },
+                "proof:cancelled",
+            ),
+        ];
+        for (event, expected) in cases {
+            let value = json(event);
+            assert_eq!(value["type"], expected);
+            if expected != "workspace:changed" {
+                assert_eq!(value["depositId"], "deposit-a", "{expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn progress_extra_fields_are_inlined() {
+        let value = json(ServerEvent::NoteProgress {
+            deposit_id: "deposit-a".to_string(),
+            note_index: 1,
+            total_notes: 3,
+            message: "proving note 2/3".to_string(),
+            extra: ProgressExtra {
+                block_number: Some(7),
+                stage: Some("proving".to_string()),
+                ..Default::default()
+            },
+        });
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "type": "proof:note_progress",
+                "depositId": "deposit-a",
+                "noteIndex": 1,
+                "totalNotes": 3,
+                "message": "proving note 2/3",
+                "blockNumber": 7,
+                "stage": "proving",
+            })
+        );
+    }
+}
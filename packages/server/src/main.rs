@@ -1,30 +1,70 @@
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use axum::Router;
 use clap::Parser;
-use tokio::sync::broadcast;
+use tokio::{
+    sync::{broadcast, watch},
+    task::JoinHandle,
+    time::Instant,
+};
 use tower_http::cors::CorsLayer;
 
 mod chain;
+mod events;
 mod mining;
 mod prover;
 mod routes;
 mod state;
 mod workspace;
 
-use chain::ChainClient;
-use prover::ProofQueue;
+use chain::{
+    rpc_client::{build_rpc_client, RpcAuth},
+    shadow_contract::{StatusCachePolicy, DEFAULT_STATUS_CONCURRENCY},
+    ChainClient,
+};
+use mining::MinesInFlight;
+#[cfg(feature = "prove")]
+use prover::proof_cache::ProofCache;
+use prover::{audit::ProofAuditLog, block_cache::BlockCache, ProofQueue};
+use routes::ws::{EventOverflow, DEFAULT_EVENT_BUFFER};
 use state::AppState;
+use workspace::{
+    index_cache::WorkspaceIndexCache,
+    perms::WritePerms,
+    recipient_cache::RecipientHashCache,
+    scanner::{quarantine_invalid_files, ProofNaming, DEFAULT_PROOF_NAMING},
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "shadow-server")]
 #[command(about = "Shadow Protocol local backend server")]
 struct Cli {
+    #[cfg(feature = "prove")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Workspace directory (where deposit and proof files live).
     #[arg(long)]
     workspace: Option<PathBuf>,
 
+    /// Serve another workspace under a path prefix, as `PREFIX=DIR`
+    /// (repeatable). Its API is at `/PREFIX/api` and events at `/PREFIX/ws`,
+    /// with its own proof queue, so jobs in different workspaces prove at the
+    /// same time.
+    #[arg(
+        long = "workspace-prefix",
+        env = "WORKSPACE_PREFIXES",
+        value_delimiter = ',',
+        value_parser = parse_workspace_prefix
+    )]
+    workspace_prefixes: Vec<WorkspacePrefix>,
+
     /// Port to listen on.
     #[arg(long, default_value = "3000")]
     port: u16,
@@ -33,6 +73,20 @@ struct Cli {
     #[arg(long, env = "RPC_URL")]
     rpc_url: Option<String>,
 
+    /// Extra header sent with every RPC request, as `Name: value` (e.g. an
+    /// API key). Proxies are taken from `HTTPS_PROXY`/`NO_PROXY`.
+    #[arg(long, env = "RPC_AUTH_HEADER", hide_env_values = true)]
+    rpc_auth_header: Option<String>,
+
+    /// Token sent as `Authorization: Bearer <token>` with every RPC request.
+    #[arg(
+        long,
+        env = "RPC_BEARER_TOKEN",
+        hide_env_values = true,
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    rpc_bearer_token: Option<String>,
+
     /// Shadow contract address for on-chain nullifier queries.
     #[arg(long, env = "SHADOW_ADDRESS")]
     shadow_address: Option<String>,
@@ -40,6 +94,167 @@ struct Cli {
     /// Directory containing the built UI static files.
     #[arg(long, default_value = "/app/ui")]
     ui_dir: PathBuf,
+
+    /// Proof file naming template, relative to the workspace. Placeholders:
+    /// `{stem}` (deposit file stem), `{ts}` (timestamp), `{note_index}` (`all` for bundles).
+    #[arg(long, env = "PROOF_NAMING", default_value = DEFAULT_PROOF_NAMING)]
+    proof_naming: String,
+
+    /// Maximum notes per new deposit; must not exceed the circuit's MAX_NOTES.
+    #[arg(
+        long,
+        env = "SHADOW_MAX_NOTES",
+        default_value_t = shadow_proof_core::MAX_NOTES,
+        value_parser = parse_max_notes
+    )]
+    max_notes: usize,
+
+    /// Reject proof nodes larger than this before proving; must not exceed the
+    /// circuit's MAX_NODE_BYTES.
+    #[arg(
+        long,
+        env = "MAX_PROOF_NODE_BYTES",
+        default_value_t = shadow_proof_core::MAX_NODE_BYTES,
+        value_parser = parse_max_proof_node_bytes
+    )]
+    max_proof_node_bytes: usize,
+
+    /// Warn before proving when the deposit balance exceeds the note total by
+    /// less than this many wei (token base units for ERC20 deposits).
+    #[arg(long, env = "BALANCE_MARGIN_WEI")]
+    balance_margin_wei: Option<u128>,
+
+    /// Append a JSON line per finished proof job to this file (never includes secrets).
+    #[arg(long, env = "AUDIT_LOG")]
+    audit_log: Option<PathBuf>,
+
+    /// Create workspace directories 0700 and written files 0600 (Unix only).
+    #[arg(long, env = "SECURE_PERMS")]
+    secure_perms: bool,
+
+    /// Maximum concurrent on-chain nullifier status requests (a batch counts once).
+    #[arg(long, env = "STATUS_CONCURRENCY", default_value_t = DEFAULT_STATUS_CONCURRENCY)]
+    status_concurrency: usize,
+
+    /// Serve per-note claim inputs and repro bundles (exposes deposit secrets).
+    #[arg(long, env = "SERVE_CLAIM_INPUTS")]
+    serve_claim_inputs: bool,
+
+    /// Seconds to wait on shutdown (SIGTERM or Ctrl-C) for the running proof
+    /// job to stop and open connections to close.
+    #[arg(long, env = "SHUTDOWN_GRACE_SECS", default_value_t = 30)]
+    shutdown_grace_secs: u64,
+
+    /// At startup, move unparseable deposit and proof files into the
+    /// workspace's `quarantine/` directory (never deletes).
+    #[arg(long, env = "PRUNE_INVALID")]
+    prune_invalid: bool,
+
+    /// Events buffered per WebSocket client before it starts missing them.
+    #[arg(
+        long,
+        env = "EVENT_BUFFER",
+        default_value_t = DEFAULT_EVENT_BUFFER,
+        value_parser = parse_positive
+    )]
+    event_buffer: usize,
+
+    /// How clients that fall behind the event stream catch up: forward every
+    /// buffered event, or coalesce consecutive progress updates into the latest.
+    #[arg(long, env = "EVENT_OVERFLOW", value_enum, default_value_t)]
+    event_overflow: EventOverflow,
+
+    /// Require WebSocket clients to present this token, as a `token` query
+    /// parameter or a `Sec-WebSocket-Protocol` entry. Without it `/ws` is open.
+    #[arg(
+        long,
+        env = "WS_TOKEN",
+        hide_env_values = true,
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    ws_token: Option<String>,
+
+    /// Notes of a deposit proved in parallel, capped at the CPU count. Proofs
+    /// share one Rayon pool, but each holds its own prover memory (several GB
+    /// for Groth16), so raise this only with memory to spare.
+    #[arg(
+        long,
+        env = "SHADOW_PROVE_CONCURRENCY",
+        default_value_t = 1,
+        value_parser = parse_positive
+    )]
+    prove_concurrency: usize,
+
+    /// Reuse receipts of earlier proofs of the same input from this directory.
+    #[cfg(feature = "prove")]
+    #[arg(long, env = "PROVE_CACHE_DIR", requires = "prove_cache_key")]
+    prove_cache_dir: Option<PathBuf>,
+
+    /// 32-byte hex key encrypting the proof cache at rest.
+    #[cfg(feature = "prove")]
+    #[arg(long, env = "PROVE_CACHE_KEY", hide_env_values = true)]
+    prove_cache_key: Option<shadow_util::Hex32>,
+}
+
+#[cfg(feature = "prove")]
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Regenerate claim calldata in every proof file from its stored receipts.
+    Recalldata { workspace: PathBuf },
+}
+
+/// A workspace served under `/{prefix}`.
+#[derive(Debug, Clone)]
+struct WorkspacePrefix {
+    prefix: String,
+    dir: PathBuf,
+}
+
+fn parse_workspace_prefix(s: &str) -> Result<WorkspacePrefix, String> {
+    let (prefix, dir) = s
+        .split_once('=')
+        .ok_or_else(|| "expected PREFIX=DIR".to_string())?;
+    let valid_chars = prefix
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if prefix.is_empty() || !valid_chars {
+        return Err("prefix must be letters, digits, '-' or '_'".to_string());
+    }
+    if matches!(prefix, "api" | "ws") {
+        return Err(format!("prefix '{prefix}' is reserved"));
+    }
+    if dir.is_empty() {
+        return Err("missing workspace directory".to_string());
+    }
+    Ok(WorkspacePrefix {
+        prefix: prefix.to_string(),
+        dir: PathBuf::from(dir),
+    })
+}
+
+fn parse_max_notes(s: &str) -> Result<usize, String> {
+    parse_circuit_limit(s, shadow_proof_core::MAX_NOTES, "MAX_NOTES")
+}
+
+fn parse_max_proof_node_bytes(s: &str) -> Result<usize, String> {
+    parse_circuit_limit(s, shadow_proof_core::MAX_NODE_BYTES, "MAX_NODE_BYTES")
+}
+
+fn parse_positive(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(format!("{e}")),
+    }
+}
+
+/// Parse a positive limit no larger than the circuit constant `name`.
+fn parse_circuit_limit(s: &str, ceiling: usize, name: &str) -> Result<usize, String> {
+    let n: usize = s.parse().map_err(|e| format!("{e}"))?;
+    if n == 0 || n > ceiling {
+        return Err(format!("must be between 1 and {ceiling} (circuit {name})"));
+    }
+    Ok(n)
 }
 
 #[tokio::main]
@@ -53,41 +268,51 @@ async fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
+    let proof_naming = ProofNaming::parse(&cli.proof_naming)?;
+    let write_perms = WritePerms::new(cli.secure_perms);
+
+    #[cfg(feature = "prove")]
+    if let Some(Command::Recalldata { workspace }) = &cli.command {
+        let updated =
+            prover::recalldata::recalldata_workspace(workspace, &proof_naming, write_perms)?;
+        println!("updated {updated} note(s)");
+        return Ok(());
+    }
 
     // Resolve workspace to absolute path, defaulting to ~/.taikoshadow
-    let workspace_path = cli.workspace.unwrap_or_else(|| {
+    let workspace_path = cli.workspace.clone().unwrap_or_else(|| {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join(".taikoshadow")
     });
-    std::fs::create_dir_all(&workspace_path)
-        .with_context(|| format!("failed to create workspace: {}", workspace_path.display()))?;
-    let workspace = workspace_path
-        .canonicalize()
-        .with_context(|| format!("workspace not found: {}", workspace_path.display()))?;
-
+    let workspace = open_workspace(&cli, &workspace_path, &proof_naming, write_perms)?;
     tracing::info!(workspace = %workspace.display(), "starting shadow-server");
+    let mut prefixed = Vec::new();
+    for entry in &cli.workspace_prefixes {
+        if prefixed.iter().any(|(prefix, _)| prefix == &entry.prefix) {
+            bail!("workspace prefix '{}' given more than once", entry.prefix);
+        }
+        let dir = open_workspace(&cli, &entry.dir, &proof_naming, write_perms)?;
+        tracing::info!(
+            prefix = %entry.prefix,
+            workspace = %dir.display(),
+            "serving prefixed workspace"
+        );
+        prefixed.push((entry.prefix.clone(), dir));
+    }
     tracing::info!(port = cli.port, "listening on port");
     if let Some(ref rpc) = cli.rpc_url {
         tracing::info!(rpc_url = %rpc, "RPC endpoint configured");
     }
 
-    // Broadcast channel for WebSocket events (proof progress, workspace changes)
-    let (event_tx, _) = broadcast::channel::<String>(64);
-
-    // Proof generation queue
-    let proof_queue = ProofQueue::new(event_tx.clone());
-
-    // On-chain client (optional, requires RPC URL)
-    let chain_client = cli
-        .rpc_url
-        .as_ref()
-        .map(|url| ChainClient::new(url.clone()));
+    let rpc_http = build_rpc_client(&RpcAuth {
+        header: cli.rpc_auth_header.clone(),
+        bearer_token: cli.rpc_bearer_token.clone(),
+    })?;
 
     // Fetch chain ID from RPC at startup
     let chain_id = if let Some(ref rpc_url) = cli.rpc_url {
-        let http = reqwest::Client::new();
-        match prover::rpc::eth_chain_id(&http, rpc_url).await {
+        match prover::rpc::eth_chain_id(&rpc_http, rpc_url).await {
             Ok(id) => {
                 tracing::info!(chain_id = id, "chain ID from RPC");
                 Some(id)
@@ -101,16 +326,42 @@ async fn main() -> Result<()> {
         None
     };
 
-    let state = Arc::new(AppState {
-        workspace,
-        rpc_url: cli.rpc_url,
+    #[cfg(feature = "prove")]
+    let proof_cache = match (cli.prove_cache_dir.clone(), cli.prove_cache_key) {
+        (Some(dir), Some(key)) => {
+            tracing::info!(dir = %dir.display(), "proof cache enabled");
+            Some(Arc::new(ProofCache::new(
+                dir,
+                key.into_bytes(),
+                write_perms,
+            )?))
+        }
+        _ => None,
+    };
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if cli.prove_concurrency > cores {
+        tracing::warn!(
+            requested = cli.prove_concurrency,
+            cores,
+            "prove concurrency capped at the CPU count"
+        );
+    }
+
+    let shared = SharedSettings {
+        rpc_http,
+        proof_naming,
+        write_perms,
         chain_id,
-        ui_dir: cli.ui_dir,
-        event_tx,
-        proof_queue,
-        chain_client,
-        shadow_address: cli.shadow_address,
-    });
+        prove_concurrency: cli.prove_concurrency.min(cores),
+        #[cfg(feature = "prove")]
+        proof_cache,
+    };
+    let state = Arc::new(workspace_state(&cli, &shared, workspace));
+    let prefixed: Vec<(String, Arc<AppState>)> = prefixed
+        .into_iter()
+        .map(|(prefix, dir)| (prefix, Arc::new(workspace_state(&cli, &shared, dir))))
+        .collect();
 
     // ---------------------------------------------------------------------------
     // Circuit ID verification
@@ -146,6 +397,7 @@ async fn main() -> Result<()> {
                             tracing::warn!("could not read circuit ID from verifier: {:#}", e)
                         }
                     }
+                    check_groth16_selector(chain, &verifier_addr).await;
                 }
                 Err(e) => tracing::warn!("could not resolve circuit verifier from Shadow: {:#}", e),
             }
@@ -154,7 +406,16 @@ async fn main() -> Result<()> {
         }
     }
 
-    let app = build_router(state);
+    // Background workers that run queued proof jobs, one per workspace
+    let states: Vec<Arc<AppState>> = std::iter::once(state.clone())
+        .chain(prefixed.iter().map(|(_, s)| s.clone()))
+        .collect();
+    let workers: Vec<JoinHandle<()>> = states
+        .iter()
+        .map(|s| tokio::spawn(prover::worker::run(s.clone())))
+        .collect();
+
+    let app = build_router(state, &prefixed);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], cli.port));
     tracing::info!(%addr, "server listening");
@@ -163,17 +424,204 @@ async fn main() -> Result<()> {
         .await
         .with_context(|| format!("failed to bind to {}", addr))?;
 
-    axum::serve(listener, app).await.context("server error")?;
+    let (stop_tx, mut stop_rx) = watch::channel(());
+    let mut server = tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async move {
+                let _ = stop_rx.changed().await;
+            })
+            .await
+    });
+    tokio::select! {
+        result = &mut server => {
+            return result.context("server task failed")?.context("server error");
+        }
+        () = shutdown_signal() => {}
+    }
+
+    tracing::info!(grace_secs = cli.shutdown_grace_secs, "shutting down");
+    let deadline = Instant::now() + Duration::from_secs(cli.shutdown_grace_secs);
+    let _ = stop_tx.send(());
+    drain_proof_queues(&states, workers, deadline).await;
+    if tokio::time::timeout_at(deadline, &mut server)
+        .await
+        .is_err()
+    {
+        tracing::warn!("connections still open at the end of the grace period; closing them");
+        server.abort();
+    }
 
     Ok(())
 }
 
-fn build_router(state: Arc<AppState>) -> Router {
-    let api = routes::api_router(state.clone());
+/// Resolve on Ctrl-C, or on SIGTERM (`docker stop`) on Unix.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!(error = %e, "failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
+
+/// Shut down every workspace's proof queue, then wait until `deadline` for
+/// the workers to record the cancelled jobs. Notes proved before the signal
+/// stay in the deposit's `.partial` checkpoint for `?resume=true`.
+async fn drain_proof_queues(
+    states: &[Arc<AppState>],
+    workers: Vec<JoinHandle<()>>,
+    deadline: Instant,
+) {
+    for state in states {
+        let cancelled = state.proof_queue.shutdown().await;
+        if cancelled > 0 {
+            tracing::info!(
+                workspace = %state.workspace.display(),
+                cancelled,
+                "proof jobs cancelled"
+            );
+        }
+    }
+    for worker in workers {
+        if tokio::time::timeout_at(deadline, worker).await.is_err() {
+            tracing::warn!("proof job still running at the end of the grace period");
+            return;
+        }
+    }
+}
+
+/// Create `path` if needed and return its canonical form, quarantining
+/// invalid files when `--prune-invalid` is set.
+fn open_workspace(
+    cli: &Cli,
+    path: &Path,
+    proof_naming: &ProofNaming,
+    write_perms: WritePerms,
+) -> Result<PathBuf> {
+    write_perms
+        .create_dir_all(path)
+        .and_then(|()| write_perms.restrict_dir(path))
+        .with_context(|| format!("failed to create workspace: {}", path.display()))?;
+    let workspace = path
+        .canonicalize()
+        .with_context(|| format!("workspace not found: {}", path.display()))?;
+
+    if cli.prune_invalid {
+        let moved = quarantine_invalid_files(&workspace, proof_naming, write_perms)?;
+        tracing::info!(count = moved.len(), "quarantined invalid workspace files");
+    }
+    Ok(workspace)
+}
+
+/// Settings resolved once at startup and shared by every served workspace.
+struct SharedSettings {
+    rpc_http: reqwest::Client,
+    proof_naming: ProofNaming,
+    write_perms: WritePerms,
+    chain_id: Option<u64>,
+    prove_concurrency: usize,
+    #[cfg(feature = "prove")]
+    proof_cache: Option<Arc<ProofCache>>,
+}
+
+/// State for one workspace, with its own event channel, proof queue and caches.
+fn workspace_state(cli: &Cli, shared: &SharedSettings, workspace: PathBuf) -> AppState {
+    // Broadcast channel for WebSocket events (proof progress, workspace changes)
+    let (event_tx, _) = broadcast::channel::<String>(cli.event_buffer);
+
+    AppState {
+        workspace,
+        rpc_url: cli.rpc_url.clone(),
+        rpc_http: shared.rpc_http.clone(),
+        chain_id: shared.chain_id,
+        ui_dir: cli.ui_dir.clone(),
+        proof_queue: ProofQueue::new(event_tx.clone()),
+        event_tx,
+        event_overflow: cli.event_overflow,
+        ws_token: cli.ws_token.clone(),
+        // On-chain client (optional, requires RPC URL)
+        chain_client: cli.rpc_url.as_ref().map(|url| {
+            ChainClient::new(
+                shared.rpc_http.clone(),
+                url.clone(),
+                cli.status_concurrency,
+                StatusCachePolicy::default(),
+            )
+        }),
+        shadow_address: cli.shadow_address.clone(),
+        proof_naming: shared.proof_naming.clone(),
+        max_notes: cli.max_notes,
+        max_proof_node_bytes: cli.max_proof_node_bytes,
+        balance_margin_wei: cli.balance_margin_wei,
+        audit_log: cli.audit_log.clone().map(ProofAuditLog::new),
+        write_perms: shared.write_perms,
+        recipient_hashes: RecipientHashCache::default(),
+        index_cache: WorkspaceIndexCache::default(),
+        mines_in_flight: MinesInFlight::default(),
+        block_cache: BlockCache::default(),
+        prove_concurrency: shared.prove_concurrency,
+        serve_claim_inputs: cli.serve_claim_inputs,
+        #[cfg(feature = "prove")]
+        proof_cache: shared.proof_cache.clone(),
+    }
+}
+
+/// Warn if the Groth16 verifier behind `verifier_addr` expects a different
+/// seal selector than exported proofs carry, which makes every claim revert.
+#[cfg(feature = "prove")]
+async fn check_groth16_selector(chain: &ChainClient, verifier_addr: &str) {
+    let local = format!(
+        "0x{}",
+        hex::encode(shadow_prover_lib::groth16_selector(
+            &shadow_prover_lib::Groth16ReceiptVerifierParameters::default()
+        ))
+    );
+    match chain.read_groth16_selector(verifier_addr).await {
+        Ok(onchain) if onchain != local => tracing::warn!(
+            onchain = %onchain,
+            local = %local,
+            "Groth16 selector mismatch — the deployed RISC Zero verifier uses different \
+             verifier parameters, so exported Groth16 proofs will be rejected."
+        ),
+        Ok(_) => tracing::info!(selector = %local, "Groth16 selector matches on-chain verifier ✓"),
+        Err(e) => tracing::info!("Groth16 selector check skipped: {:#}", e),
+    }
+}
 
-    let mut app = Router::new()
-        .nest("/api", api)
-        .merge(routes::ws::router().with_state(state.clone()));
+/// Routes for one workspace: its API under `/api` and its events at `/ws`.
+fn workspace_router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .nest("/api", routes::api_router(state.clone()))
+        .merge(routes::ws::router().with_state(state))
+}
+
+fn build_router(state: Arc<AppState>, prefixed: &[(String, Arc<AppState>)]) -> Router {
+    let mut app = workspace_router(state.clone());
+    for (prefix, prefixed_state) in prefixed {
+        app = app.nest(
+            &format!("/{prefix}"),
+            workspace_router(prefixed_state.clone()),
+        );
+    }
 
     // Serve static UI files if directory exists
     if state.ui_dir.is_dir() {
@@ -184,7 +632,89 @@ fn build_router(state: Arc<AppState>) -> Router {
     }
 
     // CORS for local development
-    let cors = CorsLayer::very_permissive();
+    let cors =
+        CorsLayer::very_permissive().expose_headers([routes::request_id::X_REQUEST_ID.clone()]);
 
-    app.layer(cors)
+    app.layer(axum::middleware::from_fn(routes::request_id::layer))
+        .layer(cors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_notes_is_bounded_by_circuit_limit() {
+        let cli = Cli::try_parse_from(["shadow-server", "--max-notes", "2"]).unwrap();
+        assert_eq!(cli.max_notes, 2);
+
+        let cli = Cli::try_parse_from(["shadow-server"]).unwrap();
+        assert_eq!(cli.max_notes, shadow_proof_core::MAX_NOTES);
+
+        let too_many = (shadow_proof_core::MAX_NOTES + 1).to_string();
+        assert!(Cli::try_parse_from(["shadow-server", "--max-notes", &too_many]).is_err());
+        assert!(Cli::try_parse_from(["shadow-server", "--max-notes", "0"]).is_err());
+    }
+
+    #[test]
+    fn workspace_prefixes_parse_and_reject_reserved_names() {
+        let cli = Cli::try_parse_from([
+            "shadow-server",
+            "--workspace-prefix",
+            "teamA=/srv/a",
+            "--workspace-prefix",
+            "team_b=/srv/b",
+        ])
+        .unwrap();
+        let prefixes: Vec<&str> = cli
+            .workspace_prefixes
+            .iter()
+            .map(|p| p.prefix.as_str())
+            .collect();
+        assert_eq!(prefixes, ["teamA", "team_b"]);
+        assert_eq!(cli.workspace_prefixes[0].dir, PathBuf::from("/srv/a"));
+
+        for bad in ["api=/srv/a", "a/b=/srv/a", "=/srv/a", "teamA", "teamA="] {
+            let args = ["shadow-server", "--workspace-prefix", bad];
+            assert!(Cli::try_parse_from(args).is_err(), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn prefixed_workspaces_do_not_share_deposits() {
+        let dirs: Vec<_> = (0..3).map(|_| tempfile::tempdir().unwrap()).collect();
+        let state = |i: usize| Arc::new(AppState::for_tests(dirs[i].path().into()));
+        let prefixed = [
+            ("teamA".to_string(), state(1)),
+            ("teamB".to_string(), state(2)),
+        ];
+        let app = build_router(state(0), &prefixed);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "chainId": "167013",
+            "notes": [{"recipient": format!("0x{}", "11".repeat(20)), "amount": "1000"}],
+        });
+        let created = client
+            .post(format!("{base}/teamA/api/deposits"))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert!(created.status().is_success());
+
+        let list = |path: &str| {
+            let request = client.get(format!("{base}{path}")).send();
+            async move {
+                let deposits: Vec<serde_json::Value> = request.await.unwrap().json().await.unwrap();
+                deposits.len()
+            }
+        };
+        assert_eq!(list("/teamA/api/deposits").await, 1);
+        assert_eq!(list("/teamB/api/deposits").await, 0);
+        assert_eq!(list("/api/deposits").await, 0);
+    }
 }
@@ -1,12 +1,14 @@
 //! Deposit creation: generate a random secret and derive the target address.
 
-use std::path::Path;
+use std::{collections::HashSet, path::Path, sync::Mutex};
 
 use anyhow::{bail, Context, Result};
 use rand::RngCore;
-use shadow_proof_core::{
-    compute_notes_hash, compute_recipient_hash, derive_target_address, MAX_NOTES,
-};
+use shadow_proof_core::{compute_notes_hash, derive_target_address, MAX_NOTES};
+use shadow_util::to_checksum_address;
+use zeroize::Zeroizing;
+
+use crate::workspace::{perms::WritePerms, recipient_cache::RecipientHashCache};
 
 /// Input for creating a new deposit.
 pub struct MineRequest {
@@ -22,12 +24,16 @@ pub struct MineNote {
 
 /// Result of deposit creation.
 pub struct MineResult {
-    pub secret: [u8; 32],
+    /// Scrubbed from memory on drop.
+    pub secret: Zeroizing<[u8; 32]>,
     pub target_address: [u8; 20],
 }
 
 /// Create a deposit by generating a random secret and deriving the target address.
-pub fn mine_deposit(req: &MineRequest) -> Result<MineResult> {
+pub fn mine_deposit(
+    req: &MineRequest,
+    recipient_hashes: &RecipientHashCache,
+) -> Result<MineResult> {
     if req.notes.is_empty() || req.notes.len() > MAX_NOTES {
         bail!(
             "invalid note count: {} (must be 1..={})",
@@ -36,19 +42,11 @@ pub fn mine_deposit(req: &MineRequest) -> Result<MineResult> {
         );
     }
 
-    let amounts: Vec<u128> = req.notes.iter().map(|n| n.amount).collect();
-    let recipient_hashes: Vec<[u8; 32]> = req
-        .notes
-        .iter()
-        .map(|n| compute_recipient_hash(&n.recipient))
-        .collect();
-
-    let notes_hash = compute_notes_hash(req.notes.len(), &amounts, &recipient_hashes)
-        .map_err(|e| anyhow::anyhow!("notes hash computation failed: {}", e.as_str()))?;
+    let notes_hash = notes_hash(&req.notes, recipient_hashes)?;
 
     let mut rng = rand::thread_rng();
-    let mut secret = [0u8; 32];
-    rng.fill_bytes(&mut secret);
+    let mut secret = Zeroizing::new([0u8; 32]);
+    rng.fill_bytes(secret.as_mut());
 
     let target_address = derive_target_address(&secret, req.chain_id, &notes_hash);
     Ok(MineResult {
@@ -57,30 +55,108 @@ pub fn mine_deposit(req: &MineRequest) -> Result<MineResult> {
     })
 }
 
+/// Notes hash committed to by a deposit over `notes`.
+pub fn notes_hash(notes: &[MineNote], recipient_hashes: &RecipientHashCache) -> Result<[u8; 32]> {
+    let amounts: Vec<u128> = notes.iter().map(|n| n.amount).collect();
+    let hashes: Vec<[u8; 32]> = notes
+        .iter()
+        .map(|n| recipient_hashes.get(&n.recipient))
+        .collect();
+
+    compute_notes_hash(notes.len(), &amounts, &hashes)
+        .map_err(|e| anyhow::anyhow!("notes hash computation failed: {}", e.as_str()))
+}
+
+/// What a deposit being created commits to: its chain, token and notes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MineKey {
+    pub chain_id: u64,
+    /// Lowercase token address, `None` for ETH.
+    pub token: Option<String>,
+    pub notes_hash: [u8; 32],
+}
+
+impl MineKey {
+    pub fn new(chain_id: u64, token: Option<&str>, notes_hash: [u8; 32]) -> Self {
+        Self {
+            chain_id,
+            token: token.map(str::to_ascii_lowercase),
+            notes_hash,
+        }
+    }
+}
+
+/// Deposits with a creation in flight, so concurrent requests for the same
+/// chain, token and note set do not both mine.
+#[derive(Default)]
+pub struct MinesInFlight {
+    keys: Mutex<HashSet<MineKey>>,
+}
+
+impl MinesInFlight {
+    /// Mark `key` as being mined. Returns `None` if another request already
+    /// holds it; the entry is cleared when the guard drops.
+    pub fn begin(&self, key: MineKey) -> Option<MineGuard<'_>> {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        if !keys.insert(key.clone()) {
+            return None;
+        }
+        Some(MineGuard {
+            registry: self,
+            key,
+        })
+    }
+}
+
+/// Clears a `MinesInFlight` entry on drop.
+pub struct MineGuard<'a> {
+    registry: &'a MinesInFlight,
+    key: MineKey,
+}
+
+impl Drop for MineGuard<'_> {
+    fn drop(&mut self) {
+        let mut keys = self.registry.keys.lock().unwrap_or_else(|e| e.into_inner());
+        keys.remove(&self.key);
+    }
+}
+
+/// Contents of a new deposit file.
+pub struct NewDeposit<'a> {
+    pub chain_id: u64,
+    pub secret: &'a [u8; 32],
+    pub target_address: &'a [u8; 20],
+    pub notes: &'a [MineNote],
+    pub comment: Option<&'a str>,
+    pub token: Option<&'a str>,
+    pub token_symbol: Option<&'a str>,
+}
+
 /// Write a v2 deposit JSON file to the workspace directory.
 ///
 /// Returns the filename that was written.
 pub fn write_deposit_file(
     workspace: &Path,
-    chain_id: u64,
-    secret: &[u8; 32],
-    target_address: &[u8; 20],
-    notes: &[MineNote],
-    comment: Option<&str>,
-    token: Option<&str>,
-    token_symbol: Option<&str>,
+    perms: WritePerms,
+    deposit: &NewDeposit<'_>,
 ) -> Result<String> {
-    let hex_addr = hex::encode(target_address);
-    let first4 = &hex_addr[..4];
-    let last4 = &hex_addr[hex_addr.len() - 4..];
+    let NewDeposit {
+        chain_id,
+        secret,
+        target_address,
+        notes,
+        comment,
+        token,
+        token_symbol,
+    } = *deposit;
     let timestamp = timestamp_now();
-    let filename = format!("deposit-{first4}-{last4}-{timestamp}.json");
+    let filename = deposit_filename(target_address, &timestamp);
 
     let notes_json: Vec<serde_json::Value> = notes
         .iter()
         .map(|n| {
             let mut obj = serde_json::json!({
-                "recipient": format!("0x{}", hex::encode(n.recipient)),
+                "recipient": to_checksum_address(&n.recipient),
                 "amount": n.amount.to_string(),
             });
             if let Some(ref label) = n.label {
@@ -96,7 +172,7 @@ pub fn write_deposit_file(
         "chainId": chain_id.to_string(),
         "secret": format!("0x{}", hex::encode(secret)),
         "notes": notes_json,
-        "targetAddress": format!("0x{}", hex::encode(target_address)),
+        "targetAddress": to_checksum_address(target_address),
     });
     if let Some(c) = comment {
         deposit_json["comment"] = serde_json::Value::String(c.to_string());
@@ -111,14 +187,24 @@ pub fn write_deposit_file(
     let path = workspace.join(&filename);
     let contents =
         serde_json::to_string_pretty(&deposit_json).context("failed to serialize deposit JSON")?;
-    std::fs::write(&path, contents)
+    perms
+        .write(&path, contents)
         .with_context(|| format!("failed to write {}", path.display()))?;
 
     Ok(filename)
 }
 
+/// Deposit filename: `deposit-<first4>-<last4>-<timestamp>.json`, using the
+/// first and last 4 hex chars of the target address.
+pub fn deposit_filename(target_address: &[u8; 20], timestamp: &str) -> String {
+    let hex_addr = hex::encode(target_address);
+    let first4 = &hex_addr[..4];
+    let last4 = &hex_addr[hex_addr.len() - 4..];
+    format!("deposit-{first4}-{last4}-{timestamp}.json")
+}
+
 /// Generate a compact ISO 8601 UTC timestamp: `YYYYMMDDTHHMMSS`.
-fn timestamp_now() -> String {
+pub fn timestamp_now() -> String {
     use std::time::SystemTime;
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -151,18 +237,3 @@ fn civil_from_days(days: i64) -> (i32, u32, u32) {
     let y = if m <= 2 { y + 1 } else { y };
     (y as i32, m, d)
 }
-
-/// Parse a 0x-prefixed hex address string into 20 bytes.
-pub fn parse_hex_address(hex_str: &str) -> Result<[u8; 20]> {
-    let stripped = hex_str
-        .strip_prefix("0x")
-        .or_else(|| hex_str.strip_prefix("0X"))
-        .context("address must start with 0x")?;
-    if stripped.len() != 40 {
-        bail!("expected 20 bytes (40 hex chars), got {}", stripped.len());
-    }
-    let bytes = hex::decode(stripped).context("invalid hex")?;
-    let mut out = [0u8; 20];
-    out.copy_from_slice(&bytes);
-    Ok(out)
-}
@@ -0,0 +1,152 @@
+//! Append-only audit log of proof requests.
+//!
+//! Each finished job appends one JSON line. Records carry public data only
+//! (deposit id, block, nullifiers, outcome) and never the deposit secret.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use super::pipeline::BundledProof;
+use crate::{mining::timestamp_now, workspace::scanner::iso_from_compact_timestamp};
+
+/// How a proof job ended.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Fail,
+    Cancel,
+}
+
+/// One line of the audit log.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub deposit_id: String,
+    pub timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<String>,
+    pub nullifiers: Vec<String>,
+    pub outcome: AuditOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// Record for a job that produced `bundled`.
+    pub fn success(deposit_id: &str, bundled: &BundledProof) -> Self {
+        Self {
+            deposit_id: deposit_id.to_string(),
+            timestamp: now_iso(),
+            block_number: Some(bundled.block_number.clone()),
+            nullifiers: bundled.notes.iter().map(|n| n.nullifier.clone()).collect(),
+            outcome: AuditOutcome::Success,
+            error: None,
+        }
+    }
+
+    /// Record for a job that failed or was cancelled before producing a proof.
+    pub fn unfinished(deposit_id: &str, outcome: AuditOutcome, error: &str) -> Self {
+        Self {
+            deposit_id: deposit_id.to_string(),
+            timestamp: now_iso(),
+            block_number: None,
+            nullifiers: Vec::new(),
+            outcome,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Audit log file opened in append mode for every record.
+#[derive(Debug, Clone)]
+pub struct ProofAuditLog {
+    path: PathBuf,
+}
+
+impl ProofAuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `record` as a single JSON line.
+    ///
+    /// The line is written with one `write_all` on an `O_APPEND` handle, so
+    /// concurrent writers never interleave partial records.
+    pub fn append(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record).context("failed to serialize audit record")?;
+        line.push(b'\n');
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open audit log {}", self.path.display()))?;
+        file.write_all(&line)
+            .with_context(|| format!("failed to write audit log {}", self.path.display()))
+    }
+}
+
+fn now_iso() -> String {
+    let ts = timestamp_now();
+    iso_from_compact_timestamp(&ts).unwrap_or(ts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::pipeline::NoteProofResult;
+
+    #[test]
+    fn completed_job_appends_one_audit_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = ProofAuditLog::new(dir.path().join("audit.jsonl"));
+        let note = |index: u32, nullifier: &str| NoteProofResult {
+            note_index: index,
+            amount: "1000".to_string(),
+            recipient: "0x1111111111111111111111111111111111111111".to_string(),
+            nullifier: nullifier.to_string(),
+            seal: String::new(),
+            journal: String::new(),
+            proof: String::new(),
+            receipt_base64: None,
+            token: None,
+        };
+        let bundled = BundledProof {
+            version: "v2".to_string(),
+            created: None,
+            circuit_id: None,
+            deposit_file: "deposit-ffe8-fde9-20260224T214613.json".to_string(),
+            block_number: "100".to_string(),
+            block_hash: format!("0x{}", "11".repeat(32)),
+            chain_id: "167013".to_string(),
+            notes: vec![note(0, "0xaa"), note(1, "0xbb")],
+        };
+
+        log.append(&AuditRecord::success(
+            "deposit-ffe8-fde9-20260224T214613",
+            &bundled,
+        ))
+        .unwrap();
+
+        let contents = std::fs::read_to_string(log.path()).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["depositId"], "deposit-ffe8-fde9-20260224T214613");
+        assert_eq!(record["blockNumber"], "100");
+        assert_eq!(record["nullifiers"], serde_json::json!(["0xaa", "0xbb"]));
+        assert_eq!(record["outcome"], "success");
+        assert!(record["timestamp"].as_str().unwrap().ends_with('Z'));
+        assert!(record.get("error").is_none());
+        assert!(!contents.contains("secret"));
+    }
+}
@@ -0,0 +1,62 @@
+//! Short-lived cache of fetched blocks.
+//!
+//! Proof jobs prove against the latest block, so jobs started within a few
+//! seconds of each other usually land on the same block and can share one
+//! `eth_getBlockByNumber` call.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use tokio::sync::Mutex;
+
+use super::rpc::{self, BlockData};
+
+/// How long a fetched block is reused.
+pub const DEFAULT_BLOCK_CACHE_TTL: Duration = Duration::from_secs(12);
+
+/// `(chain_id, block_number)` → block data and when it was fetched.
+pub struct BlockCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<(u64, u64), (BlockData, Instant)>>,
+}
+
+impl Default for BlockCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_BLOCK_CACHE_TTL)
+    }
+}
+
+impl BlockCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The block at `block_number`, fetched unless a fresh copy is cached.
+    /// The lock is held across the fetch so concurrent callers share it.
+    pub async fn get_or_fetch(
+        &self,
+        client: &reqwest::Client,
+        rpc_url: &str,
+        chain_id: u64,
+        block_number: u64,
+    ) -> Result<BlockData> {
+        let mut entries = self.entries.lock().await;
+        entries.retain(|_, (_, fetched_at)| fetched_at.elapsed() < self.ttl);
+
+        if let Some((block, _)) = entries.get(&(chain_id, block_number)) {
+            tracing::debug!(block_number = block_number, "block cache hit");
+            return Ok(block.clone());
+        }
+
+        let tag = format!("0x{:x}", block_number);
+        let block = rpc::eth_get_block(client, rpc_url, &tag).await?;
+        entries.insert((chain_id, block_number), (block.clone(), Instant::now()));
+        Ok(block)
+    }
+}
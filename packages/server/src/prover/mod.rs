@@ -1,5 +1,14 @@
+pub mod audit;
+pub mod block_cache;
 pub mod pipeline;
+#[cfg(feature = "prove")]
+pub mod proof_cache;
 pub mod queue;
+#[cfg(feature = "prove")]
+pub mod recalldata;
 pub mod rpc;
+#[cfg(any(feature = "prove", test))]
+pub mod selftest;
+pub mod worker;
 
 pub use queue::ProofQueue;
@@ -1,21 +1,34 @@
 //! Proof generation pipeline.
 //!
-//! Given a deposit file and RPC URL, proves ALL notes in the deposit sequentially
-//! and bundles the results into a single proof file.
-
-use std::{path::Path, sync::Arc};
+//! Given a deposit file and RPC URL, proves ALL notes in the deposit (up to
+//! `--prove-concurrency` at a time) and bundles the results into a single
+//! proof file. Finished notes are checkpointed to a `.partial` sidecar so a
+//! resumed run only proves the notes that are missing.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::{bail, Context, Result};
+use futures_util::{StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use shadow_proof_core::{
-    compute_notes_hash, compute_recipient_hash, derive_nullifier, derive_target_address,
-    ClaimInput, TokenClaimInput, MAX_NODE_BYTES, MAX_NOTES,
+    derive_nullifier, verify_account_proof_and_get_balance,
+    verify_account_proof_and_get_storage_root, verify_storage_proof_and_get_value, ClaimInput,
+    ClaimValidationError, TokenClaimInput, MAX_NODE_BYTES,
 };
+use shadow_util::deposit::{derive_deposit_info, DepositFile};
+use zeroize::Zeroizing;
 
+#[cfg(feature = "prove")]
+use super::proof_cache::ProofCache;
 use super::{
+    block_cache::BlockCache,
     queue::{ProgressExtra, ProofQueue},
     rpc::{self, BlockData},
 };
+use crate::{chain::ChainClient, workspace::perms::WritePerms};
 
 /// Bundled proof file: contains proofs for ALL notes in a deposit.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,11 +77,33 @@ pub struct NoteProofResult {
     pub token: Option<String>,
 }
 
+/// Operator settings and shared caches used by a pipeline run.
+pub struct PipelineConfig<'a> {
+    pub rpc_url: &'a str,
+    pub http_client: &'a reqwest::Client,
+    /// Re-checks the proving block once the notes are proved.
+    pub chain_client: Option<&'a ChainClient>,
+    /// Operator limit on proof node size (at most the circuit's `MAX_NODE_BYTES`).
+    pub max_node_bytes: usize,
+    pub block_cache: &'a BlockCache,
+    /// Warn when the target's balance exceeds the note total by less than this
+    /// (in the deposit token's base units).
+    pub balance_margin_wei: Option<u128>,
+    /// Notes of a deposit proved in parallel (at least 1).
+    pub prove_concurrency: usize,
+    /// Permissions for the `.partial` checkpoint file.
+    pub write_perms: WritePerms,
+    /// Encrypted receipts of earlier proofs, consulted before proving.
+    #[cfg(feature = "prove")]
+    pub proof_cache: Option<Arc<ProofCache>>,
+}
+
 /// Shared context for proving all notes in a deposit.
 struct ProveContext {
     block: BlockData,
     chain_id: u64,
-    secret: [u8; 32],
+    /// Scrubbed from memory on drop.
+    secret: Zeroizing<[u8; 32]>,
     amounts: Vec<u128>,
     recipients: Vec<[u8; 20]>,
     recipient_hashes: Vec<[u8; 32]>,
@@ -76,23 +111,46 @@ struct ProveContext {
     account_proof_nodes: Vec<Vec<u8>>,
     token_address: Option<[u8; 20]>,
     erc20_proof: Option<rpc::Erc20BalanceProofData>,
+    /// Operator limit on proof node size (at most the circuit's `MAX_NODE_BYTES`).
+    max_node_bytes: usize,
 }
 
 /// Run the proof pipeline for a deposit file.
+///
+/// With `resume`, notes already in the deposit's [`partial_path`] checkpoint
+/// are kept and only the missing ones are proved, at the checkpoint's block.
 pub async fn run_pipeline(
     workspace: &Path,
     deposit_filename: &str,
-    rpc_url: &str,
+    config: &PipelineConfig<'_>,
     queue: Arc<ProofQueue>,
     mut cancel_rx: tokio::sync::oneshot::Receiver<()>,
+    resume: bool,
 ) -> Result<BundledProof> {
     let pipeline_start = std::time::Instant::now();
 
-    let ctx = load_deposit_and_fetch_proofs(workspace, deposit_filename, rpc_url, &queue).await?;
-
-    let note_results = prove_all_notes(&ctx, &queue, &mut cancel_rx, &pipeline_start).await?;
-
-    let bundled = BundledProof {
+    let checkpoint = partial_path(workspace, deposit_filename);
+    let partial = if resume {
+        load_partial(&checkpoint)?
+    } else {
+        None
+    };
+    let pinned_block = partial
+        .as_ref()
+        .map(|p| p.block_number.parse::<u64>())
+        .transpose()
+        .context("invalid block number in partial proof")?;
+
+    let ctx = load_deposit_and_fetch_proofs(
+        workspace,
+        deposit_filename,
+        config,
+        Some(&queue),
+        pinned_block,
+    )
+    .await?;
+
+    let mut bundled = BundledProof {
         version: "v2".to_string(),
         created: None,
         circuit_id: None,
@@ -100,8 +158,31 @@ pub async fn run_pipeline(
         block_number: ctx.block.number.to_string(),
         block_hash: format!("0x{}", hex::encode(ctx.block.hash)),
         chain_id: ctx.chain_id.to_string(),
-        notes: note_results,
+        notes: Vec::new(),
     };
+    if let Some(partial) = partial {
+        bundled.notes = resumable_notes(&ctx, &bundled, partial)?;
+        tracing::info!(
+            deposit = %deposit_filename,
+            resumed_notes = bundled.notes.len(),
+            "resuming from partial proof"
+        );
+    }
+
+    prove_all_notes(
+        &ctx,
+        config,
+        &queue,
+        &mut cancel_rx,
+        &pipeline_start,
+        &mut bundled,
+        &checkpoint,
+    )
+    .await?;
+    bundled.notes.sort_by_key(|n| n.note_index);
+    if let Some(chain_client) = config.chain_client {
+        ensure_block_canonical(chain_client, &ctx.block).await?;
+    }
 
     tracing::info!(
         deposit = %deposit_filename,
@@ -113,101 +194,141 @@ pub async fn run_pipeline(
     Ok(bundled)
 }
 
+/// Fail if `block` was reorged out while its notes were being proved, since
+/// the anchor would not know its hash and every claim would revert.
+///
+/// An RPC failure only logs a warning: the proofs are most likely fine and
+/// `GET /api/deposits/:id/verify-block` can re-check later.
+async fn ensure_block_canonical(chain_client: &ChainClient, block: &BlockData) -> Result<()> {
+    let block_hash = format!("0x{}", hex::encode(block.hash));
+    match chain_client
+        .block_still_canonical(block.number, &block_hash)
+        .await
+    {
+        Ok(check) if check.still_canonical => Ok(()),
+        Ok(check) => bail!(
+            "block {} was reorged out while proving (canonical hash is now {}); \
+             prove the deposit again",
+            block.number,
+            check.canonical_hash.as_deref().unwrap_or("unknown")
+        ),
+        Err(e) => {
+            tracing::warn!(
+                error = %format!("{:#}", e),
+                block_number = block.number,
+                "could not re-check the proving block"
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Checkpoint file holding the notes proved so far for `deposit_filename`.
+/// It is a [`BundledProof`] whose `notes` may be incomplete.
+pub fn partial_path(workspace: &Path, deposit_filename: &str) -> PathBuf {
+    workspace.join(deposit_filename).with_extension("partial")
+}
+
+fn load_partial(path: &Path) -> Result<Option<BundledProof>> {
+    let raw = match std::fs::read(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let partial = serde_json::from_slice(&raw)
+        .with_context(|| format!("invalid partial proof {}", path.display()))?;
+    Ok(Some(partial))
+}
+
+/// Notes of `partial` that belong to this run: same deposit, chain and block,
+/// and each note's amount, recipient and nullifier unchanged. The nullifier
+/// derives from the secret and the notes hash, so it catches a change to
+/// either.
+fn resumable_notes(
+    ctx: &ProveContext,
+    bundled: &BundledProof,
+    partial: BundledProof,
+) -> Result<Vec<NoteProofResult>> {
+    if partial.deposit_file != bundled.deposit_file || partial.chain_id != bundled.chain_id {
+        bail!("partial proof belongs to a different deposit or chain");
+    }
+    if partial.block_hash != bundled.block_hash {
+        bail!(
+            "block {} changed since the partial proof was written; prove without resume",
+            partial.block_number
+        );
+    }
+    let mut notes: Vec<NoteProofResult> = Vec::new();
+    for note in partial.notes {
+        let i = note.note_index as usize;
+        let nullifier =
+            derive_nullifier(&ctx.secret, ctx.chain_id, note.note_index, &ctx.notes_hash);
+        let matches = i < ctx.amounts.len()
+            && note.amount == ctx.amounts[i].to_string()
+            && note.recipient == format!("0x{}", hex::encode(ctx.recipients[i]))
+            && note
+                .nullifier
+                .eq_ignore_ascii_case(&format!("0x{}", hex::encode(nullifier)));
+        if !matches || notes.iter().any(|n| n.note_index == note.note_index) {
+            bail!("partial proof note {} does not match the deposit", i);
+        }
+        notes.push(note);
+    }
+    Ok(notes)
+}
+
 async fn load_deposit_and_fetch_proofs(
     workspace: &Path,
     deposit_filename: &str,
-    rpc_url: &str,
-    queue: &ProofQueue,
+    config: &PipelineConfig<'_>,
+    queue: Option<&ProofQueue>,
+    pinned_block: Option<u64>,
 ) -> Result<ProveContext> {
+    let rpc_url = config.rpc_url;
     let deposit_path = workspace.join(deposit_filename);
     let raw = std::fs::read(&deposit_path)
         .with_context(|| format!("failed reading {}", deposit_filename))?;
 
-    #[derive(Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    struct DepositJson {
-        version: String,
-        chain_id: String,
-        secret: String,
-        notes: Vec<NoteJson>,
-        target_address: Option<String>,
-        token: Option<String>,
-    }
-
-    #[derive(Deserialize)]
-    struct NoteJson {
-        recipient: String,
-        amount: String,
-    }
-
-    let deposit: DepositJson = serde_json::from_slice(&raw)?;
-    if deposit.version != "v2" {
-        bail!("unsupported deposit version: {}", deposit.version);
-    }
-
-    let chain_id: u64 = deposit.chain_id.parse()?;
-    let secret = parse_hex_bytes32(&deposit.secret)?;
-    let note_count = deposit.notes.len();
-    let token_address: Option<[u8; 20]> = deposit
-        .token
-        .as_ref()
-        .map(|t| parse_hex_address(t))
-        .transpose()?;
-
-    if note_count == 0 || note_count > MAX_NOTES {
-        bail!("invalid note count: {}", note_count);
-    }
+    let deposit: DepositFile = serde_json::from_slice(&raw).context("invalid deposit JSON")?;
+    // Notes come back in derivation order, which honours `noteOrder`.
+    let derived = derive_deposit_info(&deposit)?;
+    let chain_id = derived.chain_id;
+    let secret = derived.secret;
+    let token_address = derived.token;
+    let target_address = derived.target_address;
+    let notes_hash = derived.notes_hash;
+    let amounts: Vec<u128> = derived.notes.iter().map(|n| n.amount).collect();
+    let recipients: Vec<[u8; 20]> = derived.notes.iter().map(|n| n.recipient).collect();
+    let recipient_hashes: Vec<[u8; 32]> = derived.notes.iter().map(|n| n.recipient_hash).collect();
 
     tracing::info!(
         deposit = %deposit_filename,
         chain_id = chain_id,
-        note_count = note_count,
+        note_count = amounts.len(),
         "pipeline started"
     );
-
-    let mut amounts: Vec<u128> = Vec::new();
-    let mut recipients: Vec<[u8; 20]> = Vec::new();
-    let mut recipient_hashes: Vec<[u8; 32]> = Vec::new();
-
-    for note in &deposit.notes {
-        let recipient = parse_hex_address(&note.recipient)?;
-        let amount: u128 = note.amount.parse()?;
-        recipients.push(recipient);
-        amounts.push(amount);
-        recipient_hashes.push(compute_recipient_hash(&recipient));
-    }
-
-    let notes_hash = compute_notes_hash(note_count, &amounts, &recipient_hashes)
-        .map_err(|e| anyhow::anyhow!("notes hash failed: {}", e.as_str()))?;
-    let target_address = derive_target_address(&secret, chain_id, &notes_hash);
-
     tracing::debug!(
         target_address = %format!("0x{}", hex::encode(target_address)),
         "target address derived"
     );
 
-    if let Some(ref expected) = deposit.target_address {
-        let expected_bytes = parse_hex_address(expected)?;
-        if expected_bytes != target_address {
-            bail!("targetAddress mismatch");
-        }
+    if let Some(queue) = queue {
+        queue
+            .update_progress(
+                0,
+                "Fetching block data from chain...",
+                Some(&ProgressExtra {
+                    chain_id: Some(chain_id),
+                    stage: Some("rpc_block".into()),
+                    ..Default::default()
+                }),
+            )
+            .await;
     }
 
-    queue
-        .update_progress(
-            0,
-            "Fetching block data from chain...",
-            Some(&ProgressExtra {
-                chain_id: Some(chain_id),
-                stage: Some("rpc_block".into()),
-                ..Default::default()
-            }),
-        )
-        .await;
+    let http_client = config.http_client;
 
-    let http_client = reqwest::Client::new();
-
-    let rpc_chain_id = rpc::eth_chain_id(&http_client, rpc_url).await?;
+    let rpc_chain_id = rpc::eth_chain_id(http_client, rpc_url).await?;
     if rpc_chain_id != chain_id {
         bail!(
             "chain ID mismatch: deposit says {} but RPC returns {}",
@@ -218,25 +339,44 @@ async fn load_deposit_and_fetch_proofs(
 
     tracing::debug!(chain_id = chain_id, "chain ID verified against RPC");
 
-    let block = rpc::eth_get_block(&http_client, rpc_url, "latest").await?;
+    let block_number = match pinned_block {
+        Some(number) => number,
+        None => rpc::eth_block_number(http_client, rpc_url).await?,
+    };
+    let block = config
+        .block_cache
+        .get_or_fetch(http_client, rpc_url, chain_id, block_number)
+        .await?;
 
-    tracing::info!(block_number = block.number, "block fetched for proving");
+    tracing::info!(
+        block_number = block.number,
+        block_timestamp = block.header.timestamp,
+        "block fetched for proving"
+    );
+    tracing::debug!(
+        state_root = %format!("0x{}", hex::encode(block.header.state_root)),
+        parent_hash = %format!("0x{}", hex::encode(block.header.parent_hash)),
+        base_fee = block.header.base_fee,
+        "proving block header"
+    );
 
-    queue
-        .update_progress(
-            0,
-            "Fetching account proof from Merkle tree...",
-            Some(&ProgressExtra {
-                chain_id: Some(chain_id),
-                block_number: Some(block.number),
-                stage: Some("rpc_proof".into()),
-                ..Default::default()
-            }),
-        )
-        .await;
+    if let Some(queue) = queue {
+        queue
+            .update_progress(
+                0,
+                "Fetching account proof from Merkle tree...",
+                Some(&ProgressExtra {
+                    chain_id: Some(chain_id),
+                    block_number: Some(block.number),
+                    stage: Some("rpc_proof".into()),
+                    ..Default::default()
+                }),
+            )
+            .await;
+    }
 
     let account_proof =
-        rpc::eth_get_proof(&http_client, rpc_url, &target_address, block.number).await?;
+        rpc::eth_get_proof(http_client, rpc_url, &target_address, block.number).await?;
 
     tracing::info!(
         proof_depth = account_proof.proof_nodes.len(),
@@ -246,23 +386,35 @@ async fn load_deposit_and_fetch_proofs(
     if account_proof.proof_nodes.is_empty() {
         bail!("account proof is empty; target address may not exist on-chain");
     }
+    check_proof_root("account", &account_proof.proof_nodes, &block)?;
+    check_reported_balance(
+        "account",
+        &account_proof.balance,
+        verify_account_proof_and_get_balance(
+            &block.header.state_root,
+            &target_address,
+            &account_proof.proof_nodes,
+        ),
+    )?;
 
     let erc20_proof = if let Some(ref token_addr) = token_address {
-        queue
-            .update_progress(
-                0,
-                "Fetching ERC20 balance proof...",
-                Some(&ProgressExtra {
-                    chain_id: Some(chain_id),
-                    block_number: Some(block.number),
-                    stage: Some("rpc_erc20_proof".into()),
-                    ..Default::default()
-                }),
-            )
-            .await;
+        if let Some(queue) = queue {
+            queue
+                .update_progress(
+                    0,
+                    "Fetching ERC20 balance proof...",
+                    Some(&ProgressExtra {
+                        chain_id: Some(chain_id),
+                        block_number: Some(block.number),
+                        stage: Some("rpc_erc20_proof".into()),
+                        ..Default::default()
+                    }),
+                )
+                .await;
+        }
 
         let proof = rpc::eth_get_erc20_balance_proof(
-            &http_client,
+            http_client,
             rpc_url,
             token_addr,
             &target_address,
@@ -270,6 +422,21 @@ async fn load_deposit_and_fetch_proofs(
         )
         .await?;
 
+        check_proof_root("token account", &proof.token_account_proof_nodes, &block)?;
+        let proven = verify_account_proof_and_get_storage_root(
+            &block.header.state_root,
+            token_addr,
+            &proof.token_account_proof_nodes,
+        )
+        .and_then(|storage_root| {
+            verify_storage_proof_and_get_value(
+                &storage_root,
+                &proof.balance_storage_key,
+                &proof.balance_storage_proof_nodes,
+            )
+        });
+        check_reported_balance("token", &proof.balance, proven)?;
+
         tracing::info!(
             token = %format!("0x{}", hex::encode(token_addr)),
             account_proof_depth = proof.token_account_proof_nodes.len(),
@@ -282,6 +449,30 @@ async fn load_deposit_and_fetch_proofs(
         None
     };
 
+    if let Some(margin) = config.balance_margin_wei {
+        let balance = erc20_proof
+            .as_ref()
+            .map_or(&account_proof.balance, |p| &p.balance);
+        let total = amounts.iter().fold(0u128, |sum, a| sum.saturating_add(*a));
+        if let Some(warning) = balance_margin_warning(balance, total, margin) {
+            tracing::warn!(deposit = %deposit_filename, "{}", warning);
+            if let Some(queue) = queue {
+                queue
+                    .update_progress(
+                        0,
+                        &warning,
+                        Some(&ProgressExtra {
+                            chain_id: Some(chain_id),
+                            block_number: Some(block.number),
+                            stage: Some("balance_margin".into()),
+                            ..Default::default()
+                        }),
+                    )
+                    .await;
+            }
+        }
+    }
+
     Ok(ProveContext {
         block,
         chain_id,
@@ -293,106 +484,159 @@ async fn load_deposit_and_fetch_proofs(
         account_proof_nodes: account_proof.proof_nodes,
         token_address,
         erc20_proof,
+        max_node_bytes: config.max_node_bytes,
     })
 }
 
+/// Prove every note missing from `bundled`, up to `config.prove_concurrency`
+/// at a time, adding results in note order and checkpointing each one to
+/// `checkpoint`.
+///
+/// Concurrent proofs share the global Rayon pool, so raising the limit does
+/// not add STARK threads, but each in-flight proof holds its own prover
+/// memory. On cancellation or a failed note the remaining proofs are
+/// abandoned; prover threads already running finish in the background.
 async fn prove_all_notes(
     ctx: &ProveContext,
+    config: &PipelineConfig<'_>,
     queue: &ProofQueue,
     cancel_rx: &mut tokio::sync::oneshot::Receiver<()>,
     pipeline_start: &std::time::Instant,
-) -> Result<Vec<NoteProofResult>> {
+    bundled: &mut BundledProof,
+    checkpoint: &Path,
+) -> Result<()> {
+    let missing: Vec<usize> = (0..ctx.amounts.len())
+        .filter(|i| !bundled.notes.iter().any(|n| n.note_index as usize == *i))
+        .collect();
+    let mut proving = futures_util::stream::iter(missing)
+        .map(|i| prove_note(ctx, config, queue, pipeline_start, i))
+        .buffered(config.prove_concurrency.max(1));
+
+    let checkpointed = async {
+        while let Some(note) = proving.try_next().await? {
+            bundled.notes.push(note);
+            let written = serde_json::to_vec_pretty(&*bundled)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(config.write_perms.write(checkpoint, json)?));
+            if let Err(e) = written {
+                tracing::warn!(
+                    error = %format!("{:#}", e),
+                    file = %checkpoint.display(),
+                    "failed to write partial proof"
+                );
+            }
+        }
+        Ok(())
+    };
+
+    tokio::select! {
+        result = checkpointed => result,
+        _ = &mut *cancel_rx => bail!("proof generation cancelled"),
+    }
+}
+
+async fn prove_note(
+    ctx: &ProveContext,
+    config: &PipelineConfig<'_>,
+    queue: &ProofQueue,
+    pipeline_start: &std::time::Instant,
+    i: usize,
+) -> Result<NoteProofResult> {
     let note_count = ctx.amounts.len();
-    let mut note_results: Vec<NoteProofResult> = Vec::with_capacity(note_count);
+    let note_start = std::time::Instant::now();
 
-    for i in 0..note_count {
-        let note_start = std::time::Instant::now();
+    tracing::info!(
+        note = i,
+        total = note_count,
+        amount = ctx.amounts[i],
+        "proving note"
+    );
 
-        if cancel_rx.try_recv().is_ok() {
-            bail!("proof generation cancelled by user");
-        }
+    queue
+        .update_progress(
+            i as u32,
+            &format!("Proving note {}/{}", i + 1, note_count),
+            Some(&ProgressExtra {
+                block_number: Some(ctx.block.number),
+                chain_id: Some(ctx.chain_id),
+                elapsed_secs: Some(pipeline_start.elapsed().as_secs_f64()),
+                stage: Some("proving".into()),
+                ..Default::default()
+            }),
+        )
+        .await;
 
-        tracing::info!(
-            note = i,
-            total = note_count,
-            amount = ctx.amounts[i],
-            "proving note"
-        );
+    let nullifier = derive_nullifier(&ctx.secret, ctx.chain_id, i as u32, &ctx.notes_hash);
+    let claim_input = build_claim_input(ctx, i as u32)?;
 
-        queue
-            .update_progress(
-                i as u32,
-                &format!("Proving note {}/{}", i + 1, note_count),
-                Some(&ProgressExtra {
-                    block_number: Some(ctx.block.number),
-                    chain_id: Some(ctx.chain_id),
-                    elapsed_secs: Some(pipeline_start.elapsed().as_secs_f64()),
-                    stage: Some("proving".into()),
-                    ..Default::default()
-                }),
-            )
-            .await;
+    let note_proof = prove_single_note(claim_input, config)
+        .await
+        .inspect_err(|e| {
+            let chain: Vec<String> = std::iter::once(e.to_string())
+                .chain(e.chain().skip(1).map(|c| c.to_string()))
+                .collect();
+            tracing::error!(note = i, detail = %chain.join(" | "), "prove_single_note failed");
+        })?;
 
-        let nullifier = derive_nullifier(&ctx.secret, ctx.chain_id, i as u32, &ctx.notes_hash);
-        let claim_input = build_claim_input(ctx, i as u32)?;
-
-        let note_proof = tokio::select! {
-            result = prove_single_note(claim_input) => match result {
-                Ok(p) => p,
-                Err(e) => {
-                    let chain: Vec<String> = std::iter::once(e.to_string())
-                        .chain(e.chain().skip(1).map(|c| c.to_string()))
-                        .collect();
-                    tracing::error!(note = i, detail = %chain.join(" | "), "prove_single_note failed");
-                    return Err(e);
-                }
-            },
-            _ = &mut *cancel_rx => bail!("proof generation cancelled by user"),
-        };
+    let note_elapsed = note_start.elapsed();
+    tracing::info!(
+        note = i,
+        elapsed_secs = note_elapsed.as_secs_f64(),
+        seal_len = note_proof.seal_hex.len() / 2,
+        journal_len = note_proof.journal_hex.len() / 2,
+        "note proved"
+    );
 
-        let note_elapsed = note_start.elapsed();
-        tracing::info!(
-            note = i,
-            elapsed_secs = note_elapsed.as_secs_f64(),
-            seal_len = note_proof.seal_hex.len() / 2,
-            journal_len = note_proof.journal_hex.len() / 2,
-            "note proved"
-        );
+    queue
+        .update_progress(
+            i as u32,
+            &format!(
+                "Note {}/{} proved in {:.1}s",
+                i + 1,
+                note_count,
+                note_elapsed.as_secs_f64()
+            ),
+            Some(&ProgressExtra {
+                block_number: Some(ctx.block.number),
+                chain_id: Some(ctx.chain_id),
+                elapsed_secs: Some(pipeline_start.elapsed().as_secs_f64()),
+                note_elapsed_secs: Some(note_elapsed.as_secs_f64()),
+                stage: Some("note_complete".into()),
+            }),
+        )
+        .await;
 
-        queue
-            .update_progress(
-                i as u32,
-                &format!(
-                    "Note {}/{} proved in {:.1}s",
-                    i + 1,
-                    note_count,
-                    note_elapsed.as_secs_f64()
-                ),
-                Some(&ProgressExtra {
-                    block_number: Some(ctx.block.number),
-                    chain_id: Some(ctx.chain_id),
-                    elapsed_secs: Some(pipeline_start.elapsed().as_secs_f64()),
-                    note_elapsed_secs: Some(note_elapsed.as_secs_f64()),
-                    stage: Some("note_complete".into()),
-                    ..Default::default()
-                }),
-            )
-            .await;
+    Ok(NoteProofResult {
+        note_index: i as u32,
+        amount: ctx.amounts[i].to_string(),
+        recipient: format!("0x{}", hex::encode(ctx.recipients[i])),
+        nullifier: format!("0x{}", hex::encode(nullifier)),
+        seal: note_proof.seal_hex,
+        journal: note_proof.journal_hex,
+        proof: note_proof.proof_hex,
+        receipt_base64: note_proof.receipt_base64,
+        token: ctx.token_address.map(|a| format!("0x{}", hex::encode(a))),
+    })
+}
 
-        note_results.push(NoteProofResult {
-            note_index: i as u32,
-            amount: ctx.amounts[i].to_string(),
-            recipient: format!("0x{}", hex::encode(ctx.recipients[i])),
-            nullifier: format!("0x{}", hex::encode(nullifier)),
-            seal: note_proof.seal_hex,
-            journal: note_proof.journal_hex,
-            proof: note_proof.proof_hex,
-            receipt_base64: note_proof.receipt_base64,
-            token: ctx.token_address.map(|a| format!("0x{}", hex::encode(a))),
-        });
+/// Rebuild the `ClaimInput` the prover would receive for one note against
+/// fresh chain data, without touching the proof queue.
+pub async fn build_note_claim_input(
+    workspace: &Path,
+    deposit_filename: &str,
+    config: &PipelineConfig<'_>,
+    note_index: u32,
+) -> Result<ClaimInput> {
+    let ctx =
+        load_deposit_and_fetch_proofs(workspace, deposit_filename, config, None, None).await?;
+    if note_index as usize >= ctx.amounts.len() {
+        bail!(
+            "note index {} out of range ({} notes)",
+            note_index,
+            ctx.amounts.len()
+        );
     }
-
-    Ok(note_results)
+    build_claim_input(&ctx, note_index)
 }
 
 /// Build a ClaimInput for a single note.
@@ -410,26 +654,28 @@ fn build_claim_input(ctx: &ProveContext, note_index: u32) -> Result<ClaimInput>
         bail!("empty account proof");
     }
 
-    let mut validated_nodes = Vec::with_capacity(ctx.account_proof_nodes.len());
-    for node in &ctx.account_proof_nodes {
-        if node.len() > MAX_NODE_BYTES {
-            bail!(
-                "proof node exceeds {} bytes (got {})",
-                MAX_NODE_BYTES,
-                node.len()
-            );
-        }
-        validated_nodes.push(node.clone());
-    }
+    check_node_sizes("account", &ctx.account_proof_nodes, ctx.max_node_bytes)?;
 
     let token = match (&ctx.token_address, &ctx.erc20_proof) {
-        (Some(addr), Some(proof)) => Some(TokenClaimInput {
-            token_address: *addr,
-            balance_slot: proof.balance_slot,
-            balance_storage_key: proof.balance_storage_key,
-            token_account_proof_nodes: proof.token_account_proof_nodes.clone(),
-            balance_storage_proof_nodes: proof.balance_storage_proof_nodes.clone(),
-        }),
+        (Some(addr), Some(proof)) => {
+            check_node_sizes(
+                "token account",
+                &proof.token_account_proof_nodes,
+                ctx.max_node_bytes,
+            )?;
+            check_node_sizes(
+                "balance storage",
+                &proof.balance_storage_proof_nodes,
+                ctx.max_node_bytes,
+            )?;
+            Some(TokenClaimInput {
+                token_address: *addr,
+                balance_slot: proof.balance_slot,
+                balance_storage_key: proof.balance_storage_key,
+                token_account_proof_nodes: proof.token_account_proof_nodes.clone(),
+                balance_storage_proof_nodes: proof.balance_storage_proof_nodes.clone(),
+            })
+        }
         _ => None,
     };
 
@@ -440,17 +686,97 @@ fn build_claim_input(ctx: &ProveContext, note_index: u32) -> Result<ClaimInput>
         note_index,
         amount: ctx.amounts[note_index as usize],
         recipient: ctx.recipients[note_index as usize],
-        secret: ctx.secret,
+        secret: *ctx.secret,
         note_count: ctx.amounts.len() as u32,
         amounts: ctx.amounts.clone(),
         recipient_hashes: ctx.recipient_hashes.clone(),
         block_header_rlp: ctx.block.header_rlp.clone(),
         proof_depth,
-        proof_nodes: validated_nodes,
+        proof_nodes: ctx.account_proof_nodes.clone(),
         token,
     })
 }
 
+/// A warning when `balance` (big-endian) is below `total + margin`. A balance
+/// that drops between proving and claiming invalidates the proof.
+fn balance_margin_warning(balance: &[u8], total: u128, margin: u128) -> Option<String> {
+    let significant = &balance[balance
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(balance.len())..];
+    if significant.len() > 16 {
+        return None;
+    }
+    let mut word = [0u8; 16];
+    word[16 - significant.len()..].copy_from_slice(significant);
+    let balance = u128::from_be_bytes(word);
+    if balance >= total.saturating_add(margin) {
+        return None;
+    }
+    Some(format!(
+        "target balance {} exceeds the note total {} by less than the {} margin \
+         (--balance-margin-wei); a balance change before claiming would invalidate the proof",
+        balance, total, margin
+    ))
+}
+
+/// Reject a proof whose root node is not `block`'s state root, e.g. when the
+/// RPC answered for a different block than the header the claim commits to.
+fn check_proof_root(kind: &str, nodes: &[Vec<u8>], block: &BlockData) -> Result<()> {
+    if nodes.first().map(|root| rpc::keccak256(root)) != Some(block.header.state_root) {
+        bail!(
+            "{} proof does not match the state root of block {}; \
+             the RPC may have answered for another block",
+            kind,
+            block.number
+        );
+    }
+    Ok(())
+}
+
+/// Fail fast when the balance the RPC reported differs from `proven`, the
+/// value the circuit will read out of the proof nodes.
+fn check_reported_balance(
+    kind: &str,
+    reported: &[u8],
+    proven: Result<[u8; 32], ClaimValidationError>,
+) -> Result<()> {
+    let proven =
+        proven.map_err(|e| anyhow::anyhow!("{} proof is invalid: {}", kind, e.as_str()))?;
+    let significant = |bytes: &[u8]| {
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        format!("0x{}", hex::encode(&bytes[start..]))
+    };
+    if significant(reported) != significant(&proven) {
+        bail!(
+            "{} balance mismatch: RPC reports {} but the proof holds {}",
+            kind,
+            significant(reported),
+            significant(&proven)
+        );
+    }
+    Ok(())
+}
+
+/// Reject proof nodes over the operator's limit before they reach the prover,
+/// naming both the configured limit and the circuit's compiled ceiling.
+fn check_node_sizes(kind: &str, nodes: &[Vec<u8>], max_node_bytes: usize) -> Result<()> {
+    for (i, node) in nodes.iter().enumerate() {
+        if node.len() > max_node_bytes {
+            bail!(
+                "{} proof node {} is {} bytes, over the {}-byte limit \
+                 (--max-proof-node-bytes; circuit MAX_NODE_BYTES is {})",
+                kind,
+                i,
+                node.len(),
+                max_node_bytes,
+                MAX_NODE_BYTES
+            );
+        }
+    }
+    Ok(())
+}
+
 struct SingleNoteProof {
     seal_hex: String,
     journal_hex: String,
@@ -464,10 +790,16 @@ struct SingleNoteProof {
 /// Receipt kind is controlled by the `RECEIPT_KIND` environment variable:
 /// - "groth16" (default in Docker) — produces on-chain-ready proofs
 /// - "succinct" — faster, but needs separate compression for on-chain use
-async fn prove_single_note(input: ClaimInput) -> Result<SingleNoteProof> {
+///
+/// With a proof cache configured, a verified cached receipt for the same input
+/// and receipt kind is reused instead of proving.
+async fn prove_single_note(
+    input: ClaimInput,
+    config: &PipelineConfig<'_>,
+) -> Result<SingleNoteProof> {
     #[cfg(feature = "prove")]
     {
-        use shadow_prover_lib::{configure_risc0_env, export_proof, prove_claim};
+        use shadow_prover_lib::{configure_risc0_env, prove_claim, VerifyPolicy};
 
         // Spawn a dedicated thread off the tokio blocking pool.
         // The heavy recursive STARK work happens in Rayon workers which inherit
@@ -476,10 +808,13 @@ async fn prove_single_note(input: ClaimInput) -> Result<SingleNoteProof> {
         tracing::info!("spawning prover thread");
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<SingleNoteProof>>();
         let note_index = input.note_index;
+        let proof_cache = config.proof_cache.clone();
+        let span = tracing::Span::current();
         std::thread::Builder::new()
             .name("shadow-prover".into())
             .stack_size(256 * 1024 * 1024)
             .spawn(move || {
+                let _span = span.enter();
                 let outcome = (|| {
                     tracing::info!(note_index = note_index, "prover thread started");
                     configure_risc0_env();
@@ -490,39 +825,24 @@ async fn prove_single_note(input: ClaimInput) -> Result<SingleNoteProof> {
                         receipt_kind = %receipt_kind,
                         "RISC Zero env configured"
                     );
-                    let prove_result = prove_claim(&input, &receipt_kind)?;
+                    let cached = proof_cache
+                        .as_ref()
+                        .and_then(|cache| cache.get(&input, &receipt_kind));
+                    if let Some(receipt) = cached {
+                        return export_receipt(&receipt);
+                    }
+                    let prove_result = prove_claim(&input, &receipt_kind, VerifyPolicy::Verify)?;
                     tracing::info!(
                         note_index = note_index,
                         elapsed_secs = prove_result.elapsed.as_secs_f64(),
                         "prove_claim completed; exporting proof"
                     );
-                    let exported = export_proof(&prove_result.receipt)?;
-
-                    let receipt_bytes =
-                        shadow_prover_lib::serialize_receipt(&prove_result.receipt)?;
-                    let receipt_b64 = base64_encode(&receipt_bytes);
-
-                    tracing::debug!(
-                        seal_len = exported.seal_hex.len() / 2,
-                        journal_len = exported.journal_hex.len() / 2,
-                        "proof exported"
-                    );
-
-                    let journal_bytes = hex::decode(
-                        exported
-                            .journal_hex
-                            .strip_prefix("0x")
-                            .unwrap_or(&exported.journal_hex),
-                    )?;
-                    let proof_calldata =
-                        encode_proof_for_chain(&exported.seal_hex, &journal_bytes)?;
-
-                    Ok::<_, anyhow::Error>(SingleNoteProof {
-                        seal_hex: exported.seal_hex,
-                        journal_hex: exported.journal_hex,
-                        proof_hex: format!("0x{}", hex::encode(proof_calldata)),
-                        receipt_base64: Some(receipt_b64),
-                    })
+                    if let Some(cache) = &proof_cache {
+                        if let Err(e) = cache.put(&input, &receipt_kind, &prove_result.receipt) {
+                            tracing::warn!(error = %format!("{:#}", e), "failed to cache receipt");
+                        }
+                    }
+                    export_single_note(&prove_result)
                 })();
                 match &outcome {
                     Ok(_) => tracing::info!(note_index = note_index, "prover thread finished"),
@@ -544,11 +864,12 @@ async fn prove_single_note(input: ClaimInput) -> Result<SingleNoteProof> {
         tracing::info!(note_index = note_index, "waiting for prover thread result");
         let result = rx.await.context("prover thread dropped sender")??;
 
-        return Ok(result);
+        Ok(result)
     }
 
     #[cfg(not(feature = "prove"))]
     {
+        let _ = config;
         // Without the prove feature, we can still validate the input
         let _ = shadow_proof_core::evaluate_claim(&input)
             .map_err(|e| anyhow::anyhow!("claim validation failed: {}", e.as_str()))?;
@@ -562,40 +883,62 @@ async fn prove_single_note(input: ClaimInput) -> Result<SingleNoteProof> {
     }
 }
 
-// ---------------------------------------------------------------------------
-// Hex parsing helpers
-// ---------------------------------------------------------------------------
-
-fn parse_hex_bytes32(hex_str: &str) -> Result<[u8; 32]> {
-    let stripped = hex_str
-        .strip_prefix("0x")
-        .or_else(|| hex_str.strip_prefix("0X"))
-        .context("expected 0x prefix")?;
-    let bytes = hex::decode(stripped)?;
-    if bytes.len() != 32 {
-        bail!("expected 32 bytes, got {}", bytes.len());
-    }
-    let mut out = [0u8; 32];
-    out.copy_from_slice(&bytes);
-    Ok(out)
+/// Export a proved note and encode its `_proof` argument for `claim`.
+#[cfg(feature = "prove")]
+fn export_single_note(prove_result: &shadow_prover_lib::ProveResult) -> Result<SingleNoteProof> {
+    export_receipt(&prove_result.receipt)
 }
 
-fn parse_hex_address(hex_str: &str) -> Result<[u8; 20]> {
-    let stripped = hex_str
-        .strip_prefix("0x")
-        .or_else(|| hex_str.strip_prefix("0X"))
-        .context("expected 0x prefix")?;
-    let bytes = hex::decode(stripped)?;
-    if bytes.len() != 20 {
-        bail!("expected 20 bytes, got {}", bytes.len());
-    }
-    let mut out = [0u8; 20];
-    out.copy_from_slice(&bytes);
-    Ok(out)
+#[cfg(feature = "prove")]
+fn export_receipt(receipt: &shadow_prover_lib::Receipt) -> Result<SingleNoteProof> {
+    let exported = shadow_prover_lib::export_proof(receipt)?;
+
+    let receipt_bytes = shadow_prover_lib::serialize_receipt(receipt)?;
+    let receipt_b64 = base64_encode(&receipt_bytes);
+
+    tracing::debug!(
+        seal_len = exported.seal_hex.len() / 2,
+        journal_len = exported.journal_hex.len() / 2,
+        "proof exported"
+    );
+
+    let proof_calldata = shadow_prover_lib::encode_claim_proof_calldata(&exported)?;
+
+    Ok(SingleNoteProof {
+        seal_hex: exported.seal_hex,
+        journal_hex: exported.journal_hex,
+        proof_hex: format!("0x{}", hex::encode(proof_calldata)),
+        receipt_base64: Some(receipt_b64),
+    })
+}
+
+/// Re-export every note that carries a receipt, refreshing its seal, journal
+/// and `proof` calldata. Returns how many notes' `proof` changed.
+#[cfg(feature = "prove")]
+pub fn recalldata_bundle(bundle: &mut BundledProof) -> Result<usize> {
+    let mut updated = 0;
+    for note in &mut bundle.notes {
+        let Some(ref receipt_b64) = note.receipt_base64 else {
+            continue;
+        };
+        let receipt_bytes = base64_decode(receipt_b64)
+            .with_context(|| format!("note {}: invalid receiptBase64", note.note_index))?;
+        let receipt = shadow_prover_lib::deserialize_receipt(&receipt_bytes)?;
+        let fresh = export_receipt(&receipt)
+            .with_context(|| format!("note {}: failed re-exporting receipt", note.note_index))?;
+
+        if note.proof != fresh.proof_hex {
+            updated += 1;
+        }
+        note.seal = fresh.seal_hex;
+        note.journal = fresh.journal_hex;
+        note.proof = fresh.proof_hex;
+    }
+    Ok(updated)
 }
 
 #[cfg(feature = "prove")]
-fn base64_encode(data: &[u8]) -> String {
+pub fn base64_encode(data: &[u8]) -> String {
     const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
     let mut out = String::new();
     for chunk in data.chunks(3) {
@@ -619,46 +962,364 @@ fn base64_encode(data: &[u8]) -> String {
     out
 }
 
-/// Encode proof for on-chain `Risc0CircuitVerifier.decodeProof()`.
-///
-/// The contract decodes: `(bytes seal, bytes journal) = abi.decode(_proof, (bytes, bytes))`
-/// SHA-256 of the journal is computed inside the contract; we pass the full journal bytes.
 #[cfg(feature = "prove")]
-fn encode_proof_for_chain(seal_hex: &str, journal_bytes: &[u8]) -> Result<Vec<u8>> {
-    let seal = hex::decode(seal_hex.strip_prefix("0x").unwrap_or(seal_hex))?;
-
-    let seal_padded_len = (seal.len() + 31) / 32 * 32;
-    let journal_padded_len = (journal_bytes.len() + 31) / 32 * 32;
-
-    // ABI encode: (bytes seal, bytes journal)
-    // Head: offset_seal (32) | offset_journal (32)
-    // Tail: seal_len (32) + seal_data (padded) + journal_len (32) + journal_data (padded)
-    let mut encoded = Vec::new();
-
-    // Offset of seal: 64 (past the two head slots)
-    let mut seal_offset = [0u8; 32];
-    seal_offset[28..32].copy_from_slice(&64u32.to_be_bytes());
-    encoded.extend_from_slice(&seal_offset);
-
-    // Offset of journal: 64 + 32 + seal_padded_len
-    let journal_offset = 64u32 + 32 + seal_padded_len as u32;
-    let mut j_offset = [0u8; 32];
-    j_offset[28..32].copy_from_slice(&journal_offset.to_be_bytes());
-    encoded.extend_from_slice(&j_offset);
-
-    // Seal length + data
-    let mut seal_len = [0u8; 32];
-    seal_len[28..32].copy_from_slice(&(seal.len() as u32).to_be_bytes());
-    encoded.extend_from_slice(&seal_len);
-    encoded.extend_from_slice(&seal);
-    encoded.extend(std::iter::repeat(0u8).take(seal_padded_len - seal.len()));
-
-    // Journal length + data
-    let mut journal_len = [0u8; 32];
-    journal_len[28..32].copy_from_slice(&(journal_bytes.len() as u32).to_be_bytes());
-    encoded.extend_from_slice(&journal_len);
-    encoded.extend_from_slice(journal_bytes);
-    encoded.extend(std::iter::repeat(0u8).take(journal_padded_len - journal_bytes.len()));
-
-    Ok(encoded)
+pub fn base64_decode(encoded: &str) -> Result<Vec<u8>> {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let bytes = encoded.trim().as_bytes();
+    if !bytes.len().is_multiple_of(4) {
+        bail!("base64 length {} is not a multiple of 4", bytes.len());
+    }
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+    for chunk in bytes.chunks(4) {
+        let padding = chunk.iter().rev().take_while(|&&b| b == b'=').count();
+        let mut triple = 0u32;
+        for &b in &chunk[..4 - padding] {
+            let value = CHARSET
+                .iter()
+                .position(|&c| c == b)
+                .with_context(|| format!("invalid base64 character {:?}", b as char))?;
+            triple = (triple << 6) | value as u32;
+        }
+        triple <<= 6 * padding as u32;
+        out.extend_from_slice(&triple.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "prove")]
+    use shadow_proof_core::{evaluate_claim, pack_journal};
+    #[cfg(feature = "prove")]
+    use shadow_prover_lib::{prove_claim, VerifyPolicy};
+
+    use super::*;
+    #[cfg(feature = "prove")]
+    use crate::{
+        chain::abi::{decode_claim_calldata, encode_claim_calldata},
+        prover::selftest::selftest_claim_input,
+    };
+    use crate::{chain::shadow_contract::StatusCachePolicy, prover::rpc::DecodedHeader};
+    use shadow_proof_core::test_support::build_single_account_trie;
+    use shadow_proof_core::{compute_notes_hash, compute_recipient_hash};
+
+    #[test]
+    fn balance_just_above_total_warns_when_margin_is_not_met() {
+        let total = 1_000_000u128;
+        let balance = (total + 1).to_be_bytes();
+
+        let warning = balance_margin_warning(&balance, total, 10).unwrap();
+        assert!(warning.contains("target balance 1000001"), "{warning}");
+        assert!(warning.contains("--balance-margin-wei"), "{warning}");
+
+        assert!(balance_margin_warning(&balance, total, 1).is_none());
+        assert!(balance_margin_warning(&[0x01; 20], total, u128::MAX).is_none());
+    }
+
+    #[test]
+    fn reported_balance_must_match_the_proven_leaf() {
+        let target = [0x44; 20];
+        let (leaf, state_root) = build_single_account_trie(&target, 1_000);
+        let proven = || {
+            verify_account_proof_and_get_balance(&state_root, &target, std::slice::from_ref(&leaf))
+        };
+
+        check_reported_balance("account", &[0x03, 0xe8], proven()).unwrap();
+        let err = check_reported_balance("account", &[0x03, 0xe9], proven()).unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "account balance mismatch: RPC reports 0x03e9 but the proof holds 0x03e8"
+            ),
+            "{err:#}"
+        );
+
+        let wrong_root =
+            verify_account_proof_and_get_balance(&[0; 32], &target, std::slice::from_ref(&leaf));
+        let err = check_reported_balance("account", &[0x03, 0xe8], wrong_root).unwrap_err();
+        assert!(
+            err.to_string().contains("account proof is invalid"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn node_over_configured_limit_is_rejected_before_proving() {
+        let amounts = vec![1_000u128];
+        let recipient_hashes = vec![compute_recipient_hash(&[0x11; 20])];
+        let notes_hash = compute_notes_hash(1, &amounts, &recipient_hashes).unwrap();
+        let ctx = ProveContext {
+            block: BlockData {
+                number: 1,
+                hash: [0; 32],
+                header_rlp: Vec::new(),
+                header: DecodedHeader {
+                    parent_hash: [0; 32],
+                    state_root: [0; 32],
+                    timestamp: 0,
+                    base_fee: 0,
+                },
+            },
+            chain_id: 167013,
+            secret: Zeroizing::new([0x42; 32]),
+            amounts,
+            recipients: vec![[0x11; 20]],
+            recipient_hashes,
+            notes_hash,
+            // Under the circuit's MAX_NODE_BYTES, over the operator limit.
+            account_proof_nodes: vec![vec![0; 64], vec![0; 3000]],
+            token_address: None,
+            erc20_proof: None,
+            max_node_bytes: 2048,
+        };
+
+        let err = build_claim_input(&ctx, 0).unwrap_err().to_string();
+        assert!(
+            err.contains("account proof node 1 is 3000 bytes, over the 2048-byte limit"),
+            "{err}"
+        );
+        assert!(err.contains("--max-proof-node-bytes"), "{err}");
+    }
+
+    #[test]
+    fn resume_rejects_notes_proved_under_another_secret() {
+        let recipient = [0x11; 20];
+        let recipient_hashes = vec![compute_recipient_hash(&recipient)];
+        let notes_hash = compute_notes_hash(1, &[1000], &recipient_hashes).unwrap();
+        let ctx = ProveContext {
+            block: BlockData {
+                number: 100,
+                hash: [0x99; 32],
+                header_rlp: Vec::new(),
+                header: DecodedHeader {
+                    parent_hash: [0; 32],
+                    state_root: [0; 32],
+                    timestamp: 0,
+                    base_fee: 0,
+                },
+            },
+            chain_id: 167013,
+            secret: Zeroizing::new([0x5e; 32]),
+            amounts: vec![1000],
+            recipients: vec![recipient],
+            recipient_hashes,
+            notes_hash,
+            account_proof_nodes: Vec::new(),
+            token_address: None,
+            erc20_proof: None,
+            max_node_bytes: MAX_NODE_BYTES,
+        };
+        let bundle = |secret: [u8; 32]| BundledProof {
+            version: "v2".to_string(),
+            created: None,
+            circuit_id: None,
+            deposit_file: "deposit.json".to_string(),
+            block_number: "100".to_string(),
+            block_hash: format!("0x{}", "99".repeat(32)),
+            chain_id: "167013".to_string(),
+            notes: vec![NoteProofResult {
+                note_index: 0,
+                amount: "1000".to_string(),
+                recipient: format!("0x{}", hex::encode(recipient)),
+                nullifier: format!(
+                    "0x{}",
+                    hex::encode(derive_nullifier(&secret, 167013, 0, &notes_hash))
+                ),
+                seal: String::new(),
+                journal: String::new(),
+                proof: String::new(),
+                receipt_base64: None,
+                token: None,
+            }],
+        };
+        let mut bundled = bundle(*ctx.secret);
+        bundled.notes.clear();
+
+        let resumed = resumable_notes(&ctx, &bundled, bundle(*ctx.secret)).unwrap();
+        assert_eq!(resumed.len(), 1);
+
+        // Same amounts and recipients, but the deposit was re-keyed.
+        let err = resumable_notes(&ctx, &bundled, bundle([0x77; 32])).unwrap_err();
+        assert!(
+            err.to_string().contains("does not match the deposit"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn reorged_proving_block_is_detected() {
+        use axum::{routing::post, Json, Router};
+
+        let canonical = format!("0x{}", "99".repeat(32));
+        let app = Router::new().route(
+            "/",
+            post(move || async move {
+                Json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": {"number": "0x64", "hash": canonical},
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let block = |hash| BlockData {
+            number: 100,
+            hash,
+            header_rlp: Vec::new(),
+            header: DecodedHeader {
+                parent_hash: [0; 32],
+                state_root: [0; 32],
+                timestamp: 0,
+                base_fee: 0,
+            },
+        };
+        let chain_client =
+            ChainClient::new(reqwest::Client::new(), url, 1, StatusCachePolicy::default());
+        ensure_block_canonical(&chain_client, &block([0x99; 32]))
+            .await
+            .unwrap();
+        let err = ensure_block_canonical(&chain_client, &block([0x11; 32]))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("block 100 was reorged out"), "{err}");
+        assert!(err.contains(&"99".repeat(32)), "{err}");
+    }
+
+    /// Read the `bytes journal` member of an `abi.encode(bytes, bytes)` proof.
+    #[cfg(feature = "prove")]
+    fn proof_journal(proof: &[u8]) -> &[u8] {
+        let word = |at: usize| u32::from_be_bytes(proof[at + 28..at + 32].try_into().unwrap());
+        let offset = word(32) as usize;
+        let len = word(offset) as usize;
+        &proof[offset + 32..offset + 32 + len]
+    }
+
+    /// Guards the prove → export → proof encoding → claim calldata chain
+    /// against an encoding change in any one layer.
+    #[test]
+    #[cfg(feature = "prove")]
+    fn dev_proof_round_trips_through_claim_calldata() {
+        std::env::set_var("RISC0_DEV_MODE", "1");
+        let input = selftest_claim_input();
+        let expected = evaluate_claim(&input).unwrap();
+
+        let result = prove_claim(&input, "succinct", VerifyPolicy::Verify).unwrap();
+        let note = export_single_note(&result).unwrap();
+        let proof = hex::decode(note.proof_hex.trim_start_matches("0x")).unwrap();
+        assert_eq!(proof_journal(&proof), pack_journal(&expected));
+
+        let calldata = encode_claim_calldata(
+            &proof,
+            result.journal.block_number,
+            result.journal.chain_id,
+            result.journal.amount,
+            &result.journal.recipient,
+            &result.journal.nullifier,
+            &result.journal.token,
+        );
+        let decoded = decode_claim_calldata(&calldata).unwrap();
+        assert_eq!(decoded.proof, proof);
+        assert_eq!(decoded.block_number, expected.block_number);
+        assert_eq!(decoded.chain_id, expected.chain_id);
+        assert_eq!(decoded.amount, expected.amount);
+        assert_eq!(decoded.recipient, expected.recipient);
+        assert_eq!(decoded.nullifier, expected.nullifier);
+        assert_eq!(decoded.token, expected.token);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "prove")]
+    async fn identical_input_is_served_from_proof_cache() {
+        use crate::workspace::perms::WritePerms;
+
+        std::env::set_var("RISC0_DEV_MODE", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Arc::new(
+            ProofCache::new(dir.path().to_path_buf(), [7; 32], WritePerms::default()).unwrap(),
+        );
+        let block_cache = BlockCache::default();
+        let http_client = reqwest::Client::new();
+        let config = PipelineConfig {
+            rpc_url: "",
+            http_client: &http_client,
+            chain_client: None,
+            max_node_bytes: MAX_NODE_BYTES,
+            block_cache: &block_cache,
+            balance_margin_wei: None,
+            prove_concurrency: 1,
+            write_perms: WritePerms::default(),
+            proof_cache: Some(cache.clone()),
+        };
+
+        let first = prove_single_note(selftest_claim_input(), &config)
+            .await
+            .unwrap();
+        assert_eq!(cache.hits(), 0);
+        let second = prove_single_note(selftest_claim_input(), &config)
+            .await
+            .unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(second.proof_hex, first.proof_hex);
+    }
+
+    #[test]
+    #[cfg(feature = "prove")]
+    fn base64_round_trips_every_padding_length() {
+        for data in [&b""[..], b"a", b"ab", b"abc", b"abcd"] {
+            assert_eq!(base64_decode(&base64_encode(data)).unwrap(), data);
+        }
+        assert!(base64_decode("abc").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "prove")]
+    fn recalldata_rewrites_stale_proof_calldata() {
+        use crate::{
+            prover::recalldata::recalldata_workspace,
+            workspace::{perms::WritePerms, scanner::ProofNaming},
+        };
+
+        std::env::set_var("RISC0_DEV_MODE", "1");
+        let result =
+            prove_claim(&selftest_claim_input(), "succinct", VerifyPolicy::Verify).unwrap();
+        let fresh = export_single_note(&result).unwrap();
+        let bundle = BundledProof {
+            version: "v2".to_string(),
+            created: None,
+            circuit_id: None,
+            deposit_file: "deposit-ffe8-fde9-20260224T214613.json".to_string(),
+            block_number: result.journal.block_number.to_string(),
+            block_hash: format!("0x{}", hex::encode(result.journal.block_hash)),
+            chain_id: result.journal.chain_id.to_string(),
+            notes: vec![NoteProofResult {
+                note_index: 0,
+                amount: result.journal.amount.to_string(),
+                recipient: format!("0x{}", hex::encode(result.journal.recipient)),
+                nullifier: format!("0x{}", hex::encode(result.journal.nullifier)),
+                seal: fresh.seal_hex.clone(),
+                journal: fresh.journal_hex.clone(),
+                proof: "0xdead".to_string(),
+                receipt_base64: fresh.receipt_base64.clone(),
+                token: None,
+            }],
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let naming = ProofNaming::default();
+        let name =
+            naming.proof_filename("deposit-ffe8-fde9-20260224T214613", "20260225T103000", None);
+        let path = dir.path().join(&name);
+        std::fs::write(&path, serde_json::to_vec(&bundle).unwrap()).unwrap();
+
+        let perms = WritePerms::default();
+        assert_eq!(recalldata_workspace(dir.path(), &naming, perms).unwrap(), 1);
+        let rewritten: BundledProof =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(rewritten.notes[0].proof, fresh.proof_hex);
+
+        assert_eq!(recalldata_workspace(dir.path(), &naming, perms).unwrap(), 0);
+    }
 }
@@ -0,0 +1,182 @@
+//! Encrypted on-disk cache of proved receipts.
+//!
+//! Entries are keyed by `sha256(receipt kind || serialized ClaimInput)` and
+//! stored as `nonce || AES-256-GCM ciphertext`, with the entry key as
+//! associated data so a file cannot be swapped in under another input. A
+//! cached receipt is only reused after it verifies and its journal matches
+//! what the input evaluates to.
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    digest::{digest, SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
+use shadow_proof_core::{evaluate_claim, pack_journal, ClaimInput};
+use shadow_prover_lib::{deserialize_receipt, serialize_receipt, verify_receipt, Receipt};
+
+use crate::workspace::perms::WritePerms;
+
+/// Receipts of earlier proofs, encrypted under an operator-supplied key.
+pub struct ProofCache {
+    dir: PathBuf,
+    key: LessSafeKey,
+    rng: SystemRandom,
+    perms: WritePerms,
+    hits: AtomicUsize,
+}
+
+impl ProofCache {
+    /// Cache in `dir` (created if missing), encrypted under `key`.
+    pub fn new(dir: PathBuf, key: [u8; 32], perms: WritePerms) -> Result<Self> {
+        perms
+            .create_dir_all(&dir)
+            .and_then(|()| perms.restrict_dir(&dir))
+            .with_context(|| format!("failed to create proof cache: {}", dir.display()))?;
+        let key =
+            UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow!("invalid proof cache key"))?;
+        Ok(Self {
+            dir,
+            key: LessSafeKey::new(key),
+            rng: SystemRandom::new(),
+            perms,
+            hits: AtomicUsize::new(0),
+        })
+    }
+
+    /// A verified cached receipt proving `input`, if there is one. Unreadable,
+    /// undecryptable or mismatching entries are logged and treated as misses.
+    pub fn get(&self, input: &ClaimInput, receipt_kind: &str) -> Option<Receipt> {
+        let entry = entry_key(input, receipt_kind).ok()?;
+        let path = self.entry_path(&entry);
+        let sealed = std::fs::read(&path).ok()?;
+        match self.load(&entry, sealed, input) {
+            Ok(receipt) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                tracing::info!(entry = %entry, "proof cache hit");
+                Some(receipt)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error = %format!("{:#}", e),
+                    file = %path.display(),
+                    "ignoring unusable proof cache entry"
+                );
+                None
+            }
+        }
+    }
+
+    /// Store the receipt proving `input`.
+    pub fn put(&self, input: &ClaimInput, receipt_kind: &str, receipt: &Receipt) -> Result<()> {
+        let entry = entry_key(input, receipt_kind)?;
+        let sealed = self.seal(&entry, serialize_receipt(receipt)?)?;
+        self.perms
+            .write(&self.entry_path(&entry), sealed)
+            .context("failed to write proof cache entry")
+    }
+
+    /// Number of lookups served from the cache.
+    #[cfg(test)]
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    fn entry_path(&self, entry: &str) -> PathBuf {
+        self.dir.join(format!("{entry}.bin"))
+    }
+
+    fn load(&self, entry: &str, sealed: Vec<u8>, input: &ClaimInput) -> Result<Receipt> {
+        let receipt = deserialize_receipt(&self.open(entry, sealed)?)?;
+        let journal = verify_receipt(&receipt)?;
+        let expected = evaluate_claim(input)
+            .map_err(|e| anyhow!("claim evaluation failed: {}", e.as_str()))?;
+        if pack_journal(&journal) != pack_journal(&expected) {
+            bail!("cached receipt journal does not match the claim input");
+        }
+        Ok(receipt)
+    }
+
+    fn seal(&self, entry: &str, mut plaintext: Vec<u8>) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("failed to generate nonce"))?;
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(entry.as_bytes()),
+                &mut plaintext,
+            )
+            .map_err(|_| anyhow!("proof cache encryption failed"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&plaintext);
+        Ok(sealed)
+    }
+
+    fn open(&self, entry: &str, mut sealed: Vec<u8>) -> Result<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            bail!("proof cache entry is truncated");
+        }
+        let mut ciphertext = sealed.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&sealed)
+            .map_err(|_| anyhow!("invalid proof cache nonce"))?;
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(entry.as_bytes()), &mut ciphertext)
+            .map_err(|_| anyhow!("proof cache entry failed to decrypt (wrong key?)"))?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Hex `sha256` of the receipt kind and the serialized input.
+fn entry_key(input: &ClaimInput, receipt_kind: &str) -> Result<String> {
+    let mut preimage = receipt_kind.as_bytes().to_vec();
+    preimage.push(0);
+    preimage.extend(serde_json::to_vec(input).context("failed to serialize claim input")?);
+    Ok(hex::encode(digest(&SHA256, &preimage)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::prover::selftest::selftest_claim_input;
+
+    fn open_cache(dir: &Path, key: [u8; 32]) -> ProofCache {
+        ProofCache::new(dir.to_path_buf(), key, WritePerms::default()).unwrap()
+    }
+
+    #[test]
+    fn entries_are_encrypted_and_bound_to_key_and_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = open_cache(dir.path(), [7; 32]);
+        let plaintext = b"receipt bytes that must not be stored in the clear".to_vec();
+
+        let sealed = cache.seal("entry", plaintext.clone()).unwrap();
+        assert!(!sealed.windows(plaintext.len()).any(|w| w == plaintext));
+        assert_eq!(cache.open("entry", sealed.clone()).unwrap(), plaintext);
+
+        assert!(cache.open("other-entry", sealed.clone()).is_err());
+        let other_key = open_cache(dir.path(), [8; 32]);
+        assert!(other_key.open("entry", sealed).is_err());
+    }
+
+    #[test]
+    fn entry_key_depends_on_input_and_receipt_kind() {
+        let input = selftest_claim_input();
+        let mut other = input.clone();
+        other.note_index ^= 1;
+
+        let key = entry_key(&input, "groth16").unwrap();
+        assert_eq!(key, entry_key(&input, "groth16").unwrap());
+        assert_ne!(key, entry_key(&input, "succinct").unwrap());
+        assert_ne!(key, entry_key(&other, "groth16").unwrap());
+    }
+}
@@ -1,12 +1,24 @@
 //! Proof generation job queue.
 //!
-//! Single-slot queue: only one proof job runs at a time. All notes in a deposit
-//! are proved sequentially within one job.
+//! One proof job runs at a time; further jobs wait in a pending list ordered by
+//! priority (highest first, FIFO within a level). All notes in a deposit are
+//! proved sequentially within one job.
 
-use std::sync::Arc;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use serde::Serialize;
-use tokio::sync::{broadcast, watch, Mutex};
+use tokio::sync::{broadcast, oneshot, watch, Mutex, Notify};
+
+use crate::events::ServerEvent;
+
+/// How many recent job durations feed the wait estimate.
+const RECENT_DURATIONS: usize = 10;
 
 /// Current state of a proof job.
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
@@ -27,24 +39,57 @@ pub struct ProofJob {
     pub status: JobStatus,
     pub current_note: u32,
     pub total_notes: u32,
+    /// Scheduling priority; higher values start first.
+    pub priority: u8,
+    /// Keep notes from the deposit's partial proof instead of reproving them.
+    pub resume: bool,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Span of the request that queued the job, so the worker's logs carry its
+    /// request ID.
+    #[serde(skip)]
+    pub span: tracing::Span,
 }
 
 impl ProofJob {
-    pub fn new(deposit_id: &str, total_notes: u32) -> Self {
+    pub fn new(deposit_id: &str, total_notes: u32, priority: u8, resume: bool) -> Self {
         Self {
             deposit_id: deposit_id.to_string(),
             status: JobStatus::Queued,
             current_note: 0,
             total_notes,
+            priority,
+            resume,
             message: "Queued for proving".to_string(),
             error: None,
+            span: tracing::Span::current(),
         }
     }
 }
 
+/// Where a prove request landed in the queue.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueTicket {
+    /// False when the deposit already had a job; the position is then that job's.
+    pub accepted: bool,
+    pub deposit_id: String,
+    /// Jobs that start before this one, counting the running job.
+    pub queue_position: u32,
+    /// Position times the average recent job duration, once a job has completed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_wait_secs: Option<f64>,
+}
+
+/// The job in the slot and the jobs waiting behind it, in start order.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSnapshot {
+    pub current: Option<ProofJob>,
+    pub pending: Vec<ProofJob>,
+}
+
 /// Optional extra data attached to progress events for richer UI display.
 #[derive(Debug, Clone, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -61,16 +106,26 @@ pub struct ProgressExtra {
     pub stage: Option<String>,
 }
 
-/// The proof queue manages a single proof job at a time.
+/// The proof queue runs a single proof job at a time.
 pub struct ProofQueue {
     /// Current job state (None if idle).
     current: Mutex<Option<ProofJob>>,
+    /// Jobs waiting for the slot, highest priority first.
+    pending: Mutex<Vec<ProofJob>>,
+    /// Wakes the worker when a job is enqueued or the slot frees.
+    job_ready: Notify,
     /// Watch channel to observe job state changes.
     job_tx: watch::Sender<Option<ProofJob>>,
     /// Broadcast channel for WebSocket events.
     event_tx: broadcast::Sender<String>,
-    /// Cancel signal: send () to cancel the current job.
-    cancel_tx: Mutex<Option<tokio::sync::oneshot::Sender<()>>>,
+    /// Cancel signal: send () to cancel the current job. Installed together
+    /// with the job in [`start_next`](Self::start_next) and dropped when it
+    /// completes or fails.
+    cancel_tx: Mutex<Option<oneshot::Sender<()>>>,
+    /// Durations (seconds) of the most recently completed jobs.
+    recent_durations: Mutex<VecDeque<f64>>,
+    /// Set on server shutdown: no job is accepted or started afterwards.
+    closed: AtomicBool,
 }
 
 impl ProofQueue {
@@ -78,9 +133,13 @@ impl ProofQueue {
         let (job_tx, _) = watch::channel(None);
         Arc::new(Self {
             current: Mutex::new(None),
+            pending: Mutex::new(Vec::new()),
+            job_ready: Notify::new(),
             job_tx,
             event_tx,
             cancel_tx: Mutex::new(None),
+            recent_durations: Mutex::new(VecDeque::with_capacity(RECENT_DURATIONS)),
+            closed: AtomicBool::new(false),
         })
     }
 
@@ -89,38 +148,150 @@ impl ProofQueue {
         self.current.lock().await.clone()
     }
 
-    /// Try to enqueue a new proof job. Returns Err if a job is already running.
-    pub async fn enqueue(&self, deposit_id: &str, total_notes: u32) -> Result<(), String> {
+    /// The current job and every pending one.
+    pub async fn snapshot(&self) -> QueueSnapshot {
+        let current = self.current.lock().await;
+        let pending = self.pending.lock().await;
+        QueueSnapshot {
+            current: current.clone(),
+            pending: pending.clone(),
+        }
+    }
+
+    /// Add a proof job to the pending list. The ticket is not accepted if the
+    /// deposit is already running or waiting, or the queue is shut down.
+    pub async fn enqueue(
+        &self,
+        deposit_id: &str,
+        total_notes: u32,
+        priority: u8,
+        resume: bool,
+    ) -> QueueTicket {
+        if self.is_closed() {
+            return self.ticket(false, deposit_id, 0).await;
+        }
+        let current = self.current.lock().await;
+        let mut pending = self.pending.lock().await;
+
+        let running = current
+            .as_ref()
+            .filter(|job| matches!(job.status, JobStatus::Running | JobStatus::Queued));
+        if running.is_some_and(|job| job.deposit_id == deposit_id) {
+            return self.ticket(false, deposit_id, 0).await;
+        }
+        let ahead = running.is_some() as u32;
+        if let Some(index) = pending.iter().position(|j| j.deposit_id == deposit_id) {
+            return self.ticket(false, deposit_id, ahead + index as u32).await;
+        }
+
+        let job = ProofJob::new(deposit_id, total_notes, priority, resume);
+        let position = pending
+            .iter()
+            .position(|j| j.priority < priority)
+            .unwrap_or(pending.len());
+        pending.insert(position, job);
+        self.job_ready.notify_one();
+
+        self.broadcast_event(ServerEvent::ProofQueued {
+            deposit_id: deposit_id.to_string(),
+            priority,
+            position,
+        });
+
+        tracing::info!(deposit_id = %deposit_id, total_notes = total_notes, priority = priority, position = position, "proof job enqueued");
+
+        self.ticket(true, deposit_id, ahead + position as u32).await
+    }
+
+    async fn ticket(&self, accepted: bool, deposit_id: &str, queue_position: u32) -> QueueTicket {
+        let durations = self.recent_durations.lock().await;
+        let average =
+            (!durations.is_empty()).then(|| durations.iter().sum::<f64>() / durations.len() as f64);
+        QueueTicket {
+            accepted,
+            deposit_id: deposit_id.to_string(),
+            queue_position,
+            estimated_wait_secs: average.map(|avg| avg * queue_position as f64),
+        }
+    }
+
+    /// Move the highest-priority pending job into the slot if it is free.
+    ///
+    /// Returns the job with the receiver of its cancel signal; the sender is
+    /// installed before the slot lock is released, so a cancel is never lost.
+    pub async fn start_next(&self) -> Option<(ProofJob, oneshot::Receiver<()>)> {
         let mut current = self.current.lock().await;
-        if let Some(ref job) = *current {
-            match job.status {
-                JobStatus::Running | JobStatus::Queued => {
-                    return Err(format!(
-                        "a proof job is already {} for deposit {}",
-                        if job.status == JobStatus::Running {
-                            "running"
-                        } else {
-                            "queued"
-                        },
-                        job.deposit_id
-                    ));
-                }
-                _ => {}
-            }
+        if self.is_closed()
+            || current
+                .as_ref()
+                .is_some_and(|job| matches!(job.status, JobStatus::Running | JobStatus::Queued))
+        {
+            return None;
         }
 
-        let job = ProofJob::new(deposit_id, total_notes);
+        let mut pending = self.pending.lock().await;
+        if pending.is_empty() {
+            return None;
+        }
+        let job = pending.remove(0);
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        *self.cancel_tx.lock().await = Some(cancel_tx);
         *current = Some(job.clone());
-        let _ = self.job_tx.send(Some(job));
+        let _ = self.job_tx.send(Some(job.clone()));
+
+        self.broadcast_event(ServerEvent::ProofStarted {
+            deposit_id: job.deposit_id.clone(),
+        });
 
-        self.broadcast_event(serde_json::json!({
-            "type": "proof:started",
-            "depositId": deposit_id
-        }));
+        tracing::info!(deposit_id = %job.deposit_id, priority = job.priority, "proof job started");
 
-        tracing::info!(deposit_id = %deposit_id, total_notes = total_notes, "proof job enqueued");
+        Some((job, cancel_rx))
+    }
+
+    /// Wait until a pending job can be started, then start it. Returns `None`
+    /// once the queue is shut down.
+    pub async fn wait_for_job(&self) -> Option<(ProofJob, oneshot::Receiver<()>)> {
+        loop {
+            if self.is_closed() {
+                return None;
+            }
+            if let Some(started) = self.start_next().await {
+                return Some(started);
+            }
+            self.job_ready.notified().await;
+        }
+    }
+
+    /// Whether [`shutdown`](Self::shutdown) was called.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting and starting jobs, drop the pending ones and cancel the
+    /// running one. Returns how many jobs were cancelled.
+    ///
+    /// Unlike [`flush`](Self::flush) this leaves the running job in the slot,
+    /// so the worker still records its outcome while it unwinds.
+    pub async fn shutdown(&self) -> usize {
+        self.closed.store(true, Ordering::SeqCst);
+        // Wake the worker if it is idle so it sees the queue is closed.
+        self.job_ready.notify_one();
+
+        let pending: Vec<ProofJob> = self.pending.lock().await.drain(..).collect();
+        for job in &pending {
+            self.broadcast_event(ServerEvent::ProofCancelled {
+                deposit_id: job.deposit_id.clone(),
+            });
+        }
+        let running = self
+            .cancel_running("Cancelled: server shutting down", None)
+            .await;
 
-        Ok(())
+        tracing::info!(
+            cancelled = pending.len() + running as usize,
+            "proof queue shut down"
+        );
+        pending.len() + running as usize
     }
 
     /// Update job progress (called by the pipeline during proving).
@@ -138,23 +309,13 @@ impl ProofQueue {
             let snapshot = job.clone();
             let _ = self.job_tx.send(Some(snapshot.clone()));
 
-            let mut event = serde_json::json!({
-                "type": "proof:note_progress",
-                "depositId": snapshot.deposit_id,
-                "noteIndex": current_note,
-                "totalNotes": snapshot.total_notes,
-                "message": message
+            self.broadcast_event(ServerEvent::NoteProgress {
+                deposit_id: snapshot.deposit_id.clone(),
+                note_index: current_note,
+                total_notes: snapshot.total_notes,
+                message: message.to_string(),
+                extra: extra.cloned().unwrap_or_default(),
             });
-            if let Some(extra) = extra {
-                if let Ok(extra_val) = serde_json::to_value(extra) {
-                    if let Some(obj) = extra_val.as_object() {
-                        for (k, v) in obj {
-                            event.as_object_mut().unwrap().insert(k.clone(), v.clone());
-                        }
-                    }
-                }
-            }
-            self.broadcast_event(event);
 
             tracing::debug!(deposit_id = %snapshot.deposit_id, note = current_note, total = snapshot.total_notes, message = %message, "proof progress");
         }
@@ -162,7 +323,16 @@ impl ProofQueue {
 
     /// Mark the current job as completed.
     pub async fn complete(&self, proof_file: &str, elapsed_secs: Option<f64>) {
+        if let Some(secs) = elapsed_secs {
+            let mut durations = self.recent_durations.lock().await;
+            if durations.len() == RECENT_DURATIONS {
+                durations.pop_front();
+            }
+            durations.push_back(secs);
+        }
+
         let mut current = self.current.lock().await;
+        self.cancel_tx.lock().await.take();
         if let Some(ref mut job) = *current {
             let deposit_id = job.deposit_id.clone();
             job.status = JobStatus::Completed;
@@ -170,34 +340,41 @@ impl ProofQueue {
             let snapshot = job.clone();
             let _ = self.job_tx.send(Some(snapshot));
 
-            self.broadcast_event(serde_json::json!({
-                "type": "proof:completed",
-                "depositId": deposit_id,
-                "proofFile": proof_file,
-                "elapsedSecs": elapsed_secs
-            }));
+            self.broadcast_event(ServerEvent::ProofCompleted {
+                deposit_id: deposit_id.clone(),
+                proof_file: proof_file.to_string(),
+                elapsed_secs,
+            });
 
             tracing::info!(deposit_id = %deposit_id, proof_file = %proof_file, "proof job completed");
         }
     }
 
-    /// Mark the current job as failed.
+    /// Mark the current job as failed. A cancelled job stays cancelled: its
+    /// pipeline fails only because it was told to stop.
     pub async fn fail(&self, note_index: u32, error: &str) {
         let mut current = self.current.lock().await;
+        self.cancel_tx.lock().await.take();
         if let Some(ref mut job) = *current {
             let deposit_id = job.deposit_id.clone();
+            if job.status == JobStatus::Cancelled {
+                self.broadcast_event(ServerEvent::ProofCancelled {
+                    deposit_id: deposit_id.clone(),
+                });
+                tracing::info!(deposit_id = %deposit_id, "proof job stopped after cancel");
+                return;
+            }
             job.status = JobStatus::Failed;
             job.error = Some(error.to_string());
             job.message = format!("Failed at note {}: {}", note_index, error);
             let snapshot = job.clone();
             let _ = self.job_tx.send(Some(snapshot));
 
-            self.broadcast_event(serde_json::json!({
-                "type": "proof:failed",
-                "depositId": deposit_id,
-                "noteIndex": note_index,
-                "error": error
-            }));
+            self.broadcast_event(ServerEvent::ProofFailed {
+                deposit_id: deposit_id.clone(),
+                note_index,
+                error: error.to_string(),
+            });
 
             tracing::error!(deposit_id = %deposit_id, note_index = note_index, error = %error, "proof job failed");
         }
@@ -205,21 +382,92 @@ impl ProofQueue {
 
     /// Cancel the current job (best-effort).
     pub async fn cancel(&self) -> bool {
-        let mut cancel_tx = self.cancel_tx.lock().await;
-        if let Some(tx) = cancel_tx.take() {
-            let _ = tx.send(());
-            tracing::info!("proof job cancelled by user");
-            let mut current = self.current.lock().await;
-            if let Some(ref mut job) = *current {
-                job.status = JobStatus::Cancelled;
-                job.message = "Cancelled by user".to_string();
-                let snapshot = job.clone();
-                let _ = self.job_tx.send(Some(snapshot));
+        self.cancel_running("Cancelled by user", None).await
+    }
+
+    /// Signal the running job to stop and mark it cancelled with `message`.
+    /// With `deposit_id`, only a job for that deposit is cancelled.
+    async fn cancel_running(&self, message: &str, deposit_id: Option<&str>) -> bool {
+        let mut current = self.current.lock().await;
+        self.cancel_locked(&mut current, message, deposit_id).await
+    }
+
+    /// [`cancel_running`](Self::cancel_running) with the slot already locked.
+    /// Only a queued or running job is cancelled; a finished one keeps its
+    /// status.
+    async fn cancel_locked(
+        &self,
+        current: &mut Option<ProofJob>,
+        message: &str,
+        deposit_id: Option<&str>,
+    ) -> bool {
+        let Some(job) = current.as_mut().filter(|job| {
+            matches!(job.status, JobStatus::Running | JobStatus::Queued)
+                && deposit_id.is_none_or(|id| job.deposit_id == id)
+        }) else {
+            return false;
+        };
+        // Lock order matches `start_next`: slot first, then cancel sender.
+        let Some(tx) = self.cancel_tx.lock().await.take() else {
+            return false;
+        };
+        let _ = tx.send(());
+        tracing::info!(deposit_id = %job.deposit_id, reason = %message, "proof job cancelled");
+        job.status = JobStatus::Cancelled;
+        job.message = message.to_string();
+        let _ = self.job_tx.send(Some(job.clone()));
+        true
+    }
+
+    /// Cancel the job for `deposit_id`, whether it is pending or running.
+    /// Returns false if the deposit has no active job.
+    pub async fn cancel_deposit(&self, deposit_id: &str) -> bool {
+        let mut pending = self.pending.lock().await;
+        if let Some(position) = pending.iter().position(|j| j.deposit_id == deposit_id) {
+            pending.remove(position);
+            drop(pending);
+            self.broadcast_event(ServerEvent::ProofCancelled {
+                deposit_id: deposit_id.to_string(),
+            });
+            tracing::info!(deposit_id = %deposit_id, "pending proof job cancelled");
+            return true;
+        }
+        drop(pending);
+
+        self.cancel_running("Cancelled by user", Some(deposit_id))
+            .await
+    }
+
+    /// Cancel the running job and drop every pending one. Returns how many
+    /// jobs were cancelled; the slot is left empty.
+    pub async fn flush(&self) -> usize {
+        let mut cancelled = 0;
+        let mut current = self.current.lock().await;
+        if self
+            .cancel_locked(&mut current, "Cancelled by user", None)
+            .await
+        {
+            // The slot is cleared below, so the worker's `fail` won't see it.
+            if let Some(ref job) = *current {
+                self.broadcast_event(ServerEvent::ProofCancelled {
+                    deposit_id: job.deposit_id.clone(),
+                });
             }
-            true
-        } else {
-            false
+            cancelled += 1;
         }
+
+        let mut pending = self.pending.lock().await;
+        for job in pending.drain(..) {
+            self.broadcast_event(ServerEvent::ProofCancelled {
+                deposit_id: job.deposit_id,
+            });
+            cancelled += 1;
+        }
+        *current = None;
+        let _ = self.job_tx.send(None);
+
+        tracing::info!(cancelled = cancelled, "proof queue flushed");
+        cancelled
     }
 
     /// Clear the current job unconditionally (used to dismiss failed/completed jobs).
@@ -229,13 +477,206 @@ impl ProofQueue {
         let _ = self.job_tx.send(None);
     }
 
-    /// Set the cancel sender for the current job (called by pipeline before starting).
-    pub async fn set_cancel_tx(&self, tx: tokio::sync::oneshot::Sender<()>) {
-        let mut cancel = self.cancel_tx.lock().await;
-        *cancel = Some(tx);
+    fn broadcast_event(&self, event: ServerEvent) {
+        event.send(&self.event_tx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue() -> Arc<ProofQueue> {
+        let (event_tx, _) = broadcast::channel(16);
+        ProofQueue::new(event_tx)
+    }
+
+    #[tokio::test]
+    async fn higher_priority_job_starts_first() {
+        let queue = queue();
+        queue.enqueue("deposit-running", 1, 0, false).await;
+        assert_eq!(
+            queue.start_next().await.unwrap().0.deposit_id,
+            "deposit-running"
+        );
+
+        queue.enqueue("deposit-low", 1, 0, false).await;
+        queue.enqueue("deposit-high", 1, 9, false).await;
+        // The slot is busy, so nothing starts yet.
+        assert!(queue.start_next().await.is_none());
+
+        queue.complete("deposit-running.proof.json", None).await;
+        assert_eq!(
+            queue.start_next().await.unwrap().0.deposit_id,
+            "deposit-high"
+        );
+
+        queue.fail(0, "boom").await;
+        assert_eq!(
+            queue.start_next().await.unwrap().0.deposit_id,
+            "deposit-low"
+        );
     }
 
-    fn broadcast_event(&self, event: serde_json::Value) {
-        let _ = self.event_tx.send(event.to_string());
+    #[tokio::test]
+    async fn equal_priority_jobs_stay_fifo() {
+        let queue = queue();
+        for id in ["deposit-a", "deposit-b", "deposit-c"] {
+            queue.enqueue(id, 1, 5, false).await;
+        }
+        assert!(!queue.enqueue("deposit-b", 1, 7, false).await.accepted);
+
+        for expected in ["deposit-a", "deposit-b", "deposit-c"] {
+            let (job, _) = queue.start_next().await.unwrap();
+            assert_eq!(job.deposit_id, expected);
+            queue.complete("proof.json", None).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn snapshot_lists_pending_jobs_in_start_order() {
+        let queue = queue();
+        queue.enqueue("deposit-running", 1, 0, false).await;
+        queue.start_next().await.unwrap();
+        queue.enqueue("deposit-low", 1, 0, false).await;
+        queue.enqueue("deposit-high", 1, 9, false).await;
+        queue.enqueue("deposit-low-2", 1, 0, false).await;
+
+        let snapshot = queue.snapshot().await;
+        assert_eq!(snapshot.current.unwrap().deposit_id, "deposit-running");
+        let pending: Vec<_> = snapshot
+            .pending
+            .iter()
+            .map(|j| j.deposit_id.as_str())
+            .collect();
+        assert_eq!(pending, ["deposit-high", "deposit-low", "deposit-low-2"]);
+    }
+
+    #[tokio::test]
+    async fn cancel_deposit_removes_pending_job() {
+        let queue = queue();
+        queue.enqueue("deposit-running", 1, 0, false).await;
+        queue.start_next().await.unwrap();
+        queue.enqueue("deposit-waiting", 1, 0, false).await;
+
+        assert!(queue.cancel_deposit("deposit-waiting").await);
+        assert!(!queue.cancel_deposit("deposit-waiting").await);
+        // Another deposit's running job is left alone.
+        assert!(!queue.cancel_deposit("deposit-other").await);
+        assert_eq!(queue.status().await.unwrap().status, JobStatus::Queued);
+
+        queue.complete("proof.json", None).await;
+        assert!(queue.start_next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancel_reaches_a_job_as_soon_as_it_starts() {
+        let queue = queue();
+        queue.enqueue("deposit-a", 1, 0, false).await;
+        let (_, mut cancel_rx) = queue.start_next().await.unwrap();
+
+        assert!(queue.cancel_deposit("deposit-a").await);
+        assert!(cancel_rx.try_recv().is_ok());
+        assert_eq!(queue.status().await.unwrap().status, JobStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn cancel_after_completion_leaves_the_job_completed() {
+        let queue = queue();
+        queue.enqueue("deposit-a", 1, 0, false).await;
+        queue.start_next().await.unwrap();
+        queue.complete("proof.json", None).await;
+
+        assert!(!queue.cancel().await);
+        assert!(!queue.cancel_deposit("deposit-a").await);
+        assert_eq!(queue.flush().await, 0);
+
+        queue.enqueue("deposit-b", 1, 0, false).await;
+        queue.start_next().await.unwrap();
+        queue.fail(0, "boom").await;
+        assert_eq!(queue.shutdown().await, 0);
+        assert_eq!(queue.status().await.unwrap().status, JobStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn cancelled_job_stays_cancelled_when_its_pipeline_fails() {
+        let (event_tx, mut events) = broadcast::channel(16);
+        let queue = ProofQueue::new(event_tx);
+        queue.enqueue("deposit-a", 1, 0, false).await;
+        queue.start_next().await.unwrap();
+
+        assert!(queue.cancel().await);
+        queue.fail(0, "proof generation cancelled").await;
+
+        let job = queue.status().await.unwrap();
+        assert_eq!(job.status, JobStatus::Cancelled);
+        assert_eq!(job.error, None);
+        let events: Vec<_> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert!(events.iter().any(|e| e.contains("proof:cancelled")));
+        assert!(!events.iter().any(|e| e.contains("proof:failed")));
+    }
+
+    #[tokio::test]
+    async fn flush_cancels_running_and_pending_jobs() {
+        let queue = queue();
+        for id in ["deposit-a", "deposit-b", "deposit-c"] {
+            queue.enqueue(id, 1, 0, false).await;
+        }
+        let (_, mut cancel_rx) = queue.start_next().await.unwrap();
+
+        assert_eq!(queue.flush().await, 3);
+        assert!(cancel_rx.try_recv().is_ok());
+        assert!(queue.status().await.is_none());
+        assert!(queue.start_next().await.is_none());
+        assert_eq!(queue.flush().await, 0);
+    }
+
+    #[tokio::test]
+    async fn shutdown_cancels_jobs_and_stops_the_worker() {
+        let (event_tx, mut events) = broadcast::channel(16);
+        let queue = ProofQueue::new(event_tx);
+        for id in ["deposit-a", "deposit-b"] {
+            queue.enqueue(id, 1, 0, false).await;
+        }
+        let (_, mut cancel_rx) = queue.start_next().await.unwrap();
+
+        assert_eq!(queue.shutdown().await, 2);
+        assert!(cancel_rx.try_recv().is_ok());
+        let running = queue.status().await.unwrap();
+        assert_eq!(running.deposit_id, "deposit-a");
+        assert_eq!(running.status, JobStatus::Cancelled);
+
+        let cancelled = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|e| e.contains("proof:cancelled"))
+            .collect::<Vec<_>>();
+        assert_eq!(cancelled.len(), 1);
+        assert!(cancelled[0].contains("deposit-b"));
+
+        assert!(!queue.enqueue("deposit-c", 1, 0, false).await.accepted);
+        queue.fail(0, "proof generation cancelled").await;
+        assert!(queue.wait_for_job().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn second_enqueue_reports_queue_position_and_wait() {
+        let queue = queue();
+        queue.enqueue("deposit-a", 1, 0, false).await;
+        queue.start_next().await.unwrap();
+        queue.complete("proof.json", Some(30.0)).await;
+
+        let first = queue.enqueue("deposit-b", 1, 0, false).await;
+        assert!(first.accepted);
+        assert_eq!(first.queue_position, 0);
+        queue.start_next().await.unwrap();
+
+        let second = queue.enqueue("deposit-c", 1, 0, false).await;
+        assert!(second.accepted);
+        assert_eq!(second.queue_position, 1);
+        assert_eq!(second.estimated_wait_secs, Some(30.0));
+
+        let busy = queue.enqueue("deposit-b", 1, 0, false).await;
+        assert!(!busy.accepted);
+        assert_eq!(busy.deposit_id, "deposit-b");
+        assert_eq!(busy.queue_position, 0);
     }
 }
@@ -0,0 +1,50 @@
+//! Regenerate claim calldata for every proof file in a workspace.
+//!
+//! Used after a change to the on-chain proof encoding: each note's stored
+//! receipt is re-exported, so no proving is needed.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use super::pipeline::{recalldata_bundle, BundledProof};
+use crate::workspace::{
+    perms::WritePerms,
+    scanner::{list_proof_files, ProofNaming},
+};
+
+/// Rewrite every proof bundle in `workspace` whose calldata is stale.
+/// Returns how many notes were updated; unreadable bundles are skipped.
+pub fn recalldata_workspace(
+    workspace: &Path,
+    naming: &ProofNaming,
+    perms: WritePerms,
+) -> Result<usize> {
+    let mut updated = 0;
+    for name in list_proof_files(workspace, naming) {
+        match recalldata_file(&workspace.join(&name), perms) {
+            Ok(0) => {}
+            Ok(count) => {
+                tracing::info!(file = %name, notes = count, "proof calldata regenerated");
+                updated += count;
+            }
+            Err(e) => {
+                tracing::warn!(file = %name, error = %format!("{:#}", e), "skipping proof file")
+            }
+        }
+    }
+    Ok(updated)
+}
+
+fn recalldata_file(path: &Path, perms: WritePerms) -> Result<usize> {
+    let raw = std::fs::read(path).with_context(|| format!("failed reading {}", path.display()))?;
+    let mut bundle: BundledProof = serde_json::from_slice(&raw)?;
+    let updated = recalldata_bundle(&mut bundle)?;
+    if updated > 0 {
+        let json_bytes = serde_json::to_vec_pretty(&bundle).context("serialization error")?;
+        perms
+            .write(path, json_bytes)
+            .with_context(|| format!("failed writing {}", path.display()))?;
+    }
+    Ok(updated)
+}
@@ -3,6 +3,9 @@
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use shadow_proof_core::{
+    block_header_number, block_header_state_root, block_header_timestamp, EthereumHeaderLayout,
+};
 
 /// JSON-RPC request wrapper.
 #[derive(Serialize)]
@@ -26,7 +29,15 @@ struct RpcError {
     message: String,
 }
 
-/// Perform a raw JSON-RPC call.
+/// Attempts per JSON-RPC call before a transient failure is returned.
+const RPC_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each further one.
+const RPC_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Perform a raw JSON-RPC call, retrying transient failures (connection
+/// errors, HTTP 429 and 5xx) with exponential backoff. JSON-RPC error
+/// responses are returned as-is.
 async fn rpc_call(
     client: &reqwest::Client,
     url: &str,
@@ -44,15 +55,34 @@ async fn rpc_call(
         params,
     };
 
-    let resp: RpcResponse = client
-        .post(url)
-        .json(&req)
-        .send()
-        .await
-        .with_context(|| format!("RPC request to {} failed", method))?
-        .json()
-        .await
-        .with_context(|| format!("failed to parse RPC response for {}", method))?;
+    let mut delay = RPC_RETRY_DELAY;
+    let mut attempt = 1;
+    let resp: RpcResponse = loop {
+        let err = match client.post(url).json(&req).send().await {
+            Ok(resp) if is_transient_status(resp.status()) => {
+                anyhow::anyhow!("RPC request to {} failed: HTTP {}", method, resp.status())
+            }
+            Ok(resp) => {
+                break resp
+                    .json()
+                    .await
+                    .with_context(|| format!("failed to parse RPC response for {}", method))?
+            }
+            Err(e) => anyhow::Error::new(e).context(format!("RPC request to {} failed", method)),
+        };
+        if attempt == RPC_ATTEMPTS {
+            return Err(err);
+        }
+        tracing::warn!(
+            rpc_method = %method,
+            attempt,
+            error = %format!("{:#}", err),
+            "retrying RPC call"
+        );
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+        attempt += 1;
+    };
 
     let elapsed = start.elapsed();
 
@@ -67,6 +97,10 @@ async fn rpc_call(
         .ok_or_else(|| anyhow::anyhow!("RPC response has no result for {}", method))
 }
 
+fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
 /// Fetch `eth_chainId` and return it as a u64.
 pub async fn eth_chain_id(client: &reqwest::Client, url: &str) -> Result<u64> {
     let result = rpc_call(client, url, "eth_chainId", serde_json::json!([])).await?;
@@ -76,12 +110,32 @@ pub async fn eth_chain_id(client: &reqwest::Client, url: &str) -> Result<u64> {
     Ok(chain_id)
 }
 
+/// Fetch `eth_blockNumber` and return it as a u64.
+pub async fn eth_block_number(client: &reqwest::Client, url: &str) -> Result<u64> {
+    let result = rpc_call(client, url, "eth_blockNumber", serde_json::json!([])).await?;
+    let hex_str = result
+        .as_str()
+        .context("eth_blockNumber: expected string")?;
+    parse_hex_u64(hex_str).context("eth_blockNumber: invalid hex")
+}
+
 /// Block data from `eth_getBlockByNumber`.
 #[derive(Debug, Clone)]
 pub struct BlockData {
     pub number: u64,
     pub hash: [u8; 32],
     pub header_rlp: Vec<u8>,
+    pub header: DecodedHeader,
+}
+
+/// Header fields read from the block JSON and checked against `header_rlp`.
+#[derive(Debug, Clone)]
+pub struct DecodedHeader {
+    pub parent_hash: [u8; 32],
+    pub state_root: [u8; 32],
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub base_fee: u128,
 }
 
 /// Fetch a block by number (or "latest") and encode its header as RLP.
@@ -109,6 +163,20 @@ pub async fn eth_get_block(
 
     let header_rlp = encode_block_header_rlp(block)?;
 
+    // The circuit checks the header's number, so catch a disagreement with
+    // the JSON number here rather than in the prover.
+    let header_number = block_header_number::<EthereumHeaderLayout>(&header_rlp)
+        .map_err(|e| anyhow::anyhow!("encoded block header: {}", e.as_str()))?;
+    if header_number != number {
+        bail!(
+            "block number mismatch: RPC says {} but the header encodes {}",
+            number,
+            header_number
+        );
+    }
+
+    let header = decode_header(block, &header_rlp)?;
+
     // Compute block hash as keccak256(headerRlp)
     let hash = keccak256(&header_rlp);
 
@@ -131,14 +199,54 @@ pub async fn eth_get_block(
         number,
         hash,
         header_rlp,
+        header,
     })
 }
 
+/// Read the [`DecodedHeader`] fields from block JSON, checking the state root
+/// and timestamp against what the circuit will parse out of `header_rlp`.
+fn decode_header(
+    block: &serde_json::Map<String, Value>,
+    header_rlp: &[u8],
+) -> Result<DecodedHeader> {
+    let field = |key: &str| {
+        block
+            .get(key)
+            .and_then(|v| v.as_str())
+            .with_context(|| format!("missing block {key}"))
+    };
+    let word = |key: &str| -> Result<[u8; 32]> {
+        parse_hex_bytes(field(key)?)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("block {key} is not 32 bytes"))
+    };
+    let base_fee_key = if block.contains_key("baseFeePerGas") {
+        "baseFeePerGas"
+    } else {
+        "baseFee"
+    };
+    let header = DecodedHeader {
+        parent_hash: word("parentHash")?,
+        state_root: word("stateRoot")?,
+        timestamp: parse_hex_u64(field("timestamp")?)?,
+        base_fee: shadow_util::parse_u128(field(base_fee_key)?)
+            .with_context(|| format!("invalid block {base_fee_key}"))?,
+    };
+
+    let rlp_state_root = block_header_state_root::<EthereumHeaderLayout>(header_rlp)
+        .map_err(|e| anyhow::anyhow!("encoded block header: {}", e.as_str()))?;
+    let rlp_timestamp = block_header_timestamp::<EthereumHeaderLayout>(header_rlp)
+        .map_err(|e| anyhow::anyhow!("encoded block header: {}", e.as_str()))?;
+    if rlp_state_root != header.state_root || rlp_timestamp != header.timestamp {
+        bail!("block JSON state root or timestamp disagrees with the encoded header");
+    }
+    Ok(header)
+}
+
 /// Account proof from `eth_getProof`.
 #[derive(Debug, Clone)]
 pub struct AccountProofData {
     /// Balance as big-endian bytes (up to 32 bytes).
-    #[allow(dead_code)]
     pub balance: Vec<u8>,
     /// Account proof nodes (each is an RLP-encoded trie node).
     pub proof_nodes: Vec<Vec<u8>>,
@@ -209,6 +317,8 @@ pub struct Erc20BalanceProofData {
     pub balance_storage_key: [u8; 32],
     /// The raw _balances mapping slot index.
     pub balance_slot: u64,
+    /// Token balance held in the slot, as minimal big-endian bytes.
+    pub balance: Vec<u8>,
 }
 
 fn compute_selector(signature: &str) -> [u8; 4] {
@@ -325,6 +435,11 @@ pub async fn eth_get_erc20_balance_proof(
             .context("missing storage proof nodes")?,
         "storage proof",
     )?;
+    let balance = storage_entry
+        .get("value")
+        .and_then(|v| v.as_str())
+        .map(normalize_quantity)
+        .unwrap_or_default();
 
     tracing::info!(
         token = %token_hex,
@@ -338,6 +453,7 @@ pub async fn eth_get_erc20_balance_proof(
         balance_storage_proof_nodes,
         balance_storage_key,
         balance_slot,
+        balance,
     })
 }
 
@@ -361,12 +477,12 @@ fn encode_block_header_rlp(block: &serde_json::Map<String, Value>) -> Result<Vec
         block
             .get(key)
             .and_then(|v| v.as_str())
-            .map(|s| normalize_quantity(s))
+            .map(normalize_quantity)
             .unwrap_or_default()
     };
 
-    // 17 header fields in order
-    let fields: Vec<Vec<u8>> = vec![
+    // London header fields in order
+    let mut fields: Vec<Vec<u8>> = vec![
         get_hex("parentHash"),       // 0
         get_hex("sha3Uncles"),       // 1
         get_hex("miner"),            // 2
@@ -390,9 +506,30 @@ fn encode_block_header_rlp(block: &serde_json::Map<String, Value>) -> Result<Vec
                 "baseFee"
             },
         ),
-        get_hex("withdrawalsRoot"), // 16
     ];
 
+    // Later forks append fields; mirror what the RPC returned so the header
+    // hashes to the block hash.
+    let has = |key: &str| block.get(key).is_some_and(|v| !v.is_null());
+    if has("withdrawalsRoot") {
+        fields.push(get_hex("withdrawalsRoot")); // 16 (Shanghai)
+    }
+    let cancun = ["blobGasUsed", "excessBlobGas", "parentBeaconBlockRoot"];
+    let present = cancun.iter().filter(|k| has(k)).count();
+    if present == cancun.len() {
+        fields.push(get_quantity("blobGasUsed")); // 17
+        fields.push(get_quantity("excessBlobGas")); // 18
+        fields.push(get_hex("parentBeaconBlockRoot")); // 19
+    } else if present > 0 {
+        bail!("block has only some of the Cancun header fields");
+    }
+    if has("requestsHash") {
+        if present < cancun.len() {
+            bail!("block has requestsHash but not the Cancun header fields");
+        }
+        fields.push(get_hex("requestsHash")); // 20 (Prague)
+    }
+
     // RLP-encode each field as a byte string, then wrap in a list
     let mut encoded_items: Vec<Vec<u8>> = Vec::with_capacity(fields.len());
     for field in &fields {
@@ -407,7 +544,7 @@ fn encode_block_header_rlp(block: &serde_json::Map<String, Value>) -> Result<Vec
 // ---------------------------------------------------------------------------
 
 /// RLP-encode a byte string.
-fn rlp_encode_bytes(data: &[u8]) -> Vec<u8> {
+pub fn rlp_encode_bytes(data: &[u8]) -> Vec<u8> {
     if data.len() == 1 && data[0] <= 0x7f {
         return vec![data[0]];
     }
@@ -432,7 +569,7 @@ fn rlp_encode_bytes(data: &[u8]) -> Vec<u8> {
 }
 
 /// RLP-encode a list of already-encoded items.
-fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+pub fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
     let payload_len: usize = items.iter().map(|it| it.len()).sum();
     let mut payload = Vec::with_capacity(payload_len);
     for it in items {
@@ -523,7 +660,7 @@ fn parse_hex_u64(hex_str: &str) -> Result<u64> {
     u64::from_str_radix(stripped, 16).context("invalid hex u64")
 }
 
-fn keccak256(data: &[u8]) -> [u8; 32] {
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
     use tiny_keccak::{Hasher, Keccak};
     let mut keccak = Keccak::v256();
     keccak.update(data);
@@ -534,6 +671,13 @@ fn keccak256(data: &[u8]) -> [u8; 32] {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use axum::{routing::post, Json, Router};
+
     use super::*;
 
     #[test]
@@ -576,4 +720,217 @@ mod tests {
         // [0x01, 0x02] → list prefix 0xc0+2 = 0xc2, then 0x01, 0x02
         assert_eq!(list, vec![0xc2, 0x01, 0x02]);
     }
+
+    #[test]
+    fn encoded_header_carries_block_timestamp() {
+        let block = serde_json::json!({
+            "number": "0x485123",
+            "timestamp": "0x6718a3c0",
+        });
+        let header_rlp = encode_block_header_rlp(block.as_object().unwrap()).unwrap();
+        let timestamp = block_header_timestamp::<EthereumHeaderLayout>(&header_rlp).unwrap();
+        assert_eq!(timestamp, 0x6718_a3c0);
+    }
+
+    /// `eth_getBlockByNumber` result for a post-Cancun block.
+    fn cancun_block() -> serde_json::Value {
+        let word = |b: &str| format!("0x{}", b.repeat(32));
+        serde_json::json!({
+            "parentHash": word("11"),
+            "sha3Uncles": word("22"),
+            "miner": format!("0x{}", "33".repeat(20)),
+            "stateRoot": word("aa"),
+            "transactionsRoot": word("44"),
+            "receiptsRoot": word("55"),
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "difficulty": "0x0",
+            "number": "0x13a5e5c",
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0xd3f2a1",
+            "timestamp": "0x6718a3c0",
+            "extraData": "0x6265617665726275696c642e6f7267",
+            "mixHash": word("66"),
+            "nonce": "0x0000000000000000",
+            "baseFeePerGas": "0x2b8a1e7c4",
+            "withdrawalsRoot": word("77"),
+            "blobGasUsed": "0x60000",
+            "excessBlobGas": "0x0",
+            "parentBeaconBlockRoot": word("88"),
+        })
+    }
+
+    #[test]
+    fn cancun_header_mirrors_blob_and_beacon_root_fields() {
+        let block = cancun_block();
+        let header_rlp = encode_block_header_rlp(block.as_object().unwrap()).unwrap();
+        assert_eq!(
+            block_header_number::<EthereumHeaderLayout>(&header_rlp).unwrap(),
+            0x13a_5e5c
+        );
+
+        let mut expected: Vec<Vec<u8>> = Vec::new();
+        for (key, quantity) in [
+            ("parentHash", false),
+            ("sha3Uncles", false),
+            ("miner", false),
+            ("stateRoot", false),
+            ("transactionsRoot", false),
+            ("receiptsRoot", false),
+            ("logsBloom", false),
+            ("difficulty", true),
+            ("number", true),
+            ("gasLimit", true),
+            ("gasUsed", true),
+            ("timestamp", true),
+            ("extraData", false),
+            ("mixHash", false),
+            ("nonce", false),
+            ("baseFeePerGas", true),
+            ("withdrawalsRoot", false),
+            ("blobGasUsed", true),
+            ("excessBlobGas", true),
+            ("parentBeaconBlockRoot", false),
+        ] {
+            let value = block[key].as_str().unwrap();
+            let bytes = if quantity {
+                normalize_quantity(value)
+            } else {
+                parse_hex_bytes(value).unwrap()
+            };
+            expected.push(rlp_encode_bytes(&bytes));
+        }
+        assert_eq!(header_rlp, rlp_encode_list(&expected));
+    }
+
+    #[test]
+    fn prague_header_appends_requests_hash() {
+        let mut block = cancun_block();
+        block["requestsHash"] = serde_json::json!(format!("0x{}", "99".repeat(32)));
+        let header_rlp = encode_block_header_rlp(block.as_object().unwrap()).unwrap();
+
+        // The Cancun fields, in order, followed by requestsHash.
+        let cancun_rlp = encode_block_header_rlp(cancun_block().as_object().unwrap()).unwrap();
+        let cancun_fields = cancun_rlp[1 + (cancun_rlp[0] - 0xf7) as usize..].to_vec();
+        let expected = rlp_encode_list(&[cancun_fields, rlp_encode_bytes(&[0x99; 32])]);
+        assert_eq!(header_rlp, expected);
+        assert_eq!(
+            block_header_state_root::<EthereumHeaderLayout>(&header_rlp).unwrap(),
+            [0xaa; 32]
+        );
+    }
+
+    #[test]
+    fn decoded_header_matches_the_core_header_parser() {
+        let block = cancun_block();
+        let block = block.as_object().unwrap();
+        let header_rlp = encode_block_header_rlp(block).unwrap();
+        let header = decode_header(block, &header_rlp).unwrap();
+
+        assert_eq!(
+            header.state_root,
+            block_header_state_root::<EthereumHeaderLayout>(&header_rlp).unwrap()
+        );
+        assert_eq!(header.state_root, [0xaa; 32]);
+        assert_eq!(header.parent_hash, [0x11; 32]);
+        assert_eq!(header.timestamp, 0x6718_a3c0);
+        assert_eq!(header.base_fee, 0x2_b8a1_e7c4);
+
+        let mut short_root = block.clone();
+        short_root["stateRoot"] = serde_json::json!("0xaaaa");
+        let header_rlp = encode_block_header_rlp(&short_root).unwrap();
+        assert!(decode_header(&short_root, &header_rlp).is_err());
+    }
+
+    #[test]
+    fn header_field_count_follows_the_rpc_fork() {
+        let mut london = cancun_block();
+        for key in [
+            "withdrawalsRoot",
+            "blobGasUsed",
+            "excessBlobGas",
+            "parentBeaconBlockRoot",
+        ] {
+            london.as_object_mut().unwrap().remove(key);
+        }
+        let header_rlp = encode_block_header_rlp(london.as_object().unwrap()).unwrap();
+        assert!(block_header_number::<EthereumHeaderLayout>(&header_rlp).is_ok());
+
+        let mut partial = cancun_block();
+        partial["excessBlobGas"] = serde_json::Value::Null;
+        let err = encode_block_header_rlp(partial.as_object().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Cancun"), "{err:#}");
+
+        let mut no_cancun = london;
+        no_cancun["requestsHash"] = serde_json::json!(format!("0x{}", "99".repeat(32)));
+        let err = encode_block_header_rlp(no_cancun.as_object().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("requestsHash"), "{err:#}");
+    }
+
+    #[tokio::test]
+    async fn block_number_disagreeing_with_header_is_rejected() {
+        // `from_str_radix` accepts the sign but the header encoder cannot
+        // decode it, so the JSON number is 1 while the header encodes 0.
+        let block = serde_json::json!({
+            "number": "0x+1",
+            "timestamp": "0x6718a3c0",
+        });
+        let app = Router::new().route(
+            "/",
+            post(move || {
+                let block = block.clone();
+                async move { Json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": block})) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let err = eth_get_block(&client, &format!("http://{addr}"), "latest")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("RPC says 1 but the header encodes 0"),
+            "{err:#}"
+        );
+    }
+
+    /// Node that answers HTTP 503 to the first `failures` requests.
+    async fn flaky_node(failures: usize) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let app = Router::new().route(
+            "/",
+            post(move || {
+                let seen = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if seen < failures {
+                        return Err(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+                    }
+                    Ok(Json(
+                        serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": "0xa"}),
+                    ))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), requests)
+    }
+
+    #[tokio::test]
+    async fn transient_rpc_failures_are_retried_a_bounded_number_of_times() {
+        let client = reqwest::Client::new();
+
+        let (url, requests) = flaky_node(2).await;
+        assert_eq!(eth_chain_id(&client, &url).await.unwrap(), 10);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let (url, requests) = flaky_node(3).await;
+        let err = eth_chain_id(&client, &url).await.unwrap_err();
+        assert!(err.to_string().contains("503"), "{err:#}");
+        assert_eq!(requests.load(Ordering::SeqCst), RPC_ATTEMPTS as usize);
+    }
 }
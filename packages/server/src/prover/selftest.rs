@@ -0,0 +1,187 @@
+//! Deployment self-test: proves and verifies a fixed single-note claim.
+//!
+//! The fixture is a one-leaf account trie holding the deposit's target address
+//! under a minimal block header, so it needs no RPC access.
+
+use shadow_proof_core::{
+    amount_from_u128, compute_notes_hash, compute_recipient_hash, derive_target_address, ClaimInput,
+};
+
+use super::rpc::{keccak256, rlp_encode_bytes, rlp_encode_list};
+
+const SELFTEST_SECRET: [u8; 32] = [0x5e; 32];
+const SELFTEST_CHAIN_ID: u64 = 167013;
+const SELFTEST_BLOCK_NUMBER: u64 = 1;
+const SELFTEST_RECIPIENT: [u8; 20] = [0x11; 20];
+const SELFTEST_AMOUNT: u128 = 1_000_000_000_000;
+
+/// Outcome of a self-test run.
+#[cfg(feature = "prove")]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelftestReport {
+    pub ok: bool,
+    pub elapsed_secs: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt_kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Build the fixed, valid claim input proved by the self-test.
+pub fn selftest_claim_input() -> ClaimInput {
+    let amounts = vec![amount_from_u128(SELFTEST_AMOUNT)];
+    let recipient_hashes = vec![compute_recipient_hash(&SELFTEST_RECIPIENT)];
+    let notes_hash =
+        compute_notes_hash(1, &amounts, &recipient_hashes).expect("self-test notes are valid");
+    let target_address = derive_target_address(&SELFTEST_SECRET, SELFTEST_CHAIN_ID, &notes_hash);
+
+    let (leaf, state_root) = single_account_trie(&target_address, SELFTEST_AMOUNT);
+
+    let header = rlp_encode_list(&[
+        rlp_encode_bytes(&[0u8; 32]),  // parentHash
+        rlp_encode_bytes(&[0u8; 32]),  // sha3Uncles
+        rlp_encode_bytes(&[0u8; 20]),  // miner
+        rlp_encode_bytes(&state_root), // stateRoot
+        rlp_encode_bytes(&[0u8; 32]),  // transactionsRoot
+        rlp_encode_bytes(&[0u8; 32]),  // receiptsRoot
+        rlp_encode_bytes(&[0u8; 256]), // logsBloom
+        rlp_encode_bytes(&[]),         // difficulty
+        rlp_encode_bytes(&SELFTEST_BLOCK_NUMBER.to_be_bytes()[7..]), // number
+        rlp_encode_bytes(&[0x01]),     // gasLimit
+        rlp_encode_bytes(&[]),         // gasUsed
+        rlp_encode_bytes(&[0x01]),     // timestamp
+        rlp_encode_bytes(&[]),         // extraData
+        rlp_encode_bytes(&[0u8; 32]),  // mixHash
+        rlp_encode_bytes(&[0u8; 8]),   // nonce
+        rlp_encode_bytes(&[0x01]),     // baseFeePerGas
+        rlp_encode_bytes(&[0u8; 32]),  // withdrawalsRoot
+    ]);
+
+    ClaimInput {
+        block_number: SELFTEST_BLOCK_NUMBER,
+        block_hash: keccak256(&header),
+        chain_id: SELFTEST_CHAIN_ID,
+        note_index: 0,
+        amount: amounts[0],
+        recipient: SELFTEST_RECIPIENT,
+        secret: SELFTEST_SECRET,
+        note_count: 1,
+        amounts,
+        recipient_hashes,
+        block_header_rlp: header,
+        proof_depth: 1,
+        proof_nodes: vec![leaf],
+        token: None,
+    }
+}
+
+/// Build a state trie holding only `address` with `balance` wei.
+///
+/// Returns the single leaf node (the whole account proof) and the state root.
+fn single_account_trie(address: &[u8; 20], balance: u128) -> (Vec<u8>, [u8; 32]) {
+    // Account: [nonce, balance, storageRoot, codeHash]
+    let balance = balance.to_be_bytes();
+    let first_nonzero = balance
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(balance.len());
+    let account = rlp_encode_list(&[
+        rlp_encode_bytes(&[]),
+        rlp_encode_bytes(&balance[first_nonzero..]),
+        rlp_encode_bytes(&keccak256(&[0x80])), // empty storage trie
+        rlp_encode_bytes(&keccak256(&[])),     // no code
+    ]);
+
+    // Single leaf covering all 64 nibbles of keccak(address): flag 0x20 = even leaf.
+    let mut path = vec![0x20];
+    path.extend_from_slice(&keccak256(address));
+    let leaf = rlp_encode_list(&[rlp_encode_bytes(&path), rlp_encode_bytes(&account)]);
+    let state_root = keccak256(&leaf);
+    (leaf, state_root)
+}
+
+/// Prove the fixture with `receipt_kind`, verify the receipt, and check the
+/// journal against the expected claim.
+#[cfg(feature = "prove")]
+pub fn run(receipt_kind: &str) -> SelftestReport {
+    let started = std::time::Instant::now();
+    let receipt_kind = receipt_kind.to_string();
+
+    // Same dedicated large-stack thread the pipeline proves on.
+    let outcome = std::thread::Builder::new()
+        .name("shadow-selftest".into())
+        .stack_size(256 * 1024 * 1024)
+        .spawn(move || prove_and_verify(&receipt_kind))
+        .map_err(anyhow::Error::from)
+        .and_then(|handle| {
+            handle.join().map_err(|panic| {
+                let detail = panic
+                    .downcast_ref::<String>()
+                    .map(String::as_str)
+                    .or_else(|| panic.downcast_ref::<&str>().copied())
+                    .unwrap_or("unknown panic");
+                anyhow::anyhow!("self-test prover thread panicked: {}", detail)
+            })?
+        });
+
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    match outcome {
+        Ok(kind) => SelftestReport {
+            ok: true,
+            elapsed_secs,
+            receipt_kind: Some(kind.to_string()),
+            error: None,
+        },
+        Err(e) => SelftestReport {
+            ok: false,
+            elapsed_secs,
+            receipt_kind: None,
+            error: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+#[cfg(feature = "prove")]
+fn prove_and_verify(receipt_kind: &str) -> anyhow::Result<&'static str> {
+    use anyhow::{bail, Context};
+    use shadow_prover_lib::{
+        configure_risc0_env, describe_receipt_kind, prove_claim, verify_receipt, VerifyPolicy,
+    };
+
+    let input = selftest_claim_input();
+    let expected = shadow_proof_core::evaluate_claim(&input)
+        .map_err(|e| anyhow::anyhow!("self-test input invalid: {}", e.as_str()))?;
+
+    configure_risc0_env();
+    let result = prove_claim(&input, receipt_kind, VerifyPolicy::Verify)
+        .context("self-test proving failed")?;
+    let journal = verify_receipt(&result.receipt).context("self-test verification failed")?;
+    if journal.nullifier != expected.nullifier || journal.block_hash != expected.block_hash {
+        bail!("self-test journal does not match the expected claim");
+    }
+
+    Ok(describe_receipt_kind(&result.receipt.inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selftest_input_passes_claim_evaluation() {
+        let input = selftest_claim_input();
+        let journal = shadow_proof_core::evaluate_claim(&input).unwrap();
+        assert_eq!(journal.amount, SELFTEST_AMOUNT);
+        assert_eq!(journal.recipient, SELFTEST_RECIPIENT);
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn selftest_succeeds_with_dev_prover() {
+        std::env::set_var("RISC0_DEV_MODE", "1");
+        let report = run("succinct");
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(report.receipt_kind.as_deref(), Some("fake"));
+    }
+}
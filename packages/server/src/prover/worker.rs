@@ -0,0 +1,174 @@
+//! Background proof worker.
+//!
+//! Takes jobs from the [`ProofQueue`](super::ProofQueue) one at a time, runs the
+//! pipeline for each, and writes the bundled proof file to the workspace.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::sync::oneshot;
+use tracing::Instrument;
+
+use super::{
+    audit::{AuditOutcome, AuditRecord},
+    pipeline::{self, BundledProof},
+    queue::{JobStatus, ProofJob},
+};
+use crate::{mining::timestamp_now, state::AppState};
+
+/// Run queued proof jobs, highest priority first, until the queue is shut down.
+pub async fn run(state: Arc<AppState>) {
+    while let Some((job, cancel_rx)) = state.proof_queue.wait_for_job().await {
+        let span = job_span(&job);
+        run_job(&state, &job, cancel_rx).instrument(span).await;
+    }
+}
+
+/// Span for running `job`, nested under the request that queued it.
+pub fn job_span(job: &ProofJob) -> tracing::Span {
+    tracing::info_span!(parent: &job.span, "proof_job", deposit = %job.deposit_id)
+}
+
+/// Prove one deposit and record the outcome on the queue.
+async fn run_job(state: &AppState, job: &ProofJob, cancel_rx: oneshot::Receiver<()>) {
+    let queue = state.proof_queue.clone();
+
+    let Some(rpc_url) = state.rpc_url.clone() else {
+        fail_job(state, job, "RPC URL not configured").await;
+        return;
+    };
+
+    // Re-resolve the deposit: it may have changed while the job was pending.
+    let index = state.workspace_index();
+    let Some(deposit) = index.deposits.iter().find(|d| d.id == job.deposit_id) else {
+        fail_job(state, job, &format!("deposit {} not found", job.deposit_id)).await;
+        return;
+    };
+
+    let prove_start = std::time::Instant::now();
+    let bundled = match pipeline::run_pipeline(
+        &state.workspace,
+        &deposit.filename,
+        &state.pipeline_config(&rpc_url),
+        queue.clone(),
+        cancel_rx,
+        job.resume,
+    )
+    .await
+    {
+        Ok(bundled) => bundled,
+        Err(e) => {
+            // Use {:#} to include the full anyhow cause chain (e.g. RISC Zero panic message)
+            let detail = format!("{:#}", e);
+            tracing::error!(error = %detail, deposit = %job.deposit_id, "proof pipeline failed");
+            fail_job(state, job, &detail).await;
+            return;
+        }
+    };
+
+    // Rename any existing proof file to .bkup before writing the new one
+    if let Some(ref old_proof) = deposit.proof_file {
+        let old_path = state.workspace.join(old_proof);
+        if old_path.is_file() {
+            let bkup_path = old_path.with_extension("bkup");
+            if let Err(e) = std::fs::rename(&old_path, &bkup_path) {
+                tracing::warn!(error = %e, file = %old_proof, "failed to rename old proof to .bkup");
+            } else {
+                tracing::info!(file = %old_proof, "renamed old proof to .bkup");
+            }
+        }
+    }
+
+    let record = AuditRecord::success(&job.deposit_id, &bundled);
+    match write_proof_file(state, &deposit.filename, bundled) {
+        Ok(proof_filename) => {
+            tracing::info!(file = %proof_filename, "proof file written");
+            remove_partial(state, &deposit.filename);
+            audit(state, &record);
+            queue
+                .complete(&proof_filename, Some(prove_start.elapsed().as_secs_f64()))
+                .await;
+            state.workspace_changed();
+        }
+        Err(e) => {
+            tracing::error!(error = %format!("{:#}", e), "failed to write proof file");
+            fail_job(state, job, &format!("{:#}", e)).await;
+        }
+    }
+}
+
+/// Mark the current job failed and audit it as failed or cancelled.
+async fn fail_job(state: &AppState, job: &ProofJob, error: &str) {
+    let cancelled = state
+        .proof_queue
+        .status()
+        .await
+        .is_some_and(|current| current.status == JobStatus::Cancelled);
+    let outcome = if cancelled {
+        AuditOutcome::Cancel
+    } else {
+        AuditOutcome::Fail
+    };
+    state.proof_queue.fail(0, error).await;
+    audit(
+        state,
+        &AuditRecord::unfinished(&job.deposit_id, outcome, error),
+    );
+}
+
+/// Delete the deposit's checkpoint once its full proof is on disk.
+fn remove_partial(state: &AppState, deposit_filename: &str) {
+    let path = pipeline::partial_path(&state.workspace, deposit_filename);
+    match std::fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            tracing::warn!(error = %e, file = %path.display(), "failed to remove partial proof")
+        }
+    }
+}
+
+fn audit(state: &AppState, record: &AuditRecord) {
+    if let Some(ref log) = state.audit_log {
+        if let Err(e) = log.append(record) {
+            tracing::warn!(error = %format!("{:#}", e), file = %log.path().display(), "failed to append audit record");
+        }
+    }
+}
+
+/// Write the bundled proof under the configured naming scheme and return its
+/// workspace-relative path.
+fn write_proof_file(
+    state: &AppState,
+    deposit_filename: &str,
+    mut bundled: BundledProof,
+) -> Result<String> {
+    let deposit_stem = deposit_filename
+        .strip_suffix(".json")
+        .unwrap_or(deposit_filename);
+    let proof_ts = timestamp_now();
+    let proof_filename = state
+        .proof_naming
+        .proof_filename(deposit_stem, &proof_ts, None);
+    let proof_path = state.workspace.join(&proof_filename);
+    if let Some(parent) = proof_path.parent() {
+        state
+            .write_perms
+            .create_dir_all(parent)
+            .context("failed to create proof directory")?;
+    }
+
+    bundled.created = Some(proof_ts);
+    #[cfg(feature = "prove")]
+    {
+        bundled.circuit_id = Some(shadow_prover_lib::circuit_id_hex());
+    }
+
+    let json_bytes = serde_json::to_vec_pretty(&bundled).context("serialization error")?;
+    state
+        .write_perms
+        .write(&proof_path, json_bytes)
+        .context("failed to write proof file")?;
+
+    Ok(proof_filename)
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use axum::{
     extract::{Path, State},
@@ -6,9 +6,9 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::{state::AppState, workspace::scanner::scan_workspace};
+use crate::{prover::rpc, state::AppState};
 
 /// `GET /api/config` — returns server configuration and chain info.
 async fn get_config(State(state): State<Arc<AppState>>) -> Json<ConfigResponse> {
@@ -22,6 +22,9 @@ async fn get_config(State(state): State<Arc<AppState>>) -> Json<ConfigResponse>
         local_circuit_id,
         circuit_mismatch: None,
         shadow_address: state.shadow_address.clone(),
+        max_notes: state.max_notes,
+        max_proof_node_bytes: state.max_proof_node_bytes,
+        circuit_max_node_bytes: shadow_proof_core::MAX_NODE_BYTES,
     };
 
     // Resolve Risc0CircuitVerifier from Shadow, then read its imageId
@@ -65,6 +68,136 @@ struct ConfigResponse {
     circuit_mismatch: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     shadow_address: Option<String>,
+    /// Operator limit on notes per new deposit.
+    max_notes: usize,
+    /// Operator limit on proof node size.
+    max_proof_node_bytes: usize,
+    /// Proof node size ceiling compiled into the circuit.
+    circuit_max_node_bytes: usize,
+}
+
+/// Upper bound on the whole `/api/test-rpc` probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// `POST /api/test-rpc` — probe an RPC endpoint (the configured one by default)
+/// for the calls proving relies on.
+///
+/// Only `http` and `https` URLs are probed, and a URL from the request gets a
+/// client that neither sends the RPC credentials nor follows redirects.
+async fn test_rpc(
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<TestRpcRequest>>,
+) -> Result<Json<TestRpcResponse>, (StatusCode, String)> {
+    let rpc_url = body
+        .and_then(|Json(req)| req.rpc_url)
+        .or_else(|| state.rpc_url.clone())
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                "no rpcUrl given and no --rpc-url configured".to_string(),
+            )
+        })?;
+    let scheme = reqwest::Url::parse(&rpc_url)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid rpcUrl: {e}")))?
+        .scheme()
+        .to_string();
+    if scheme != "http" && scheme != "https" {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("rpcUrl must be http or https, not {scheme}"),
+        ));
+    }
+
+    // Auth headers are only for the configured RPC, never a URL from the request.
+    let http = if state.rpc_url.as_ref() == Some(&rpc_url) {
+        state.rpc_http.clone()
+    } else {
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    };
+    let report = tokio::time::timeout(PROBE_TIMEOUT, probe_rpc(&http, &rpc_url))
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("RPC probe timed out after {}s", PROBE_TIMEOUT.as_secs()),
+            )
+        })?;
+
+    Ok(Json(report))
+}
+
+/// Run the three probes against `rpc_url`.
+async fn probe_rpc(http: &reqwest::Client, rpc_url: &str) -> TestRpcResponse {
+    let chain_id = rpc::eth_chain_id(http, rpc_url).await;
+    let latest_block = rpc::eth_get_block(http, rpc_url, "latest").await;
+    let block_number = match &latest_block {
+        Ok(block) => Ok(block.number),
+        Err(_) => rpc::eth_block_number(http, rpc_url).await,
+    };
+    let get_proof = match block_number {
+        Ok(number) => rpc::eth_get_proof(http, rpc_url, &[0u8; 20], number)
+            .await
+            .map(drop),
+        Err(e) => Err(e.context("no block number to request a proof at")),
+    };
+
+    TestRpcResponse {
+        chain_id: chain_id.as_ref().ok().map(|id| id.to_string()),
+        block_number: latest_block.as_ref().ok().map(|block| block.number),
+        checks: RpcChecks {
+            chain_id: RpcCheck::from(&chain_id),
+            latest_block: RpcCheck::from(&latest_block),
+            get_proof: RpcCheck::from(&get_proof),
+        },
+        rpc_url: rpc_url.to_string(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestRpcRequest {
+    rpc_url: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TestRpcResponse {
+    rpc_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain_id: Option<String>,
+    /// Number of the latest block, when it could be fetched and encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_number: Option<u64>,
+    checks: RpcChecks,
+}
+
+/// Outcome of each probe: `eth_chainId`, `eth_getBlockByNumber("latest")` and
+/// `eth_getProof` for the zero address.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcChecks {
+    chain_id: RpcCheck,
+    latest_block: RpcCheck,
+    get_proof: RpcCheck,
+}
+
+#[derive(Serialize)]
+struct RpcCheck {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl<T> From<&anyhow::Result<T>> for RpcCheck {
+    fn from(result: &anyhow::Result<T>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{e:#}")),
+        }
+    }
 }
 
 #[cfg(feature = "prove")]
@@ -82,7 +215,7 @@ async fn note_status(
     State(state): State<Arc<AppState>>,
     Path((id, note_index)): Path<(String, u32)>,
 ) -> Result<Json<NoteStatusResponse>, StatusCode> {
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let deposit = index
         .deposits
         .iter()
@@ -105,12 +238,64 @@ async fn note_status(
     }))
 }
 
+/// `GET /api/deposits/:id/notes/status` — cached claim status for every note.
+///
+/// Uncached notes are checked with one JSON-RPC batch request.
+async fn deposit_notes_status(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<NoteStatusResponse>>, StatusCode> {
+    let index = state.workspace_index();
+    let deposit = index
+        .deposits
+        .iter()
+        .find(|d| d.id == id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let nullifiers: Vec<String> = deposit.notes.iter().map(|n| n.nullifier.clone()).collect();
+    let statuses = match (&state.chain_client, &state.shadow_address) {
+        (Some(client), Some(address)) => client
+            .are_consumed(address, &nullifiers)
+            .await
+            .into_iter()
+            .zip(&nullifiers)
+            .map(|(result, nullifier)| match result {
+                Ok(true) => "claimed".to_string(),
+                Ok(false) => "unclaimed".to_string(),
+                Err(e) => {
+                    tracing::warn!(
+                        error = %e,
+                        nullifier = %nullifier,
+                        "failed to check claim status"
+                    );
+                    "unknown".to_string()
+                }
+            })
+            .collect(),
+        _ => vec!["unknown".to_string(); nullifiers.len()],
+    };
+
+    Ok(Json(
+        deposit
+            .notes
+            .iter()
+            .zip(statuses)
+            .map(|(note, claim_status)| NoteStatusResponse {
+                deposit_id: id.clone(),
+                note_index: note.index,
+                nullifier: note.nullifier.clone(),
+                claim_status,
+            })
+            .collect(),
+    ))
+}
+
 /// `POST /api/deposits/:id/notes/:noteIndex/refresh` — force refresh nullifier status.
 async fn refresh_note_status(
     State(state): State<Arc<AppState>>,
     Path((id, note_index)): Path<(String, u32)>,
 ) -> Result<Json<NoteStatusResponse>, StatusCode> {
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let deposit = index
         .deposits
         .iter()
@@ -180,9 +365,105 @@ async fn refresh_claim_status(state: &AppState, nullifier: &str) -> String {
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/config", get(get_config))
+        .route("/test-rpc", post(test_rpc))
+        .route("/deposits/{id}/notes/status", get(deposit_notes_status))
         .route("/deposits/{id}/notes/{noteIndex}/status", get(note_status))
         .route(
             "/deposits/{id}/notes/{noteIndex}/refresh",
             post(refresh_note_status),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// JSON-RPC node answering the three probes, optionally without `eth_getProof`.
+    async fn mock_rpc(supports_get_proof: bool) -> String {
+        let app = Router::new().route(
+            "/",
+            post(move |Json(req): Json<serde_json::Value>| async move {
+                let result = match req["method"].as_str() {
+                    Some("eth_chainId") => serde_json::json!(format!("0x{:x}", 167_000)),
+                    Some("eth_getBlockByNumber") => serde_json::json!({
+                        "parentHash": format!("0x{}", "11".repeat(32)),
+                        "stateRoot": format!("0x{}", "aa".repeat(32)),
+                        "number": "0x7",
+                        "timestamp": "0x6718a3c0",
+                        "baseFeePerGas": "0x1",
+                    }),
+                    Some("eth_getProof") if supports_get_proof => {
+                        serde_json::json!({"balance": "0x0", "accountProof": []})
+                    }
+                    _ => {
+                        return Json(serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": 1,
+                            "error": {"code": -32601, "message": "method not found"},
+                        }))
+                    }
+                };
+                Json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": result}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    fn state() -> Arc<AppState> {
+        Arc::new(AppState::for_tests(std::env::temp_dir()))
+    }
+
+    #[tokio::test]
+    async fn capability_report_reflects_supported_calls() {
+        let full = mock_rpc(true).await;
+        let Json(report) = test_rpc(
+            State(state()),
+            Some(Json(TestRpcRequest {
+                rpc_url: Some(full.clone()),
+            })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.rpc_url, full);
+        assert_eq!(report.chain_id.as_deref(), Some("167000"));
+        assert_eq!(report.block_number, Some(7));
+        assert!(report.checks.chain_id.ok && report.checks.latest_block.ok);
+        assert!(
+            report.checks.get_proof.ok,
+            "{:?}",
+            report.checks.get_proof.error
+        );
+
+        // Without a body the configured URL is probed.
+        let mut configured = AppState::for_tests(std::env::temp_dir());
+        configured.rpc_url = Some(mock_rpc(false).await);
+        let Json(report) = test_rpc(State(Arc::new(configured)), None).await.unwrap();
+        assert!(report.checks.chain_id.ok && report.checks.latest_block.ok);
+        assert!(!report.checks.get_proof.ok);
+        let error = report.checks.get_proof.error.unwrap();
+        assert!(error.contains("method not found"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn missing_rpc_url_is_a_bad_request() {
+        let result = test_rpc(State(state()), None).await;
+        assert!(matches!(result, Err((StatusCode::BAD_REQUEST, _))));
+    }
+
+    #[tokio::test]
+    async fn non_http_rpc_url_is_rejected() {
+        for url in ["file:///etc/passwd", "ftp://127.0.0.1/", "not a url"] {
+            let result = test_rpc(
+                State(state()),
+                Some(Json(TestRpcRequest {
+                    rpc_url: Some(url.to_string()),
+                })),
+            )
+            .await;
+            assert!(matches!(result, Err((StatusCode::BAD_REQUEST, _))), "{url}");
+        }
+    }
+}
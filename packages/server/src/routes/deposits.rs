@@ -1,5 +1,6 @@
-use std::sync::Arc;
+use std::{io::Write, sync::Arc};
 
+use anyhow::{bail, Context};
 use axum::{
     body::Body,
     extract::{Multipart, Path, Query, State},
@@ -9,17 +10,26 @@ use axum::{
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use shadow_util::{parse_u128, to_checksum_address, Hex20, Hex32, MAX_NOTE_LABEL_LEN};
 
 use crate::{
+    chain::{abi, shadow_contract::BlockCheck, ChainClient},
     mining,
+    prover::{
+        pipeline::{BundledProof, NoteProofResult},
+        queue::QueueTicket,
+    },
     state::AppState,
-    workspace::scanner::{scan_workspace, DepositEntry},
+    workspace::{
+        perms::WritePerms,
+        scanner::{validate_deposit, DepositEntry},
+        templates::{self, DepositTemplate},
+    },
 };
 
 /// `GET /api/deposits` — list all deposits with summary info.
 async fn list_deposits(State(state): State<Arc<AppState>>) -> Json<Vec<DepositEntry>> {
-    let index = scan_workspace(&state.workspace);
-    Json(index.deposits)
+    Json(state.workspace_index().deposits.clone())
 }
 
 /// `GET /api/deposits/:id` — full deposit details.
@@ -27,11 +37,12 @@ async fn get_deposit(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<DepositEntry>, StatusCode> {
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     index
         .deposits
-        .into_iter()
+        .iter()
         .find(|d| d.id == id)
+        .cloned()
         .map(Json)
         .ok_or(StatusCode::NOT_FOUND)
 }
@@ -53,7 +64,7 @@ async fn delete_deposit(
     Path(id): Path<String>,
     Query(query): Query<DeleteQuery>,
 ) -> Result<Json<DeleteResponse>, StatusCode> {
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let entry = index
         .deposits
         .iter()
@@ -87,9 +98,7 @@ async fn delete_deposit(
     }
 
     // Broadcast workspace change
-    let _ = state
-        .event_tx
-        .send(serde_json::json!({"type": "workspace:changed"}).to_string());
+    state.workspace_changed();
 
     Ok(Json(DeleteResponse { deleted }))
 }
@@ -99,7 +108,7 @@ async fn delete_proof(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<DeleteResponse>, StatusCode> {
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let entry = index
         .deposits
         .iter()
@@ -119,9 +128,7 @@ async fn delete_proof(
     })?;
 
     // Broadcast workspace change
-    let _ = state
-        .event_tx
-        .send(serde_json::json!({"type": "workspace:changed"}).to_string());
+    state.workspace_changed();
 
     Ok(Json(DeleteResponse {
         deleted: vec![proof_name.clone()],
@@ -151,19 +158,37 @@ struct CreateDepositNote {
     label: Option<String>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+struct CreateDepositQuery {
+    /// Queue a proof job for the new deposit once it is written.
+    #[serde(default)]
+    auto_prove: bool,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct CreateDepositResponse {
     filename: String,
     target_address: String,
     total_amount: String,
+    /// Proof job queued by `?auto_prove=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof_job: Option<QueueTicket>,
 }
 
 /// `POST /api/deposits` — create a new deposit and save to workspace.
 async fn create_deposit(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<CreateDepositQuery>,
     Json(body): Json<CreateDepositRequest>,
 ) -> Result<Json<CreateDepositResponse>, (StatusCode, String)> {
+    if query.auto_prove && state.rpc_url.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "auto_prove requires an RPC URL; start server with --rpc-url".to_string(),
+        ));
+    }
+
     // Parse and validate chain ID
     let chain_id: u64 = body.chain_id.parse().map_err(|_| {
         (
@@ -173,10 +198,13 @@ async fn create_deposit(
     })?;
 
     // Parse and validate notes
-    if body.notes.is_empty() || body.notes.len() > 5 {
+    if body.notes.is_empty() || body.notes.len() > state.max_notes {
         return Err((
             StatusCode::BAD_REQUEST,
-            "notes must contain 1-5 entries".to_string(),
+            format!(
+                "notes must contain 1-{} entries (server limit)",
+                state.max_notes
+            ),
         ));
     }
 
@@ -184,14 +212,18 @@ async fn create_deposit(
     let mut total_amount: u128 = 0;
 
     for (i, note) in body.notes.iter().enumerate() {
-        let recipient = mining::parse_hex_address(&note.recipient).map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("invalid recipient in note {}: {}", i, e),
-            )
-        })?;
+        let recipient = note
+            .recipient
+            .parse::<Hex20>()
+            .map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid recipient in note {}: {}", i, e),
+                )
+            })?
+            .into_bytes();
 
-        let amount: u128 = note.amount.parse().map_err(|_| {
+        let amount = parse_u128(&note.amount).map_err(|_| {
             (
                 StatusCode::BAD_REQUEST,
                 format!("invalid amount in note {}: {}", i, note.amount),
@@ -210,10 +242,10 @@ async fn create_deposit(
             .ok_or_else(|| (StatusCode::BAD_REQUEST, "total amount overflow".to_string()))?;
 
         if let Some(ref label) = note.label {
-            if label.len() > 64 {
+            if label.len() > MAX_NOTE_LABEL_LEN {
                 return Err((
                     StatusCode::BAD_REQUEST,
-                    format!("note {} label exceeds 64 characters", i),
+                    format!("note {} label exceeds {} characters", i, MAX_NOTE_LABEL_LEN),
                 ));
             }
         }
@@ -258,6 +290,7 @@ async fn create_deposit(
     }
 
     let workspace = state.workspace.clone();
+    let perms = state.write_perms;
     let comment = body.comment.clone();
     let token = body.token.clone();
 
@@ -276,25 +309,35 @@ async fn create_deposit(
         None
     };
 
+    // Reject a create racing an identical one instead of mining twice
+    let notes_hash = mining::notes_hash(&mine_notes, &state.recipient_hashes)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let mine_key = mining::MineKey::new(chain_id, token.as_deref(), notes_hash);
+    let _mine_guard = state.mines_in_flight.begin(mine_key).ok_or((
+        StatusCode::CONFLICT,
+        "a deposit with these notes is already being created".to_string(),
+    ))?;
+
     // Run deposit creation in a blocking thread
+    let blocking_state = state.clone();
     let result = tokio::task::spawn_blocking(move || {
         let req = mining::MineRequest {
             chain_id,
             notes: mine_notes,
         };
 
-        let mine_result = mining::mine_deposit(&req)?;
+        let mine_result = mining::mine_deposit(&req, &blocking_state.recipient_hashes)?;
 
-        let filename = mining::write_deposit_file(
-            &workspace,
+        let deposit = mining::NewDeposit {
             chain_id,
-            &mine_result.secret,
-            &mine_result.target_address,
-            &req.notes,
-            comment.as_deref(),
-            token.as_deref(),
-            token_symbol.as_deref(),
-        )?;
+            secret: &mine_result.secret,
+            target_address: &mine_result.target_address,
+            notes: &req.notes,
+            comment: comment.as_deref(),
+            token: token.as_deref(),
+            token_symbol: token_symbol.as_deref(),
+        };
+        let filename = mining::write_deposit_file(&workspace, perms, &deposit)?;
 
         Ok::<_, anyhow::Error>((filename, mine_result))
     })
@@ -321,17 +364,107 @@ async fn create_deposit(
     );
 
     // Broadcast workspace change via WebSocket
-    let _ = state
-        .event_tx
-        .send(serde_json::json!({"type": "workspace:changed"}).to_string());
+    state.workspace_changed();
+
+    let proof_job = if query.auto_prove {
+        let deposit_id = filename.trim_end_matches(".json");
+        Some(
+            state
+                .proof_queue
+                .enqueue(deposit_id, body.notes.len() as u32, 0, false)
+                .await,
+        )
+    } else {
+        None
+    };
 
     Ok(Json(CreateDepositResponse {
         filename,
-        target_address: format!("0x{}", hex::encode(mine_result.target_address)),
+        target_address: to_checksum_address(&mine_result.target_address),
         total_amount: total_amount.to_string(),
+        proof_job,
     }))
 }
 
+// ---------------------------------------------------------------------------
+// Deposit templates
+// ---------------------------------------------------------------------------
+
+/// `POST /api/templates` — save a named recipient list for later deposits.
+async fn create_template(
+    State(state): State<Arc<AppState>>,
+    Json(mut template): Json<DepositTemplate>,
+) -> Result<Json<DepositTemplate>, (StatusCode, String)> {
+    template
+        .validate(state.max_notes)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{e:#}")))?;
+    let exists = templates::template_path(&state.workspace, &template.name)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .exists();
+    if exists {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("template {} already exists", template.name),
+        ));
+    }
+    templates::save_template(&state.workspace, state.write_perms, &template)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    Ok(Json(template))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FromTemplateRequest {
+    chain_id: String,
+    /// One amount per template note, in template order.
+    amounts: Vec<String>,
+    #[serde(default)]
+    comment: Option<String>,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+/// `POST /api/deposits/from-template/:name` — create a deposit paying the
+/// template's recipients the given amounts.
+async fn create_deposit_from_template(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<CreateDepositQuery>,
+    Json(body): Json<FromTemplateRequest>,
+) -> Result<Json<CreateDepositResponse>, (StatusCode, String)> {
+    let template = templates::load_template(&state.workspace, &name)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{e:#}")))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("no template named {name}")))?;
+    if body.amounts.len() != template.notes.len() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "template {name} has {} notes but {} amounts were given",
+                template.notes.len(),
+                body.amounts.len()
+            ),
+        ));
+    }
+
+    let notes = template
+        .notes
+        .into_iter()
+        .zip(body.amounts)
+        .map(|(note, amount)| CreateDepositNote {
+            recipient: note.recipient,
+            amount,
+            label: note.label,
+        })
+        .collect();
+    let request = CreateDepositRequest {
+        chain_id: body.chain_id,
+        notes,
+        comment: body.comment,
+        token: body.token,
+    };
+    create_deposit(State(state), Query(query), Json(request)).await
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct BalanceResponse {
@@ -359,6 +492,10 @@ struct ClaimTxResponse {
     data: String,
     /// Chain ID (hex).
     chain_id: String,
+    /// Whether the proving block is still canonical (absent if the RPC check
+    /// was unavailable). A `false` means a reorg invalidated the proof.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_still_canonical: Option<bool>,
 }
 
 /// `GET /api/deposits/:id/notes/:noteIndex/claim-tx` — build claim tx calldata.
@@ -377,43 +514,8 @@ async fn get_claim_tx(
         ))?
         .clone();
 
-    let index = scan_workspace(&state.workspace);
-    let deposit = index
-        .deposits
-        .iter()
-        .find(|d| d.id == id)
-        .ok_or((StatusCode::NOT_FOUND, format!("deposit {} not found", id)))?;
-
-    let proof_file = deposit.proof_file.as_ref().ok_or((
-        StatusCode::BAD_REQUEST,
-        "deposit has no proof file".to_string(),
-    ))?;
-
-    // Read and parse the proof file
-    let proof_path = state.workspace.join(proof_file);
-    let proof_raw = std::fs::read(&proof_path).map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("failed to read proof file: {}", e),
-        )
-    })?;
-
-    let bundled: crate::prover::pipeline::BundledProof = serde_json::from_slice(&proof_raw)
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("failed to parse proof file: {}", e),
-            )
-        })?;
-
-    let note_proof = bundled
-        .notes
-        .iter()
-        .find(|n| n.note_index == note_index)
-        .ok_or((
-            StatusCode::NOT_FOUND,
-            format!("note {} not found in proof file", note_index),
-        ))?;
+    let (bundled, note_proof) = load_note_proof(&state, &id, note_index)?;
+    check_note_against_deposit(&state, &id, &bundled, &note_proof)?;
 
     if note_proof.proof.is_empty() {
         return Err((
@@ -494,7 +596,7 @@ async fn get_claim_tx(
         .transpose()?
         .unwrap_or_default();
 
-    let calldata = encode_claim_calldata(
+    let calldata = abi::encode_claim_calldata(
         &proof_bytes,
         block_number,
         chain_id,
@@ -504,91 +606,286 @@ async fn get_claim_tx(
         &token,
     );
 
+    let block_still_canonical = match state.chain_client {
+        Some(ref chain_client) => {
+            match check_proving_block(chain_client, &id, block_number, &bundled).await {
+                Ok(check) => Some(check.still_canonical),
+                Err(e) => {
+                    tracing::warn!(error = %e, "failed to check proving block hash");
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
     Ok(Json(ClaimTxResponse {
         to: shadow_address,
         data: format!("0x{}", hex::encode(calldata)),
         chain_id: format!("0x{:x}", chain_id),
+        block_still_canonical,
     }))
 }
 
-/// ABI-encode `claim(bytes _proof, (uint64,uint64,uint256,address,bytes32,address) _input)`.
-fn encode_claim_calldata(
-    proof_bytes: &[u8],
+/// Read the deposit's bundled proof and the proof of one of its notes.
+fn load_note_proof(
+    state: &AppState,
+    id: &str,
+    note_index: u32,
+) -> Result<(BundledProof, NoteProofResult), (StatusCode, String)> {
+    let bundled = load_bundled_proof(state, id)?;
+    let note_proof = bundled
+        .notes
+        .iter()
+        .find(|n| n.note_index == note_index)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("note {} not found in proof file", note_index),
+        ))?
+        .clone();
+    Ok((bundled, note_proof))
+}
+
+/// Reject a proof file whose chain ID, amount, recipient or nullifier
+/// disagree with the deposit as the workspace scan derived it (409).
+fn check_note_against_deposit(
+    state: &AppState,
+    id: &str,
+    bundled: &BundledProof,
+    note_proof: &NoteProofResult,
+) -> Result<(), (StatusCode, String)> {
+    let index = state.workspace_index();
+    let deposit = index
+        .deposits
+        .iter()
+        .find(|d| d.id == id)
+        .ok_or((StatusCode::NOT_FOUND, format!("deposit {} not found", id)))?;
+    note_matches_deposit(deposit, bundled, note_proof).map_err(|e| {
+        (
+            StatusCode::CONFLICT,
+            format!("proof file does not match the deposit: {:#}", e),
+        )
+    })
+}
+
+fn note_matches_deposit(
+    deposit: &DepositEntry,
+    bundled: &BundledProof,
+    note_proof: &NoteProofResult,
+) -> anyhow::Result<()> {
+    if bundled.chain_id.parse::<u64>().ok() != deposit.chain_id.parse::<u64>().ok() {
+        bail!(
+            "chain ID {} is not the deposit's {}",
+            bundled.chain_id,
+            deposit.chain_id
+        );
+    }
+
+    let index = note_proof.note_index;
+    let note = deposit
+        .notes
+        .iter()
+        .find(|n| n.index == index)
+        .with_context(|| format!("deposit has no note {}", index))?;
+    if parse_u128(&note_proof.amount).ok() != note.amount.parse::<u128>().ok() {
+        bail!("note {} amount differs", index);
+    }
+    if note_proof.recipient.parse::<Hex20>()? != note.recipient.parse::<Hex20>()? {
+        bail!("note {} recipient differs", index);
+    }
+    if note_proof.nullifier.parse::<Hex32>()? != note.nullifier.parse::<Hex32>()? {
+        bail!("note {} nullifier differs", index);
+    }
+    Ok(())
+}
+
+/// Read the deposit's bundled proof.
+fn load_bundled_proof(state: &AppState, id: &str) -> Result<BundledProof, (StatusCode, String)> {
+    let index = state.workspace_index();
+    let deposit = index
+        .deposits
+        .iter()
+        .find(|d| d.id == id)
+        .ok_or((StatusCode::NOT_FOUND, format!("deposit {} not found", id)))?;
+
+    let proof_file = deposit.proof_file.as_ref().ok_or((
+        StatusCode::BAD_REQUEST,
+        "deposit has no proof file".to_string(),
+    ))?;
+
+    // Read and parse the proof file
+    let proof_path = state.workspace.join(proof_file);
+    let proof_raw = std::fs::read(&proof_path).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("failed to read proof file: {}", e),
+        )
+    })?;
+
+    serde_json::from_slice(&proof_raw).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("failed to parse proof file: {}", e),
+        )
+    })
+}
+
+// ---------------------------------------------------------------------------
+// GET /api/deposits/:id/verify-block — is the proving block still canonical?
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyBlockResponse {
+    block_number: u64,
+    /// Hash of the block the proofs were generated against.
+    block_hash: String,
+    /// Canonical hash at `block_number`; absent if the node has no such block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canonical_hash: Option<String>,
+    /// The proving block was reorged out, so claims with these proofs revert.
+    block_stale: bool,
+}
+
+/// `GET /api/deposits/:id/verify-block` — re-check the proving block hash
+/// against the canonical chain.
+async fn verify_proving_block(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<VerifyBlockResponse>, (StatusCode, String)> {
+    let chain_client = state.chain_client.as_ref().ok_or((
+        StatusCode::BAD_REQUEST,
+        "RPC URL not configured".to_string(),
+    ))?;
+    let bundled = load_bundled_proof(&state, &id)?;
+    let block_number: u64 = bundled.block_number.parse().map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "invalid block number".to_string(),
+        )
+    })?;
+
+    let check = check_proving_block(chain_client, &id, block_number, &bundled)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("{:#}", e)))?;
+
+    Ok(Json(VerifyBlockResponse {
+        block_number,
+        block_hash: bundled.block_hash,
+        canonical_hash: check.canonical_hash,
+        block_stale: !check.still_canonical,
+    }))
+}
+
+/// Re-check the proving block of deposit `id`, warning if it was reorged out.
+async fn check_proving_block(
+    chain_client: &ChainClient,
+    id: &str,
+    block_number: u64,
+    bundled: &BundledProof,
+) -> anyhow::Result<BlockCheck> {
+    let check = chain_client
+        .block_still_canonical(block_number, &bundled.block_hash)
+        .await?;
+    if !check.still_canonical {
+        tracing::warn!(deposit = %id, block_number, "proving block is no longer canonical");
+    }
+    Ok(check)
+}
+
+// ---------------------------------------------------------------------------
+// GET /api/deposits/:id/notes/:noteIndex/export — raw seal and journal
+// ---------------------------------------------------------------------------
+
+/// Length of an exported Groth16 seal: the 4-byte verifier selector followed
+/// by the 256-byte proof.
+const GROTH16_SEAL_LEN: usize = 4 + 256;
+
+/// A note's proof in the shape of `shadow_prover_lib::ExportedProof`, plus the
+/// `(seal, journal)` calldata and the circuit it was proven against.
+#[derive(Debug, Serialize)]
+struct ExportedNoteProof {
+    receipt_kind: &'static str,
+    seal_hex: String,
+    journal_hex: String,
+    proof_hex: String,
+    /// Absent for proof files written before the circuit ID was recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    circuit_id: Option<String>,
+}
+
+/// `GET /api/deposits/:id/notes/:noteIndex/export` — the stored seal and
+/// journal of one note, for relayers that build their own claim transaction.
+async fn export_note_proof(
+    State(state): State<Arc<AppState>>,
+    Path((id, note_index)): Path<(String, u32)>,
+) -> Result<Json<ExportedNoteProof>, (StatusCode, String)> {
+    let (bundled, note_proof) = load_note_proof(&state, &id, note_index)?;
+    let seal = note_proof
+        .seal
+        .strip_prefix("0x")
+        .unwrap_or(&note_proof.seal);
+    if seal.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "proof was generated without the prove feature; no seal to export".to_string(),
+        ));
+    }
+
+    // The bundle does not record the receipt kind, but Groth16 seals have a
+    // fixed length and succinct seals are far longer.
+    let receipt_kind = if seal.len() == GROTH16_SEAL_LEN * 2 {
+        "groth16"
+    } else {
+        "succinct"
+    };
+    Ok(Json(ExportedNoteProof {
+        receipt_kind,
+        seal_hex: note_proof.seal,
+        journal_hex: note_proof.journal,
+        proof_hex: note_proof.proof,
+        circuit_id: bundled.circuit_id,
+    }))
+}
+
+#[derive(Deserialize)]
+struct DecodeCalldataRequest {
+    /// `claim` calldata (0x-prefixed hex), as returned by the claim-tx endpoint.
+    data: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DecodedCalldataResponse {
+    proof_len: usize,
     block_number: u64,
     chain_id: u64,
-    amount: u128,
-    recipient: &[u8],
-    nullifier: &[u8],
-    token: &[u8],
-) -> Vec<u8> {
-    // Function selector: claim(bytes,(uint64,uint64,uint256,address,bytes32,address))
-    use tiny_keccak::{Hasher, Keccak};
-    let mut keccak = Keccak::v256();
-    keccak.update(b"claim(bytes,(uint64,uint64,uint256,address,bytes32,address))");
-    let mut selector = [0u8; 32];
-    keccak.finalize(&mut selector);
-
-    let mut calldata = Vec::new();
-    // Function selector (4 bytes)
-    calldata.extend_from_slice(&selector[..4]);
-
-    // For function(bytes, Tuple) where Tuple is static (6 fields = 6 words):
-    // Head: [offset_param1 (32)] [param2.field1..6 (6 x 32)]
-    // = 7 x 32 = 224 bytes head
-    // Tail: [length (32)] [data (padded)]
-    // offset_param1 = 224
-
-    let mut offset_bytes = [0u8; 32];
-    offset_bytes[28..32].copy_from_slice(&224u32.to_be_bytes());
-    calldata.extend_from_slice(&offset_bytes);
-
-    // _input.blockNumber (uint64, left-padded to 32 bytes)
-    let mut bn = [0u8; 32];
-    bn[24..32].copy_from_slice(&block_number.to_be_bytes());
-    calldata.extend_from_slice(&bn);
-
-    // _input.chainId (uint64)
-    let mut cid = [0u8; 32];
-    cid[24..32].copy_from_slice(&chain_id.to_be_bytes());
-    calldata.extend_from_slice(&cid);
-
-    // _input.amount (uint256)
-    let mut amt = [0u8; 32];
-    amt[16..32].copy_from_slice(&amount.to_be_bytes());
-    calldata.extend_from_slice(&amt);
-
-    // _input.recipient (address, left-padded to 32 bytes)
-    let mut rcpt = [0u8; 32];
-    if recipient.len() == 20 {
-        rcpt[12..32].copy_from_slice(recipient);
-    }
-    calldata.extend_from_slice(&rcpt);
-
-    // _input.nullifier (bytes32)
-    let mut nul = [0u8; 32];
-    if nullifier.len() == 32 {
-        nul.copy_from_slice(nullifier);
-    }
-    calldata.extend_from_slice(&nul);
-
-    // _input.token (address, left-padded to 32 bytes)
-    let mut tok = [0u8; 32];
-    if token.len() == 20 {
-        tok[12..32].copy_from_slice(token);
-    }
-    calldata.extend_from_slice(&tok);
-
-    // Proof bytes dynamic data
-    let mut proof_len = [0u8; 32];
-    proof_len[28..32].copy_from_slice(&(proof_bytes.len() as u32).to_be_bytes());
-    calldata.extend_from_slice(&proof_len);
-
-    calldata.extend_from_slice(proof_bytes);
-    let proof_padded_len = (proof_bytes.len() + 31) / 32 * 32;
-    let padding = proof_padded_len - proof_bytes.len();
-    calldata.extend(std::iter::repeat(0u8).take(padding));
-
-    calldata
+    amount: String,
+    recipient: String,
+    nullifier: String,
+    token: String,
+}
+
+/// `POST /api/decode-claim-calldata` — decode `claim` calldata so the user can
+/// confirm what they are about to submit.
+async fn decode_claim_calldata(
+    Json(body): Json<DecodeCalldataRequest>,
+) -> Result<Json<DecodedCalldataResponse>, (StatusCode, String)> {
+    let hex_str = body.data.strip_prefix("0x").unwrap_or(&body.data);
+    let calldata = hex::decode(hex_str)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid hex: {}", e)))?;
+    let decoded = abi::decode_claim_calldata(&calldata)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))?;
+
+    Ok(Json(DecodedCalldataResponse {
+        proof_len: decoded.proof.len(),
+        block_number: decoded.block_number,
+        chain_id: decoded.chain_id,
+        amount: decoded.amount.to_string(),
+        recipient: format!("0x{}", hex::encode(decoded.recipient)),
+        nullifier: format!("0x{}", hex::encode(decoded.nullifier)),
+        token: format!("0x{}", hex::encode(decoded.token)),
+    }))
 }
 
 /// `GET /api/deposits/:id/balance` — ETH balance of the deposit target address.
@@ -601,7 +898,7 @@ async fn get_deposit_balance(
         "RPC URL not configured".to_string(),
     ))?;
 
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let deposit = index
         .deposits
         .iter()
@@ -624,11 +921,7 @@ async fn get_deposit_balance(
 
     let required: u128 = deposit.total_amount.parse().unwrap_or(0);
     let bal: u128 = balance.parse().unwrap_or(0);
-    let due = if bal >= required {
-        0u128
-    } else {
-        required - bal
-    };
+    let due = required.saturating_sub(bal);
 
     Ok(Json(BalanceResponse {
         target_address: deposit.target_address.clone(),
@@ -646,7 +939,7 @@ async fn download_deposit(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Response<Body>, StatusCode> {
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let entry = index
         .deposits
         .iter()
@@ -669,7 +962,7 @@ async fn download_proof(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Response<Body>, StatusCode> {
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let entry = index
         .deposits
         .iter()
@@ -693,7 +986,7 @@ async fn import_deposit(
     State(state): State<Arc<AppState>>,
     mut multipart: Multipart,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    while let Some(field) = multipart
+    if let Some(field) = multipart
         .next_field()
         .await
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("multipart error: {}", e)))?
@@ -710,23 +1003,129 @@ async fn import_deposit(
             .await
             .map_err(|e| (StatusCode::BAD_REQUEST, format!("read error: {}", e)))?;
         // Validate it's valid JSON
-        let _: serde_json::Value = serde_json::from_slice(&data)
+        let mut deposit: serde_json::Value = serde_json::from_slice(&data)
             .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid JSON: {}", e)))?;
+        normalize_note_amounts(&mut deposit);
+        let data = serde_json::to_vec_pretty(&deposit).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to serialize deposit: {}", e),
+            )
+        })?;
         let path = state.workspace.join(&filename);
-        std::fs::write(&path, &data).map_err(|e| {
+        state.write_perms.write(&path, &data).map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("write failed: {}", e),
             )
         })?;
-        let _ = state
-            .event_tx
-            .send(serde_json::json!({"type": "workspace:changed"}).to_string());
+        state.workspace_changed();
         return Ok(Json(serde_json::json!({ "filename": filename })));
     }
     Err((StatusCode::BAD_REQUEST, "no file uploaded".to_string()))
 }
 
+// ---------------------------------------------------------------------------
+// POST /api/deposits/import-bulk — import many deposits from one JSON array
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+struct BulkImportRequest {
+    deposits: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct BulkImportResponse {
+    results: Vec<BulkImportItem>,
+}
+
+/// Outcome for one entry of a bulk import; exactly one of `filename`/`error` is set.
+#[derive(Debug, Serialize)]
+struct BulkImportItem {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `POST /api/deposits/import-bulk` — validate and save each deposit in the array.
+///
+/// Invalid entries are reported per item and do not abort the batch.
+async fn import_deposits_bulk(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<BulkImportRequest>,
+) -> Json<BulkImportResponse> {
+    let results = import_bulk(&state.workspace, state.write_perms, &body.deposits);
+
+    if results.iter().any(|r| r.filename.is_some()) {
+        state.workspace_changed();
+    }
+
+    Json(BulkImportResponse { results })
+}
+
+fn import_bulk(
+    workspace: &std::path::Path,
+    perms: WritePerms,
+    deposits: &[serde_json::Value],
+) -> Vec<BulkImportItem> {
+    deposits
+        .iter()
+        .enumerate()
+        .map(
+            |(index, deposit)| match import_one(workspace, perms, deposit) {
+                Ok(filename) => BulkImportItem {
+                    index,
+                    filename: Some(filename),
+                    error: None,
+                },
+                Err(e) => BulkImportItem {
+                    index,
+                    filename: None,
+                    error: Some(format!("{:#}", e)),
+                },
+            },
+        )
+        .collect()
+}
+
+fn import_one(
+    workspace: &std::path::Path,
+    perms: WritePerms,
+    deposit: &serde_json::Value,
+) -> anyhow::Result<String> {
+    let mut deposit = deposit.clone();
+    normalize_note_amounts(&mut deposit);
+
+    let data = serde_json::to_vec_pretty(&deposit).context("failed to serialize deposit")?;
+    let target_address = validate_deposit(&data)?;
+    let filename = mining::deposit_filename(&target_address, &mining::timestamp_now());
+
+    // Never overwrite: a clash means the same deposit was already imported.
+    let mut file = perms
+        .create_new(&workspace.join(&filename))
+        .with_context(|| format!("failed to create {}", filename))?;
+    file.write_all(&data)
+        .with_context(|| format!("failed to write {}", filename))?;
+
+    Ok(filename)
+}
+
+/// Store hex note amounts as decimal, like deposits created by the server.
+/// Amounts that do not parse are left for validation to reject.
+fn normalize_note_amounts(deposit: &mut serde_json::Value) {
+    let Some(notes) = deposit.get_mut("notes").and_then(|n| n.as_array_mut()) else {
+        return;
+    };
+    for note in notes {
+        let amount = note.get("amount").and_then(|a| a.as_str()).map(parse_u128);
+        if let Some(Ok(amount)) = amount {
+            note["amount"] = amount.to_string().into();
+        }
+    }
+}
+
 /// `GET /api/tokens/:address` — query ERC20 token info (symbol, maxShadowMintAmount).
 async fn get_token_info(
     State(state): State<Arc<AppState>>,
@@ -766,14 +1165,529 @@ pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/deposits", get(list_deposits).post(create_deposit))
         .route("/deposits/import", post(import_deposit))
+        .route(
+            "/deposits/from-template/{name}",
+            post(create_deposit_from_template),
+        )
+        .route("/templates", post(create_template))
+        .route("/deposits/import-bulk", post(import_deposits_bulk))
         .route("/deposits/{id}", get(get_deposit).delete(delete_deposit))
         .route("/deposits/{id}/proof", delete(delete_proof))
         .route("/deposits/{id}/balance", get(get_deposit_balance))
         .route("/deposits/{id}/download", get(download_deposit))
         .route("/deposits/{id}/proof/download", get(download_proof))
+        .route("/deposits/{id}/verify-block", get(verify_proving_block))
         .route(
             "/deposits/{id}/notes/{note_index}/claim-tx",
             get(get_claim_tx),
         )
+        .route(
+            "/deposits/{id}/notes/{note_index}/export",
+            get(export_note_proof),
+        )
         .route("/tokens/{address}", get(get_token_info))
+        .route("/decode-claim-calldata", post(decode_claim_calldata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::{shadow_contract::DEFAULT_STATUS_CONCURRENCY, ChainClient};
+    use shadow_proof_core::{compute_notes_hash, compute_recipient_hash, derive_nullifier};
+
+    const DEPOSIT_STEM: &str = "deposit-ffe8-fde9-20260224T214613";
+    const PROVING_BLOCK_HASH: &str =
+        "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+    /// Serve a JSON-RPC endpoint that answers every call with `result`.
+    async fn mock_rpc(result: serde_json::Value) -> String {
+        let app = Router::new().route(
+            "/",
+            post(move || {
+                let result = result.clone();
+                async move { Json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": result})) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    /// Write a deposit and a proof bundle for note 0 proved at `PROVING_BLOCK_HASH`.
+    fn write_proved_deposit(workspace: &std::path::Path) {
+        write_proved_deposit_with_seal(workspace, "");
+    }
+
+    fn write_proved_deposit_with_seal(workspace: &std::path::Path, seal: &str) {
+        std::fs::write(
+            workspace.join(format!("{DEPOSIT_STEM}.json")),
+            deposit(1, "1000").to_string(),
+        )
+        .unwrap();
+        let bundled = BundledProof {
+            version: "v2".to_string(),
+            created: None,
+            circuit_id: None,
+            deposit_file: format!("{DEPOSIT_STEM}.json"),
+            block_number: "100".to_string(),
+            block_hash: PROVING_BLOCK_HASH.to_string(),
+            chain_id: "167013".to_string(),
+            notes: vec![NoteProofResult {
+                note_index: 0,
+                amount: "1000".to_string(),
+                recipient: "0x1111111111111111111111111111111111111111".to_string(),
+                nullifier: format!("0x{}", hex::encode(fixture_nullifier())),
+                seal: seal.to_string(),
+                journal: String::new(),
+                proof: "0xabcd".to_string(),
+                receipt_base64: None,
+                token: None,
+            }],
+        };
+        std::fs::write(
+            workspace.join(format!("{DEPOSIT_STEM}.proof-20260225T103000.json")),
+            serde_json::to_vec(&bundled).unwrap(),
+        )
+        .unwrap();
+    }
+
+    /// Nullifier of note 0 of `deposit(1, "1000")`.
+    fn fixture_nullifier() -> [u8; 32] {
+        let recipient_hash = compute_recipient_hash(&[0x11; 20]);
+        let notes_hash = compute_notes_hash(1, &[1000], &[recipient_hash]).unwrap();
+        derive_nullifier(&[1; 32], 167013, 0, &notes_hash)
+    }
+
+    fn deposit(secret_byte: u8, amount: &str) -> serde_json::Value {
+        serde_json::json!({
+            "version": "v2",
+            "chainId": "167013",
+            "secret": format!("0x{}", hex::encode([secret_byte; 32])),
+            "notes": [
+                { "recipient": "0x1111111111111111111111111111111111111111", "amount": amount }
+            ]
+        })
+    }
+
+    #[test]
+    fn bulk_import_reports_partial_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let deposits = vec![deposit(1, "1000"), deposit(2, "0"), deposit(3, "3000")];
+
+        let results = import_bulk(dir.path(), WritePerms::default(), &deposits);
+
+        assert_eq!(results.len(), 3);
+        let indices: Vec<usize> = results.iter().map(|r| r.index).collect();
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert!(results[0].filename.is_some() && results[0].error.is_none());
+        assert!(results[1].filename.is_none());
+        assert!(results[1].error.as_deref().unwrap().contains("non-zero"));
+        assert!(results[2].filename.is_some() && results[2].error.is_none());
+
+        for result in [&results[0], &results[2]] {
+            let path = dir.path().join(result.filename.as_ref().unwrap());
+            assert!(path.is_file());
+        }
+        let index = crate::workspace::scanner::scan_workspace(
+            dir.path(),
+            &Default::default(),
+            &Default::default(),
+        );
+        assert_eq!(index.deposits.len(), 2);
+    }
+
+    #[test]
+    fn imported_hex_amounts_are_stored_as_decimal() {
+        for (amount, stored) in [("0x3e8", "1000"), ("0X3E8", "1000"), ("1000", "1000")] {
+            let mut imported = deposit(1, amount);
+            normalize_note_amounts(&mut imported);
+            assert_eq!(imported["notes"][0]["amount"], stored);
+        }
+        // Signed amounts are not rewritten, so validation rejects them.
+        for amount in ["+1000", "0x+3e8"] {
+            let mut imported = deposit(1, amount);
+            normalize_note_amounts(&mut imported);
+            assert_eq!(imported["notes"][0]["amount"], amount);
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let results = import_bulk(dir.path(), WritePerms::default(), &[deposit(1, "+1000")]);
+        assert!(results[0].error.as_deref().unwrap().contains("sign"));
+    }
+
+    #[tokio::test]
+    async fn create_deposit_enforces_operator_note_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.max_notes = 2;
+
+        let note = || CreateDepositNote {
+            recipient: "0x1111111111111111111111111111111111111111".to_string(),
+            amount: "1000".to_string(),
+            label: None,
+        };
+        let body = CreateDepositRequest {
+            chain_id: "167013".to_string(),
+            notes: vec![note(), note(), note()],
+            comment: None,
+            token: None,
+        };
+
+        let (status, message) =
+            create_deposit(State(Arc::new(state)), Query::default(), Json(body))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("1-2 entries"), "{message}");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn concurrent_identical_creates_mine_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState::for_tests(dir.path().to_path_buf()));
+
+        let body = || CreateDepositRequest {
+            chain_id: "167013".to_string(),
+            notes: vec![CreateDepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".to_string(),
+                amount: "1000".to_string(),
+                label: None,
+            }],
+            comment: None,
+            token: None,
+        };
+        // The first create holds its guard while awaiting the blocking mine,
+        // so the second is polled against an in-flight entry.
+        let (first, second) = tokio::join!(
+            create_deposit(State(state.clone()), Query::default(), Json(body())),
+            create_deposit(State(state.clone()), Query::default(), Json(body())),
+        );
+
+        assert!(first.is_ok());
+        let (status, message) = second.unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(message.contains("already being created"), "{message}");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // The entry is cleared once the first create finishes.
+        assert!(create_deposit(State(state), Query::default(), Json(body()))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn same_notes_on_another_chain_or_token_mine_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState::for_tests(dir.path().to_path_buf()));
+
+        let body = |chain_id: &str, token: Option<&str>| CreateDepositRequest {
+            chain_id: chain_id.to_string(),
+            notes: vec![CreateDepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".to_string(),
+                amount: "1000".to_string(),
+                label: None,
+            }],
+            comment: None,
+            token: token.map(str::to_string),
+        };
+        let token = "0x2222222222222222222222222222222222222222";
+        let (taiko, mainnet, erc20) = tokio::join!(
+            create_deposit(
+                State(state.clone()),
+                Query::default(),
+                Json(body("167013", None))
+            ),
+            create_deposit(
+                State(state.clone()),
+                Query::default(),
+                Json(body("1", None))
+            ),
+            create_deposit(
+                State(state.clone()),
+                Query::default(),
+                Json(body("167013", Some(token)))
+            ),
+        );
+
+        assert!(taiko.is_ok());
+        assert!(mainnet.is_ok());
+        assert!(erc20.is_ok());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn auto_prove_enqueues_job_for_new_deposit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.rpc_url = Some("http://127.0.0.1:1".to_string());
+        let state = Arc::new(state);
+
+        let body = |amount: &str| CreateDepositRequest {
+            chain_id: "167013".to_string(),
+            notes: vec![CreateDepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".to_string(),
+                amount: amount.to_string(),
+                label: None,
+            }],
+            comment: None,
+            token: None,
+        };
+
+        let Json(plain) = create_deposit(State(state.clone()), Query::default(), Json(body("1")))
+            .await
+            .unwrap();
+        assert!(plain.proof_job.is_none());
+        assert!(state.proof_queue.start_next().await.is_none());
+
+        let query = CreateDepositQuery { auto_prove: true };
+        let Json(proved) = create_deposit(State(state.clone()), Query(query), Json(body("2")))
+            .await
+            .unwrap();
+        let ticket = proved.proof_job.unwrap();
+        assert!(ticket.accepted);
+        assert_eq!(format!("{}.json", ticket.deposit_id), proved.filename);
+        let (job, _) = state.proof_queue.start_next().await.unwrap();
+        assert_eq!(job.deposit_id, ticket.deposit_id);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn secure_perms_write_deposit_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.write_perms = WritePerms::new(true);
+
+        let body = CreateDepositRequest {
+            chain_id: "167013".to_string(),
+            notes: vec![CreateDepositNote {
+                recipient: "0x1111111111111111111111111111111111111111".to_string(),
+                amount: "1000".to_string(),
+                label: None,
+            }],
+            comment: None,
+            token: None,
+        };
+        let Json(response) = create_deposit(State(Arc::new(state)), Query::default(), Json(body))
+            .await
+            .unwrap();
+
+        let metadata = std::fs::metadata(dir.path().join(&response.filename)).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    }
+
+    #[tokio::test]
+    async fn deposit_from_template_pays_its_recipients() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = Arc::new(AppState::for_tests(dir.path().to_path_buf()));
+        let recipients = [
+            format!("0x{}", "11".repeat(20)),
+            format!("0x{}", "22".repeat(20)),
+        ];
+        let template: DepositTemplate = serde_json::from_value(serde_json::json!({
+            "name": "team",
+            "notes": [
+                {"recipient": recipients[0], "label": "alice"},
+                {"recipient": recipients[1]},
+            ],
+        }))
+        .unwrap();
+        let Json(saved) = create_template(State(state.clone()), Json(template.clone()))
+            .await
+            .unwrap();
+        assert_eq!(saved.notes.len(), 2);
+        let duplicate = create_template(State(state.clone()), Json(template)).await;
+        assert!(matches!(duplicate, Err((StatusCode::CONFLICT, _))));
+
+        let body = |amounts: &[&str]| FromTemplateRequest {
+            chain_id: "167013".to_string(),
+            amounts: amounts.iter().map(|a| a.to_string()).collect(),
+            comment: None,
+            token: None,
+        };
+        let from_team = |amounts: &[&str]| {
+            create_deposit_from_template(
+                State(state.clone()),
+                Path("team".to_string()),
+                Query::default(),
+                Json(body(amounts)),
+            )
+        };
+        let Json(response) = from_team(&["1000", "2000"]).await.unwrap();
+        assert!(matches!(
+            from_team(&["1000"]).await,
+            Err((StatusCode::BAD_REQUEST, _))
+        ));
+
+        let raw = std::fs::read(dir.path().join(&response.filename)).unwrap();
+        let deposit: serde_json::Value = serde_json::from_slice(&raw).unwrap();
+        let notes = deposit["notes"].as_array().unwrap();
+        assert_eq!(notes[0]["recipient"], recipients[0]);
+        assert_eq!(notes[0]["label"], "alice");
+        assert_eq!(notes[1]["recipient"], recipients[1]);
+        assert_eq!(notes[1]["amount"], "2000");
+        assert_eq!(response.total_amount, "3000");
+    }
+
+    #[tokio::test]
+    async fn claim_tx_flags_reorged_proving_block() {
+        let dir = tempfile::tempdir().unwrap();
+        write_proved_deposit(dir.path());
+
+        let rpc_url = mock_rpc(serde_json::json!({
+            "number": "0x64",
+            "hash": format!("0x{}", "99".repeat(32)),
+        }))
+        .await;
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.shadow_address = Some("0x77cdA0575e66A5FC95404fdA856615AD507d8A07".to_string());
+        state.chain_client = Some(ChainClient::new(
+            reqwest::Client::new(),
+            rpc_url,
+            DEFAULT_STATUS_CONCURRENCY,
+            Default::default(),
+        ));
+
+        let Json(response) =
+            get_claim_tx(State(Arc::new(state)), Path((DEPOSIT_STEM.to_string(), 0)))
+                .await
+                .unwrap();
+        assert_eq!(response.block_still_canonical, Some(false));
+        assert!(response.data.starts_with("0x"));
+    }
+
+    #[tokio::test]
+    async fn claim_tx_confirms_canonical_proving_block() {
+        let dir = tempfile::tempdir().unwrap();
+        write_proved_deposit(dir.path());
+
+        let rpc_url = mock_rpc(serde_json::json!({
+            "number": "0x64",
+            "hash": PROVING_BLOCK_HASH,
+        }))
+        .await;
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.shadow_address = Some("0x77cdA0575e66A5FC95404fdA856615AD507d8A07".to_string());
+        state.chain_client = Some(ChainClient::new(
+            reqwest::Client::new(),
+            rpc_url,
+            DEFAULT_STATUS_CONCURRENCY,
+            Default::default(),
+        ));
+
+        let Json(response) =
+            get_claim_tx(State(Arc::new(state)), Path((DEPOSIT_STEM.to_string(), 0)))
+                .await
+                .unwrap();
+        assert_eq!(response.block_still_canonical, Some(true));
+    }
+
+    #[tokio::test]
+    async fn claim_tx_rejects_a_tampered_nullifier() {
+        let dir = tempfile::tempdir().unwrap();
+        write_proved_deposit(dir.path());
+        let proof_path = dir
+            .path()
+            .join(format!("{DEPOSIT_STEM}.proof-20260225T103000.json"));
+        let mut bundled: BundledProof =
+            serde_json::from_slice(&std::fs::read(&proof_path).unwrap()).unwrap();
+        bundled.notes[0].nullifier = format!("0x{}", "22".repeat(32));
+        std::fs::write(&proof_path, serde_json::to_vec(&bundled).unwrap()).unwrap();
+
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.shadow_address = Some("0x77cdA0575e66A5FC95404fdA856615AD507d8A07".to_string());
+        let (status, message) =
+            get_claim_tx(State(Arc::new(state)), Path((DEPOSIT_STEM.to_string(), 0)))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(message.contains("nullifier"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn verify_block_flags_a_reorg_after_proving() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        write_proved_deposit(dir.path());
+
+        // The first lookup sees the proving block; later ones see a reorg.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = Router::new().route(
+            "/",
+            post(move || async move {
+                let hash = match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => PROVING_BLOCK_HASH.to_string(),
+                    _ => format!("0x{}", "99".repeat(32)),
+                };
+                Json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": {"number": "0x64", "hash": hash},
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.chain_client = Some(ChainClient::new(
+            reqwest::Client::new(),
+            rpc_url,
+            DEFAULT_STATUS_CONCURRENCY,
+            Default::default(),
+        ));
+        let state = Arc::new(state);
+        let verify = || verify_proving_block(State(state.clone()), Path(DEPOSIT_STEM.to_string()));
+
+        let Json(before) = verify().await.unwrap();
+        assert!(!before.block_stale);
+        assert_eq!(before.block_number, 100);
+
+        let Json(after) = verify().await.unwrap();
+        assert!(after.block_stale);
+        assert_eq!(after.block_hash, PROVING_BLOCK_HASH);
+        assert_eq!(after.canonical_hash, Some(format!("0x{}", "99".repeat(32))));
+    }
+
+    #[tokio::test]
+    async fn export_returns_the_stored_seal_and_journal() {
+        let dirs: Vec<_> = (0..2).map(|_| tempfile::tempdir().unwrap()).collect();
+        let seal = format!("0x{}", "ab".repeat(GROTH16_SEAL_LEN));
+        write_proved_deposit_with_seal(dirs[0].path(), &seal);
+        write_proved_deposit(dirs[1].path());
+
+        let mut bases = Vec::new();
+        for dir in &dirs {
+            let state = Arc::new(AppState::for_tests(dir.path().to_path_buf()));
+            let app = Router::new().nest("/api", crate::routes::api_router(state));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            bases.push(format!("http://{}", listener.local_addr().unwrap()));
+            tokio::spawn(async move { axum::serve(listener, app).await });
+        }
+        let export = |base: &str, note: u32| {
+            reqwest::get(format!(
+                "{base}/api/deposits/{DEPOSIT_STEM}/notes/{note}/export"
+            ))
+        };
+
+        let response = export(&bases[0], 0).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["receipt_kind"], "groth16");
+        assert_eq!(body["seal_hex"], seal);
+        assert_eq!(body["proof_hex"], "0xabcd");
+        assert_eq!(export(&bases[0], 1).await.unwrap().status(), 404);
+
+        let unproven = export(&bases[1], 0).await.unwrap();
+        assert_eq!(unproven.status(), StatusCode::BAD_REQUEST);
+        assert!(unproven
+            .text()
+            .await
+            .unwrap()
+            .contains("without the prove feature"));
+    }
 }
@@ -2,6 +2,10 @@ mod config_routes;
 mod deposits;
 mod health;
 mod proofs;
+mod receipts;
+pub mod request_id;
+#[cfg(feature = "prove")]
+mod selftest;
 pub mod ws;
 
 use std::sync::Arc;
@@ -12,10 +16,15 @@ use crate::state::AppState;
 
 /// Build the `/api` sub-router with all API routes.
 pub fn api_router(state: Arc<AppState>) -> Router {
-    Router::new()
+    let router = Router::new()
         .merge(health::router())
         .merge(deposits::router())
         .merge(proofs::router())
-        .merge(config_routes::router())
-        .with_state(state)
+        .merge(receipts::router())
+        .merge(config_routes::router());
+
+    #[cfg(feature = "prove")]
+    let router = router.merge(selftest::router());
+
+    router.with_state(state)
 }
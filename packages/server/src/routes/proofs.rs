@@ -1,42 +1,70 @@
-use std::sync::Arc;
+use std::{convert::Infallible, sync::Arc};
 
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use shadow_proof_core::{evaluate_claim, ClaimInput};
+use tokio::sync::broadcast;
 
 use crate::{
-    prover::{pipeline, queue::ProofJob},
+    prover::{
+        pipeline,
+        queue::{JobStatus, QueueSnapshot},
+        ProofQueue,
+    },
     state::AppState,
-    workspace::scanner::scan_workspace,
 };
 
 #[derive(Debug, Deserialize)]
 struct ProveQuery {
     #[serde(default)]
     force: bool,
+    /// Scheduling priority; higher values start first (default 0).
+    #[serde(default)]
+    priority: u8,
+    /// Stream the job's progress as NDJSON instead of returning immediately.
+    #[serde(default)]
+    stream: bool,
+    /// Keep notes already proved by an interrupted run of this deposit.
+    #[serde(default)]
+    resume: bool,
 }
 
 /// `POST /api/deposits/:id/prove` — queue proof generation for a deposit.
+///
+/// The background worker starts the job once it reaches the head of the queue.
+/// Responds with the job's queue position and estimated wait, or `409` with
+/// `accepted: false` when the deposit already has a job.
+/// With `?stream=true` the response stays open and carries one JSON event per
+/// line until the job finishes; closing the connection cancels the job.
+/// With `?resume=true` notes checkpointed by an interrupted run are kept.
 async fn start_proof(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Query(query): Query<ProveQuery>,
-) -> Result<Json<ProofJob>, (StatusCode, String)> {
-    let rpc_url = state
-        .rpc_url
-        .as_ref()
-        .ok_or((
+) -> Result<Response, (StatusCode, String)> {
+    if state.rpc_url.is_none() {
+        return Err((
             StatusCode::BAD_REQUEST,
             "RPC URL not configured; start server with --rpc-url".to_string(),
-        ))?
-        .clone();
+        ));
+    }
+
+    if state.proof_queue.is_closed() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "server is shutting down".to_string(),
+        ));
+    }
 
     // Find the deposit
-    let index = scan_workspace(&state.workspace);
+    let index = state.workspace_index();
     let deposit = index
         .deposits
         .iter()
@@ -59,107 +87,112 @@ async fn start_proof(
         }
     }
 
+    // Subscribe before enqueueing so the stream cannot miss early events.
+    let events = state.event_tx.subscribe();
     let note_count = deposit.note_count as u32;
-    let deposit_filename = deposit.filename.clone();
-    let deposit_id = deposit.id.clone();
-    // Capture existing proof filename before spawn (will be renamed to .bkup on success)
-    let existing_proof = deposit.proof_file.clone();
-
-    // Enqueue
-    state
+    let ticket = state
         .proof_queue
-        .enqueue(&deposit_id, note_count)
-        .await
-        .map_err(|e| (StatusCode::CONFLICT, e))?;
-
-    // Spawn the proof pipeline
-    let queue = state.proof_queue.clone();
-    let workspace = state.workspace.clone();
-    let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel();
-    queue.set_cancel_tx(cancel_tx).await;
-
-    let event_tx = state.event_tx.clone();
-
-    tokio::spawn(async move {
-        let prove_start = std::time::Instant::now();
-        match pipeline::run_pipeline(
-            &workspace,
-            &deposit_filename,
-            &rpc_url,
-            queue.clone(),
-            cancel_rx,
-        )
-        .await
-        {
-            Ok(bundled) => {
-                // Rename any existing proof file to .bkup before writing the new one
-                if let Some(ref old_proof) = existing_proof {
-                    let old_path = workspace.join(old_proof);
-                    if old_path.is_file() {
-                        let bkup_path = old_path.with_extension("bkup");
-                        if let Err(e) = std::fs::rename(&old_path, &bkup_path) {
-                            tracing::warn!(error = %e, file = %old_proof, "failed to rename old proof to .bkup");
-                        } else {
-                            tracing::info!(file = %old_proof, "renamed old proof to .bkup");
-                        }
-                    }
-                }
+        .enqueue(&deposit.id, note_count, query.priority, query.resume)
+        .await;
 
-                // Write proof file
-                let deposit_stem = deposit_filename
-                    .strip_suffix(".json")
-                    .unwrap_or(&deposit_filename);
-                let proof_ts = timestamp_now();
-                let proof_filename = format!("{}.proof-{}.json", deposit_stem, proof_ts);
-                let proof_path = workspace.join(&proof_filename);
-                let mut bundled = bundled;
-                bundled.created = Some(proof_ts.clone());
-                #[cfg(feature = "prove")]
-                {
-                    bundled.circuit_id = Some(shadow_prover_lib::circuit_id_hex());
-                }
+    if !ticket.accepted {
+        return Ok((StatusCode::CONFLICT, Json(ticket)).into_response());
+    }
+    if !query.stream {
+        return Ok(Json(ticket).into_response());
+    }
 
-                match serde_json::to_vec_pretty(&bundled) {
-                    Ok(json_bytes) => {
-                        if let Err(e) = std::fs::write(&proof_path, json_bytes) {
-                            tracing::error!(error = %e, "failed to write proof file");
-                            queue
-                                .fail(0, &format!("failed to write proof file: {:#}", e))
-                                .await;
-                            return;
-                        }
-                        tracing::info!(file = %proof_filename, "proof file written");
-                        queue
-                            .complete(&proof_filename, Some(prove_start.elapsed().as_secs_f64()))
-                            .await;
-
-                        let _ = event_tx
-                            .send(serde_json::json!({"type": "workspace:changed"}).to_string());
-                    }
-                    Err(e) => {
-                        tracing::error!(error = %e, "failed to serialize proof");
-                        queue
-                            .fail(0, &format!("serialization error: {:#}", e))
-                            .await;
-                    }
+    let guard = CancelOnDisconnect {
+        queue: state.proof_queue.clone(),
+        deposit_id: ticket.deposit_id,
+        finished: false,
+    };
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(progress_stream(events, guard)),
+    )
+        .into_response())
+}
+
+/// Cancels the job when dropped before it finished, i.e. when the client
+/// disconnects from a streaming prove request.
+struct CancelOnDisconnect {
+    queue: Arc<ProofQueue>,
+    deposit_id: String,
+    finished: bool,
+}
+
+impl Drop for CancelOnDisconnect {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let queue = self.queue.clone();
+        let deposit_id = std::mem::take(&mut self.deposit_id);
+        tokio::spawn(async move {
+            if queue.cancel_deposit(&deposit_id).await {
+                tracing::info!(deposit_id = %deposit_id, "stream closed, proof job cancelled");
+            }
+        });
+    }
+}
+
+/// Queue events for the guarded deposit, one per line, ending with the
+/// job's terminal event.
+fn progress_stream(
+    events: broadcast::Receiver<String>,
+    guard: CancelOnDisconnect,
+) -> impl futures_util::Stream<Item = Result<String, Infallible>> {
+    futures_util::stream::unfold(Some((events, guard)), |state| async move {
+        let (mut events, mut guard) = state?;
+        loop {
+            let msg = match events.recv().await {
+                Ok(msg) => msg,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!(skipped = n, "proof stream lagged, skipping events");
+                    continue;
                 }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(&msg) else {
+                continue;
+            };
+            if event["depositId"].as_str() != Some(guard.deposit_id.as_str()) {
+                continue;
             }
-            Err(e) => {
-                // Use {:#} to include the full anyhow cause chain (e.g. RISC Zero panic message)
-                let detail = format!("{:#}", e);
-                tracing::error!(error = %detail, deposit = %deposit_id, "proof pipeline failed");
-                queue.fail(0, &detail).await;
+            let line = format!("{}\n", msg);
+            if matches!(
+                event["type"].as_str(),
+                Some("proof:completed" | "proof:failed" | "proof:cancelled")
+            ) {
+                guard.finished = true;
+                return Some((Ok(line), None));
             }
+            return Some((Ok(line), Some((events, guard))));
         }
-    });
+    })
+}
 
-    let job = state.proof_queue.status().await.unwrap();
-    Ok(Json(job))
+/// `GET /api/queue` — the current job and the pending ones in start order.
+async fn queue_status(State(state): State<Arc<AppState>>) -> Json<QueueSnapshot> {
+    Json(state.proof_queue.snapshot().await)
 }
 
-/// `GET /api/queue` — get queue status.
-async fn queue_status(State(state): State<Arc<AppState>>) -> Json<Option<ProofJob>> {
-    Json(state.proof_queue.status().await)
+/// `DELETE /api/queue/:id` — cancel the deposit's job, running or pending.
+async fn cancel_deposit_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<CancelResponse>, (StatusCode, String)> {
+    if !state.proof_queue.cancel_deposit(&id).await {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("deposit {} has no queued or running job", id),
+        ));
+    }
+    Ok(Json(CancelResponse {
+        cancelled: true,
+        message: "proof job cancelled".to_string(),
+    }))
 }
 
 /// `DELETE /api/queue/current` — cancel or clear the current proof job.
@@ -179,7 +212,106 @@ async fn cancel_job(State(state): State<Arc<AppState>>) -> Json<CancelResponse>
     }
 }
 
+/// `DELETE /api/queue` — cancel the running job and drop all queued jobs.
+async fn flush_queue(State(state): State<Arc<AppState>>) -> Json<FlushResponse> {
+    Json(FlushResponse {
+        cancelled: state.proof_queue.flush().await,
+    })
+}
+
+/// Everything needed to replay one note's proof outside the server. The
+/// flattened `ClaimInput` fields make the file a valid `inspect`/`prove` input.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReproBundle {
+    deposit_id: String,
+    /// Error from the last proof job for this deposit, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job_error: Option<String>,
+    /// Error `evaluate_claim` reports for the rebuilt input, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evaluation_error: Option<String>,
+    #[serde(flatten)]
+    input: ClaimInput,
+}
+
+/// `GET /api/deposits/:id/notes/:noteIndex/claim-input` — the `ClaimInput`
+/// the pipeline would prove for one note, secret included. Requires
+/// `--serve-claim-inputs`.
+async fn get_claim_input(
+    State(state): State<Arc<AppState>>,
+    Path((id, note_index)): Path<(String, u32)>,
+) -> Result<Json<ClaimInput>, (StatusCode, String)> {
+    note_claim_input(&state, &id, note_index).await.map(Json)
+}
+
+/// `GET /api/deposits/:id/notes/:noteIndex/repro-bundle` — export the claim
+/// input for one note with the last job error. Requires `--serve-claim-inputs`.
+async fn get_repro_bundle(
+    State(state): State<Arc<AppState>>,
+    Path((id, note_index)): Path<(String, u32)>,
+) -> Result<Json<ReproBundle>, (StatusCode, String)> {
+    let input = note_claim_input(&state, &id, note_index).await?;
+    let job_error = state
+        .proof_queue
+        .status()
+        .await
+        .filter(|job| job.deposit_id == id && job.status == JobStatus::Failed)
+        .and_then(|job| job.error);
+    let evaluation_error = evaluate_claim(&input).err().map(|e| e.as_str().to_string());
+
+    Ok(Json(ReproBundle {
+        deposit_id: id,
+        job_error,
+        evaluation_error,
+        input,
+    }))
+}
+
+/// Build one note's `ClaimInput` from fresh chain data, if the operator
+/// allows claim inputs (which carry the deposit secret) to be served.
+async fn note_claim_input(
+    state: &AppState,
+    id: &str,
+    note_index: u32,
+) -> Result<ClaimInput, (StatusCode, String)> {
+    if !state.serve_claim_inputs {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "claim inputs contain the deposit secret; start server with --serve-claim-inputs"
+                .to_string(),
+        ));
+    }
+    let Some(ref rpc_url) = state.rpc_url else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "RPC URL not configured; start server with --rpc-url".to_string(),
+        ));
+    };
+
+    let index = state.workspace_index();
+    let deposit = index
+        .deposits
+        .iter()
+        .find(|d| d.id == id)
+        .ok_or((StatusCode::NOT_FOUND, format!("deposit {} not found", id)))?;
+
+    pipeline::build_note_claim_input(
+        &state.workspace,
+        &deposit.filename,
+        &state.pipeline_config(rpc_url),
+        note_index,
+    )
+    .await
+    .map_err(|e| (StatusCode::BAD_GATEWAY, format!("{:#}", e)))
+}
+
 #[derive(Serialize)]
+struct FlushResponse {
+    cancelled: usize,
+}
+
+#[derive(Debug, Serialize)]
 struct CancelResponse {
     cancelled: bool,
     message: String,
@@ -188,42 +320,393 @@ struct CancelResponse {
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/deposits/{id}/prove", post(start_proof))
-        .route("/queue", get(queue_status))
+        .route(
+            "/deposits/{id}/notes/{note_index}/claim-input",
+            get(get_claim_input),
+        )
+        .route(
+            "/deposits/{id}/notes/{note_index}/repro-bundle",
+            get(get_repro_bundle),
+        )
+        .route("/queue", get(queue_status).delete(flush_queue))
         .route("/queue/current", delete(cancel_job))
+        .route("/queue/{id}", delete(cancel_deposit_job))
 }
 
-fn timestamp_now() -> String {
-    use std::time::SystemTime;
-    let secs = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-
-    // Manual UTC formatting: YYYYMMDDTHHMMSS
-    let days = secs / 86400;
-    let tod = secs % 86400;
-    let (y, m, d) = civil_from_days(days as i64);
-    format!(
-        "{:04}{:02}{:02}T{:02}{:02}{:02}",
-        y,
-        m,
-        d,
-        tod / 3600,
-        (tod % 3600) / 60,
-        tod % 60
-    )
-}
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::prover::worker;
+    use shadow_proof_core::{
+        compute_notes_hash, compute_recipient_hash, derive_target_address,
+        test_support::build_single_account_trie,
+    };
+
+    const SECRET: [u8; 32] = [0x42; 32];
+    const CHAIN_ID: u64 = 167013;
+    const AMOUNTS: [u128; 2] = [1_000_000_000_000, 2_000_000_000_000];
+    const RECIPIENT: [u8; 20] = [0x11; 20];
+
+    fn zero_hex(len: usize) -> String {
+        format!("0x{}", "00".repeat(len))
+    }
+
+    /// JSON-RPC node whose state holds only the deposit's funded target address.
+    async fn mock_rpc(target: [u8; 20]) -> String {
+        counting_mock_rpc(target).await.0
+    }
+
+    /// `mock_rpc` that also counts `eth_getBlockByNumber` calls.
+    async fn counting_mock_rpc(target: [u8; 20]) -> (String, Arc<AtomicUsize>) {
+        let (leaf, state_root) = build_single_account_trie(&target, AMOUNTS.iter().sum());
+        let block = mock_block(state_root, 1);
+        let proof = serde_json::json!({
+            "balance": format!("0x{:x}", AMOUNTS.iter().sum::<u128>()),
+            "accountProof": [format!("0x{}", hex::encode(leaf))],
+        });
+
+        let block_fetches = Arc::new(AtomicUsize::new(0));
+        let counter = block_fetches.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(req): Json<serde_json::Value>| {
+                let result = match req["method"].as_str() {
+                    Some("eth_chainId") => serde_json::json!(format!("0x{:x}", CHAIN_ID)),
+                    Some("eth_blockNumber") => serde_json::json!("0x1"),
+                    Some("eth_getBlockByNumber") => {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        block.clone()
+                    }
+                    Some("eth_getProof") => proof.clone(),
+                    _ => serde_json::Value::Null,
+                };
+                async move { Json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": result})) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), block_fetches)
+    }
+
+    fn mock_block(state_root: [u8; 32], number: u64) -> serde_json::Value {
+        serde_json::json!({
+            "parentHash": zero_hex(32),
+            "sha3Uncles": zero_hex(32),
+            "miner": zero_hex(20),
+            "stateRoot": format!("0x{}", hex::encode(state_root)),
+            "transactionsRoot": zero_hex(32),
+            "receiptsRoot": zero_hex(32),
+            "logsBloom": zero_hex(256),
+            "difficulty": "0x0",
+            "number": format!("0x{number:x}"),
+            "gasLimit": "0x1",
+            "gasUsed": "0x0",
+            "timestamp": "0x1",
+            "extraData": "0x",
+            "mixHash": zero_hex(32),
+            "nonce": zero_hex(8),
+            "baseFeePerGas": "0x1",
+            "withdrawalsRoot": zero_hex(32),
+        })
+    }
+
+    /// Node whose head advances on every `eth_blockNumber`, with the target's
+    /// balance (and so the state root) changing each block. `eth_getProof`
+    /// answers for the requested block, or for a newer head when
+    /// `proofs_at_head` is set.
+    async fn advancing_mock_rpc(target: [u8; 20], proofs_at_head: bool) -> String {
+        let head = Arc::new(AtomicUsize::new(0));
+        let account = move |number: u64| {
+            let balance = AMOUNTS.iter().sum::<u128>() + number as u128;
+            (balance, build_single_account_trie(&target, balance))
+        };
+        let app = Router::new().route(
+            "/",
+            post(move |Json(req): Json<serde_json::Value>| {
+                let number = |tag: &serde_json::Value| {
+                    u64::from_str_radix(tag.as_str().unwrap().trim_start_matches("0x"), 16)
+                        .unwrap()
+                };
+                let result = match req["method"].as_str() {
+                    Some("eth_chainId") => serde_json::json!(format!("0x{:x}", CHAIN_ID)),
+                    Some("eth_blockNumber") => {
+                        let latest = head.fetch_add(1, Ordering::SeqCst) + 1;
+                        serde_json::json!(format!("0x{latest:x}"))
+                    }
+                    Some("eth_getBlockByNumber") => {
+                        let n = number(&req["params"][0]);
+                        mock_block(account(n).1 .1, n)
+                    }
+                    Some("eth_getProof") => {
+                        let n = match proofs_at_head {
+                            true => head.fetch_add(1, Ordering::SeqCst) as u64 + 1,
+                            false => number(&req["params"][2]),
+                        };
+                        let (balance, (leaf, _)) = account(n);
+                        serde_json::json!({
+                            "balance": format!("0x{balance:x}"),
+                            "accountProof": [format!("0x{}", hex::encode(leaf))],
+                        })
+                    }
+                    _ => serde_json::Value::Null,
+                };
+                async move { Json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": result})) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    /// Write the test deposit into `workspace` and return its id and target address.
+    fn write_deposit(workspace: &std::path::Path) -> (String, [u8; 20]) {
+        let recipient_hashes = vec![compute_recipient_hash(&RECIPIENT); AMOUNTS.len()];
+        let notes_hash = compute_notes_hash(AMOUNTS.len(), &AMOUNTS, &recipient_hashes).unwrap();
+        let target = derive_target_address(&SECRET, CHAIN_ID, &notes_hash);
+
+        let deposit = serde_json::json!({
+            "version": "v2",
+            "chainId": CHAIN_ID.to_string(),
+            "secret": format!("0x{}", hex::encode(SECRET)),
+            "notes": AMOUNTS.iter().map(|amount| serde_json::json!({
+                "recipient": format!("0x{}", hex::encode(RECIPIENT)),
+                "amount": amount.to_string(),
+            })).collect::<Vec<_>>(),
+        });
+        let filename = crate::mining::deposit_filename(&target, "20260224T214613");
+        std::fs::write(workspace.join(&filename), deposit.to_string()).unwrap();
+        (filename.trim_end_matches(".json").to_string(), target)
+    }
+
+    #[tokio::test]
+    async fn streaming_prove_emits_progress_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let (id, target) = write_deposit(dir.path());
+
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.rpc_url = Some(mock_rpc(target).await);
+        let state = Arc::new(state);
+        tokio::spawn(worker::run(state.clone()));
+
+        let query = ProveQuery {
+            force: false,
+            priority: 0,
+            stream: true,
+            resume: false,
+        };
+        let response = start_proof(State(state), Path(id), Query(query))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let events: Vec<serde_json::Value> = std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let progress = events
+            .iter()
+            .filter(|e| e["type"] == "proof:note_progress")
+            .count();
+        assert!(progress >= AMOUNTS.len() * 2, "{events:?}");
+        assert!(events.iter().any(|e| e["noteIndex"] == 1));
+        assert_eq!(events.last().unwrap()["type"], "proof:completed");
+    }
+
+    #[tokio::test]
+    async fn claim_input_matches_requested_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let (id, target) = write_deposit(dir.path());
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.rpc_url = Some(mock_rpc(target).await);
+        state.serve_claim_inputs = true;
+
+        let Json(input) = get_claim_input(State(Arc::new(state)), Path((id, 1)))
+            .await
+            .unwrap();
+        assert_eq!(input.note_index, 1);
+        assert_eq!(input.amount, AMOUNTS[1]);
+        assert!(!input.proof_nodes.is_empty());
+    }
 
-fn civil_from_days(days: i64) -> (i32, u32, u32) {
-    let z = days + 719468;
-    let era = if z >= 0 { z } else { z - 146096 } / 146097;
-    let doe = (z - era * 146097) as u32;
-    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
-    let y = yoe as i64 + era * 400;
-    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
-    let mp = (5 * doy + 2) / 153;
-    let d = doy - (153 * mp + 2) / 5 + 1;
-    let m = if mp < 10 { mp + 3 } else { mp - 9 };
-    let y = if m <= 2 { y + 1 } else { y };
-    (y as i32, m, d)
+    #[tokio::test]
+    async fn runs_at_the_same_block_share_one_block_fetch() {
+        let dir = tempfile::tempdir().unwrap();
+        let (id, target) = write_deposit(dir.path());
+        let (rpc_url, block_fetches) = counting_mock_rpc(target).await;
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.rpc_url = Some(rpc_url);
+        state.serve_claim_inputs = true;
+        let state = Arc::new(state);
+
+        for note_index in 0..2 {
+            let path = Path((id.clone(), note_index));
+            let Json(input) = get_claim_input(State(state.clone()), path).await.unwrap();
+            assert_eq!(input.block_number, 1);
+        }
+        assert_eq!(block_fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn account_proof_stays_pinned_to_the_fetched_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let (id, target) = write_deposit(dir.path());
+        let claim_input = |proofs_at_head| {
+            let id = id.clone();
+            let workspace = dir.path().to_path_buf();
+            async move {
+                let mut state = AppState::for_tests(workspace);
+                state.rpc_url = Some(advancing_mock_rpc(target, proofs_at_head).await);
+                state.serve_claim_inputs = true;
+                get_claim_input(State(Arc::new(state)), Path((id, 0))).await
+            }
+        };
+
+        let Json(input) = claim_input(false).await.unwrap();
+        assert_eq!(input.block_number, 1);
+        assert!(evaluate_claim(&input).is_ok());
+
+        let (status, message) = claim_input(true).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert!(
+            message.contains("does not match the state root of block 1"),
+            "{message}"
+        );
+    }
+
+    #[tokio::test]
+    async fn parallel_proving_keeps_note_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let (id, target) = write_deposit(dir.path());
+        let rpc_url = mock_rpc(target).await;
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.prove_concurrency = AMOUNTS.len();
+
+        let (_cancel_tx, cancel_rx) = tokio::sync::oneshot::channel();
+        let bundled = crate::prover::pipeline::run_pipeline(
+            dir.path(),
+            &format!("{id}.json"),
+            &state.pipeline_config(&rpc_url),
+            state.proof_queue.clone(),
+            cancel_rx,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let indices: Vec<u32> = bundled.notes.iter().map(|n| n.note_index).collect();
+        assert_eq!(indices, [0, 1]);
+        assert_eq!(bundled.notes[1].amount, AMOUNTS[1].to_string());
+    }
+
+    #[tokio::test]
+    async fn resume_only_reproves_notes_missing_from_the_partial() {
+        let dir = tempfile::tempdir().unwrap();
+        let (id, target) = write_deposit(dir.path());
+        let rpc_url = mock_rpc(target).await;
+        let state = AppState::for_tests(dir.path().to_path_buf());
+        let deposit_file = format!("{id}.json");
+        let config = state.pipeline_config(&rpc_url);
+        // Dropping the cancel sender cancels the run, so callers keep it alive.
+        let run = |resume, cancel_rx| {
+            pipeline::run_pipeline(
+                dir.path(),
+                &deposit_file,
+                &config,
+                state.proof_queue.clone(),
+                cancel_rx,
+                resume,
+            )
+        };
+        let (_cancel_tx, cancel_rx) = tokio::sync::oneshot::channel();
+        run(false, cancel_rx).await.unwrap();
+
+        // Simulate a crash after note 0: keep it, marked, and drop note 1.
+        let partial_path = pipeline::partial_path(dir.path(), &deposit_file);
+        let mut partial: pipeline::BundledProof =
+            serde_json::from_slice(&std::fs::read(&partial_path).unwrap()).unwrap();
+        assert_eq!(partial.notes.len(), AMOUNTS.len());
+        partial.notes.truncate(1);
+        partial.notes[0].seal = "0x5ea1".to_string();
+        std::fs::write(&partial_path, serde_json::to_vec(&partial).unwrap()).unwrap();
+
+        state.proof_queue.enqueue(&id, 2, 0, true).await;
+        let (_, cancel_rx) = state.proof_queue.start_next().await.unwrap();
+        let mut events = state.event_tx.subscribe();
+        let bundled = run(true, cancel_rx).await.unwrap();
+        assert_eq!(bundled.notes.len(), AMOUNTS.len());
+        assert_eq!(bundled.notes[0].seal, "0x5ea1");
+        assert_eq!(bundled.notes[1].note_index, 1);
+
+        let mut proved = Vec::new();
+        while let Ok(msg) = events.try_recv() {
+            let event: serde_json::Value = serde_json::from_str(&msg).unwrap();
+            if event["stage"] == "proving" {
+                proved.push(event["noteIndex"].as_u64().unwrap());
+            }
+        }
+        assert_eq!(proved, [1]);
+    }
+
+    #[tokio::test]
+    async fn repro_bundle_round_trips_to_valid_claim_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let (id, target) = write_deposit(dir.path());
+        let state = Arc::new(AppState::for_tests(dir.path().to_path_buf()));
+        let err = get_repro_bundle(State(state), Path((id.clone(), 1)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let mut state = AppState::for_tests(dir.path().to_path_buf());
+        state.rpc_url = Some(mock_rpc(target).await);
+        state.serve_claim_inputs = true;
+        let Json(bundle) = get_repro_bundle(State(Arc::new(state)), Path((id, 1)))
+            .await
+            .unwrap();
+        assert!(bundle.evaluation_error.is_none());
+
+        let json = serde_json::to_vec(&bundle).unwrap();
+        let input: ClaimInput = serde_json::from_slice(&json).unwrap();
+        assert_eq!(input.note_index, 1);
+        assert_eq!(input.secret, SECRET);
+        let journal = evaluate_claim(&input).unwrap();
+        assert_eq!(journal.amount, AMOUNTS[1]);
+    }
+
+    #[tokio::test]
+    async fn queue_lists_jobs_and_cancels_by_deposit() {
+        let state = Arc::new(AppState::for_tests(std::env::temp_dir()));
+        let queue = &state.proof_queue;
+        for id in ["deposit-a", "deposit-b", "deposit-c"] {
+            queue.enqueue(id, 1, 0, false).await;
+        }
+        queue.start_next().await.unwrap();
+
+        let cancel = |id: &str| cancel_deposit_job(State(state.clone()), Path(id.to_string()));
+        assert!(cancel("deposit-b").await.unwrap().cancelled);
+        assert_eq!(
+            cancel("deposit-b").await.unwrap_err().0,
+            StatusCode::NOT_FOUND
+        );
+
+        let Json(snapshot) = queue_status(State(state.clone())).await;
+        assert_eq!(snapshot.current.unwrap().deposit_id, "deposit-a");
+        let pending: Vec<_> = snapshot
+            .pending
+            .iter()
+            .map(|j| j.deposit_id.as_str())
+            .collect();
+        assert_eq!(pending, ["deposit-c"]);
+    }
 }
@@ -0,0 +1,257 @@
+use std::sync::Arc;
+
+use axum::{extract::DefaultBodyLimit, routing::post, Router};
+
+use crate::state::AppState;
+
+/// Largest receipt upload accepted, as sent (multipart file or base64 text).
+/// Composite receipts run to a few MiB; Groth16 receipts are far smaller.
+pub const MAX_RECEIPT_UPLOAD_BYTES: usize = 16 * 1024 * 1024;
+
+#[cfg(feature = "prove")]
+mod verify {
+    use axum::{
+        body::to_bytes,
+        extract::{FromRequest, Multipart, Request},
+        http::{header, StatusCode},
+        Json,
+    };
+    use serde::Serialize;
+    use shadow_proof_core::ClaimJournal;
+    use shadow_prover_lib::{
+        circuit_id_hex, describe_receipt_kind, deserialize_receipt_auto, image_id_hex,
+        receipt_claimed_image_id, verify_receipt,
+    };
+
+    use super::MAX_RECEIPT_UPLOAD_BYTES;
+    use crate::prover::pipeline::base64_decode;
+
+    /// Result of checking an uploaded receipt; `journal` is set only when it verifies.
+    #[derive(Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ReceiptVerification {
+        receipt_kind: &'static str,
+        circuit_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        receipt_image_id: Option<String>,
+        matches_circuit: bool,
+        verified: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        journal: Option<JournalView>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    }
+
+    /// `ClaimJournal` with hex-encoded byte fields.
+    #[derive(Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct JournalView {
+        block_number: u64,
+        block_hash: String,
+        chain_id: u64,
+        amount: String,
+        recipient: String,
+        nullifier: String,
+        token: String,
+    }
+
+    impl From<ClaimJournal> for JournalView {
+        fn from(journal: ClaimJournal) -> Self {
+            Self {
+                block_number: journal.block_number,
+                block_hash: format!("0x{}", hex::encode(journal.block_hash)),
+                chain_id: journal.chain_id,
+                amount: journal.amount.to_string(),
+                recipient: format!("0x{}", hex::encode(journal.recipient)),
+                nullifier: format!("0x{}", hex::encode(journal.nullifier)),
+                token: format!("0x{}", hex::encode(journal.token)),
+            }
+        }
+    }
+
+    /// `POST /api/receipts/verify` — verify an uploaded receipt against the
+    /// guest this server was built with.
+    ///
+    /// Takes a multipart file (bincode or JSON receipt) or a raw body holding
+    /// the receipt base64-encoded. A receipt that decodes but fails
+    /// verification is reported with `verified: false`, not as an error.
+    pub async fn verify_uploaded_receipt(
+        request: Request,
+    ) -> Result<Json<ReceiptVerification>, (StatusCode, String)> {
+        let bytes = receipt_upload(request).await?;
+        let report = tokio::task::spawn_blocking(move || check_receipt(&bytes))
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("verification task failed: {}", e),
+                )
+            })?
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))?;
+        Ok(Json(report))
+    }
+
+    /// The receipt bytes from a multipart file field or a base64 body.
+    pub async fn receipt_upload(request: Request) -> Result<Vec<u8>, (StatusCode, String)> {
+        let is_multipart = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("multipart/form-data"));
+        if is_multipart {
+            let mut multipart = Multipart::from_request(request, &())
+                .await
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("multipart error: {}", e)))?;
+            let field = multipart
+                .next_field()
+                .await
+                .map_err(|e| (e.status(), format!("multipart error: {}", e)))?
+                .ok_or((StatusCode::BAD_REQUEST, "no file uploaded".to_string()))?;
+            let data = field
+                .bytes()
+                .await
+                .map_err(|e| (e.status(), format!("read error: {}", e)))?;
+            return Ok(data.to_vec());
+        }
+
+        let body = to_bytes(request.into_body(), MAX_RECEIPT_UPLOAD_BYTES)
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("receipt exceeds {} bytes", MAX_RECEIPT_UPLOAD_BYTES),
+                )
+            })?;
+        let text = std::str::from_utf8(&body).map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                "body is not base64 text".to_string(),
+            )
+        })?;
+        base64_decode(text).map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))
+    }
+
+    fn check_receipt(bytes: &[u8]) -> anyhow::Result<ReceiptVerification> {
+        let receipt = deserialize_receipt_auto(bytes)?;
+        let circuit_id = circuit_id_hex();
+        let receipt_image_id = receipt_claimed_image_id(&receipt).map(|id| image_id_hex(&id));
+        let (journal, error) = match verify_receipt(&receipt) {
+            Ok(journal) => (Some(journal.into()), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+        Ok(ReceiptVerification {
+            receipt_kind: describe_receipt_kind(&receipt.inner),
+            matches_circuit: receipt_image_id.as_deref() == Some(circuit_id.as_str()),
+            circuit_id,
+            receipt_image_id,
+            verified: journal.is_some(),
+            journal,
+            error,
+        })
+    }
+}
+
+#[cfg(feature = "prove")]
+use verify::verify_uploaded_receipt;
+
+/// `POST /api/receipts/verify` without the `prove` feature: the verifier is
+/// not compiled in.
+#[cfg(not(feature = "prove"))]
+async fn verify_uploaded_receipt() -> (axum::http::StatusCode, &'static str) {
+    (
+        axum::http::StatusCode::NOT_IMPLEMENTED,
+        "receipt verification requires the prove feature",
+    )
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/receipts/verify",
+        post(verify_uploaded_receipt).layer(DefaultBodyLimit::max(MAX_RECEIPT_UPLOAD_BYTES)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn serve() -> String {
+        let state = Arc::new(AppState::for_tests(std::env::temp_dir()));
+        let app = router().with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/receipts/verify", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    #[cfg(not(feature = "prove"))]
+    #[tokio::test]
+    async fn verify_is_not_implemented_without_prove() {
+        let resp = reqwest::Client::new()
+            .post(serve().await)
+            .body("AAAA")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 501);
+    }
+
+    #[cfg(feature = "prove")]
+    #[tokio::test]
+    async fn oversized_upload_is_rejected() {
+        let resp = reqwest::Client::new()
+            .post(serve().await)
+            .body(vec![b'A'; MAX_RECEIPT_UPLOAD_BYTES + 4])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 413);
+    }
+
+    #[cfg(feature = "prove")]
+    #[tokio::test]
+    async fn dev_mode_receipt_verifies_and_decodes() {
+        use shadow_prover_lib::{prove_claim, serialize_receipt, VerifyPolicy};
+
+        std::env::set_var("RISC0_DEV_MODE", "1");
+        let input = crate::prover::selftest::selftest_claim_input();
+        let expected = shadow_proof_core::evaluate_claim(&input).unwrap();
+        // Prove on a large stack, as the pipeline does.
+        let receipt = std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || {
+                prove_claim(&input, "succinct", VerifyPolicy::Verify)
+                    .unwrap()
+                    .receipt
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        let encoded = crate::prover::pipeline::base64_encode(&serialize_receipt(&receipt).unwrap());
+
+        let resp = reqwest::Client::new()
+            .post(serve().await)
+            .body(encoded)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["receiptKind"], "fake");
+        assert_eq!(body["verified"], true);
+        assert_eq!(body["matchesCircuit"], true);
+        assert_eq!(body["journal"]["amount"], expected.amount.to_string());
+        assert_eq!(
+            body["journal"]["nullifier"],
+            format!("0x{}", hex::encode(expected.nullifier))
+        );
+
+        let resp = reqwest::Client::new()
+            .post(serve().await)
+            .body("not a receipt")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+    }
+}
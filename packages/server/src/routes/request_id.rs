@@ -0,0 +1,160 @@
+//! Request correlation IDs.
+//!
+//! Every request runs inside a `request` span carrying a `request_id` field,
+//! taken from the client's `X-Request-Id` header or generated, and the ID is
+//! echoed on the response. Proof jobs keep the span of the request that
+//! queued them (see [`ProofJob::span`](crate::prover::queue::ProofJob)), so
+//! queue, pipeline and prover-thread logs for one proof share the ID.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied ID kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Middleware that assigns the request ID, runs the request in its span and
+/// sets `X-Request-Id` on the response.
+pub async fn layer(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| hex::encode(rand::random::<[u8; 8]>()));
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(X_REQUEST_ID.clone(), value);
+    }
+    response
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, routing::post, Router};
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    use super::*;
+    use crate::prover::{queue::ProofQueue, worker::job_span};
+
+    /// `request_id` recorded on a span.
+    struct RequestId(String);
+
+    /// An event's message and the `request_id` of its enclosing spans.
+    type LogLine = (String, Option<String>);
+
+    /// Records every event as a [`LogLine`].
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<LogLine>>>);
+
+    #[derive(Default)]
+    struct FieldVisitor {
+        request_id: Option<String>,
+        message: Option<String>,
+    }
+
+    impl Visit for FieldVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            match field.name() {
+                "request_id" => self.request_id = Some(format!("{value:?}")),
+                "message" => self.message = Some(format!("{value:?}")),
+                _ => {}
+            }
+        }
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+            let mut fields = FieldVisitor::default();
+            attrs.record(&mut fields);
+            if let (Some(request_id), Some(span)) = (fields.request_id, ctx.span(id)) {
+                span.extensions_mut().insert(RequestId(request_id));
+            }
+        }
+
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let mut fields = FieldVisitor::default();
+            event.record(&mut fields);
+            let request_id = ctx.event_scope(event).and_then(|scope| {
+                scope
+                    .from_root()
+                    .find_map(|span| span.extensions().get::<RequestId>().map(|r| r.0.clone()))
+            });
+            let message = fields.message.unwrap_or_default();
+            self.0.lock().unwrap().push((message, request_id));
+        }
+    }
+
+    async fn enqueue(State(queue): State<Arc<ProofQueue>>) {
+        tracing::info!("handling prove request");
+        queue.enqueue("deposit-a", 1, 0, false).await;
+    }
+
+    #[tokio::test]
+    async fn request_id_is_echoed_and_follows_the_proof_job() {
+        let capture = Capture::default();
+        let _guard = tracing_subscriber::registry()
+            .with(capture.clone())
+            .set_default();
+
+        let (event_tx, _) = tokio::sync::broadcast::channel(8);
+        let queue = ProofQueue::new(event_tx);
+        let app = Router::new()
+            .route("/prove", post(enqueue))
+            .with_state(queue.clone())
+            .layer(axum::middleware::from_fn(layer));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("{base}/prove"))
+            .header("x-request-id", "req-42")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["x-request-id"], "req-42");
+
+        let (job, _) = queue.start_next().await.unwrap();
+        job_span(&job).in_scope(|| tracing::info!("proving deposit"));
+
+        let logs = capture.0.lock().unwrap().clone();
+        for message in [
+            "handling prove request",
+            "proof job enqueued",
+            "proving deposit",
+        ] {
+            let request_id = logs.iter().find(|(m, _)| m == message).map(|(_, id)| id);
+            assert_eq!(request_id, Some(&Some("req-42".to_string())), "{message}");
+        }
+
+        let generated = client.post(format!("{base}/prove")).send().await.unwrap();
+        let id = generated.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(id.len(), 16);
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::{http::StatusCode, routing::post, Json, Router};
+
+use crate::{
+    prover::selftest::{self, SelftestReport},
+    state::AppState,
+};
+
+/// `POST /api/selftest` — prove and verify a fixed claim to smoke-test the
+/// proving stack (guest, Groth16 artifacts, environment).
+async fn run_selftest() -> (StatusCode, Json<SelftestReport>) {
+    let receipt_kind = std::env::var("RECEIPT_KIND").unwrap_or_else(|_| "groth16".into());
+    let report = match tokio::task::spawn_blocking(move || selftest::run(&receipt_kind)).await {
+        Ok(report) => report,
+        Err(e) => SelftestReport {
+            ok: false,
+            elapsed_secs: 0.0,
+            receipt_kind: None,
+            error: Some(format!("self-test task failed: {}", e)),
+        },
+    };
+
+    let status = if report.ok {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    (status, Json(report))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/selftest", post(run_selftest))
+}
@@ -3,40 +3,105 @@ use std::sync::Arc;
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Query, State,
     },
-    response::IntoResponse,
+    http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::get,
     Router,
 };
-use tokio::sync::broadcast;
+use tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+};
 
 use crate::state::AppState;
 
+/// Default number of events buffered for each WebSocket client.
+pub const DEFAULT_EVENT_BUFFER: usize = 64;
+
+/// What a WebSocket client receives when events arrive faster than it reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum EventOverflow {
+    /// Forward every buffered event; once the buffer is full the oldest are lost.
+    #[default]
+    DropOldest,
+    /// Forward only the latest of consecutive progress events for a deposit.
+    Coalesce,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct WsQuery {
+    token: Option<String>,
+}
+
 /// `GET /ws` — WebSocket upgrade for real-time events.
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+///
+/// With `--ws-token` set, the client must send the token as `?token=` or as
+/// one of its requested subprotocols (which browsers can set but cannot add
+/// headers to); otherwise the upgrade is refused with 401.
+async fn ws_handler(
+    mut ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(expected) = &state.ws_token {
+        let offered = offered_protocols(&headers);
+        if offered.iter().any(|p| token_matches(p, expected)) {
+            // Echo the token protocol back, or the browser drops the connection
+            ws = ws.protocols([expected.clone()]);
+        } else if !query
+            .token
+            .as_deref()
+            .is_some_and(|t| token_matches(t, expected))
+        {
+            tracing::warn!("rejected WebSocket client without a valid token");
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    }
+
     let rx = state.event_tx.subscribe();
-    ws.on_upgrade(move |socket| handle_socket(socket, rx))
+    let overflow = state.event_overflow;
+    ws.on_upgrade(move |socket| handle_socket(socket, rx, overflow))
+}
+
+/// Subprotocols listed in the client's `Sec-WebSocket-Protocol` headers.
+fn offered_protocols(headers: &HeaderMap) -> Vec<&str> {
+    headers
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect()
+}
+
+/// Compare without returning early, so response timing doesn't leak the token.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
 }
 
-async fn handle_socket(mut socket: WebSocket, mut rx: broadcast::Receiver<String>) {
+async fn handle_socket(
+    mut socket: WebSocket,
+    mut rx: broadcast::Receiver<String>,
+    overflow: EventOverflow,
+) {
     tracing::info!("WebSocket client connected");
 
-    loop {
+    'outer: loop {
         tokio::select! {
             // Forward broadcast events to the WebSocket client
-            result = rx.recv() => {
-                match result {
-                    Ok(msg) => {
-                        if socket.send(Message::Text(msg.into())).await.is_err() {
-                            break;
-                        }
-                    }
-                    Err(broadcast::error::RecvError::Lagged(n)) => {
-                        tracing::warn!(skipped = n, "WebSocket client lagged, skipping messages");
-                    }
-                    Err(broadcast::error::RecvError::Closed) => {
-                        break;
+            events = next_events(&mut rx, overflow) => {
+                let Some(events) = events else { break };
+                for msg in events {
+                    if socket.send(Message::Text(msg.into())).await.is_err() {
+                        break 'outer;
                     }
                 }
             }
@@ -62,3 +127,159 @@ async fn handle_socket(mut socket: WebSocket, mut rx: broadcast::Receiver<String
 pub fn router() -> Router<Arc<AppState>> {
     Router::new().route("/ws", get(ws_handler))
 }
+
+/// Wait for the next event, then take whatever else is already buffered when
+/// coalescing. Returns `None` once the channel is closed.
+async fn next_events(
+    rx: &mut broadcast::Receiver<String>,
+    overflow: EventOverflow,
+) -> Option<Vec<String>> {
+    let first = loop {
+        match rx.recv().await {
+            Ok(msg) => break msg,
+            Err(RecvError::Lagged(n)) => {
+                tracing::warn!(skipped = n, "WebSocket client lagged, skipping messages");
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    };
+    let mut events = vec![first];
+    if overflow == EventOverflow::DropOldest {
+        return Some(events);
+    }
+
+    loop {
+        let msg = match rx.try_recv() {
+            Ok(msg) => msg,
+            Err(TryRecvError::Lagged(n)) => {
+                tracing::warn!(skipped = n, "WebSocket client lagged, skipping messages");
+                continue;
+            }
+            Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+        };
+        let superseded = match (
+            events.last().and_then(|e| progress_deposit(e)),
+            progress_deposit(&msg),
+        ) {
+            (Some(prev), Some(next)) => prev == next,
+            _ => false,
+        };
+        if superseded {
+            events.pop();
+        }
+        events.push(msg);
+    }
+    Some(events)
+}
+
+/// Deposit ID of a `proof:note_progress` event.
+fn progress_deposit(event: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(event).ok()?;
+    if value.get("type")?.as_str()? != "proof:note_progress" {
+        return None;
+    }
+    Some(value.get("depositId")?.as_str()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(deposit_id: &str, note: u32) -> String {
+        serde_json::json!({
+            "type": "proof:note_progress",
+            "depositId": deposit_id,
+            "noteIndex": note,
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn coalescing_slow_consumer_sees_the_final_progress() {
+        let (tx, mut rx) = broadcast::channel(8);
+        for note in 0..100 {
+            tx.send(progress("d1", note)).unwrap();
+        }
+        let changed = serde_json::json!({"type": "workspace:changed"}).to_string();
+        tx.send(changed.clone()).unwrap();
+        tx.send(progress("d1", 100)).unwrap();
+        tx.send(progress("d2", 0)).unwrap();
+
+        let events = next_events(&mut rx, EventOverflow::Coalesce).await.unwrap();
+        assert_eq!(
+            events,
+            [
+                progress("d1", 99),
+                changed,
+                progress("d1", 100),
+                progress("d2", 0)
+            ]
+        );
+
+        drop(tx);
+        assert!(next_events(&mut rx, EventOverflow::Coalesce)
+            .await
+            .is_none());
+    }
+
+    /// Send a WebSocket handshake for `path` and return the response status.
+    async fn handshake_status(
+        addr: std::net::SocketAddr,
+        path: &str,
+        protocol: Option<&str>,
+    ) -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let protocol = protocol
+            .map(|p| format!("Sec-WebSocket-Protocol: {p}\r\n"))
+            .unwrap_or_default();
+        let request = format!(
+            "GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: Upgrade\r\n\
+             Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n{protocol}\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut buf = [0u8; 256];
+        let n = stream.read(&mut buf).await.unwrap();
+        let response = String::from_utf8_lossy(&buf[..n]);
+        response.split(' ').nth(1).unwrap().parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn ws_token_gates_the_upgrade() {
+        let mut state = AppState::for_tests(std::path::PathBuf::new());
+        state.ws_token = Some("s3cret".to_string());
+        let app = router().with_state(Arc::new(state));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        assert_eq!(handshake_status(addr, "/ws", None).await, 401);
+        assert_eq!(handshake_status(addr, "/ws?token=wrong", None).await, 401);
+        assert_eq!(handshake_status(addr, "/ws", Some("s3cre")).await, 401);
+        assert_eq!(handshake_status(addr, "/ws?token=s3cret", None).await, 101);
+        assert_eq!(
+            handshake_status(addr, "/ws", Some("json, s3cret")).await,
+            101
+        );
+
+        let open = router().with_state(Arc::new(AppState::for_tests(std::path::PathBuf::new())));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, open).await });
+        assert_eq!(handshake_status(addr, "/ws", None).await, 101);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_forwards_events_one_at_a_time() {
+        let (tx, mut rx) = broadcast::channel(8);
+        tx.send(progress("d1", 0)).unwrap();
+        tx.send(progress("d1", 1)).unwrap();
+
+        let events = next_events(&mut rx, EventOverflow::DropOldest)
+            .await
+            .unwrap();
+        assert_eq!(events, [progress("d1", 0)]);
+    }
+}
@@ -2,7 +2,21 @@ use std::{path::PathBuf, sync::Arc};
 
 use tokio::sync::broadcast;
 
-use crate::{chain::ChainClient, prover::ProofQueue};
+#[cfg(feature = "prove")]
+use crate::prover::proof_cache::ProofCache;
+use crate::{
+    chain::ChainClient,
+    events::ServerEvent,
+    mining::MinesInFlight,
+    prover::{audit::ProofAuditLog, block_cache::BlockCache, pipeline::PipelineConfig, ProofQueue},
+    routes::ws::EventOverflow,
+    workspace::{
+        index_cache::WorkspaceIndexCache,
+        perms::WritePerms,
+        recipient_cache::RecipientHashCache,
+        scanner::{ProofNaming, WorkspaceIndex},
+    },
+};
 
 /// Shared application state.
 pub struct AppState {
@@ -10,16 +24,114 @@ pub struct AppState {
     pub workspace: PathBuf,
     /// Ethereum JSON-RPC URL (optional).
     pub rpc_url: Option<String>,
+    /// HTTP client for RPC requests, carrying the configured auth headers.
+    pub rpc_http: reqwest::Client,
     /// Chain ID fetched from RPC at startup (optional).
     pub chain_id: Option<u64>,
     /// Directory containing built UI static files.
     pub ui_dir: PathBuf,
     /// Broadcast channel for server-sent events (WebSocket).
     pub event_tx: broadcast::Sender<String>,
+    /// How WebSocket clients that fall behind the event stream catch up.
+    pub event_overflow: EventOverflow,
+    /// Shared secret WebSocket clients must present; open when unset.
+    pub ws_token: Option<String>,
     /// Proof generation queue.
     pub proof_queue: Arc<ProofQueue>,
     /// On-chain query client (requires RPC URL).
     pub chain_client: Option<ChainClient>,
     /// Shadow contract address (optional, for on-chain queries).
     pub shadow_address: Option<String>,
+    /// Naming scheme for proof files written to the workspace.
+    pub proof_naming: ProofNaming,
+    /// Operator limit on notes per new deposit (at most `MAX_NOTES`).
+    pub max_notes: usize,
+    /// Operator limit on proof node size (at most the circuit's `MAX_NODE_BYTES`).
+    pub max_proof_node_bytes: usize,
+    /// Warn when a deposit's balance exceeds its note total by less than this.
+    pub balance_margin_wei: Option<u128>,
+    /// Append-only log of finished proof jobs (optional).
+    pub audit_log: Option<ProofAuditLog>,
+    /// Permissions for files and directories written to the workspace.
+    pub write_perms: WritePerms,
+    /// Recipient hashes shared by workspace scans and deposit creation.
+    pub recipient_hashes: RecipientHashCache,
+    /// Last workspace scan, reused until the workspace changes.
+    pub index_cache: WorkspaceIndexCache,
+    /// Notes hashes currently being mined by create requests.
+    pub mines_in_flight: MinesInFlight,
+    /// Recently fetched blocks shared by proof jobs.
+    pub block_cache: BlockCache,
+    /// Notes of a deposit proved in parallel (at least 1).
+    pub prove_concurrency: usize,
+    /// Serve per-note claim inputs and repro bundles, which include the deposit secret.
+    pub serve_claim_inputs: bool,
+    /// Encrypted on-disk cache of proved receipts (optional).
+    #[cfg(feature = "prove")]
+    pub proof_cache: Option<Arc<ProofCache>>,
+}
+
+impl AppState {
+    /// Pipeline settings for a run against `rpc_url`.
+    pub fn pipeline_config<'a>(&'a self, rpc_url: &'a str) -> PipelineConfig<'a> {
+        PipelineConfig {
+            rpc_url,
+            http_client: &self.rpc_http,
+            chain_client: self.chain_client.as_ref(),
+            max_node_bytes: self.max_proof_node_bytes,
+            block_cache: &self.block_cache,
+            balance_margin_wei: self.balance_margin_wei,
+            prove_concurrency: self.prove_concurrency,
+            write_perms: self.write_perms,
+            #[cfg(feature = "prove")]
+            proof_cache: self.proof_cache.clone(),
+        }
+    }
+
+    /// Deposits in the workspace, from the cached scan when nothing changed.
+    pub fn workspace_index(&self) -> Arc<WorkspaceIndex> {
+        self.index_cache
+            .get(&self.workspace, &self.proof_naming, &self.recipient_hashes)
+    }
+
+    /// Record that the server changed workspace files and tell clients.
+    pub fn workspace_changed(&self) {
+        self.index_cache.invalidate();
+        ServerEvent::WorkspaceChanged.send(&self.event_tx);
+    }
+}
+
+#[cfg(test)]
+impl AppState {
+    /// Minimal state over `workspace` with no RPC or contract configured.
+    pub fn for_tests(workspace: PathBuf) -> Self {
+        let (event_tx, _) = broadcast::channel::<String>(crate::routes::ws::DEFAULT_EVENT_BUFFER);
+        Self {
+            workspace,
+            rpc_url: None,
+            rpc_http: reqwest::Client::new(),
+            chain_id: None,
+            ui_dir: PathBuf::new(),
+            proof_queue: ProofQueue::new(event_tx.clone()),
+            event_tx,
+            event_overflow: EventOverflow::default(),
+            ws_token: None,
+            chain_client: None,
+            shadow_address: None,
+            proof_naming: ProofNaming::default(),
+            max_notes: shadow_proof_core::MAX_NOTES,
+            max_proof_node_bytes: shadow_proof_core::MAX_NODE_BYTES,
+            balance_margin_wei: None,
+            audit_log: None,
+            write_perms: WritePerms::default(),
+            recipient_hashes: RecipientHashCache::default(),
+            index_cache: WorkspaceIndexCache::default(),
+            mines_in_flight: MinesInFlight::default(),
+            block_cache: BlockCache::default(),
+            prove_concurrency: 1,
+            serve_claim_inputs: false,
+            #[cfg(feature = "prove")]
+            proof_cache: None,
+        }
+    }
 }
@@ -0,0 +1,109 @@
+//! Cached workspace scan.
+//!
+//! [`scan_workspace`] parses every deposit file and derives its nullifiers, which
+//! is too slow to repeat on every request once a workspace holds a few hundred
+//! deposits. The cached index is reused until the workspace's files change on
+//! disk or the server itself reports a change.
+
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use super::{
+    recipient_cache::RecipientHashCache,
+    scanner::{scan_workspace, workspace_fingerprint, ProofNaming, WorkspaceIndex},
+};
+
+type Fingerprint = Vec<(String, Option<SystemTime>, u64)>;
+
+#[derive(Default)]
+pub struct WorkspaceIndexCache {
+    inner: RwLock<Option<(Fingerprint, Arc<WorkspaceIndex>)>>,
+}
+
+impl WorkspaceIndexCache {
+    /// The workspace index, rescanned if any file was added, removed or
+    /// modified since the cached scan.
+    pub fn get(
+        &self,
+        workspace: &Path,
+        naming: &ProofNaming,
+        recipient_hashes: &RecipientHashCache,
+    ) -> Arc<WorkspaceIndex> {
+        // Fingerprint before scanning: a change made during the scan then
+        // shows up as a mismatch on the next call instead of being missed.
+        let fingerprint = workspace_fingerprint(workspace, naming);
+        if let Some((cached, index)) = &*self.inner.read().unwrap_or_else(|e| e.into_inner()) {
+            if *cached == fingerprint {
+                return index.clone();
+            }
+        }
+
+        let index = Arc::new(scan_workspace(workspace, naming, recipient_hashes));
+        *self.inner.write().unwrap_or_else(|e| e.into_inner()) = Some((fingerprint, index.clone()));
+        index
+    }
+
+    /// Drop the cached index so the next [`get`](Self::get) rescans, for
+    /// changes a fingerprint could miss (same mtime and size).
+    pub fn invalidate(&self) {
+        *self.inner.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::state::AppState;
+
+    const DEPOSIT: &str = "deposit-ffe8-fde9-20260224T214613.json";
+
+    fn write_deposit(workspace: &Path, amount: &str) {
+        let deposit = serde_json::json!({
+            "version": "v2",
+            "chainId": "167013",
+            "secret": format!("0x{}", "01".repeat(32)),
+            "notes": [{"recipient": format!("0x{}", "11".repeat(20)), "amount": amount}],
+        });
+        std::fs::write(workspace.join(DEPOSIT), deposit.to_string()).unwrap();
+    }
+
+    fn amount(state: &AppState) -> String {
+        state.workspace_index().deposits[0].total_amount.clone()
+    }
+
+    #[test]
+    fn workspace_changes_invalidate_the_cached_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::for_tests(dir.path().to_path_buf());
+        write_deposit(dir.path(), "1000");
+        let first = state.workspace_index();
+        assert!(Arc::ptr_eq(&first, &state.workspace_index()));
+
+        // A rewrite on disk changes the fingerprint.
+        write_deposit(dir.path(), "20000");
+        assert_eq!(amount(&state), "20000");
+
+        // A same-size rewrite with the old mtime only shows up once reported.
+        let modified = std::fs::metadata(dir.path().join(DEPOSIT))
+            .unwrap()
+            .modified()
+            .unwrap();
+        write_deposit(dir.path(), "30000");
+        let file = File::options()
+            .write(true)
+            .open(dir.path().join(DEPOSIT))
+            .unwrap();
+        file.set_modified(modified).unwrap();
+        assert_eq!(amount(&state), "20000");
+        state.workspace_changed();
+        assert_eq!(amount(&state), "30000");
+
+        std::fs::remove_file(dir.path().join(DEPOSIT)).unwrap();
+        assert!(state.workspace_index().deposits.is_empty());
+    }
+}
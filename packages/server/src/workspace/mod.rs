@@ -0,0 +1,5 @@
+pub mod index_cache;
+pub mod perms;
+pub mod recipient_cache;
+pub mod scanner;
+pub mod templates;
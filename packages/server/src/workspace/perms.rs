@@ -0,0 +1,74 @@
+//! Permissions for files and directories the server writes.
+//!
+//! With `--secure-perms`, new directories are created `0700` and files `0600`
+//! on Unix so deposit secrets are not readable by other local users. Without
+//! it (and on other platforms) the process umask applies as before.
+
+use std::{fs::File, io, io::Write, path::Path};
+
+/// How to create workspace files and directories.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WritePerms {
+    secure: bool,
+}
+
+impl WritePerms {
+    pub fn new(secure: bool) -> Self {
+        Self { secure }
+    }
+
+    /// `fs::create_dir_all`, using mode `0700` for new directories when secure.
+    pub fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        if self.secure {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(path)
+    }
+
+    /// Restrict an existing directory to `0700` when secure (e.g. the workspace root).
+    pub fn restrict_dir(&self, path: &Path) -> io::Result<()> {
+        #[cfg(unix)]
+        if self.secure {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o700))?;
+        }
+        #[cfg(not(unix))]
+        let _ = path;
+        Ok(())
+    }
+
+    /// `fs::write`, creating or truncating `path` with mode `0600` when secure.
+    pub fn write(&self, path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        let mut file = self.open(&mut options, path)?;
+        file.write_all(contents.as_ref())
+    }
+
+    /// Create a new file, failing if `path` exists, with mode `0600` when secure.
+    pub fn create_new(&self, path: &Path) -> io::Result<File> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        self.open(&mut options, path)
+    }
+
+    fn open(&self, options: &mut std::fs::OpenOptions, path: &Path) -> io::Result<File> {
+        #[cfg(unix)]
+        if self.secure {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(path)?;
+        // `mode` only applies on creation; tighten files that already existed.
+        #[cfg(unix)]
+        if self.secure {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(file)
+    }
+}
@@ -0,0 +1,107 @@
+//! Bounded LRU cache of recipient hashes.
+//!
+//! `compute_recipient_hash` is deterministic, so every workspace scan would
+//! otherwise re-hash the same recipients across all deposits.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use shadow_proof_core::compute_recipient_hash;
+
+/// Default number of recipients kept in the cache.
+pub const DEFAULT_RECIPIENT_CACHE_SIZE: usize = 1024;
+
+/// Recipient address → recipient hash, evicting the least recently used entry.
+pub struct RecipientHashCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    /// Hash and last-use tick per recipient.
+    entries: HashMap<[u8; 20], ([u8; 32], u64)>,
+    tick: u64,
+    hits: u64,
+}
+
+impl RecipientHashCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    /// The recipient hash for `recipient`, computed on first use.
+    pub fn get(&self, recipient: &[u8; 20]) -> [u8; 32] {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if let Some(entry) = inner.entries.get_mut(recipient) {
+            entry.1 = tick;
+            let hash = entry.0;
+            inner.hits += 1;
+            return hash;
+        }
+
+        if inner.entries.len() >= self.capacity {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(addr, _)| *addr);
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+        let hash = compute_recipient_hash(recipient);
+        inner.entries.insert(*recipient, (hash, tick));
+        hash
+    }
+
+    /// Number of lookups answered from the cache.
+    #[cfg(test)]
+    pub fn hits(&self) -> u64 {
+        self.inner.lock().unwrap().hits
+    }
+}
+
+impl Default for RecipientHashCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECIPIENT_CACHE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeat_lookups_hit_the_cache() {
+        let cache = RecipientHashCache::new(2);
+        let alice = [0x11u8; 20];
+
+        assert_eq!(cache.get(&alice), compute_recipient_hash(&alice));
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(cache.get(&alice), compute_recipient_hash(&alice));
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let cache = RecipientHashCache::new(2);
+        let (a, b, c) = ([0x0au8; 20], [0x0bu8; 20], [0x0cu8; 20]);
+
+        cache.get(&a);
+        cache.get(&b);
+        cache.get(&a); // b is now least recently used
+        cache.get(&c); // evicts b
+        assert_eq!(cache.hits(), 1);
+
+        cache.get(&a);
+        assert_eq!(cache.hits(), 2);
+        cache.get(&b);
+        assert_eq!(cache.hits(), 2);
+    }
+}
@@ -0,0 +1,881 @@
+//! Workspace scanner: discovers deposit files, derives their public data
+//! (target address, nullifiers), and correlates each deposit with its latest
+//! proof file.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use shadow_proof_core::{unpack_journal_prefix, verify_nullifier};
+
+use shadow_util::{
+    deposit::{derive_deposit_info_with, DepositFile, DerivedDepositInfo},
+    to_checksum_address, Hex32,
+};
+
+use super::{perms::WritePerms, recipient_cache::RecipientHashCache};
+use crate::prover::pipeline::{BundledProof, NoteProofResult};
+
+/// Default proof naming template: `<deposit-stem>.proof-<YYYYMMDDTHHMMSS>.json`.
+pub const DEFAULT_PROOF_NAMING: &str = "{stem}.proof-{ts}.json";
+
+/// All deposits found in the workspace, sorted by filename.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceIndex {
+    pub deposits: Vec<DepositEntry>,
+}
+
+/// Summary of a deposit file and its associated proof.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositEntry {
+    /// Deposit identifier (filename without `.json`).
+    pub id: String,
+    pub filename: String,
+    pub chain_id: String,
+    /// Derived target address, EIP-55 checksummed.
+    pub target_address: String,
+    /// Sum of all note amounts (decimal wei).
+    pub total_amount: String,
+    pub note_count: usize,
+    pub notes: Vec<NoteEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Creation time as ISO 8601 UTC (`YYYY-MM-DDTHH:MM:SSZ`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    pub has_proof: bool,
+    /// Latest proof file for this deposit, relative to the workspace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_file: Option<String>,
+    /// Whether the proof file parses and matches the deposit's nullifiers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_valid: Option<bool>,
+    /// Why the proof file is invalid, when `proof_valid` is false.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_invalid_reason: Option<String>,
+    /// Token contract address (0x-prefixed hex). Absent = ETH.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_symbol: Option<String>,
+}
+
+/// Per-note summary within a deposit.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteEntry {
+    pub index: u32,
+    pub recipient: String,
+    pub amount: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Long-form note text (up to `MAX_NOTE_DESCRIPTION_LEN` chars).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Nullifier (0x-prefixed hex).
+    pub nullifier: String,
+    /// Claim status; always `unknown` here, resolved on-chain by the status routes.
+    pub claim_status: String,
+}
+
+// ---------------------------------------------------------------------------
+// Proof file naming
+// ---------------------------------------------------------------------------
+
+/// Proof file naming scheme.
+///
+/// The template is a workspace-relative path with `{stem}` (deposit file stem),
+/// `{ts}` (proof timestamp, `YYYYMMDDTHHMMSS`) and optionally `{note_index}`
+/// placeholders, e.g. `{stem}.proof-{ts}.json` or `proofs/{stem}/{ts}.json`.
+/// Bundled proofs cover every note, so `{note_index}` renders as `all` for them.
+#[derive(Debug, Clone)]
+pub struct ProofNaming {
+    template: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Stem,
+    Timestamp,
+    NoteIndex,
+}
+
+impl ProofNaming {
+    /// Parse and validate a naming template.
+    pub fn parse(template: &str) -> Result<Self> {
+        if !template.contains("{stem}") || !template.contains("{ts}") {
+            bail!("proof naming template must contain {{stem}} and {{ts}}: {template}");
+        }
+        if !template.ends_with(".json") {
+            bail!("proof naming template must end with .json: {template}");
+        }
+        if template
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            bail!("proof naming template must be a plain relative path: {template}");
+        }
+
+        let segments = parse_segments(template)?;
+        let adjacent_placeholders = segments.windows(2).any(|pair| {
+            !matches!(pair[0], Segment::Literal(_)) && !matches!(pair[1], Segment::Literal(_))
+        });
+        if adjacent_placeholders {
+            bail!("proof naming placeholders must be separated by literal text: {template}");
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            segments,
+        })
+    }
+
+    /// Render the workspace-relative proof path for a deposit stem and timestamp.
+    pub fn proof_filename(&self, stem: &str, ts: &str, note_index: Option<u32>) -> String {
+        let note_index = note_index.map_or_else(|| "all".to_string(), |i| i.to_string());
+        self.template
+            .replace("{stem}", stem)
+            .replace("{ts}", ts)
+            .replace("{note_index}", &note_index)
+    }
+
+    /// Extract the deposit stem from a workspace-relative proof path.
+    ///
+    /// Each placeholder extends up to the first occurrence of the literal that
+    /// follows it, so a stem never needs to contain the next separator.
+    pub fn proof_deposit_stem<'a>(&self, name: &'a str) -> Option<&'a str> {
+        let mut rest = name;
+        let mut stem = None;
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            if let Segment::Literal(literal) = segment {
+                rest = rest.strip_prefix(literal.as_str())?;
+                continue;
+            }
+
+            let value = match self.segments.get(i + 1) {
+                Some(Segment::Literal(next)) => &rest[..rest.find(next.as_str())?],
+                _ => rest,
+            };
+            if !placeholder_matches(segment, value) {
+                return None;
+            }
+            if matches!(segment, Segment::Stem) {
+                stem = Some(value);
+            }
+            rest = &rest[value.len()..];
+        }
+
+        if rest.is_empty() {
+            stem
+        } else {
+            None
+        }
+    }
+
+    /// Number of directory levels below the workspace that proofs live in.
+    fn depth(&self) -> usize {
+        self.template.matches('/').count()
+    }
+}
+
+impl Default for ProofNaming {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PROOF_NAMING).expect("default proof naming template is valid")
+    }
+}
+
+fn parse_segments(template: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            segments.push(Segment::Literal(rest[..open].to_string()));
+        }
+        let close = rest[open..]
+            .find('}')
+            .map(|offset| open + offset)
+            .with_context(|| format!("unterminated placeholder in proof naming: {template}"))?;
+        segments.push(match &rest[open + 1..close] {
+            "stem" => Segment::Stem,
+            "ts" => Segment::Timestamp,
+            "note_index" => Segment::NoteIndex,
+            other => bail!("unknown placeholder {{{other}}} in proof naming: {template}"),
+        });
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest.to_string()));
+    }
+
+    Ok(segments)
+}
+
+fn placeholder_matches(segment: &Segment, value: &str) -> bool {
+    match segment {
+        Segment::Stem => {
+            value.starts_with("deposit-") && !value.contains('.') && !value.contains('/')
+        }
+        Segment::Timestamp => is_compact_timestamp(value),
+        Segment::NoteIndex => {
+            value == "all" || (!value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()))
+        }
+        Segment::Literal(literal) => value == literal,
+    }
+}
+
+fn is_compact_timestamp(value: &str) -> bool {
+    value.len() == 15
+        && value.bytes().enumerate().all(|(i, b)| {
+            if i == 8 {
+                b == b'T'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+}
+
+/// Check if a filename matches the deposit file pattern.
+pub fn is_deposit_filename(name: &str) -> bool {
+    name.starts_with("deposit-") && name.ends_with(".json") && !name.contains(".proof")
+}
+
+/// Extract the deposit stem from a deposit filename (without `.json`).
+pub fn deposit_stem(filename: &str) -> &str {
+    filename.strip_suffix(".json").unwrap_or(filename)
+}
+
+// ---------------------------------------------------------------------------
+// Scanning
+// ---------------------------------------------------------------------------
+
+/// Scan the workspace for deposit files and correlate them with proof files.
+///
+/// Invalid deposit files are skipped with a warning.
+pub fn scan_workspace(
+    workspace: &Path,
+    naming: &ProofNaming,
+    recipient_hashes: &RecipientHashCache,
+) -> WorkspaceIndex {
+    let mut deposit_files = Vec::new();
+    let mut proofs: HashMap<String, Vec<String>> = HashMap::new();
+
+    for name in list_files(workspace, naming.depth()) {
+        if let Some(stem) = naming.proof_deposit_stem(&name) {
+            let stem = stem.to_string();
+            proofs.entry(stem).or_default().push(name);
+        } else if !name.contains('/') && is_deposit_filename(&name) {
+            deposit_files.push(name);
+        }
+    }
+    deposit_files.sort();
+
+    let mut deposits = Vec::with_capacity(deposit_files.len());
+    for filename in deposit_files {
+        // Proof timestamps sort lexicographically, so the max is the latest.
+        let proof_file = proofs
+            .get(deposit_stem(&filename))
+            .and_then(|files| files.iter().max())
+            .map(String::as_str);
+
+        match process_deposit(workspace, &filename, proof_file, recipient_hashes) {
+            Ok(entry) => deposits.push(entry),
+            Err(e) => {
+                tracing::warn!(file = %filename, error = %format!("{:#}", e), "skipping invalid deposit file")
+            }
+        }
+    }
+
+    WorkspaceIndex { deposits }
+}
+
+/// Workspace subdirectory that `--prune-invalid` moves unparseable files into.
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+/// Move deposit files that fail validation and proof files that do not parse
+/// into [`QUARANTINE_DIR`], keeping their relative paths. Files are never
+/// deleted or overwritten; a file whose quarantine path is taken stays put.
+/// Returns the moved paths.
+pub fn quarantine_invalid_files(
+    workspace: &Path,
+    naming: &ProofNaming,
+    perms: WritePerms,
+) -> Result<Vec<String>> {
+    let mut moved = Vec::new();
+    for name in list_files(workspace, naming.depth()) {
+        let is_proof = naming.proof_deposit_stem(&name).is_some();
+        if !is_proof && (name.contains('/') || !is_deposit_filename(&name)) {
+            continue;
+        }
+        let Ok(raw) = std::fs::read(workspace.join(&name)) else {
+            continue;
+        };
+        let valid = if is_proof {
+            serde_json::from_slice::<BundledProof>(&raw).is_ok()
+        } else {
+            validate_deposit(&raw).is_ok()
+        };
+        if valid {
+            continue;
+        }
+
+        let dest = workspace.join(QUARANTINE_DIR).join(&name);
+        if dest.exists() {
+            tracing::warn!(file = %name, "not quarantining: destination already exists");
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            perms.create_dir_all(parent)?;
+        }
+        std::fs::rename(workspace.join(&name), &dest)
+            .with_context(|| format!("failed to quarantine {name}"))?;
+        tracing::info!(file = %name, to = %dest.display(), "quarantined invalid file");
+        moved.push(name);
+    }
+    Ok(moved)
+}
+
+/// Workspace-relative paths of every proof file matching `naming`.
+#[cfg(feature = "prove")]
+pub fn list_proof_files(workspace: &Path, naming: &ProofNaming) -> Vec<String> {
+    let mut files: Vec<String> = list_files(workspace, naming.depth())
+        .into_iter()
+        .filter(|name| naming.proof_deposit_stem(name).is_some())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Name, modification time and size of every file [`scan_workspace`] may
+/// read, sorted by name. Equal fingerprints mean a rescan would see the same
+/// files, short of an edit that keeps both mtime and size.
+pub fn workspace_fingerprint(
+    workspace: &Path,
+    naming: &ProofNaming,
+) -> Vec<(String, Option<std::time::SystemTime>, u64)> {
+    let mut files: Vec<_> = list_files(workspace, naming.depth())
+        .into_iter()
+        .map(|name| {
+            let meta = std::fs::metadata(workspace.join(&name)).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.map_or(0, |m| m.len());
+            (name, modified, len)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// List regular files up to `depth` directories below `dir`, as `/`-joined
+/// workspace-relative paths.
+fn list_files(dir: &Path, depth: usize) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_file() {
+            out.push(name);
+        } else if file_type.is_dir() && depth > 0 {
+            for child in list_files(&entry.path(), depth - 1) {
+                out.push(format!("{name}/{child}"));
+            }
+        }
+    }
+    out
+}
+
+/// Parse a raw deposit JSON document and validate it against the v2 schema.
+/// Returns the derived target address.
+pub fn validate_deposit(raw: &[u8]) -> Result<[u8; 20]> {
+    let deposit: DepositFile = serde_json::from_slice(raw).context("invalid deposit JSON")?;
+    let recipient_hashes = RecipientHashCache::new(deposit.notes.len());
+    Ok(derive_deposit(&deposit, &recipient_hashes)?.target_address)
+}
+
+/// Check the deposit's fields and derive its target address and nullifiers.
+/// Notes come back in derivation order, which honours `noteOrder`.
+fn derive_deposit(
+    deposit: &DepositFile,
+    recipient_hashes: &RecipientHashCache,
+) -> Result<DerivedDepositInfo> {
+    derive_deposit_info_with(deposit, |recipient| recipient_hashes.get(recipient))
+}
+
+/// Parse a deposit file, derive its public data, and validate its proof file.
+fn process_deposit(
+    workspace: &Path,
+    filename: &str,
+    proof_file: Option<&str>,
+    recipient_hashes: &RecipientHashCache,
+) -> Result<DepositEntry> {
+    let raw = std::fs::read(workspace.join(filename))
+        .with_context(|| format!("failed reading {}", filename))?;
+    let deposit: DepositFile = serde_json::from_slice(&raw).context("invalid deposit JSON")?;
+    let derived = derive_deposit(&deposit, recipient_hashes)?;
+    let note_count = derived.notes.len();
+
+    let notes = derived
+        .notes
+        .iter()
+        .map(|note| NoteEntry {
+            index: note.index,
+            recipient: to_checksum_address(&note.recipient),
+            amount: note.amount.to_string(),
+            label: note.label.clone(),
+            description: note.description.clone(),
+            nullifier: format!("0x{}", hex::encode(note.nullifier)),
+            claim_status: "unknown".to_string(),
+        })
+        .collect();
+
+    let stem = deposit_stem(filename);
+    let created_at = deposit
+        .created
+        .as_deref()
+        .or_else(|| stem.get(stem.len().saturating_sub(15)..))
+        .and_then(iso_from_compact_timestamp);
+
+    let proof_invalid_reason = proof_file.and_then(|p| proof_mismatch(workspace, p, &derived));
+    let proof_valid = proof_file.map(|_| proof_invalid_reason.is_none());
+
+    Ok(DepositEntry {
+        id: stem.to_string(),
+        filename: filename.to_string(),
+        chain_id: deposit.chain_id,
+        target_address: to_checksum_address(&derived.target_address),
+        total_amount: derived.total_amount.to_string(),
+        note_count,
+        notes,
+        comment: deposit.comment,
+        created_at,
+        has_proof: proof_file.is_some(),
+        proof_file: proof_file.map(str::to_string),
+        proof_valid,
+        proof_invalid_reason,
+        token: deposit.token,
+        token_symbol: deposit.token_symbol,
+    })
+}
+
+/// Why a proof file does not belong to the deposit, if it does not: it must
+/// parse, be for the deposit's chain, and each note's nullifier must match its
+/// journal and derive from the deposit's secret.
+fn proof_mismatch(
+    workspace: &Path,
+    proof_file: &str,
+    derived: &DerivedDepositInfo,
+) -> Option<String> {
+    let reason = proof_mismatch_reason(workspace, proof_file, derived)?;
+    tracing::debug!(file = %proof_file, reason = %reason, "proof does not match deposit");
+    Some(reason)
+}
+
+fn proof_mismatch_reason(
+    workspace: &Path,
+    proof_file: &str,
+    derived: &DerivedDepositInfo,
+) -> Option<String> {
+    let Some(bundled) = std::fs::read(workspace.join(proof_file))
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<BundledProof>(&raw).ok())
+    else {
+        return Some("proof file is unreadable or malformed".to_string());
+    };
+
+    if bundled.chain_id.parse::<u64>().ok() != Some(derived.chain_id) {
+        return Some(format!(
+            "proof is for chain {} but the deposit is for chain {}",
+            bundled.chain_id, derived.chain_id
+        ));
+    }
+    if bundled.notes.len() != derived.notes.len() {
+        return Some("proof note count does not match deposit".to_string());
+    }
+
+    for note in &bundled.notes {
+        let Some(nullifier) = note_nullifier(note) else {
+            return Some(format!(
+                "note {} nullifier does not match its journal",
+                note.note_index
+            ));
+        };
+        if !verify_nullifier(
+            &nullifier,
+            &derived.secret,
+            derived.chain_id,
+            note.note_index,
+            &derived.notes_hash,
+        ) {
+            return Some("proof nullifiers do not derive from the deposit secret".to_string());
+        }
+    }
+    None
+}
+
+/// The note's `nullifier` field, if it equals the nullifier committed in its
+/// journal. Proofs built without `prove` record no journal, so the field
+/// stands alone.
+fn note_nullifier(note: &NoteProofResult) -> Option<[u8; 32]> {
+    let nullifier = note.nullifier.parse::<Hex32>().ok()?.into_bytes();
+    let journal_hex = note.journal.strip_prefix("0x").unwrap_or(&note.journal);
+    if journal_hex.is_empty() {
+        return Some(nullifier);
+    }
+    let journal = unpack_journal_prefix(&hex::decode(journal_hex).ok()?).ok()?;
+    (journal.nullifier == nullifier).then_some(nullifier)
+}
+
+/// Convert `YYYYMMDDTHHMMSS` into `YYYY-MM-DDTHH:MM:SSZ`.
+pub fn iso_from_compact_timestamp(ts: &str) -> Option<String> {
+    if !is_compact_timestamp(ts) {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &ts[0..4],
+        &ts[4..6],
+        &ts[6..8],
+        &ts[9..11],
+        &ts[11..13],
+        &ts[13..15]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shadow_util::MAX_NOTE_DESCRIPTION_LEN;
+
+    const DEPOSIT_STEM: &str = "deposit-ffe8-fde9-20260224T214613";
+
+    fn sample_deposit_json() -> &'static str {
+        r#"{
+            "version": "v2",
+            "chainId": "167013",
+            "secret": "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa",
+            "notes": [
+                { "recipient": "0x1111111111111111111111111111111111111111", "amount": "1230000000000" }
+            ]
+        }"#
+    }
+
+    #[test]
+    fn default_naming_preserves_current_format() {
+        let naming = ProofNaming::default();
+        let name = naming.proof_filename(DEPOSIT_STEM, "20260225T103000", None);
+        assert_eq!(
+            name,
+            "deposit-ffe8-fde9-20260224T214613.proof-20260225T103000.json"
+        );
+        assert_eq!(naming.proof_deposit_stem(&name), Some(DEPOSIT_STEM));
+        assert!(naming
+            .proof_deposit_stem("deposit-ffe8-fde9-20260224T214613.json")
+            .is_none());
+        assert!(naming.proof_deposit_stem("note-0.proof.json").is_none());
+    }
+
+    #[test]
+    fn custom_naming_round_trips_through_scanner() {
+        let naming = ProofNaming::parse("proofs/{stem}/{ts}-{note_index}.json").unwrap();
+        let proof_name = naming.proof_filename(DEPOSIT_STEM, "20260225T103000", None);
+        assert_eq!(
+            proof_name,
+            "proofs/deposit-ffe8-fde9-20260224T214613/20260225T103000-all.json"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(format!("{DEPOSIT_STEM}.json")),
+            sample_deposit_json(),
+        )
+        .unwrap();
+        let proof_path = dir.path().join(&proof_name);
+        std::fs::create_dir_all(proof_path.parent().unwrap()).unwrap();
+        std::fs::write(&proof_path, "{}").unwrap();
+
+        let index = scan_workspace(dir.path(), &naming, &RecipientHashCache::default());
+        assert_eq!(index.deposits.len(), 1);
+        let entry = &index.deposits[0];
+        assert_eq!(entry.id, DEPOSIT_STEM);
+        assert!(entry.has_proof);
+        assert_eq!(entry.proof_file.as_deref(), Some(proof_name.as_str()));
+
+        // The default scheme does not see the nested proof.
+        let index = scan_workspace(
+            dir.path(),
+            &ProofNaming::default(),
+            &RecipientHashCache::default(),
+        );
+        assert!(!index.deposits[0].has_proof);
+    }
+
+    #[test]
+    fn note_description_is_validated_and_surfaced() {
+        let deposit_with = |description: String| {
+            serde_json::json!({
+                "version": "v2",
+                "chainId": "167013",
+                "secret": "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa",
+                "notes": [{
+                    "recipient": "0x1111111111111111111111111111111111111111",
+                    "amount": "1230000000000",
+                    "label": "rent",
+                    "description": description
+                }]
+            })
+            .to_string()
+        };
+
+        let long = "d".repeat(MAX_NOTE_DESCRIPTION_LEN);
+        validate_deposit(deposit_with(long.clone()).as_bytes()).unwrap();
+        let err = validate_deposit(deposit_with(long.clone() + "d").as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("description"));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(format!("{DEPOSIT_STEM}.json")),
+            deposit_with(long.clone()),
+        )
+        .unwrap();
+        let index = scan_workspace(
+            dir.path(),
+            &ProofNaming::default(),
+            &RecipientHashCache::default(),
+        );
+        let note = &index.deposits[0].notes[0];
+        assert_eq!(note.label.as_deref(), Some("rent"));
+        assert_eq!(note.description.as_deref(), Some(long.as_str()));
+    }
+
+    #[test]
+    fn hex_note_amount_is_reported_in_decimal() {
+        let deposit = sample_deposit_json().replace("\"1230000000000\"", "\"0x64\"");
+        validate_deposit(deposit.as_bytes()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(format!("{DEPOSIT_STEM}.json")), &deposit).unwrap();
+        let index = scan_workspace(
+            dir.path(),
+            &ProofNaming::default(),
+            &RecipientHashCache::default(),
+        );
+        assert_eq!(index.deposits[0].notes[0].amount, "100");
+        assert_eq!(index.deposits[0].total_amount, "100");
+
+        let bad = sample_deposit_json().replace("\"1230000000000\"", "\"0x6g\"");
+        let err = validate_deposit(bad.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("invalid amount"), "{err:#}");
+    }
+
+    #[test]
+    fn canonical_note_order_indexes_notes_sorted() {
+        let deposit = |order: &str, recipients: [&str; 2]| {
+            serde_json::json!({
+                "version": "v2",
+                "chainId": "167013",
+                "secret": "0x8c4d3df220b9aa338eafbe43871a800a9ef971fc7242c4d0de98e056cc8c7bfa",
+                "noteOrder": order,
+                "notes": recipients.map(|r| serde_json::json!({
+                    "recipient": format!("0x{}", r.repeat(20)),
+                    "amount": "1000",
+                })),
+            })
+            .to_string()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(format!("{DEPOSIT_STEM}.json")),
+            deposit("canonical", ["22", "11"]),
+        )
+        .unwrap();
+
+        let index = scan_workspace(
+            dir.path(),
+            &ProofNaming::default(),
+            &RecipientHashCache::default(),
+        );
+        let entry = &index.deposits[0];
+        assert_eq!(entry.notes[0].index, 0);
+        assert_eq!(entry.notes[0].recipient, format!("0x{}", "11".repeat(20)));
+        let listed = validate_deposit(deposit("listed", ["11", "22"]).as_bytes()).unwrap();
+        assert_eq!(entry.target_address, to_checksum_address(&listed));
+    }
+
+    #[test]
+    fn proof_for_another_chain_is_marked_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(format!("{DEPOSIT_STEM}.json")),
+            sample_deposit_json(),
+        )
+        .unwrap();
+        let scan = || {
+            scan_workspace(
+                dir.path(),
+                &ProofNaming::default(),
+                &RecipientHashCache::default(),
+            )
+            .deposits
+            .remove(0)
+        };
+        let nullifier = scan().notes[0].nullifier.clone();
+
+        let write_proof = |chain_id: &str| {
+            let bundle = serde_json::json!({
+                "version": "v2",
+                "depositFile": format!("{DEPOSIT_STEM}.json"),
+                "blockNumber": "100",
+                "blockHash": format!("0x{}", "11".repeat(32)),
+                "chainId": chain_id,
+                "notes": [{
+                    "noteIndex": 0,
+                    "amount": "1230000000000",
+                    "recipient": "0x1111111111111111111111111111111111111111",
+                    "nullifier": nullifier,
+                    "seal": "",
+                    "journal": "",
+                    "proof": ""
+                }]
+            });
+            std::fs::write(
+                dir.path()
+                    .join(format!("{DEPOSIT_STEM}.proof-20260225T103000.json")),
+                bundle.to_string(),
+            )
+            .unwrap();
+        };
+
+        write_proof("167013");
+        let entry = scan();
+        assert_eq!(entry.proof_valid, Some(true));
+        assert!(entry.proof_invalid_reason.is_none());
+
+        write_proof("1");
+        let entry = scan();
+        assert_eq!(entry.proof_valid, Some(false));
+        assert_eq!(
+            entry.proof_invalid_reason.as_deref(),
+            Some("proof is for chain 1 but the deposit is for chain 167013")
+        );
+    }
+
+    #[test]
+    fn journal_nullifier_must_match_the_note_nullifier() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(format!("{DEPOSIT_STEM}.json")),
+            sample_deposit_json(),
+        )
+        .unwrap();
+        let scan = || {
+            scan_workspace(
+                dir.path(),
+                &ProofNaming::default(),
+                &RecipientHashCache::default(),
+            )
+            .deposits
+            .remove(0)
+        };
+        let nullifier = scan().notes[0].nullifier.clone();
+        let derived: [u8; 32] = nullifier.parse::<Hex32>().unwrap().into_bytes();
+
+        let write_proof = |note_nullifier: &str, journal_nullifier: [u8; 32]| {
+            let journal = shadow_proof_core::pack_journal(&shadow_proof_core::ClaimJournal {
+                block_number: 100,
+                block_hash: [0x11; 32],
+                chain_id: 167013,
+                amount: 1_230_000_000_000,
+                recipient: [0x11; 20],
+                nullifier: journal_nullifier,
+                token: [0; 20],
+            });
+            let bundle = serde_json::json!({
+                "version": "v2",
+                "depositFile": format!("{DEPOSIT_STEM}.json"),
+                "blockNumber": "100",
+                "blockHash": format!("0x{}", "11".repeat(32)),
+                "chainId": "167013",
+                "notes": [{
+                    "noteIndex": 0,
+                    "amount": "1230000000000",
+                    "recipient": "0x1111111111111111111111111111111111111111",
+                    "nullifier": note_nullifier,
+                    "seal": "",
+                    "journal": format!("0x{}", hex::encode(journal)),
+                    "proof": ""
+                }]
+            });
+            std::fs::write(
+                dir.path()
+                    .join(format!("{DEPOSIT_STEM}.proof-20260225T103000.json")),
+                bundle.to_string(),
+            )
+            .unwrap();
+        };
+
+        write_proof(&nullifier, derived);
+        assert_eq!(scan().proof_valid, Some(true));
+
+        // A valid journal does not vouch for a note field that disagrees with it.
+        write_proof(&format!("0x{}", "99".repeat(32)), derived);
+        let entry = scan();
+        assert_eq!(entry.proof_valid, Some(false));
+        assert_eq!(
+            entry.proof_invalid_reason.as_deref(),
+            Some("note 0 nullifier does not match its journal")
+        );
+
+        write_proof(&nullifier, [0x99; 32]);
+        assert_eq!(scan().proof_valid, Some(false));
+    }
+
+    #[test]
+    fn prune_moves_only_invalid_files_into_quarantine() {
+        let dir = tempfile::tempdir().unwrap();
+        let valid = format!("{DEPOSIT_STEM}.json");
+        let invalid = "deposit-aaaa-bbbb-20260224T214613.json";
+        let bad_proof = format!("{DEPOSIT_STEM}.proof-20260225T103000.json");
+        std::fs::write(dir.path().join(&valid), sample_deposit_json()).unwrap();
+        std::fs::write(dir.path().join(invalid), "{ not json").unwrap();
+        std::fs::write(dir.path().join(&bad_proof), "[]").unwrap();
+
+        let naming = ProofNaming::default();
+        let mut moved =
+            quarantine_invalid_files(dir.path(), &naming, WritePerms::default()).unwrap();
+        moved.sort();
+        assert_eq!(moved, vec![invalid.to_string(), bad_proof.clone()]);
+
+        assert!(dir.path().join(&valid).exists());
+        assert!(!dir.path().join(invalid).exists());
+        let quarantine = dir.path().join(QUARANTINE_DIR);
+        assert_eq!(
+            std::fs::read_to_string(quarantine.join(invalid)).unwrap(),
+            "{ not json"
+        );
+        assert!(quarantine.join(&bad_proof).exists());
+
+        let index = scan_workspace(dir.path(), &naming, &RecipientHashCache::default());
+        assert_eq!(index.deposits.len(), 1);
+        assert_eq!(index.deposits[0].filename, valid);
+        assert!(!index.deposits[0].has_proof);
+    }
+
+    #[test]
+    fn naming_rejects_invalid_templates() {
+        assert!(ProofNaming::parse("{stem}.json").is_err());
+        assert!(ProofNaming::parse("{stem}.proof-{ts}").is_err());
+        assert!(ProofNaming::parse("{stem}{ts}.json").is_err());
+        assert!(ProofNaming::parse("../{stem}.proof-{ts}.json").is_err());
+        assert!(ProofNaming::parse("{stem}.{other}-{ts}.json").is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Deposit templates: named recipient lists reused across deposits.
+//!
+//! Templates live as JSON files in the workspace's [`TEMPLATES_DIR`]. A
+//! deposit created from a template takes its recipients and labels in order
+//! and supplies the amounts per request.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use shadow_util::{Hex20, MAX_NOTE_LABEL_LEN};
+
+use super::perms::WritePerms;
+
+/// Workspace subdirectory holding deposit templates.
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Longest accepted template name.
+pub const MAX_TEMPLATE_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositTemplate {
+    pub name: String,
+    pub notes: Vec<TemplateNote>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateNote {
+    pub recipient: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl DepositTemplate {
+    /// Check the name and notes, and normalize recipients to lowercase hex.
+    pub fn validate(&mut self, max_notes: usize) -> Result<()> {
+        template_path(Path::new(""), &self.name)?;
+        if self.notes.is_empty() || self.notes.len() > max_notes {
+            bail!("notes must contain 1-{max_notes} entries (server limit)");
+        }
+        for (i, note) in self.notes.iter_mut().enumerate() {
+            let recipient = note
+                .recipient
+                .parse::<Hex20>()
+                .with_context(|| format!("invalid recipient in note {i}"))?;
+            note.recipient = format!("0x{}", hex::encode(recipient.into_bytes()));
+            if note
+                .label
+                .as_ref()
+                .is_some_and(|l| l.len() > MAX_NOTE_LABEL_LEN)
+            {
+                bail!("note {i} label exceeds {MAX_NOTE_LABEL_LEN} characters");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Path of template `name` in `workspace`. Names are limited to ASCII
+/// letters, digits, `-` and `_` so they cannot leave the templates directory.
+pub fn template_path(workspace: &Path, name: &str) -> Result<PathBuf> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if name.is_empty() || name.len() > MAX_TEMPLATE_NAME_LEN || !valid_chars {
+        bail!("invalid template name: use 1-{MAX_TEMPLATE_NAME_LEN} letters, digits, '-' or '_'");
+    }
+    Ok(workspace.join(TEMPLATES_DIR).join(format!("{name}.json")))
+}
+
+/// Write a validated template, failing if one with the same name exists.
+pub fn save_template(
+    workspace: &Path,
+    perms: WritePerms,
+    template: &DepositTemplate,
+) -> Result<()> {
+    let path = template_path(workspace, &template.name)?;
+    let dir = workspace.join(TEMPLATES_DIR);
+    perms
+        .create_dir_all(&dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let contents =
+        serde_json::to_string_pretty(template).context("failed to serialize template")?;
+    perms
+        .create_new(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .with_context(|| format!("failed to write template {}", path.display()))
+}
+
+/// Read template `name`, or `None` if it does not exist.
+pub fn load_template(workspace: &Path, name: &str) -> Result<Option<DepositTemplate>> {
+    let path = template_path(workspace, name)?;
+    let raw = match std::fs::read(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let template = serde_json::from_slice(&raw)
+        .with_context(|| format!("invalid template {}", path.display()))?;
+    Ok(Some(template))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(name: &str, recipient: &str) -> DepositTemplate {
+        DepositTemplate {
+            name: name.to_string(),
+            notes: vec![TemplateNote {
+                recipient: recipient.to_string(),
+                label: Some("alice".to_string()),
+            }],
+        }
+    }
+
+    #[test]
+    fn template_names_cannot_escape_the_templates_dir() {
+        let workspace = Path::new("/ws");
+        assert_eq!(
+            template_path(workspace, "payroll-2026_q1").unwrap(),
+            Path::new("/ws/templates/payroll-2026_q1.json")
+        );
+        for bad in ["", "../deposit", "a/b", "a.json", &"x".repeat(65)] {
+            assert!(template_path(workspace, bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn saved_templates_are_normalized_and_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut saved = template("team", &format!("0x{}", "AB".repeat(20)));
+        saved.validate(5).unwrap();
+        save_template(dir.path(), WritePerms::default(), &saved).unwrap();
+
+        let loaded = load_template(dir.path(), "team").unwrap().unwrap();
+        assert_eq!(loaded.notes[0].recipient, format!("0x{}", "ab".repeat(20)));
+        assert_eq!(loaded.notes[0].label.as_deref(), Some("alice"));
+        assert!(save_template(dir.path(), WritePerms::default(), &saved).is_err());
+        assert!(load_template(dir.path(), "other").unwrap().is_none());
+
+        assert!(template("bad", "0x1234").validate(5).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! Claim evaluation driven by the core crate's `test-support` fixtures.
+
+use shadow_proof_core::{
+    compute_notes_hash, compute_recipient_hash, derive_nullifier, evaluate_claim,
+    test_support::build_single_leaf_claim_input, ClaimValidationError,
+};
+
+const SECRET: [u8; 32] = [0x42; 32];
+const CHAIN_ID: u64 = 167013;
+const RECIPIENT: [u8; 20] = [0x11; 20];
+const AMOUNT: u128 = 1_230_000_000_000;
+
+#[test]
+fn fixture_claim_evaluates_to_expected_journal() {
+    let input = build_single_leaf_claim_input(SECRET, CHAIN_ID, RECIPIENT, AMOUNT, 4_739_555);
+    let journal = evaluate_claim(&input).unwrap();
+
+    let notes_hash =
+        compute_notes_hash(1, &[AMOUNT], &[compute_recipient_hash(&RECIPIENT)]).unwrap();
+    assert_eq!(journal.block_number, 4_739_555);
+    assert_eq!(journal.block_hash, input.block_hash);
+    assert_eq!(journal.amount, AMOUNT);
+    assert_eq!(journal.recipient, RECIPIENT);
+    assert_eq!(
+        journal.nullifier,
+        derive_nullifier(&SECRET, CHAIN_ID, 0, &notes_hash)
+    );
+    assert_eq!(journal.token, [0u8; 20]);
+}
+
+#[test]
+fn fixture_claim_rejects_a_different_secret() {
+    let mut input = build_single_leaf_claim_input(SECRET, CHAIN_ID, RECIPIENT, AMOUNT, 1);
+    // Another secret derives another target address, absent from the trie.
+    input.secret = [0x43; 32];
+    assert!(matches!(
+        evaluate_claim(&input),
+        Err(ClaimValidationError::PathDiverged)
+    ));
+}
@@ -92,9 +92,10 @@ export function startProof(depositId, force = false) {
   return apiFetch(path, { method: 'POST' });
 }
 
-/** GET /api/queue */
-export function getQueueStatus() {
-  return apiFetch('/queue');
+/** GET /api/queue — the current job; pending jobs are not shown yet. */
+export async function getQueueStatus() {
+  const queue = await apiFetch('/queue');
+  return queue.current;
 }
 
 /** DELETE /api/queue/current */
@@ -159,7 +160,10 @@ function ensureWebSocket() {
   if (wsConnection && wsConnection.readyState <= WebSocket.OPEN) return;
 
   const protocol = location.protocol === 'https:' ? 'wss:' : 'ws:';
-  const url = `${protocol}//${location.host}/ws`;
+  // Forward ?token= from the page URL for servers started with --ws-token
+  const token = new URLSearchParams(location.search).get('token');
+  const query = token ? `?token=${encodeURIComponent(token)}` : '';
+  const url = `${protocol}//${location.host}/ws${query}`;
 
   try {
     wsConnection = new WebSocket(url);
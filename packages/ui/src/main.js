@@ -419,9 +419,9 @@ async function handleProve(depositId, force = false) {
     return;
   }
   try {
-    const job = await api.startProof(depositId, force);
-    state.queueJob = job;
-    render();
+    await api.startProof(depositId, force);
+    // The job may wait behind others; the queue reflects what is actually running.
+    pollQueue();
   } catch (err) {
     const msg = err?.message || String(err);
     if (msg.includes('409') || msg.toLowerCase().includes('already running')) {
@@ -1031,7 +1031,10 @@ function proofFileRow(deposit, status) {
     el('span', { className: 'detail-label' }, 'Proof'),
     el('div', { className: 'file-row-value' }, [
       deposit.proofValid === false
-        ? el('span', { className: 'badge badge-no-proof badge-shrink' }, 'Invalid')
+        ? el('span', {
+          className: 'badge badge-no-proof badge-shrink',
+          title: deposit.proofInvalidReason || '',
+        }, 'Invalid')
         : null,
       el('span', { className: 'detail-value file-name' }, deposit.proofFile || '\u2014'),
       el('button', {
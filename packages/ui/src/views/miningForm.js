@@ -199,12 +199,13 @@ function noteRow(state, index, walletAddress, onRemove, amountLabel) {
 
 export function renderMiningFormView({ state, chainId, walletAddress, onSubmit, onClose }) {
   ensureState(state);
+  const maxNotes = state.config?.maxNotes ?? MAX_NOTES;
 
   const container = el('div', { className: 'mining-panel' });
 
   function addNote() {
     syncInputsToState(state);
-    if (state.miningNotes.length < MAX_NOTES) {
+    if (state.miningNotes.length < maxNotes) {
       state.miningNotes.push({ recipient: '', amount: '', label: '' });
     }
     renderContent();
@@ -303,7 +304,7 @@ export function renderMiningFormView({ state, chainId, walletAddress, onSubmit,
 
     container.appendChild(el('div', { className: 'mining-notes-header' }, [
       el('span', { className: 'form-label form-label-inline' }, capLabel),
-      state.miningNotes.length < MAX_NOTES
+      state.miningNotes.length < maxNotes
         ? el('button', { className: 'btn btn-small', onclick: addNote }, '+ Add Note')
         : null,
     ].filter(Boolean)));